@@ -168,7 +168,9 @@ fn testnet_genesis(
 		orml_nft: node_template_runtime::OrmlNFTConfig { tokens: vec![] },
 		nftmart: Default::default(),
 		nftmart_order: Default::default(),
+		nftmart_agent: Default::default(),
 		nftmart_auction: Default::default(),
+		nftmart_rental: Default::default(),
 		nftmart_conf: node_template_runtime::NftmartConfConfig {
 			white_list: endowed_accounts,
 			auction_close_delay: nftmart_traits::time::MINUTES * 10,
@@ -93,10 +93,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 102,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// This determines the average expected block time that we are targeting.
@@ -471,6 +471,15 @@ parameter_types! {
 	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
 }
 
+/// There is no staking to pay the stakers' share of the trading fees, it goes to the treasury.
+pub type FeeRouter = nftmart_config::FeeRouter<
+	Runtime,
+	Currencies,
+	TreasuryPalletId,
+	GetNativeCurrencyId,
+	nftmart_config::WithoutStaking,
+>;
+
 impl nftmart_order::Config for Runtime {
 	type Event = Event;
 	type MultiCurrency = Currencies;
@@ -479,7 +488,50 @@ impl nftmart_order::Config for Runtime {
 	type TokenId = nftmart_traits::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = NftmartAgent;
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
+	type OffchainSignature = Signature;
+	type OffchainPublic = <Signature as sp_runtime::traits::Verify>::Signer;
+}
+
+parameter_types! {
+	pub const MaxDropPhases: u32 = 8;
+}
+
+impl nftmart_drop::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type Currency = Balances;
+	type ClassId = nftmart_traits::ClassId;
+	type TokenId = nftmart_traits::TokenId;
+	type NFT = Nftmart;
+	type ExtraConfig = NftmartConf;
+	type FeeRouter = FeeRouter;
+	type MaxDropPhases = MaxDropPhases;
+}
+
+parameter_types! {
+	pub const MaxAgentNameLength: u32 = 64;
+	pub const MaxAgentMetadataLength: u32 = 1024;
+}
+
+impl nftmart_agent::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type CurrencyId = nftmart_traits::CurrencyId;
+	type ExtraConfig = NftmartConf;
+	type Slash = ();
+	type MaxNameLength = MaxAgentNameLength;
+	type MaxMetadataLength = MaxAgentMetadataLength;
+}
+
+impl nftmart_rental::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type ClassId = nftmart_traits::ClassId;
+	type TokenId = nftmart_traits::TokenId;
+	type NFT = Nftmart;
 }
 
 impl nftmart_auction::Config for Runtime {
@@ -490,6 +542,8 @@ impl nftmart_auction::Config for Runtime {
 	type TokenId = nftmart_traits::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = NftmartAgent;
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
 	type WeightInfo = ();
 }
@@ -522,6 +576,9 @@ construct_runtime!(
 		Nftmart: nftmart_nft::{Pallet, Call, Storage, Event<T>, Config<T>},
 		NftmartOrder: nftmart_order::{Pallet, Call, Storage, Event<T>, Config<T>},
 		NftmartAuction: nftmart_auction::{Pallet, Call, Storage, Event<T>, Config<T>},
+		NftmartDrop: nftmart_drop::{Pallet, Call, Storage, Event<T>},
+		NftmartAgent: nftmart_agent::{Pallet, Call, Storage, Event<T>, Config<T>},
+		NftmartRental: nftmart_rental::{Pallet, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -543,6 +600,25 @@ pub type SignedExtra = (
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+
+/// The fee charged for a signed extrinsic dispatching `call`, as `TransactionPaymentApi` reports
+/// it without a tip. The extrinsic is signed with a placeholder, as only its length counts.
+fn quote_tx_fee(call: Call) -> Balance {
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(generic::Era::mortal(256, System::block_number() as u64)),
+		frame_system::CheckNonce::from(0),
+		frame_system::CheckWeight::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::from(0),
+	);
+	let signature = Signature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64]));
+	let uxt = UncheckedExtrinsic::new_signed(call, Address::Id([0; 32].into()), signature, extra);
+	let len = uxt.encoded_size() as u32;
+	TransactionPayment::query_info(uxt, len).partial_fee
+}
+
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -733,13 +809,114 @@ impl_runtime_apis! {
 			created_block: BlockNumber,
 			deadline: BlockNumber,
 			current_block: BlockNumber,
+			curve: nftmart_traits::DutchAuctionCurve<BlockNumber>,
 		) -> Balance {
-			nftmart_auction::calc_current_price::<Runtime>(max_price, min_price, created_block, deadline, current_block)
+			nftmart_auction::calc_current_price::<Runtime>(max_price, min_price, created_block, deadline, current_block, &curve)
+		}
+		fn get_dutch_auction_price(
+			auction_owner: AccountId,
+			auction_id: nftmart_traits::GlobalId,
+		) -> Option<Balance> {
+			let auction = NftmartAuction::dutch_auctions(&auction_owner, auction_id)?;
+			Some(NftmartAuction::dutch_auction_price(&auction, System::block_number() + 1))
 		}
 		fn get_auction_deadline(
-			allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber
+			allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber,
+			soft_close: Option<nftmart_traits::SoftClose<BlockNumber>>,
 		) -> BlockNumber {
-			nftmart_auction::get_deadline::<Runtime>(allow_delay, deadline, last_bid_block)
+			nftmart_auction::get_deadline::<Runtime>(allow_delay, deadline, last_bid_block, &soft_close)
+		}
+		fn get_class_supply(class_id: ClassId) -> Option<(ClassSupplyCap<TokenId>, TokenId, TokenId)> {
+			Nftmart::class_supply(class_id)
+		}
+		fn get_class_admins(class_id: ClassId) -> Vec<AccountId> {
+			Nftmart::class_admins(class_id)
+		}
+		fn get_token_user(class_id: ClassId, token_id: TokenId, lender: AccountId) -> Option<(AccountId, BlockNumber)> {
+			NftmartRental::user_of(class_id, token_id, &lender)
+		}
+		fn get_token_users(class_id: ClassId, token_id: TokenId) -> Vec<(AccountId, AccountId, BlockNumber)> {
+			NftmartRental::users_of(class_id, token_id)
+		}
+		fn quote_take_order(
+			order_owner: AccountId, order_id: GlobalId, commission_agent: Option<AccountId>,
+		) -> Result<Quote<AccountId, CurrencyId>, sp_runtime::DispatchError> {
+			let mut quote = NftmartOrder::quote_take_order(&order_owner, order_id, commission_agent.clone())?;
+			quote.tx_fee = quote_tx_fee(Call::NftmartOrder(nftmart_order::Call::take_order {
+				order_id,
+				order_owner: order_owner.into(),
+				commission_agent,
+				commission_data: None,
+			}));
+			Ok(quote)
+		}
+		fn quote_submit_order(
+			currency_id: CurrencyId, price: Balance,
+			items: Vec<(ClassId, TokenId, TokenId)>,
+			commission_rate: sp_runtime::PerU16, commission_agent: Option<AccountId>,
+		) -> Result<Quote<AccountId, CurrencyId>, sp_runtime::DispatchError> {
+			let mut quote = NftmartOrder::quote_submit_order(
+				currency_id, price, items.clone(), commission_rate, commission_agent,
+			)?;
+			quote.tx_fee = quote_tx_fee(Call::NftmartOrder(nftmart_order::Call::submit_order {
+				currency_id,
+				deposit: quote.deposit,
+				price,
+				deadline: System::block_number(),
+				items,
+				commission_rate,
+			}));
+			Ok(quote)
+		}
+		fn quote_bid(
+			auction_owner: AccountId, auction_id: GlobalId, price: Balance,
+			commission_agent: Option<AccountId>,
+		) -> Result<Quote<AccountId, CurrencyId>, sp_runtime::DispatchError> {
+			let mut quote = NftmartAuction::quote_bid(&auction_owner, auction_id, price, commission_agent.clone())?;
+			let call = if NftmartAuction::british_auctions(&auction_owner, auction_id).is_some() {
+				nftmart_auction::Call::bid_british_auction {
+					price,
+					auction_owner: auction_owner.into(),
+					auction_id,
+					commission_agent,
+					commission_data: None,
+				}
+			} else {
+				nftmart_auction::Call::bid_dutch_auction {
+					price,
+					auction_owner: auction_owner.into(),
+					auction_id,
+					commission_agent,
+					commission_data: None,
+				}
+			};
+			quote.tx_fee = quote_tx_fee(Call::NftmartAuction(call));
+			Ok(quote)
+		}
+		fn quote_mint(
+			class_id: ClassId, metadata_len: u32, quantity: TokenId, charge_royalty: Option<sp_runtime::PerU16>,
+		) -> Result<Quote<AccountId, CurrencyId>, sp_runtime::DispatchError> {
+			let class_info = orml_nft::Pallet::<Runtime>::classes(class_id)
+				.ok_or(nftmart_nft::Error::<Runtime>::ClassIdNotFound)?;
+			let call = Call::Nftmart(nftmart_nft::Call::mint {
+				to: class_info.owner.into(),
+				class_id,
+				metadata: vec![0; metadata_len as usize],
+				quantity,
+				charge_royalty,
+			});
+			Ok(Quote {
+				currency_id: NATIVE_CURRENCY_ID,
+				price: 0,
+				platform_fee: 0,
+				royalty: 0,
+				royalty_beneficiary: None,
+				commission: 0,
+				commission_agent: None,
+				commission_paid: false,
+				deposit: Nftmart::mint_token_deposit(metadata_len),
+				tx_fee: quote_tx_fee(call),
+			})
 		}
 	}
 
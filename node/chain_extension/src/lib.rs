@@ -6,7 +6,9 @@ use frame_support::{
 	log::{error, trace},
 	traits::Randomness,
 };
-use nftmart_traits::{BitFlags, ClassId, ClassProperty, GlobalId, Properties, Signature, TokenId};
+use nftmart_traits::{
+	BitFlags, ClassId, ClassProperty, ClassSupplyCap, GlobalId, Properties, Signature, TokenId,
+};
 use pallet_contracts::chain_extension::{
 	ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
 };
@@ -34,6 +36,7 @@ where
 	Runtime: pallet_contracts::Config,
 	Runtime: pallet_randomness_collective_flip::Config,
 	Runtime: nftmart_nft::Config<ClassId = ClassId, TokenId = TokenId>,
+	Runtime: nftmart_rental::Config<ClassId = ClassId, TokenId = TokenId>,
 {
 	fn call<E: Ext>(func_id: u32, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
 	where
@@ -54,18 +57,21 @@ where
 					.map_err(|_| DispatchError::Other("ChainExtension failed to call random"))?;
 			},
 
-			2002 => {
+			// 2010 creates a class with a supply cap, `(max_tokens, max_supply)` follow the
+			// arguments of 2002.
+			2002 | 2010 => {
 				let mut env = env.buf_in_buf_out();
 				let caller = env.ext().caller().clone();
 				let caller: <Runtime as SysConfig>::AccountId = to_account_id(caller.as_ref())?;
-				let (metadata, name, description, properties, royalty_rate, cate_ids): (
-					_,
-					_,
-					_,
-					u8,
-					u16,
-					Vec<GlobalId>,
-				) = env.read_as_unbounded(env.in_len())?;
+				type CreateClassArgs = (Vec<u8>, Vec<u8>, Vec<u8>, u8, u16, Vec<GlobalId>);
+				let (
+					(metadata, name, description, properties, royalty_rate, cate_ids),
+					(max_tokens, max_supply),
+				): (CreateClassArgs, (Option<TokenId>, Option<TokenId>)) = if func_id == 2002 {
+					(env.read_as_unbounded(env.in_len())?, (None, None))
+				} else {
+					env.read_as_unbounded(env.in_len())?
+				};
 				let p = Properties(
 					<BitFlags<ClassProperty>>::from_bits(properties)
 						.map_err(|_| "invalid class properties value")?,
@@ -79,6 +85,7 @@ where
 					royalty_rate,
 					p,
 					cate_ids,
+					ClassSupplyCap { max_tokens, max_supply },
 				)
 				.map_err(|e| e.error)?;
 				let r = (owner, class_id).encode();
@@ -127,6 +134,76 @@ where
 				})?;
 			},
 
+			2005 => {
+				let mut env = env.buf_in_buf_out();
+				let caller = env.ext().caller().clone();
+				let caller: AccountId32 = to_account_id(caller.as_ref())?;
+				let (spender, class_id, token_id, quantity, deadline) = env.read_as()?;
+				nftmart_nft::Pallet::<Runtime>::do_approve(
+					&caller, &spender, class_id, token_id, quantity, deadline,
+				)?;
+				let r = ().encode();
+				env.write(&r, false, None).map_err(|_| {
+					DispatchError::Other("ChainExtension failed to return result from do_approve")
+				})?;
+			},
+
+			2006 => {
+				let mut env = env.buf_in_buf_out();
+				let caller = env.ext().caller().clone();
+				let caller: AccountId32 = to_account_id(caller.as_ref())?;
+				let (spender, class_id, token_id) = env.read_as()?;
+				nftmart_nft::Pallet::<Runtime>::do_revoke(&caller, &spender, class_id, token_id)?;
+				let r = ().encode();
+				env.write(&r, false, None).map_err(|_| {
+					DispatchError::Other("ChainExtension failed to return result from do_revoke")
+				})?;
+			},
+
+			2007 => {
+				let mut env = env.buf_in_buf_out();
+				let caller = env.ext().caller().clone();
+				let caller: AccountId32 = to_account_id(caller.as_ref())?;
+				let (from, to, class_id, token_id, quantity) = env.read_as()?;
+				nftmart_nft::Pallet::<Runtime>::do_transfer_from(
+					&caller, &from, &to, class_id, token_id, quantity,
+				)?;
+				let r = ().encode();
+				env.write(&r, false, None).map_err(|_| {
+					DispatchError::Other(
+						"ChainExtension failed to return result from do_transfer_from",
+					)
+				})?;
+			},
+
+			2008 => {
+				let mut env = env.buf_in_buf_out();
+				let caller = env.ext().caller().clone();
+				let caller: AccountId32 = to_account_id(caller.as_ref())?;
+				let (operator, deadline) = env.read_as()?;
+				nftmart_nft::Pallet::<Runtime>::do_approve_for_all(&caller, &operator, deadline)?;
+				let r = ().encode();
+				env.write(&r, false, None).map_err(|_| {
+					DispatchError::Other(
+						"ChainExtension failed to return result from do_approve_for_all",
+					)
+				})?;
+			},
+
+			2009 => {
+				let mut env = env.buf_in_buf_out();
+				let caller = env.ext().caller().clone();
+				let caller: AccountId32 = to_account_id(caller.as_ref())?;
+				let operator = env.read_as()?;
+				nftmart_nft::Pallet::<Runtime>::do_revoke_for_all(&caller, &operator)?;
+				let r = ().encode();
+				env.write(&r, false, None).map_err(|_| {
+					DispatchError::Other(
+						"ChainExtension failed to return result from do_revoke_for_all",
+					)
+				})?;
+			},
+
 			// ################## read only ##################
 			1001 => {
 				let mut env = env.buf_in_buf_out();
@@ -135,6 +212,35 @@ where
 				env.write(&r.encode(), false, None)?;
 			},
 
+			1002 => {
+				let mut env = env.buf_in_buf_out();
+				let (owner, spender, class_id, token_id) = env.read_as()?;
+				let r =
+					nftmart_nft::Pallet::<Runtime>::allowance(&owner, &spender, class_id, token_id);
+				env.write(&r.encode(), false, None)?;
+			},
+
+			1003 => {
+				let mut env = env.buf_in_buf_out();
+				let (owner, operator) = env.read_as()?;
+				let r = nftmart_nft::Pallet::<Runtime>::is_approved_for_all(&owner, &operator);
+				env.write(&r.encode(), false, None)?;
+			},
+
+			1004 => {
+				let mut env = env.buf_in_buf_out();
+				let class_id = env.read_as()?;
+				let r = nftmart_nft::Pallet::<Runtime>::class_supply(class_id);
+				env.write(&r.encode(), false, None)?;
+			},
+
+			1005 => {
+				let mut env = env.buf_in_buf_out();
+				let (class_id, token_id, lender) = env.read_as()?;
+				let r = nftmart_rental::Pallet::<Runtime>::user_of(class_id, token_id, &lender);
+				env.write(&r.encode(), false, None)?;
+			},
+
 			1101 => {
 				let mut env = env.buf_in_buf_out();
 				let (account_id, signature, msg): (AccountId32, Vec<u8>, Vec<u8>) =
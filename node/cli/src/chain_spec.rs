@@ -311,7 +311,9 @@ pub fn testnet_genesis(
 		orml_nft: Default::default(),
 		nftmart: Default::default(),
 		nftmart_order: Default::default(),
+		nftmart_agent: Default::default(),
 		nftmart_auction: Default::default(),
+		nftmart_rental: Default::default(),
 		nftmart_conf: node_runtime::NftmartConfConfig {
 			white_list: endowed_accounts,
 			auction_close_delay: nftmart_traits::time::MINUTES * 10,
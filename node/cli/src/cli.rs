@@ -28,6 +28,102 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub indexer: nftmart_indexer::IndexerParams,
+
+	/// Seal blocks without BABE slots and GRANDPA votes, on a development or local chain only.
+	///
+	/// `instant` seals a block for each transaction, `manual` only seals blocks on
+	/// `engine_createBlock` and `engine_fastForward` calls, and a number of milliseconds seals a
+	/// block at that interval. The blocks are finalized at once.
+	#[structopt(long, value_name = "instant|manual|MS")]
+	pub sealing: Option<Sealing>,
+
+	/// Export the `nftmart_*` marketplace metrics on the Prometheus endpoint, updated whenever a
+	/// block is finalized.
+	#[structopt(long)]
+	pub nftmart_metrics: bool,
+
+	/// The least number of seconds between two refreshes of the listing gauges of the
+	/// marketplace metrics, which scan every listing.
+	#[structopt(long, value_name = "SECONDS", default_value = "60")]
+	pub nftmart_metrics_interval: u64,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub eth: EthParams,
+}
+
+/// Options of the Ethereum RPC methods.
+#[derive(Debug, Clone, StructOpt)]
+pub struct EthParams {
+	/// Enable optional groups of Ethereum RPC methods.
+	///
+	/// `debug` enables `debug_traceTransaction`, `trace` enables `trace_filter` and `txpool`
+	/// enables `txpool_content`, `txpool_inspect` and `txpool_status`.
+	#[structopt(long, value_name = "debug,trace,txpool", use_delimiter = true)]
+	pub ethapi: Vec<node_rpc::EthApiCmd>,
+
+	/// The most logs returned by `eth_getLogs`.
+	#[structopt(long, default_value = "1024")]
+	pub max_past_logs: u32,
+
+	/// The most blocks of `eth_feeHistory`.
+	#[structopt(long, default_value = "2048")]
+	pub fee_history_limit: u64,
+
+	/// The most traces returned by `trace_filter`.
+	#[structopt(long, default_value = "500")]
+	pub ethapi_trace_max_count: u32,
+
+	/// The most blocks scanned by one `trace_filter` call.
+	#[structopt(long, default_value = "1000")]
+	pub ethapi_trace_max_block_range: u32,
+
+	/// The number of blocks whose traces are kept in memory for `trace_filter`.
+	#[structopt(long, default_value = "64")]
+	pub ethapi_trace_cache_size: usize,
+}
+
+impl EthParams {
+	/// The options passed to the RPC methods.
+	pub fn config(&self) -> node_rpc::EthConfig {
+		node_rpc::EthConfig {
+			ethapi: self.ethapi.clone(),
+			max_past_logs: self.max_past_logs,
+			fee_history_limit: self.fee_history_limit,
+			trace_max_count: self.ethapi_trace_max_count,
+			trace_max_block_range: self.ethapi_trace_max_block_range,
+			trace_cache_size: self.ethapi_trace_cache_size,
+		}
+	}
+}
+
+/// How blocks are sealed in development mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// Seal a block as soon as a transaction enters the pool.
+	Instant,
+	/// Seal blocks on RPC calls only.
+	Manual,
+	/// Seal a block every given milliseconds.
+	Interval(u64),
+}
+
+impl std::str::FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Self::Instant),
+			"manual" => Ok(Self::Manual),
+			ms => match ms.parse() {
+				Ok(ms) if ms > 0 => Ok(Self::Interval(ms)),
+				_ => Err(format!("Expected `instant`, `manual` or milliseconds, got `{}`", s)),
+			},
+		}
+	}
 }
 
 /// Possible subcommands of the main binary.
@@ -36,10 +132,10 @@ pub enum Subcommand {
 	/// Key management cli utilities
 	Key(KeySubcommand),
 
-	/// The custom inspect subcommmand for decoding blocks and extrinsics.
+	/// The custom inspect subcommmand for decoding blocks, extrinsics and the NFTMart state.
 	#[structopt(
 		name = "inspect",
-		about = "Decode given block or extrinsic using current native runtime."
+		about = "Decode given block, extrinsic or NFTMart state using current native runtime."
 	)]
 	Inspect(node_inspect::cli::InspectCmd),
 
@@ -84,4 +180,12 @@ pub enum Subcommand {
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
+
+	/// Merge an NFT snapshot into the genesis of a chain spec.
+	#[structopt(name = "merge-nfts")]
+	MergeNfts(node_inspect::cli::MergeNftsCmd),
+
+	/// Rebuild the marketplace history of the indexer.
+	#[structopt(name = "nftmart-reindex")]
+	NftmartReindex(nftmart_indexer::ReindexCmd),
 }
@@ -20,20 +20,28 @@
 
 //! Service implementation. Specialized wrapper over substrate service.
 
+use crate::cli::Sealing;
 use node_executor::ExecutorDispatch;
-use node_primitives::Block;
+use node_primitives::{Block, Hash};
 use node_runtime::RuntimeApi;
 use sc_client_api::{BlockchainEvents, ExecutorProvider};
 use sc_consensus_babe::{self, SlotProportion};
+use sc_consensus_manual_seal::{
+	consensus::{babe::BabeConsensusDataProvider, timestamp::SlotTimestampProvider},
+	EngineCommand, ManualSealParams,
+};
 use sc_executor::NativeElseWasmExecutor;
 use sc_network::{Event, NetworkService};
-use sc_service::{config::Configuration, error::Error as ServiceError, BasePath, TaskManager};
+use sc_service::{
+	config::Configuration, error::Error as ServiceError, BasePath, ChainType, TaskManager,
+};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sc_transaction_pool_api::TransactionPool;
 use sp_runtime::traits::Block as BlockT;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use fc_mapping_sync::{MappingSyncWorker, SyncStrategy};
-use futures::StreamExt;
+use futures::{channel::mpsc, stream, stream::BoxStream, StreamExt};
 use sc_cli::SubstrateCli;
 
 type FullClient =
@@ -42,17 +50,25 @@ type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 type FullGrandpaBlockImport =
 	grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
+type FullPool = sc_transaction_pool::FullPool<Block, FullClient>;
 
-pub fn frontier_database_dir(config: &Configuration) -> std::path::PathBuf {
-	let config_dir = config
+fn config_dir(config: &Configuration) -> std::path::PathBuf {
+	config
 		.base_path
 		.as_ref()
 		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
 		.unwrap_or_else(|| {
 			BasePath::from_project("", "", &crate::cli::Cli::executable_name())
 				.config_dir(config.chain_spec.id())
-		});
-	config_dir.join("frontier").join("db")
+		})
+}
+
+pub fn frontier_database_dir(config: &Configuration) -> std::path::PathBuf {
+	config_dir(config).join("frontier").join("db")
+}
+
+pub fn indexer_database_dir(config: &Configuration) -> std::path::PathBuf {
+	config_dir(config).join("nftmart-indexer").join("db")
 }
 
 pub fn open_frontier_backend(config: &Configuration) -> Result<Arc<fc_db::Backend<Block>>, String> {
@@ -64,8 +80,23 @@ pub fn open_frontier_backend(config: &Configuration) -> Result<Arc<fc_db::Backen
 	})?))
 }
 
+/// Open the database of the indexer, which requires the state of all the blocks.
+pub fn open_indexer_db(config: &Configuration) -> Result<Arc<nftmart_indexer::IndexerDb>, String> {
+	use sc_service::config::PruningMode;
+	if !matches!(config.state_pruning, PruningMode::ArchiveAll | PruningMode::ArchiveCanonical) {
+		return Err(
+			"The NFTMart indexer requires an archive node, run with `--pruning archive`".into()
+		)
+	}
+	let path = indexer_database_dir(config);
+	nftmart_indexer::IndexerDb::open(&path)
+		.map(Arc::new)
+		.map_err(|e| format!("Failed to open the indexer database at {}: {}", path.display(), e))
+}
+
 pub fn new_partial(
 	config: &Configuration,
+	sealing: Option<Sealing>,
 ) -> Result<
 	sc_service::PartialComponents<
 		FullClient,
@@ -153,31 +184,40 @@ pub fn new_partial(
 	)?;
 
 	let slot_duration = babe_link.config().slot_duration();
-	let import_queue = sc_consensus_babe::import_queue(
-		babe_link.clone(),
-		block_import.clone(),
-		Some(Box::new(justification_import)),
-		client.clone(),
-		select_chain.clone(),
-		move |_, ()| async move {
-			let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+	let import_queue = match sealing {
+		// The sealed blocks carry a BABE pre-digest but no BABE seal.
+		Some(_) => sc_consensus_manual_seal::import_queue(
+			Box::new(block_import.clone()),
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+		),
+		None => sc_consensus_babe::import_queue(
+			babe_link.clone(),
+			block_import.clone(),
+			Some(Box::new(justification_import)),
+			client.clone(),
+			select_chain.clone(),
+			move |_, ()| async move {
+				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
 
-			let slot =
-				sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
-					*timestamp,
-					slot_duration,
-				);
+				let slot =
+					sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_duration(
+						*timestamp,
+						slot_duration,
+					);
 
-			let uncles =
-				sp_authorship::InherentDataProvider::<<Block as BlockT>::Header>::check_inherents();
+				let uncles = sp_authorship::InherentDataProvider::<
+					<Block as BlockT>::Header,
+				>::check_inherents();
 
-			Ok((timestamp, slot, uncles))
-		},
-		&task_manager.spawn_essential_handle(),
-		config.prometheus_registry(),
-		sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
-		telemetry.as_ref().map(|x| x.handle()),
-	)?;
+				Ok((timestamp, slot, uncles))
+			},
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
+			telemetry.as_ref().map(|x| x.handle()),
+		)?,
+	};
 
 	let import_setup = (block_import, grandpa_link, babe_link);
 
@@ -253,18 +293,67 @@ pub struct NewFullBase {
 	pub task_manager: TaskManager,
 	pub client: Arc<FullClient>,
 	pub network: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
-	pub transaction_pool: Arc<sc_transaction_pool::FullPool<Block, FullClient>>,
+	pub transaction_pool: Arc<FullPool>,
+	/// The sink of the commands sealing blocks, if `sealing` is given.
+	pub command_sink: Option<mpsc::Sender<EngineCommand<Hash>>>,
+}
+
+/// The commands sealing blocks in development mode, which are those sent over RPC merged with a
+/// command for each imported transaction or for each interval.
+fn dev_seal_commands(
+	sealing: Sealing,
+	rpc_commands: mpsc::Receiver<EngineCommand<Hash>>,
+	pool: Arc<FullPool>,
+) -> BoxStream<'static, EngineCommand<Hash>> {
+	let seal = |create_empty| EngineCommand::SealNewBlock {
+		create_empty,
+		finalize: true,
+		parent_hash: None,
+		sender: None,
+	};
+	match sealing {
+		Sealing::Manual => rpc_commands.boxed(),
+		Sealing::Instant => stream::select(
+			rpc_commands,
+			pool.import_notification_stream().map(move |_| seal(false)),
+		)
+		.boxed(),
+		Sealing::Interval(ms) => stream::select(
+			rpc_commands,
+			stream::unfold((), move |()| async move {
+				futures_timer::Delay::new(Duration::from_millis(ms)).await;
+				Some((seal(true), ()))
+			}),
+		)
+		.boxed(),
+	}
 }
 
 /// Creates a full service from the configuration.
+///
+/// The marketplace metrics are exported if `metrics_interval` is given, refreshing the listing
+/// gauges at that interval at most. `sealing` is only allowed on a development or local chain.
 pub fn new_full_base(
 	mut config: Configuration,
+	enable_indexer: bool,
+	sealing: Option<Sealing>,
+	metrics_interval: Option<Duration>,
+	eth_config: node_rpc::EthConfig,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		// &sc_consensus_babe::BabeBlockImport<Block, FullClient, FrontierBlockImport<Block, FullGrandpaBlockImport, FullClient>>,
 		&sc_consensus_babe::BabeLink<Block>,
 	),
 ) -> Result<NewFullBase, ServiceError> {
+	if sealing.is_some() &&
+		!matches!(config.chain_spec.chain_type(), ChainType::Development | ChainType::Local)
+	{
+		return Err(ServiceError::Other(format!(
+			"`--sealing` is only allowed on a development or local chain, not on `{}`",
+			config.chain_spec.id()
+		)))
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -279,7 +368,7 @@ pub fn new_full_base(
 				/*rpc_setup,*/ mut telemetry,
 				frontier_backend,
 			),
-	} = new_partial(&config)?;
+	} = new_partial(&config, sealing)?;
 
 	// let shared_voter_state = rpc_setup;
 	let auth_disc_publish_non_global_ips = config.network.allow_non_globals_in_dht;
@@ -324,6 +413,18 @@ pub fn new_full_base(
 
 	(with_startup_data)(&block_import, &babe_link);
 
+	let indexer_db = if enable_indexer { Some(open_indexer_db(&config)?) } else { None };
+
+	let (command_sink, commands_stream) = match sealing {
+		Some(sealing) => {
+			let (command_sink, rpc_commands) = mpsc::channel(1024);
+			let commands_stream =
+				dev_seal_commands(sealing, rpc_commands, transaction_pool.clone());
+			(Some(command_sink), Some(commands_stream))
+		},
+		None => (None, None),
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
@@ -341,6 +442,9 @@ pub fn new_full_base(
 			Some(shared_authority_set.clone()),
 		);
 		let select_chain_clone = select_chain.clone();
+		let indexer_db = indexer_db.clone();
+		let command_sink = command_sink.clone();
+		let eth_config = eth_config.clone();
 
 		move |deny_unsafe: node_rpc::DenyUnsafe,
 		      subscription_executor: sc_rpc::SubscriptionTaskExecutor| {
@@ -366,6 +470,9 @@ pub fn new_full_base(
 					finality_provider: finality_proof_provider.clone(),
 				},
 				network: network.clone(),
+				indexer: indexer_db.clone(),
+				command_sink: command_sink.clone(),
+				eth: eth_config.clone(),
 			};
 			Ok(node_rpc::create_full(deps))
 		}
@@ -386,7 +493,65 @@ pub fn new_full_base(
 		telemetry: telemetry.as_mut(),
 	})?;
 
-	if let sc_service::config::Role::Authority { .. } = &role {
+	if let Some(commands_stream) = commands_stream {
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle()),
+		);
+
+		let consensus_data_provider = BabeConsensusDataProvider::new(
+			client.clone(),
+			keystore_container.sync_keystore(),
+			babe_link.epoch_changes().clone(),
+			babe_link.config().genesis_authorities.clone(),
+		)
+		.map_err(|e| ServiceError::Other(format!("{}", e)))?;
+
+		let client_clone = client.clone();
+		let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import,
+			env: proposer,
+			client: client.clone(),
+			pool: transaction_pool.clone(),
+			commands_stream,
+			select_chain: select_chain.clone(),
+			consensus_data_provider: Some(Box::new(consensus_data_provider)),
+			create_inherent_data_providers: move |parent, ()| {
+				let client_clone = client_clone.clone();
+				async move {
+					let uncles = sc_consensus_uncles::create_uncles_inherent_data_provider(
+						&*client_clone,
+						parent,
+					)?;
+
+					// One slot after the slot of the parent, whatever the time, so that blocks can be
+					// sealed faster than the slot duration.
+					let timestamp = SlotTimestampProvider::new(client_clone.clone())
+						.map_err(|e| format!("{}", e))?;
+
+					let slot = sp_consensus_babe::inherents::InherentDataProvider::new(
+						timestamp.slot().into(),
+					);
+
+					let storage_proof =
+						sp_transaction_storage_proof::registration::new_data_provider(
+							&*client_clone,
+							&parent,
+						)?;
+
+					Ok((timestamp, slot, uncles, storage_proof))
+				}
+			},
+		});
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"manual-seal",
+			Some("block-authoring"),
+			manual_seal,
+		);
+	} else if let sc_service::config::Role::Authority { .. } = &role {
 		let proposer = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
 			client.clone(),
@@ -464,6 +629,23 @@ pub fn new_full_base(
 		.for_each(|()| futures::future::ready(())),
 	);
 
+	if let Some(db) = indexer_db {
+		task_manager.spawn_handle().spawn_blocking(
+			"nftmart-indexer",
+			Some("nftmart"),
+			nftmart_indexer::Indexer::new(client.clone(), db).run(),
+		);
+	}
+
+	if let (Some(registry), Some(interval)) = (prometheus_registry.as_ref(), metrics_interval) {
+		let worker = nftmart_metrics::MetricsWorker::new(client.clone(), registry, interval)?;
+		task_manager.spawn_handle().spawn_blocking(
+			"nftmart-metrics",
+			Some("nftmart"),
+			worker.run(),
+		);
+	}
+
 	// Spawn authority discovery module.
 	if role.is_authority() {
 		let authority_discovery_role =
@@ -511,7 +693,7 @@ pub fn new_full_base(
 		telemetry: telemetry.as_ref().map(|x| x.handle()),
 	};
 
-	if enable_grandpa {
+	if enable_grandpa && sealing.is_none() {
 		// start the full GRANDPA voter
 		// NOTE: non-authorities could run the GRANDPA observer protocol, but at
 		// this point the full voter should provide better guarantees of block
@@ -538,17 +720,32 @@ pub fn new_full_base(
 	}
 
 	network_starter.start_network();
-	Ok(NewFullBase { task_manager, client, network, transaction_pool })
+	Ok(NewFullBase { task_manager, client, network, transaction_pool, command_sink })
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
-	new_full_base(config, |_, _| ()).map(|NewFullBase { task_manager, .. }| task_manager)
+pub fn new_full(
+	config: Configuration,
+	enable_indexer: bool,
+	sealing: Option<Sealing>,
+	metrics_interval: Option<Duration>,
+	eth_config: node_rpc::EthConfig,
+) -> Result<TaskManager, ServiceError> {
+	new_full_base(config, enable_indexer, sealing, metrics_interval, eth_config, |_, _| ())
+		.map(|NewFullBase { task_manager, .. }| task_manager)
 }
 
 #[cfg(test)]
 mod tests {
-	use crate::service::{new_full_base, NewFullBase};
+	use crate::{
+		cli::{Cli, Sealing},
+		service::{new_full_base, NewFullBase},
+	};
+	use futures::{channel::oneshot, SinkExt};
+	use sc_cli::SubstrateCli;
+	use sc_consensus_manual_seal::EngineCommand;
+	use sc_service::config::Configuration;
+	use structopt::StructOpt;
 
 	use node_primitives::Signature;
 
@@ -556,6 +753,69 @@ mod tests {
 
 	type AccountPublic = <Signature as Verify>::Signer;
 
+	/// The configuration of a node started with `args`, which listens on no port.
+	fn node_config(args: &[&str], tokio_handle: tokio::runtime::Handle) -> (Cli, Configuration) {
+		let cli = Cli::from_iter([&["nftmart-node", "--tmp"], args].concat());
+		let mut config = cli
+			.create_configuration(&cli.run, tokio_handle)
+			.expect("Creates the configuration");
+		config.network.listen_addresses = vec![];
+		config.rpc_http = None;
+		config.rpc_ws = None;
+		config.prometheus_config = None;
+		(cli, config)
+	}
+
+	#[test]
+	fn should_seal_blocks_manually() {
+		let runtime = tokio::runtime::Runtime::new().expect("Creates the tokio runtime");
+		let (cli, config) =
+			node_config(&["--dev", "--alice", "--sealing", "manual"], runtime.handle().clone());
+		let NewFullBase { task_manager, client, command_sink, .. } =
+			new_full_base(config, false, cli.sealing, None, Default::default(), |_, _| ())
+				.expect("Starts the node");
+		let mut command_sink = command_sink.expect("Seals blocks manually");
+
+		runtime.block_on(async {
+			for number in 1..=2 {
+				let (sender, receiver) = oneshot::channel();
+				command_sink
+					.send(EngineCommand::SealNewBlock {
+						create_empty: true,
+						finalize: true,
+						parent_hash: None,
+						sender: Some(sender),
+					})
+					.await
+					.unwrap();
+				receiver.await.unwrap().expect("Seals a block");
+				assert_eq!(client.chain_info().best_number, number);
+				assert_eq!(client.chain_info().finalized_number, number);
+			}
+		});
+		drop(task_manager);
+	}
+
+	#[test]
+	fn should_reject_sealing_on_a_live_chain() {
+		let runtime = tokio::runtime::Runtime::new().expect("Creates the tokio runtime");
+		let (_, mut config) = node_config(&["--dev"], runtime.handle().clone());
+		config.chain_spec = Box::new(crate::chain_spec::staging_testnet_config());
+		match new_full_base(
+			config,
+			false,
+			Some(Sealing::Manual),
+			None,
+			Default::default(),
+			|_, _| (),
+		) {
+			Err(e) => assert!(e
+				.to_string()
+				.contains("`--sealing` is only allowed on a development or local chain")),
+			Ok(_) => panic!("Expected `--sealing` to be rejected on a live chain"),
+		}
+	}
+
 	/*
 	#[test]
 	// It is "ignored", but the node-cli ignored tests are running on the CI.
@@ -589,6 +849,7 @@ mod tests {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
 					new_full_base(
 						config,
+						false,
 						|block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						 babe_link: &sc_consensus_babe::BabeLink<Block>| {
 							setup_handles = Some((block_import.clone(), babe_link.clone()));
@@ -762,7 +1023,7 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
-					new_full_base(config, |_, _| ())?;
+					new_full_base(config, false, None, None, Default::default(), |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(
 					task_manager,
 					client,
@@ -0,0 +1,76 @@
+//! Command line options of the indexer.
+
+use crate::{db::IndexerDb, worker::Indexer};
+use node_runtime::{opaque::Block, BlockNumber};
+use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
+use sc_client_api::{backend::Backend, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use std::sync::Arc;
+use structopt::StructOpt;
+
+/// Options of the indexer service.
+#[derive(Debug, Clone, StructOpt)]
+pub struct IndexerParams {
+	/// Index the marketplace history of the best chain and serve it over the
+	/// `nftmart_history*` RPC methods.
+	///
+	/// The indexer reads the state of every block it indexes, so the node must keep all of it
+	/// with `--pruning archive`.
+	#[structopt(long = "nftmart-indexer")]
+	pub nftmart_indexer: bool,
+}
+
+/// The `nftmart-reindex` command used to rebuild the marketplace history.
+#[derive(Debug, StructOpt)]
+pub struct ReindexCmd {
+	/// Keep the history up to this block and index the later blocks again.
+	///
+	/// The whole history is rebuilt by default. Otherwise the block must not be before the
+	/// finalized one, since the journals of the finalized blocks are pruned.
+	#[structopt(long, value_name = "BLOCK", default_value = "0")]
+	pub from: BlockNumber,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ReindexCmd {
+	/// Revert the history after `from` and index up to the best block.
+	pub fn run<C, BE>(&self, client: Arc<C>, db: Arc<IndexerDb>) -> Result<()>
+	where
+		BE: Backend<Block> + 'static,
+		C: HeaderBackend<Block> + StorageProvider<Block, BE> + BlockchainEvents<Block>,
+		C: Send + Sync + 'static,
+	{
+		if self.from == 0 {
+			db.clear().map_err(|e| e.to_string())?;
+		} else {
+			db.revert_to(self.from).map_err(|e| {
+				format!(
+					"Failed to keep the history up to block #{}: {}, use --from 0",
+					self.from, e
+				)
+			})?;
+		}
+		Indexer::new(client.clone(), db.clone()).sync()?;
+		if let Some(tip) = db.status().map_err(|e| e.to_string())? {
+			println!("Indexed up to block #{} ({:?})", tip.block_number, tip.block_hash);
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ReindexCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -0,0 +1,445 @@
+//! The embedded store of the indexer.
+//!
+//! Every indexed block writes a journal with the previous values of the keys it touched, so the
+//! indexed blocks can be reverted one by one when they leave the best chain. The journals of the
+//! finalized blocks are pruned.
+
+use crate::types::*;
+use codec::{Decode, Encode};
+use kvdb::{DBTransaction, KeyValueDB};
+use nftmart_traits::{ClassId, GlobalId, TokenId};
+use node_runtime::{AccountId, BlockNumber, Hash};
+use std::{collections::HashMap, io, path::Path, sync::Arc};
+
+pub const NUM_COLUMNS: u32 = 10;
+
+mod columns {
+	pub const META: u32 = 0;
+	pub const JOURNAL: u32 = 1;
+	pub const TRADES: u32 = 2;
+	pub const TOKEN_TRADES: u32 = 3;
+	pub const ACCOUNT_TRADES: u32 = 4;
+	pub const LISTINGS: u32 = 5;
+	pub const ACCOUNT_LISTINGS: u32 = 6;
+	pub const OWNERSHIP: u32 = 7;
+	pub const ACCOUNT_OWNERSHIP: u32 = 8;
+	pub const PRICES: u32 = 9;
+}
+
+const TIP_KEY: &[u8] = b"tip";
+/// The last block whose journal is pruned.
+const PRUNED_KEY: &[u8] = b"pruned";
+/// The maximum number of journals deleted in a transaction.
+const PRUNE_BATCH: BlockNumber = 1024;
+
+/// The previous values of the keys written by a block.
+#[derive(Encode, Decode)]
+struct Journal {
+	parent_hash: Hash,
+	undo: Vec<(u32, Vec<u8>, Option<Vec<u8>>)>,
+}
+
+fn token_key(class_id: ClassId, token_id: TokenId) -> Vec<u8> {
+	let mut key = class_id.to_be_bytes().to_vec();
+	key.extend_from_slice(&token_id.to_be_bytes());
+	key
+}
+
+fn event_key(block_number: BlockNumber, event_index: u32) -> Vec<u8> {
+	let mut key = block_number.to_be_bytes().to_vec();
+	key.extend_from_slice(&event_index.to_be_bytes());
+	key
+}
+
+fn concat(prefix: &[u8], suffix: &[u8]) -> Vec<u8> {
+	let mut key = prefix.to_vec();
+	key.extend_from_slice(suffix);
+	key
+}
+
+fn decode<T: Decode>(value: &[u8]) -> io::Result<T> {
+	T::decode(&mut &value[..])
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Marketplace history of the indexed blocks.
+pub struct IndexerDb {
+	db: Arc<dyn KeyValueDB>,
+}
+
+impl IndexerDb {
+	pub fn new(db: Arc<dyn KeyValueDB>) -> Self {
+		Self { db }
+	}
+
+	/// Open or create a RocksDB database at `path`.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		std::fs::create_dir_all(path)?;
+		let config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+		let db = kvdb_rocksdb::Database::open(&config, path)?;
+		Ok(Self::new(Arc::new(db)))
+	}
+
+	/// The last indexed block, `None` if nothing has been indexed.
+	pub fn status(&self) -> io::Result<Option<IndexerStatus>> {
+		self.db.get(columns::META, TIP_KEY)?.map(|v| decode(&v)).transpose()
+	}
+
+	/// The last block whose journal is pruned, so it can't be reverted.
+	pub fn pruned(&self) -> io::Result<BlockNumber> {
+		Ok(self
+			.db
+			.get(columns::META, PRUNED_KEY)?
+			.map(|v| decode(&v))
+			.transpose()?
+			.unwrap_or(0))
+	}
+
+	/// Delete the journals of the blocks up to `block_number`.
+	pub fn prune_journals(&self, block_number: BlockNumber) -> io::Result<()> {
+		let mut pruned = self.pruned()?;
+		while pruned < block_number {
+			let end = block_number.min(pruned.saturating_add(PRUNE_BATCH));
+			let mut tx = DBTransaction::new();
+			for n in pruned + 1..=end {
+				tx.delete(columns::JOURNAL, &n.to_be_bytes());
+			}
+			tx.put_vec(columns::META, PRUNED_KEY, end.encode());
+			self.db.write(tx)?;
+			pruned = end;
+		}
+		Ok(())
+	}
+
+	/// Delete all the records.
+	pub fn clear(&self) -> io::Result<()> {
+		let mut tx = DBTransaction::new();
+		for col in 0..NUM_COLUMNS {
+			tx.delete_prefix(col, &[]);
+		}
+		self.db.write(tx)
+	}
+
+	/// Start writing the records of the block next to the tip.
+	pub fn writer(&self) -> BlockWriter<'_> {
+		BlockWriter { db: self, overlay: HashMap::new(), undo: Vec::new() }
+	}
+
+	/// Revert the last indexed block, returning the new tip.
+	pub fn revert_tip(&self) -> io::Result<Option<IndexerStatus>> {
+		let tip = match self.status()? {
+			Some(tip) => tip,
+			None => return Ok(None),
+		};
+		if tip.block_number == 0 {
+			self.db.write({
+				let mut tx = DBTransaction::new();
+				tx.delete(columns::META, TIP_KEY);
+				tx
+			})?;
+			return Ok(None)
+		}
+		if tip.block_number <= self.pruned()? {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("block #{} is finalized and its journal is pruned", tip.block_number),
+			))
+		}
+		let number_key = tip.block_number.to_be_bytes();
+		let journal: Journal = match self.db.get(columns::JOURNAL, &number_key)? {
+			Some(v) => decode(&v)?,
+			None =>
+				return Err(io::Error::new(
+					io::ErrorKind::NotFound,
+					format!("journal of block #{} not found", tip.block_number),
+				)),
+		};
+
+		let mut tx = DBTransaction::new();
+		for (col, key, previous) in journal.undo.into_iter().rev() {
+			match previous {
+				Some(value) => tx.put_vec(col, &key, value),
+				None => tx.delete(col, &key),
+			}
+		}
+		tx.delete(columns::JOURNAL, &number_key);
+		let new_tip =
+			IndexerStatus { block_number: tip.block_number - 1, block_hash: journal.parent_hash };
+		tx.put_vec(columns::META, TIP_KEY, new_tip.encode());
+		self.db.write(tx)?;
+		Ok(Some(new_tip))
+	}
+
+	/// Revert the indexed blocks after `block_number`, which fails if any of them is finalized.
+	pub fn revert_to(&self, block_number: BlockNumber) -> io::Result<Option<IndexerStatus>> {
+		let mut tip = self.status()?;
+		while matches!(tip, Some(t) if t.block_number > block_number) {
+			tip = self.revert_tip()?;
+		}
+		Ok(tip)
+	}
+
+	fn latest<T: Decode>(
+		&self,
+		col: u32,
+		prefix: &[u8],
+		limit: usize,
+		indirect: Option<u32>,
+	) -> io::Result<Vec<T>> {
+		let entries: Vec<_> = self.db.iter_with_prefix(col, prefix).collect();
+		let mut records = Vec::with_capacity(limit.min(entries.len()));
+		for (_key, value) in entries.into_iter().rev().take(limit) {
+			let value = match indirect {
+				Some(primary) => match self.db.get(primary, &value)? {
+					Some(value) => value,
+					None => continue,
+				},
+				None => value.to_vec(),
+			};
+			records.push(decode(&value)?);
+		}
+		Ok(records)
+	}
+
+	/// The latest trades of a token, newest first.
+	pub fn token_trades(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		limit: usize,
+	) -> io::Result<Vec<TradeRecord>> {
+		let prefix = token_key(class_id, token_id);
+		self.latest(columns::TOKEN_TRADES, &prefix, limit, Some(columns::TRADES))
+	}
+
+	/// The latest trades an account bought or sold in, newest first.
+	pub fn account_trades(&self, who: &AccountId, limit: usize) -> io::Result<Vec<TradeRecord>> {
+		self.latest(columns::ACCOUNT_TRADES, who.as_ref(), limit, Some(columns::TRADES))
+	}
+
+	/// A listing by its id.
+	pub fn listing(&self, id: GlobalId) -> io::Result<Option<ListingRecord>> {
+		self.db
+			.get(columns::LISTINGS, &id.to_be_bytes())?
+			.map(|v| decode(&v))
+			.transpose()
+	}
+
+	/// The latest listings of an account, newest first.
+	pub fn account_listings(
+		&self,
+		who: &AccountId,
+		limit: usize,
+	) -> io::Result<Vec<ListingRecord>> {
+		self.latest(columns::ACCOUNT_LISTINGS, who.as_ref(), limit, Some(columns::LISTINGS))
+	}
+
+	/// The latest ownership changes of a token, newest first.
+	pub fn token_ownership(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		limit: usize,
+	) -> io::Result<Vec<OwnershipRecord>> {
+		self.latest(columns::OWNERSHIP, &token_key(class_id, token_id), limit, None)
+	}
+
+	/// The latest ownership changes an account took part in, newest first.
+	pub fn account_ownership(
+		&self,
+		who: &AccountId,
+		limit: usize,
+	) -> io::Result<Vec<OwnershipRecord>> {
+		self.latest(columns::ACCOUNT_OWNERSHIP, who.as_ref(), limit, Some(columns::OWNERSHIP))
+	}
+
+	/// The latest sale prices of a token, newest first.
+	pub fn token_prices(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		limit: usize,
+	) -> io::Result<Vec<PricePoint>> {
+		self.latest(columns::PRICES, &token_key(class_id, token_id), limit, None)
+	}
+}
+
+/// Collects the records of a block and writes them with its journal in one transaction.
+pub struct BlockWriter<'a> {
+	db: &'a IndexerDb,
+	overlay: HashMap<(u32, Vec<u8>), Vec<u8>>,
+	undo: Vec<(u32, Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl<'a> BlockWriter<'a> {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+		match self.overlay.get(&(col, key.to_vec())) {
+			Some(value) => Ok(Some(value.clone())),
+			None => self.db.db.get(col, key),
+		}
+	}
+
+	fn put(&mut self, col: u32, key: Vec<u8>, value: Vec<u8>) -> io::Result<()> {
+		if !self.overlay.contains_key(&(col, key.clone())) {
+			let previous = self.db.db.get(col, &key)?;
+			self.undo.push((col, key.clone(), previous));
+		}
+		self.overlay.insert((col, key), value);
+		Ok(())
+	}
+
+	pub fn listing(&self, id: GlobalId) -> io::Result<Option<ListingRecord>> {
+		self.get(columns::LISTINGS, &id.to_be_bytes())?.map(|v| decode(&v)).transpose()
+	}
+
+	pub fn put_listing(&mut self, listing: ListingRecord) -> io::Result<()> {
+		let key = listing.id.to_be_bytes().to_vec();
+		self.put(columns::ACCOUNT_LISTINGS, concat(listing.owner.as_ref(), &key), key.clone())?;
+		self.put(columns::LISTINGS, key, listing.encode())
+	}
+
+	pub fn put_trade(&mut self, trade: TradeRecord) -> io::Result<()> {
+		let key = event_key(trade.block_number, trade.event_index);
+		for &(class_id, token_id, quantity) in &trade.items {
+			let token = token_key(class_id, token_id);
+			self.put(columns::TOKEN_TRADES, concat(&token, &key), key.clone())?;
+			if let (Some(currency_id), Some(price)) = (trade.currency_id, trade.price) {
+				let point = PricePoint {
+					block_number: trade.block_number,
+					kind: trade.kind,
+					currency_id,
+					price,
+					quantity,
+					bundle_size: trade.items.len() as u32,
+				};
+				self.put(columns::PRICES, concat(&token, &key), point.encode())?;
+			}
+		}
+		self.put(columns::ACCOUNT_TRADES, concat(trade.seller.as_ref(), &key), key.clone())?;
+		self.put(columns::ACCOUNT_TRADES, concat(trade.buyer.as_ref(), &key), key.clone())?;
+		self.put(columns::TRADES, key, trade.encode())
+	}
+
+	pub fn put_ownership(&mut self, record: OwnershipRecord) -> io::Result<()> {
+		let key = concat(
+			&token_key(record.class_id, record.token_id),
+			&event_key(record.block_number, record.event_index),
+		);
+		let event = event_key(record.block_number, record.event_index);
+		for who in record.from.iter().chain(record.to.iter()) {
+			self.put(columns::ACCOUNT_OWNERSHIP, concat(who.as_ref(), &event), key.clone())?;
+		}
+		self.put(columns::OWNERSHIP, key, record.encode())
+	}
+
+	/// Write the records and make `block_number` the new tip.
+	pub fn commit(
+		mut self,
+		block_number: BlockNumber,
+		block_hash: Hash,
+		parent_hash: Hash,
+	) -> io::Result<()> {
+		let tip = IndexerStatus { block_number, block_hash };
+		self.put(columns::META, TIP_KEY.to_vec(), tip.encode())?;
+
+		let mut tx = DBTransaction::new();
+		for ((col, key), value) in self.overlay {
+			tx.put_vec(col, &key, value);
+		}
+		let journal = Journal { parent_hash, undo: self.undo };
+		tx.put_vec(columns::JOURNAL, &block_number.to_be_bytes(), journal.encode());
+		self.db.db.write(tx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::AccountId32;
+
+	const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+	const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+
+	fn listing(status: ListingStatus, updated_at: BlockNumber) -> ListingRecord {
+		ListingRecord {
+			kind: ListingKind::Order,
+			id: 7,
+			owner: ALICE,
+			currency_id: 0,
+			price: 100,
+			items: vec![(1, 2, 3)],
+			created_at: 1,
+			updated_at,
+			status,
+		}
+	}
+
+	#[test]
+	fn revert_should_work() {
+		let db = IndexerDb::new(Arc::new(kvdb_memorydb::create(NUM_COLUMNS)));
+
+		let mut writer = db.writer();
+		writer.put_listing(listing(ListingStatus::Open, 1)).unwrap();
+		writer.commit(1, Hash::repeat_byte(1), Hash::repeat_byte(0)).unwrap();
+
+		let mut writer = db.writer();
+		writer.put_listing(listing(ListingStatus::Filled, 2)).unwrap();
+		writer
+			.put_trade(TradeRecord {
+				block_number: 2,
+				event_index: 0,
+				kind: TradeKind::Order,
+				id: Some(7),
+				seller: ALICE,
+				buyer: BOB,
+				currency_id: Some(0),
+				price: Some(100),
+				items: vec![(1, 2, 3)],
+			})
+			.unwrap();
+		writer.commit(2, Hash::repeat_byte(2), Hash::repeat_byte(1)).unwrap();
+
+		assert_eq!(db.listing(7).unwrap(), Some(listing(ListingStatus::Filled, 2)));
+		assert_eq!(db.account_trades(&BOB, 10).unwrap().len(), 1);
+		assert_eq!(db.token_prices(1, 2, 10).unwrap()[0].price, 100);
+
+		let tip = db.revert_tip().unwrap();
+		assert_eq!(tip, Some(IndexerStatus { block_number: 1, block_hash: Hash::repeat_byte(1) }));
+		assert_eq!(db.listing(7).unwrap(), Some(listing(ListingStatus::Open, 1)));
+		assert_eq!(db.account_trades(&BOB, 10).unwrap(), vec![]);
+		assert_eq!(db.token_prices(1, 2, 10).unwrap(), vec![]);
+
+		assert_eq!(
+			db.revert_to(0).unwrap(),
+			Some(IndexerStatus { block_number: 0, block_hash: Hash::repeat_byte(0) })
+		);
+		assert_eq!(db.listing(7).unwrap(), None);
+		assert_eq!(db.account_listings(&ALICE, 10).unwrap(), vec![]);
+	}
+
+	#[test]
+	fn pruned_journals_should_not_be_reverted() {
+		let db = IndexerDb::new(Arc::new(kvdb_memorydb::create(NUM_COLUMNS)));
+		for n in 1..=3u8 {
+			let mut writer = db.writer();
+			writer.put_listing(listing(ListingStatus::Open, n as BlockNumber)).unwrap();
+			writer
+				.commit(n as BlockNumber, Hash::repeat_byte(n), Hash::repeat_byte(n - 1))
+				.unwrap();
+		}
+
+		db.prune_journals(2).unwrap();
+		assert_eq!(db.pruned().unwrap(), 2);
+		assert!(db.db.get(columns::JOURNAL, &2u32.to_be_bytes()).unwrap().is_none());
+		assert!(db.db.get(columns::JOURNAL, &3u32.to_be_bytes()).unwrap().is_some());
+
+		assert_eq!(db.revert_to(2).unwrap().map(|t| t.block_number), Some(2));
+		assert!(db.revert_tip().is_err());
+		assert_eq!(db.listing(7).unwrap(), Some(listing(ListingStatus::Open, 2)));
+
+		db.clear().unwrap();
+		assert_eq!(db.status().unwrap(), None);
+		assert_eq!(db.pruned().unwrap(), 0);
+		assert_eq!(db.listing(7).unwrap(), None);
+	}
+}
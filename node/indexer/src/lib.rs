@@ -0,0 +1,24 @@
+//! An optional service of the NFTMart node which indexes the marketplace history.
+//!
+//! The indexer follows the best chain, decodes the events of `Nftmart`, `NftmartOrder` and
+//! `NftmartAuction` into trades, listings, ownership changes and sale prices, and keeps them in
+//! an embedded database served over the `nftmart_history*` RPC methods. The blocks which leave
+//! the best chain are reverted.
+//!
+//! It reads the state of every indexed block, so it requires an archive node: the service refuses
+//! to start the indexer unless the node runs with `--pruning archive`.
+
+mod cli;
+mod db;
+mod rpc;
+mod types;
+mod worker;
+
+pub use cli::{IndexerParams, ReindexCmd};
+pub use db::IndexerDb;
+pub use rpc::{NftmartHistory, NftmartHistoryApi};
+pub use types::*;
+pub use worker::{
+	block_events, decode_events, events_key, read_storage, trade_price, Chain, ClientChain,
+	EventRecord, Indexer,
+};
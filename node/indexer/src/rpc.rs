@@ -0,0 +1,137 @@
+//! The `nftmart_history*` RPC methods.
+
+use crate::{db::IndexerDb, types::*};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use nftmart_traits::{ClassId, GlobalId, TokenId};
+use node_runtime::AccountId;
+use std::{io, sync::Arc};
+
+/// The number of records returned if `limit` is omitted.
+pub const DEFAULT_LIMIT: u32 = 100;
+/// The maximum number of records returned by a call.
+pub const MAX_LIMIT: u32 = 1000;
+
+#[rpc]
+pub trait NftmartHistoryApi {
+	/// The last block indexed.
+	#[rpc(name = "nftmart_historyStatus")]
+	fn status(&self) -> Result<Option<IndexerStatus>>;
+
+	/// The latest trades of a token, newest first.
+	#[rpc(name = "nftmart_historyTrades")]
+	fn trades(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		limit: Option<u32>,
+	) -> Result<Vec<TradeRecord>>;
+
+	/// The latest trades an account bought or sold in, newest first.
+	#[rpc(name = "nftmart_historyAccountTrades")]
+	fn account_trades(&self, who: AccountId, limit: Option<u32>) -> Result<Vec<TradeRecord>>;
+
+	/// An order, offer, auction or swap by its id.
+	#[rpc(name = "nftmart_historyListing")]
+	fn listing(&self, id: GlobalId) -> Result<Option<ListingRecord>>;
+
+	/// The latest listings created by an account, newest first.
+	#[rpc(name = "nftmart_historyAccountListings")]
+	fn account_listings(&self, who: AccountId, limit: Option<u32>) -> Result<Vec<ListingRecord>>;
+
+	/// The latest mints, transfers and burns of a token, newest first.
+	#[rpc(name = "nftmart_historyOwnership")]
+	fn ownership(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		limit: Option<u32>,
+	) -> Result<Vec<OwnershipRecord>>;
+
+	/// The latest mints, transfers and burns an account took part in, newest first.
+	#[rpc(name = "nftmart_historyAccountOwnership")]
+	fn account_ownership(&self, who: AccountId, limit: Option<u32>)
+		-> Result<Vec<OwnershipRecord>>;
+
+	/// The latest sale prices of a token, newest first.
+	#[rpc(name = "nftmart_historyPrices")]
+	fn prices(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		limit: Option<u32>,
+	) -> Result<Vec<PricePoint>>;
+}
+
+/// A struct that implements the [`NftmartHistoryApi`].
+pub struct NftmartHistory {
+	db: Arc<IndexerDb>,
+}
+
+impl NftmartHistory {
+	/// Create new `NftmartHistory` with the given reference to the indexer database.
+	pub fn new(db: Arc<IndexerDb>) -> Self {
+		Self { db }
+	}
+}
+
+fn limit(limit: Option<u32>) -> usize {
+	limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+fn db_error(e: io::Error) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to read the indexer database.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl NftmartHistoryApi for NftmartHistory {
+	fn status(&self) -> Result<Option<IndexerStatus>> {
+		self.db.status().map_err(db_error)
+	}
+
+	fn trades(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		n: Option<u32>,
+	) -> Result<Vec<TradeRecord>> {
+		self.db.token_trades(class_id, token_id, limit(n)).map_err(db_error)
+	}
+
+	fn account_trades(&self, who: AccountId, n: Option<u32>) -> Result<Vec<TradeRecord>> {
+		self.db.account_trades(&who, limit(n)).map_err(db_error)
+	}
+
+	fn listing(&self, id: GlobalId) -> Result<Option<ListingRecord>> {
+		self.db.listing(id).map_err(db_error)
+	}
+
+	fn account_listings(&self, who: AccountId, n: Option<u32>) -> Result<Vec<ListingRecord>> {
+		self.db.account_listings(&who, limit(n)).map_err(db_error)
+	}
+
+	fn ownership(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		n: Option<u32>,
+	) -> Result<Vec<OwnershipRecord>> {
+		self.db.token_ownership(class_id, token_id, limit(n)).map_err(db_error)
+	}
+
+	fn account_ownership(&self, who: AccountId, n: Option<u32>) -> Result<Vec<OwnershipRecord>> {
+		self.db.account_ownership(&who, limit(n)).map_err(db_error)
+	}
+
+	fn prices(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		n: Option<u32>,
+	) -> Result<Vec<PricePoint>> {
+		self.db.token_prices(class_id, token_id, limit(n)).map_err(db_error)
+	}
+}
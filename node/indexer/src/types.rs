@@ -0,0 +1,130 @@
+//! Records kept by the indexer and served over the `nftmart_history*` RPC methods.
+
+use codec::{Decode, Encode};
+use nftmart_traits::{ClassId, CurrencyId, GlobalId, TokenId};
+use node_runtime::{AccountId, Balance, BlockNumber, Hash};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// `(class_id, token_id, quantity)`
+pub type Item = (ClassId, TokenId, TokenId);
+
+/// Balances are serialized as strings, the same as `nftmart_*` RPC methods do.
+fn balance_to_string<S: Serializer>(balance: &Balance, s: S) -> Result<S::Ok, S::Error> {
+	s.serialize_str(&balance.to_string())
+}
+
+fn maybe_balance_to_string<S: Serializer>(
+	balance: &Option<Balance>,
+	s: S,
+) -> Result<S::Ok, S::Error> {
+	match balance {
+		Some(balance) => balance_to_string(balance, s),
+		None => s.serialize_none(),
+	}
+}
+
+/// The way a trade was made.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TradeKind {
+	Order,
+	Offer,
+	BritishAuction,
+	DutchAuction,
+	Swap,
+	Voucher,
+}
+
+/// A finished sale of NFTs.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeRecord {
+	pub block_number: BlockNumber,
+	pub event_index: u32,
+	pub kind: TradeKind,
+	/// The id of the order, offer, auction or swap, `None` for vouchers.
+	pub id: Option<GlobalId>,
+	pub seller: AccountId,
+	pub buyer: AccountId,
+	pub currency_id: Option<CurrencyId>,
+	/// The total price of `items`, `None` if it is unknown to the indexer.
+	#[serde(serialize_with = "maybe_balance_to_string")]
+	pub price: Option<Balance>,
+	/// NFTs moved from the seller to the buyer.
+	pub items: Vec<Item>,
+}
+
+/// The kind of a listing.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ListingKind {
+	Order,
+	Offer,
+	BritishAuction,
+	DutchAuction,
+	Swap,
+}
+
+/// The state of a listing.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ListingStatus {
+	Open,
+	Filled,
+	Cancelled,
+	Reaped,
+}
+
+/// An order, offer, auction or swap and what happened to it.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListingRecord {
+	pub kind: ListingKind,
+	pub id: GlobalId,
+	pub owner: AccountId,
+	pub currency_id: CurrencyId,
+	/// The price when the listing was created or last updated. The initial price of auctions.
+	#[serde(serialize_with = "balance_to_string")]
+	pub price: Balance,
+	pub items: Vec<Item>,
+	pub created_at: BlockNumber,
+	pub updated_at: BlockNumber,
+	pub status: ListingStatus,
+}
+
+/// A change of ownership. `from` is `None` for minting and `to` is `None` for burning.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipRecord {
+	pub block_number: BlockNumber,
+	pub event_index: u32,
+	pub class_id: ClassId,
+	pub token_id: TokenId,
+	pub from: Option<AccountId>,
+	pub to: Option<AccountId>,
+	pub quantity: TokenId,
+}
+
+/// A sale price of a token.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricePoint {
+	pub block_number: BlockNumber,
+	pub kind: TradeKind,
+	pub currency_id: CurrencyId,
+	/// The total price of the trade.
+	#[serde(serialize_with = "balance_to_string")]
+	pub price: Balance,
+	/// The quantity of this token in the trade.
+	pub quantity: TokenId,
+	/// The number of different tokens sold together.
+	pub bundle_size: u32,
+}
+
+/// The last block the indexer has processed.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexerStatus {
+	pub block_number: BlockNumber,
+	pub block_hash: Hash,
+}
@@ -0,0 +1,832 @@
+//! Follows the best chain and turns the marketplace events of its blocks into records.
+
+use crate::{
+	db::{BlockWriter, IndexerDb},
+	types::*,
+};
+use codec::{Compact, Decode};
+use futures::StreamExt;
+use nftmart_traits::{CurrencyId, GlobalId, OrderItem};
+use node_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Event, Hash, Runtime};
+use sc_client_api::{backend::Backend, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, twox_128};
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+const LOG_TARGET: &str = "nftmart-indexer";
+
+pub type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// The storage key of `System::Events`.
+pub fn events_key() -> Vec<u8> {
+	let mut key = twox_128(b"System").to_vec();
+	key.extend_from_slice(&twox_128(b"Events"));
+	key
+}
+
+/// Read and decode a storage value at block `at`.
+pub fn read_storage<C, BE, T>(client: &C, at: Hash, key: Vec<u8>) -> Result<Option<T>, String>
+where
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+	T: Decode,
+{
+	client
+		.storage(&BlockId::Hash(at), &StorageKey(key))
+		.map_err(|e| e.to_string())?
+		.map(|data| T::decode(&mut &data.0[..]).map_err(|e| e.to_string()))
+		.transpose()
+}
+
+/// Decode the events of block `at` one by one.
+///
+/// A record which can't be decoded, e.g. an event added by a runtime upgrade this node doesn't
+/// know yet, is logged and skipped with the records after it, which can't be located any more.
+pub fn decode_events(at: Hash, data: &[u8]) -> Vec<EventRecord> {
+	let input = &mut &data[..];
+	let len = match Compact::<u32>::decode(input) {
+		Ok(len) => len.0,
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Skipping the events of block {:?}: {}", at, e);
+			return Vec::new()
+		},
+	};
+	let mut events = Vec::new();
+	for index in 0..len {
+		match EventRecord::decode(input) {
+			Ok(record) => events.push(record),
+			Err(e) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Skipping {} of the {} events of block {:?} from the undecodable event #{}: {}",
+					len - index,
+					len,
+					at,
+					index,
+					e,
+				);
+				break
+			},
+		}
+	}
+	events
+}
+
+/// The events deposited in block `at`.
+pub fn block_events<C, BE>(client: &C, at: Hash) -> Result<Vec<EventRecord>, String>
+where
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	let data = client
+		.storage(&BlockId::Hash(at), &StorageKey(events_key()))
+		.map_err(|e| e.to_string())?;
+	Ok(data.map(|data| decode_events(at, &data.0)).unwrap_or_default())
+}
+
+fn to_items(items: &[OrderItem<nftmart_traits::ClassId, nftmart_traits::TokenId>]) -> Vec<Item> {
+	items.iter().map(|i| (i.class_id, i.token_id, i.quantity)).collect()
+}
+
+/// The blocks and the state the indexer reads.
+pub trait Chain {
+	/// The best and the finalized block numbers.
+	fn best_and_finalized(&self) -> (BlockNumber, BlockNumber);
+	/// The hash of the block at `number` in the best chain.
+	fn hash(&self, number: BlockNumber) -> Result<Option<Hash>, String>;
+	/// The parent hash of block `hash`.
+	fn parent_hash(&self, hash: Hash) -> Result<Option<Hash>, String>;
+	/// The raw storage value of `key` at block `at`.
+	fn storage(&self, at: Hash, key: &[u8]) -> Result<Option<Vec<u8>>, String>;
+
+	/// Read and decode a storage value at block `at`.
+	fn decode_storage<T: Decode>(&self, at: Hash, key: &[u8]) -> Result<Option<T>, String> {
+		self.storage(at, key)?
+			.map(|data| T::decode(&mut &data[..]).map_err(|e| e.to_string()))
+			.transpose()
+	}
+
+	/// The events deposited in block `at`.
+	fn events(&self, at: Hash) -> Result<Vec<EventRecord>, String> {
+		Ok(self
+			.storage(at, &events_key())?
+			.map(|data| decode_events(at, &data))
+			.unwrap_or_default())
+	}
+}
+
+/// The currency and the price of a trade of `kind` in block `number`, read from the state of its
+/// parent `parent_hash` where the listing `id` of `owner` is still open.
+///
+/// A british auction is sold at the hammer price when `hammered`, or at the last bid when
+/// redeemed after the deadline. A dutch auction is sold at the last bid, or bought immediately at
+/// the current price. The price of a voucher is unknown.
+pub fn trade_price<Ch: Chain>(
+	chain: &Ch,
+	parent_hash: Hash,
+	number: BlockNumber,
+	kind: TradeKind,
+	owner: &AccountId,
+	id: GlobalId,
+	hammered: bool,
+) -> Result<Option<(CurrencyId, Balance)>, String> {
+	use nftmart_auction::{BritishAuctionBids, BritishAuctions, DutchAuctionBids, DutchAuctions};
+	use nftmart_order::{Offers, Orders, Swaps};
+	let at = parent_hash;
+	Ok(match kind {
+		TradeKind::Order => chain
+			.decode_storage::<nftmart_order::OrderOf<Runtime>>(
+				at,
+				&Orders::<Runtime>::hashed_key_for(owner, id),
+			)?
+			.map(|o| (o.currency_id, o.price)),
+		TradeKind::Offer => chain
+			.decode_storage::<nftmart_order::OfferOf<Runtime>>(
+				at,
+				&Offers::<Runtime>::hashed_key_for(owner, id),
+			)?
+			.map(|o| (o.currency_id, o.price)),
+		TradeKind::Swap => chain
+			.decode_storage::<nftmart_order::SwapOf<Runtime>>(
+				at,
+				&Swaps::<Runtime>::hashed_key_for(owner, id),
+			)?
+			.map(|s| (s.currency_id, s.price)),
+		TradeKind::BritishAuction => {
+			let auction: Option<nftmart_auction::BritishAuctionOf<Runtime>> =
+				chain.decode_storage(at, &BritishAuctions::<Runtime>::hashed_key_for(owner, id))?;
+			let bid: Option<nftmart_auction::BritishAuctionBidOf<Runtime>> =
+				chain.decode_storage(at, &BritishAuctionBids::<Runtime>::hashed_key_for(id))?;
+			match (auction, bid) {
+				(Some(a), _) if hammered => Some((a.currency_id, a.hammer_price)),
+				(Some(a), Some(b)) => Some((a.currency_id, b.last_bid_price)),
+				_ => None,
+			}
+		},
+		TradeKind::DutchAuction => {
+			let auction: Option<nftmart_auction::DutchAuctionOf<Runtime>> =
+				chain.decode_storage(at, &DutchAuctions::<Runtime>::hashed_key_for(owner, id))?;
+			let bid: Option<nftmart_auction::DutchAuctionBidOf<Runtime>> =
+				chain.decode_storage(at, &DutchAuctionBids::<Runtime>::hashed_key_for(id))?;
+			auction.map(|a| match bid {
+				Some(b) if b.last_bid_account.is_some() => (a.currency_id, b.last_bid_price),
+				// Bought immediately at the current price.
+				_ => (
+					a.currency_id,
+					nftmart_auction::Pallet::<Runtime>::dutch_auction_price(&a, number),
+				),
+			})
+		},
+		TradeKind::Voucher => None,
+	})
+}
+
+/// The [`Chain`] of a client.
+pub struct ClientChain<C, BE> {
+	client: Arc<C>,
+	_marker: PhantomData<BE>,
+}
+
+impl<C, BE> ClientChain<C, BE> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: PhantomData }
+	}
+}
+
+impl<C, BE> Chain for ClientChain<C, BE>
+where
+	BE: Backend<Block>,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+{
+	fn best_and_finalized(&self) -> (BlockNumber, BlockNumber) {
+		let info = self.client.info();
+		(info.best_number, info.finalized_number)
+	}
+
+	fn hash(&self, number: BlockNumber) -> Result<Option<Hash>, String> {
+		self.client.hash(number).map_err(|e| e.to_string())
+	}
+
+	fn parent_hash(&self, hash: Hash) -> Result<Option<Hash>, String> {
+		let header = self.client.header(BlockId::Hash(hash)).map_err(|e| e.to_string())?;
+		Ok(header.map(|header| *header.parent_hash()))
+	}
+
+	fn storage(&self, at: Hash, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+		let data = self
+			.client
+			.storage(&BlockId::Hash(at), &StorageKey(key.to_vec()))
+			.map_err(|e| e.to_string())?;
+		Ok(data.map(|data| data.0))
+	}
+}
+
+/// Indexes the blocks of the best chain into an [`IndexerDb`].
+///
+/// The blocks which leave the best chain are reverted with their journals, and the journals of
+/// the finalized blocks are pruned since those blocks can't be reverted any more.
+pub struct Indexer<Ch> {
+	chain: Ch,
+	db: Arc<IndexerDb>,
+}
+
+impl<C, BE> Indexer<ClientChain<C, BE>>
+where
+	BE: Backend<Block> + 'static,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+{
+	pub fn new(client: Arc<C>, db: Arc<IndexerDb>) -> Self {
+		Self::with_chain(ClientChain::new(client), db)
+	}
+
+	/// Catch up with the best block whenever a block is imported.
+	pub async fn run(self) {
+		let mut notifications = self.chain.client.import_notification_stream();
+		self.sync_logged();
+		while notifications.next().await.is_some() {
+			self.sync_logged();
+		}
+	}
+
+	fn sync_logged(&self) {
+		if let Err(e) = self.sync() {
+			log::error!(target: LOG_TARGET, "Failed to index blocks: {}", e);
+		}
+	}
+}
+
+impl<Ch: Chain> Indexer<Ch> {
+	pub fn with_chain(chain: Ch, db: Arc<IndexerDb>) -> Self {
+		Self { chain, db }
+	}
+
+	/// Revert the indexed blocks which are not in the best chain any more, index the blocks up
+	/// to the best one and prune the journals of the finalized blocks.
+	pub fn sync(&self) -> Result<(), String> {
+		let tip = self.revert_retracted()?;
+		let (best, finalized) = self.chain.best_and_finalized();
+		let mut next = tip.map_or(1, |tip| tip.block_number + 1);
+		while next <= best {
+			self.index_block(next)?;
+			next += 1;
+		}
+		let indexed = self.db.status().map_err(|e| e.to_string())?.map_or(0, |t| t.block_number);
+		self.db.prune_journals(finalized.min(indexed)).map_err(|e| e.to_string())
+	}
+
+	fn revert_retracted(&self) -> Result<Option<IndexerStatus>, String> {
+		let mut tip = self.db.status().map_err(|e| e.to_string())?;
+		while let Some(IndexerStatus { block_number, block_hash }) = tip {
+			if self.chain.hash(block_number)? == Some(block_hash) {
+				break
+			}
+			log::warn!(
+				target: LOG_TARGET,
+				"Reverting block #{} ({:?}) which is not in the best chain",
+				block_number,
+				block_hash,
+			);
+			tip = self.db.revert_tip().map_err(|e| e.to_string())?;
+		}
+		Ok(tip)
+	}
+
+	fn storage<T: Decode>(&self, at: Hash, key: Vec<u8>) -> Result<Option<T>, String> {
+		self.chain.decode_storage(at, &key)
+	}
+
+	/// Index the block at `number` in the best chain, which must be next to the tip.
+	pub fn index_block(&self, number: BlockNumber) -> Result<(), String> {
+		let hash =
+			self.chain.hash(number)?.ok_or_else(|| format!("block #{} not found", number))?;
+		let parent_hash = self
+			.chain
+			.parent_hash(hash)?
+			.ok_or_else(|| format!("header of block #{} not found", number))?;
+		let events = self.chain.events(hash)?;
+
+		let mut block = BlockIndexer {
+			indexer: self,
+			number,
+			hash,
+			parent_hash,
+			writer: self.db.writer(),
+			transfers: HashMap::new(),
+		};
+		for (index, record) in events.into_iter().enumerate() {
+			block.handle_event(index as u32, record).map_err(|e| {
+				format!("failed to index event #{} of block #{}: {}", index, number, e)
+			})?;
+		}
+		block.writer.commit(number, hash, parent_hash).map_err(|e| e.to_string())?;
+		log::debug!(target: LOG_TARGET, "Indexed block #{} ({:?})", number, hash);
+		Ok(())
+	}
+}
+
+/// The state of indexing a single block.
+struct BlockIndexer<'a, Ch> {
+	indexer: &'a Indexer<Ch>,
+	number: BlockNumber,
+	hash: Hash,
+	parent_hash: Hash,
+	writer: BlockWriter<'a>,
+	/// NFT transfers of each extrinsic which are not attributed to a trade yet.
+	transfers: HashMap<Option<u32>, Vec<(AccountId, AccountId, Item)>>,
+}
+
+impl<'a, Ch: Chain> BlockIndexer<'a, Ch> {
+	fn handle_event(&mut self, event_index: u32, record: EventRecord) -> Result<(), String> {
+		let phase = match record.phase {
+			frame_system::Phase::ApplyExtrinsic(i) => Some(i),
+			_ => None,
+		};
+		match record.event {
+			Event::Nftmart(event) => self.handle_nft_event(phase, event_index, event),
+			Event::NftmartOrder(event) => self.handle_order_event(phase, event_index, event),
+			Event::NftmartAuction(event) => self.handle_auction_event(phase, event_index, event),
+			_ => Ok(()),
+		}
+	}
+
+	fn handle_nft_event(
+		&mut self,
+		phase: Option<u32>,
+		event_index: u32,
+		event: nftmart_nft::Event<Runtime>,
+	) -> Result<(), String> {
+		use nftmart_nft::Event::*;
+		let (from, to, class_id, token_id, quantity) = match event {
+			MintedToken(_, to, class_id, token_id, quantity) =>
+				(None, Some(to), class_id, token_id, quantity),
+			TransferredToken(from, to, class_id, token_id, quantity) => {
+				self.transfers.entry(phase).or_default().push((
+					from.clone(),
+					to.clone(),
+					(class_id, token_id, quantity),
+				));
+				(Some(from), Some(to), class_id, token_id, quantity)
+			},
+			BurnedToken(owner, class_id, token_id, quantity, _) =>
+				(Some(owner), None, class_id, token_id, quantity),
+			_ => return Ok(()),
+		};
+		let record = OwnershipRecord {
+			block_number: self.number,
+			event_index,
+			class_id,
+			token_id,
+			from,
+			to,
+			quantity,
+		};
+		self.writer.put_ownership(record).map_err(|e| e.to_string())
+	}
+
+	fn handle_order_event(
+		&mut self,
+		phase: Option<u32>,
+		event_index: u32,
+		event: nftmart_order::Event<Runtime>,
+	) -> Result<(), String> {
+		use nftmart_order::{Event::*, Offers, Orders, Swaps};
+		match event {
+			CreatedOrder(who, id) | UpdatedOrder(who, id) => {
+				let order: Option<nftmart_order::OrderOf<Runtime>> =
+					self.indexer.storage(self.hash, Orders::<Runtime>::hashed_key_for(&who, id))?;
+				if let Some(order) = order {
+					let items = to_items(&order.items);
+					self.open_listing(
+						ListingKind::Order,
+						id,
+						who,
+						order.currency_id,
+						order.price,
+						items,
+					)?;
+				}
+			},
+			CreatedOffer(who, id) | UpdatedOffer(who, id) => {
+				let offer: Option<nftmart_order::OfferOf<Runtime>> =
+					self.indexer.storage(self.hash, Offers::<Runtime>::hashed_key_for(&who, id))?;
+				if let Some(offer) = offer {
+					let items = to_items(&offer.items);
+					self.open_listing(
+						ListingKind::Offer,
+						id,
+						who,
+						offer.currency_id,
+						offer.price,
+						items,
+					)?;
+				}
+			},
+			CreatedSwap(who, id) => {
+				let swap: Option<nftmart_order::SwapOf<Runtime>> =
+					self.indexer.storage(self.hash, Swaps::<Runtime>::hashed_key_for(&who, id))?;
+				if let Some(swap) = swap {
+					let items = to_items(&swap.offered_items);
+					self.open_listing(
+						ListingKind::Swap,
+						id,
+						who,
+						swap.currency_id,
+						swap.price,
+						items,
+					)?;
+				}
+			},
+			RemovedOrder(_, id) | RemovedOffer(_, id) | RemovedSwap(_, id) =>
+				self.close_listing(id, ListingStatus::Cancelled)?,
+			ReapedOrder(_, id, _) | ReapedOffer(_, id, _) =>
+				self.close_listing(id, ListingStatus::Reaped)?,
+			TakenOrder(purchaser, owner, id, ..) => {
+				let price = self.trade_price(TradeKind::Order, &owner, id, false)?;
+				self.note_trade(phase, event_index, TradeKind::Order, id, owner, purchaser, price)?;
+			},
+			TakenOffer(token_owner, offer_owner, id, ..) => {
+				let price = self.trade_price(TradeKind::Offer, &offer_owner, id, false)?;
+				self.note_trade(
+					phase,
+					event_index,
+					TradeKind::Offer,
+					id,
+					token_owner,
+					offer_owner,
+					price,
+				)?;
+			},
+			TakenSwap(taker, maker, id) => {
+				let price = self.trade_price(TradeKind::Swap, &maker, id, false)?;
+				// The taker sells the requested items to the maker.
+				self.note_trade(phase, event_index, TradeKind::Swap, id, taker, maker, price)?;
+			},
+			RedeemedVoucher(purchaser, signer, _, class_id, token_id, quantity) => {
+				let trade = TradeRecord {
+					block_number: self.number,
+					event_index,
+					kind: TradeKind::Voucher,
+					id: None,
+					seller: signer,
+					buyer: purchaser,
+					currency_id: None,
+					price: None,
+					items: vec![(class_id, token_id, quantity)],
+				};
+				self.writer.put_trade(trade).map_err(|e| e.to_string())?;
+			},
+			_ => {},
+		}
+		Ok(())
+	}
+
+	fn handle_auction_event(
+		&mut self,
+		phase: Option<u32>,
+		event_index: u32,
+		event: nftmart_auction::Event<Runtime>,
+	) -> Result<(), String> {
+		use nftmart_auction::{BritishAuctions, DutchAuctions, Event::*};
+		match event {
+			CreatedBritishAuction(who, id) => {
+				let auction: Option<nftmart_auction::BritishAuctionOf<Runtime>> = self
+					.indexer
+					.storage(self.hash, BritishAuctions::<Runtime>::hashed_key_for(&who, id))?;
+				if let Some(a) = auction {
+					let items = to_items(&a.items);
+					let kind = ListingKind::BritishAuction;
+					self.open_listing(kind, id, who, a.currency_id, a.init_price, items)?;
+				}
+			},
+			CreatedDutchAuction(who, id) => {
+				let auction: Option<nftmart_auction::DutchAuctionOf<Runtime>> = self
+					.indexer
+					.storage(self.hash, DutchAuctions::<Runtime>::hashed_key_for(&who, id))?;
+				if let Some(a) = auction {
+					let items = to_items(&a.items);
+					let kind = ListingKind::DutchAuction;
+					self.open_listing(kind, id, who, a.currency_id, a.max_price, items)?;
+				}
+			},
+			RemovedBritishAuction(_, id) | RemovedDutchAuction(_, id) =>
+				self.close_listing(id, ListingStatus::Cancelled)?,
+			HammerBritishAuction(purchaser, id, ..) =>
+				self.note_british_auction_trade(phase, event_index, id, purchaser, true)?,
+			RedeemedBritishAuction(purchaser, id, ..) =>
+				self.note_british_auction_trade(phase, event_index, id, purchaser, false)?,
+			RedeemedDutchAuction(purchaser, id, ..) => {
+				let seller = self.seller_to(phase, &purchaser);
+				let price = match &seller {
+					Some(seller) => self.trade_price(TradeKind::DutchAuction, seller, id, false)?,
+					None => None,
+				};
+				let kind = TradeKind::DutchAuction;
+				self.note_auction_trade(phase, event_index, kind, id, seller, purchaser, price)?;
+			},
+			_ => {},
+		}
+		Ok(())
+	}
+
+	/// A british auction is sold at the hammer price when hammered, or at the last bid when
+	/// redeemed after the deadline.
+	fn note_british_auction_trade(
+		&mut self,
+		phase: Option<u32>,
+		event_index: u32,
+		id: GlobalId,
+		purchaser: AccountId,
+		hammer: bool,
+	) -> Result<(), String> {
+		let seller = self.seller_to(phase, &purchaser);
+		let price = match &seller {
+			Some(seller) => self.trade_price(TradeKind::BritishAuction, seller, id, hammer)?,
+			None => None,
+		};
+		let kind = TradeKind::BritishAuction;
+		self.note_auction_trade(phase, event_index, kind, id, seller, purchaser, price)
+	}
+
+	fn trade_price(
+		&self,
+		kind: TradeKind,
+		owner: &AccountId,
+		id: GlobalId,
+		hammered: bool,
+	) -> Result<Option<(CurrencyId, Balance)>, String> {
+		trade_price(&self.indexer.chain, self.parent_hash, self.number, kind, owner, id, hammered)
+	}
+
+	/// The owner of the NFTs transferred to `buyer` in the extrinsic.
+	fn seller_to(&self, phase: Option<u32>, buyer: &AccountId) -> Option<AccountId> {
+		self.transfers
+			.get(&phase)?
+			.iter()
+			.find(|(_, to, _)| to == buyer)
+			.map(|(from, _, _)| from.clone())
+	}
+
+	fn open_listing(
+		&mut self,
+		kind: ListingKind,
+		id: GlobalId,
+		owner: AccountId,
+		currency_id: CurrencyId,
+		price: Balance,
+		items: Vec<Item>,
+	) -> Result<(), String> {
+		let created_at = match self.writer.listing(id).map_err(|e| e.to_string())? {
+			Some(listing) => listing.created_at,
+			None => self.number,
+		};
+		let listing = ListingRecord {
+			kind,
+			id,
+			owner,
+			currency_id,
+			price,
+			items,
+			created_at,
+			updated_at: self.number,
+			status: ListingStatus::Open,
+		};
+		self.writer.put_listing(listing).map_err(|e| e.to_string())
+	}
+
+	fn close_listing(&mut self, id: GlobalId, status: ListingStatus) -> Result<(), String> {
+		if let Some(mut listing) = self.writer.listing(id).map_err(|e| e.to_string())? {
+			listing.status = status;
+			listing.updated_at = self.number;
+			self.writer.put_listing(listing).map_err(|e| e.to_string())?;
+		}
+		Ok(())
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn note_auction_trade(
+		&mut self,
+		phase: Option<u32>,
+		event_index: u32,
+		kind: TradeKind,
+		id: GlobalId,
+		seller: Option<AccountId>,
+		buyer: AccountId,
+		price: Option<(CurrencyId, Balance)>,
+	) -> Result<(), String> {
+		match seller {
+			Some(seller) => self.note_trade(phase, event_index, kind, id, seller, buyer, price),
+			None => {
+				log::warn!(
+					target: LOG_TARGET,
+					"No NFT was transferred by auction #{} in block #{}",
+					id,
+					self.number,
+				);
+				self.close_listing(id, ListingStatus::Filled)
+			},
+		}
+	}
+
+	/// Record a trade of the NFTs transferred from `seller` to `buyer` in the extrinsic.
+	#[allow(clippy::too_many_arguments)]
+	fn note_trade(
+		&mut self,
+		phase: Option<u32>,
+		event_index: u32,
+		kind: TradeKind,
+		id: GlobalId,
+		seller: AccountId,
+		buyer: AccountId,
+		price: Option<(CurrencyId, Balance)>,
+	) -> Result<(), String> {
+		// Transfers before this event belong to this trade.
+		let items = self
+			.transfers
+			.remove(&phase)
+			.unwrap_or_default()
+			.into_iter()
+			.filter(|(from, to, _)| from == &seller && to == &buyer)
+			.map(|(_, _, item)| item)
+			.collect();
+		let trade = TradeRecord {
+			block_number: self.number,
+			event_index,
+			kind,
+			id: Some(id),
+			seller,
+			buyer,
+			currency_id: price.map(|p| p.0),
+			price: price.map(|p| p.1),
+			items,
+		};
+		self.writer.put_trade(trade).map_err(|e| e.to_string())?;
+		self.close_listing(id, ListingStatus::Filled)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::db::NUM_COLUMNS;
+	use codec::Encode;
+	use frame_system::Phase;
+	use nftmart_order::Orders;
+	use sp_core::crypto::AccountId32;
+	use sp_runtime::PerU16;
+
+	const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+	const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+
+	#[derive(Default)]
+	struct TestChain {
+		/// The hashes of the best chain from block #1.
+		best: Vec<Hash>,
+		finalized: BlockNumber,
+		parents: HashMap<Hash, Hash>,
+		storage: HashMap<(Hash, Vec<u8>), Vec<u8>>,
+	}
+
+	impl TestChain {
+		/// Import a block on top of the best chain with the raw `System::Events`.
+		fn push_raw(&mut self, id: u8, events: Vec<u8>) -> Hash {
+			let hash = Hash::repeat_byte(id);
+			let parent_hash = self.best.last().copied().unwrap_or_default();
+			self.best.push(hash);
+			self.parents.insert(hash, parent_hash);
+			self.storage.insert((hash, events_key()), events);
+			hash
+		}
+
+		fn push(&mut self, id: u8, events: Vec<Event>) -> Hash {
+			let records: Vec<_> = events.into_iter().map(record).collect();
+			self.push_raw(id, records.encode())
+		}
+	}
+
+	impl Chain for &TestChain {
+		fn best_and_finalized(&self) -> (BlockNumber, BlockNumber) {
+			(self.best.len() as BlockNumber, self.finalized)
+		}
+
+		fn hash(&self, number: BlockNumber) -> Result<Option<Hash>, String> {
+			Ok(number.checked_sub(1).and_then(|i| self.best.get(i as usize)).copied())
+		}
+
+		fn parent_hash(&self, hash: Hash) -> Result<Option<Hash>, String> {
+			Ok(self.parents.get(&hash).copied())
+		}
+
+		fn storage(&self, at: Hash, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+			Ok(self.storage.get(&(at, key.to_vec())).cloned())
+		}
+	}
+
+	fn record(event: Event) -> EventRecord {
+		EventRecord { phase: Phase::ApplyExtrinsic(0), event, topics: vec![] }
+	}
+
+	fn minted(class_id: u32, token_id: u64) -> Event {
+		Event::Nftmart(nftmart_nft::Event::MintedToken(ALICE, ALICE, class_id, token_id, 1))
+	}
+
+	fn new_db() -> Arc<IndexerDb> {
+		Arc::new(IndexerDb::new(Arc::new(kvdb_memorydb::create(NUM_COLUMNS))))
+	}
+
+	#[test]
+	fn trades_should_be_priced_from_the_parent_state() {
+		let mut chain = TestChain::default();
+		let order: nftmart_order::OrderOf<Runtime> = nftmart_order::Order {
+			currency_id: 0,
+			deposit: 10,
+			price: 100,
+			deadline: 100,
+			items: vec![OrderItem { class_id: 1, token_id: 2, quantity: 3 }],
+			commission_rate: PerU16::zero(),
+		};
+		let created = Event::NftmartOrder(nftmart_order::Event::CreatedOrder(ALICE, 7));
+		let hash = chain.push(1, vec![created]);
+		chain
+			.storage
+			.insert((hash, Orders::<Runtime>::hashed_key_for(&ALICE, 7)), order.encode());
+		chain.push(
+			2,
+			vec![
+				Event::Nftmart(nftmart_nft::Event::TransferredToken(ALICE, BOB, 1, 2, 3)),
+				Event::NftmartOrder(nftmart_order::Event::TakenOrder(BOB, ALICE, 7, None, None)),
+			],
+		);
+
+		let db = new_db();
+		Indexer::with_chain(&chain, db.clone()).sync().unwrap();
+
+		let listing = db.listing(7).unwrap().unwrap();
+		assert_eq!((listing.created_at, listing.updated_at), (1, 2));
+		assert_eq!(listing.status, ListingStatus::Filled);
+		let trades = db.token_trades(1, 2, 10).unwrap();
+		assert_eq!(trades.len(), 1);
+		assert_eq!((trades[0].seller.clone(), trades[0].buyer.clone()), (ALICE, BOB));
+		assert_eq!((trades[0].currency_id, trades[0].price), (Some(0), Some(100)));
+		assert_eq!(trades[0].items, vec![(1, 2, 3)]);
+		assert_eq!(db.token_ownership(1, 2, 10).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn undecodable_events_should_be_skipped() {
+		let mut chain = TestChain::default();
+		let mut events = Compact(3u32).encode();
+		events.extend(record(minted(1, 1)).encode());
+		events.extend([0xff; 8]);
+		chain.push_raw(1, events);
+		chain.push(2, vec![minted(1, 2)]);
+
+		let db = new_db();
+		Indexer::with_chain(&chain, db.clone()).sync().unwrap();
+
+		assert_eq!(db.status().unwrap().map(|t| t.block_number), Some(2));
+		assert_eq!(db.token_ownership(1, 1, 10).unwrap().len(), 1);
+		assert_eq!(db.token_ownership(1, 2, 10).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn retracted_blocks_should_be_reverted() {
+		let mut chain = TestChain::default();
+		chain.push(1, vec![minted(1, 1)]);
+		chain.push(2, vec![minted(1, 2)]);
+
+		let db = new_db();
+		Indexer::with_chain(&chain, db.clone()).sync().unwrap();
+		assert_eq!(db.token_ownership(1, 2, 10).unwrap().len(), 1);
+
+		// Block #2 is replaced by a fork which is longer.
+		chain.best.truncate(1);
+		chain.push(0x12, vec![]);
+		let hash = chain.push(0x13, vec![minted(1, 3)]);
+		Indexer::with_chain(&chain, db.clone()).sync().unwrap();
+
+		assert_eq!(db.status().unwrap(), Some(IndexerStatus { block_number: 3, block_hash: hash }));
+		assert_eq!(db.token_ownership(1, 1, 10).unwrap().len(), 1);
+		assert_eq!(db.token_ownership(1, 2, 10).unwrap(), vec![]);
+		assert_eq!(db.token_ownership(1, 3, 10).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn finalized_journals_should_be_pruned() {
+		let mut chain = TestChain::default();
+		for id in 1..=3 {
+			chain.push(id, vec![minted(1, id as u64)]);
+		}
+		chain.finalized = 2;
+
+		let db = new_db();
+		Indexer::with_chain(&chain, db.clone()).sync().unwrap();
+		assert_eq!(db.status().unwrap().map(|t| t.block_number), Some(3));
+		assert_eq!(db.pruned().unwrap(), 2);
+
+		// The finalized blocks can't be reverted.
+		assert_eq!(db.revert_tip().unwrap().map(|t| t.block_number), Some(2));
+		assert!(db.revert_tip().is_err());
+	}
+}
@@ -18,8 +18,10 @@
 
 //! Structs to easily compose inspect sub-command for CLI.
 
+use nftmart_traits::ClassId;
+use node_runtime::AccountId;
 use sc_cli::{ImportParams, SharedParams};
-use std::fmt::Debug;
+use std::{fmt::Debug, path::PathBuf};
 use structopt::StructOpt;
 
 /// The `inspect` command used to print decoded chain data.
@@ -38,6 +40,22 @@ pub struct InspectCmd {
 	pub import_params: ImportParams,
 }
 
+/// The `merge-nfts` command used to carry the NFTs of a chain over to a new chain spec.
+#[derive(Debug, StructOpt)]
+pub struct MergeNftsCmd {
+	/// The chain spec to merge into, as built by `build-spec` without `--raw`.
+	#[structopt(long, parse(from_os_str))]
+	pub chain_spec: PathBuf,
+
+	/// The snapshot exported by `inspect export-nfts`.
+	#[structopt(long, parse(from_os_str))]
+	pub snapshot: PathBuf,
+
+	/// Write the merged chain spec to this file instead of printing it out.
+	#[structopt(long, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
 /// A possible inspect sub-commands.
 #[derive(Debug, StructOpt)]
 pub enum InspectSubCmd {
@@ -61,4 +79,74 @@ pub enum InspectSubCmd {
 		#[structopt(value_name = "BLOCK:INDEX or BYTES")]
 		input: String,
 	},
+	/// Print out all NFT classes.
+	Classes {
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		state: StateParams,
+	},
+	/// Print out an NFT class and its tokens.
+	Class {
+		/// The id of the class.
+		class_id: ClassId,
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		state: StateParams,
+	},
+	/// Print out the balances, tokens and listings of an account.
+	Account {
+		/// The SS58 address of the account.
+		account: AccountId,
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		state: StateParams,
+	},
+	/// Print out the open orders, offers, swaps and auctions.
+	Listings {
+		/// Only print out the listings of this account.
+		#[structopt(long)]
+		owner: Option<AccountId>,
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		state: StateParams,
+	},
+	/// Print out the NFT categories.
+	Categories {
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		state: StateParams,
+	},
+	/// Print out the marketplace rates and limits.
+	Config {
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		state: StateParams,
+	},
+	/// Export the NFT classes, tokens, holders, approvals and rentals of a block as a snapshot,
+	/// which can be merged into the genesis of a chain spec with `merge-nfts`.
+	ExportNfts {
+		/// Block whose state is exported, either a block hash (no 0x prefix) or a number.
+		///
+		/// Defaults to the best block.
+		#[structopt(long, value_name = "HASH or NUMBER")]
+		at: Option<String>,
+
+		/// Write the snapshot to this file instead of printing it out.
+		#[structopt(long, parse(from_os_str))]
+		output: Option<PathBuf>,
+	},
+}
+
+/// Parameters of the sub-commands reading the NFTMart state.
+#[derive(Debug, StructOpt)]
+pub struct StateParams {
+	/// Block whose state is read, either a block hash (no 0x prefix) or a number.
+	///
+	/// Defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<String>,
+
+	/// Print out JSON instead of the decoded structures.
+	#[structopt(long)]
+	pub json: bool,
 }
@@ -19,14 +19,31 @@
 //! Command ran by the CLI
 
 use crate::{
-	cli::{InspectCmd, InspectSubCmd},
-	Inspector,
+	cli::{InspectCmd, InspectSubCmd, MergeNftsCmd},
+	nftmart::{self, ClientStorage, StateInspector},
+	BlockAddress, BlockAddressFor, Inspector,
 };
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
 use sc_executor::NativeElseWasmExecutor;
-use sc_service::{new_full_client, Configuration, NativeExecutionDispatch};
-use sp_runtime::traits::Block;
-use std::str::FromStr;
+use sc_service::{new_full_client, Configuration, NativeExecutionDispatch, TFullBackend};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block};
+use std::{fs, str::FromStr};
+
+/// The block whose state is read, the best block by default.
+fn state_at<B: Block>(at: &Option<String>, best: B::Hash) -> Result<BlockId<B>>
+where
+	B::Hash: FromStr,
+{
+	match at {
+		None => Ok(BlockId::hash(best)),
+		Some(at) => match at.parse::<BlockAddressFor<B>>()? {
+			BlockAddress::Hash(hash) => Ok(BlockId::hash(hash)),
+			BlockAddress::Number(number) => Ok(BlockId::number(number)),
+			BlockAddress::Bytes(_) => Err("Expected a block hash or number".into()),
+		},
+	}
+}
 
 impl InspectCmd {
 	/// Run the inspect command, passing the inspector.
@@ -44,22 +61,85 @@ impl InspectCmd {
 		);
 
 		let client = new_full_client::<B, RA, _>(&config, None, executor)?;
-		let inspect = Inspector::<B>::new(client);
+		let best = client.info().best_hash;
+		let state = |at: &Option<String>| -> Result<_> {
+			let at = state_at::<B>(at, best)?;
+			Ok(StateInspector::new(ClientStorage::<_, TFullBackend<B>, _>::new(&client, at)))
+		};
+		let print = |res: std::result::Result<String, crate::Error>| -> Result<()> {
+			println!("{}", res.map_err(|e| format!("{}", e))?);
+			Ok(())
+		};
 
 		match &self.command {
 			InspectSubCmd::Block { input } => {
 				let input = input.parse()?;
-				let res = inspect.block(input).map_err(|e| format!("{}", e))?;
+				let res = Inspector::<B>::new(client).block(input).map_err(|e| format!("{}", e))?;
 				println!("{}", res);
 				Ok(())
 			},
 			InspectSubCmd::Extrinsic { input } => {
 				let input = input.parse()?;
-				let res = inspect.extrinsic(input).map_err(|e| format!("{}", e))?;
+				let res =
+					Inspector::<B>::new(client).extrinsic(input).map_err(|e| format!("{}", e))?;
 				println!("{}", res);
 				Ok(())
 			},
+			InspectSubCmd::Classes { state: params } => print(
+				state(&params.at)?
+					.classes(None)
+					.and_then(|view| nftmart::format(&view, params.json)),
+			),
+			InspectSubCmd::Class { class_id, state: params } => print(
+				state(&params.at)?
+					.classes(Some(*class_id))
+					.and_then(|view| nftmart::format(&view, params.json)),
+			),
+			InspectSubCmd::Account { account, state: params } => print(
+				state(&params.at)?
+					.account(account)
+					.and_then(|view| nftmart::format(&view, params.json)),
+			),
+			InspectSubCmd::Listings { owner, state: params } => print(
+				state(&params.at)?
+					.listings_of(owner.as_ref())
+					.and_then(|view| nftmart::format(&view, params.json)),
+			),
+			InspectSubCmd::Categories { state: params } => print(
+				state(&params.at)?
+					.categories()
+					.and_then(|view| nftmart::format(&view, params.json)),
+			),
+			InspectSubCmd::Config { state: params } => print(
+				state(&params.at)?.config().and_then(|view| nftmart::format(&view, params.json)),
+			),
+			InspectSubCmd::ExportNfts { at, output } => {
+				let snapshot = state(at)?.nft_snapshot().map_err(|e| format!("{}", e))?;
+				let json = nftmart::format(&snapshot, true).map_err(|e| format!("{}", e))?;
+				match output {
+					Some(path) => fs::write(path, json)?,
+					None => println!("{}", json),
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
+impl MergeNftsCmd {
+	/// Run the merge-nfts command.
+	pub fn run(&self) -> Result<()> {
+		let mut spec: serde_json::Value = serde_json::from_slice(&fs::read(&self.chain_spec)?)
+			.map_err(|e| format!("Invalid chain spec: {}", e))?;
+		let snapshot = serde_json::from_slice(&fs::read(&self.snapshot)?)
+			.map_err(|e| format!("Invalid snapshot: {}", e))?;
+		nftmart::merge_snapshot(&mut spec, snapshot)?;
+		let json = serde_json::to_string_pretty(&spec).map_err(|e| format!("{}", e))?;
+		match &self.output {
+			Some(path) => fs::write(path, json)?,
+			None => println!("{}", json),
 		}
+		Ok(())
 	}
 }
 
@@ -26,6 +26,7 @@
 
 pub mod cli;
 pub mod command;
+pub mod nftmart;
 
 use codec::{Decode, Encode};
 use sc_client_api::BlockBackend;
@@ -86,6 +87,11 @@ pub enum Error {
 	Blockchain(sp_blockchain::Error),
 	/// Given block has not been found.
 	NotFound(String),
+	/// Could not export as JSON.
+	Json(serde_json::Error),
+	/// Metadata which is not UTF-8 cannot be exported to the genesis.
+	#[from(ignore)]
+	InvalidMetadata(String),
 }
 
 impl std::error::Error for Error {
@@ -94,6 +100,8 @@ impl std::error::Error for Error {
 			Self::Codec(ref e) => Some(e),
 			Self::Blockchain(ref e) => Some(e),
 			Self::NotFound(_) => None,
+			Self::Json(ref e) => Some(e),
+			Self::InvalidMetadata(_) => None,
 		}
 	}
 }
@@ -0,0 +1,967 @@
+//! Decode the NFTMart storage of a block.
+//!
+//! The storage is read through the client, so no runtime call is made and any block still in the
+//! database can be inspected. Any other [`StateStorage`] can be read the same way.
+
+use crate::Error;
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::{StorageMap, StoragePrefixedMap, StorageValue},
+	Blake2_128Concat, ReversibleStorageHasher, StorageHasher, Twox64Concat,
+};
+use nftmart_auction::{BritishAuctionBidOf, BritishAuctionOf, DutchAuctionBidOf, DutchAuctionOf};
+use nftmart_order::{OfferOf, OrderOf, SwapOf};
+use nftmart_rental::{RentalListingOf, UserInfo, UserInfoOf};
+use nftmart_traits::{
+	AccountToken, Balance, CategoryData, ClassConfig, ClassData, ClassId, ClassSupplyCap,
+	CurrencyId, FeeDistribution, GlobalId, TokenApproval, TokenConfig, TokenData, TokenId,
+};
+use node_runtime::{AccountId, BlockNumber, Index, ProxyType, Runtime};
+use sc_client_api::{backend::Backend, StorageProvider};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sp_core::{hexdisplay::HexDisplay, storage::StorageKey};
+use sp_runtime::{generic::BlockId, traits::Block, PerU16};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fmt::Debug,
+	marker::PhantomData,
+};
+
+/// A class with its tokens.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassView {
+	pub class_id: ClassId,
+	pub owner: AccountId,
+	pub metadata: String,
+	pub total_issuance: TokenId,
+	pub data: ClassData<BlockNumber>,
+	/// The tokens, only listed when a single class is inspected.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub tokens: Option<Vec<TokenView>>,
+}
+
+/// A token of a class.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenView {
+	pub class_id: ClassId,
+	pub token_id: TokenId,
+	pub metadata: String,
+	pub quantity: TokenId,
+	pub data: TokenData<AccountId, BlockNumber>,
+}
+
+/// The free and reserved balances of an account in a currency.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceView {
+	pub currency_id: CurrencyId,
+	pub free: String,
+	pub reserved: String,
+}
+
+/// The quantity of a token held by an account.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldingView {
+	pub class_id: ClassId,
+	pub token_id: TokenId,
+	pub quantity: TokenId,
+	pub reserved: TokenId,
+}
+
+/// Everything held and listed by an account.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountView {
+	pub account: AccountId,
+	pub nonce: Index,
+	pub balances: Vec<BalanceView>,
+	pub tokens: Vec<HoldingView>,
+	pub listings: ListingsView,
+}
+
+/// A listing and its owner.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Listing<T> {
+	pub owner: AccountId,
+	pub id: GlobalId,
+	#[serde(flatten)]
+	pub listing: T,
+}
+
+/// An auction with its last bid.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuctionView<A, B> {
+	pub auction: A,
+	pub bid: Option<B>,
+}
+
+/// The open orders, offers, swaps and auctions.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListingsView {
+	pub orders: Vec<Listing<OrderOf<Runtime>>>,
+	pub offers: Vec<Listing<OfferOf<Runtime>>>,
+	pub swaps: Vec<Listing<SwapOf<Runtime>>>,
+	pub british_auctions:
+		Vec<Listing<AuctionView<BritishAuctionOf<Runtime>, BritishAuctionBidOf<Runtime>>>>,
+	pub dutch_auctions:
+		Vec<Listing<AuctionView<DutchAuctionOf<Runtime>, DutchAuctionBidOf<Runtime>>>>,
+}
+
+/// A category.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryView {
+	pub category_id: GlobalId,
+	pub metadata: String,
+	pub count: Balance,
+}
+
+/// The rates and limits of `NftmartConf`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigView {
+	pub platform_fee_rate: PerU16,
+	pub royalties_rate: PerU16,
+	pub max_commission_reward_rate: PerU16,
+	pub reaper_bounty_rate: PerU16,
+	pub fee_distribution: FeeDistribution,
+	pub min_order_deposit: String,
+	pub min_commission_agent_deposit: String,
+	pub auction_close_delay: BlockNumber,
+	pub next_id: GlobalId,
+}
+
+type ClassInfo = orml_nft::ClassInfoOf<Runtime>;
+type TokenInfo = orml_nft::TokenInfoOf<Runtime>;
+type ProxiesOf = (Vec<pallet_proxy::ProxyDefinition<AccountId, ProxyType, BlockNumber>>, Balance);
+
+/// The NFT classes and categories of a block, which can be merged into the genesis of a new chain.
+///
+/// The deadlines of the approvals and the expiries of the user rights count the blocks left from
+/// the exported block, which are the block numbers on a chain starting at genesis.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NftSnapshot {
+	/// The categories the classes are in.
+	pub categories: Vec<CategoryView>,
+	/// The classes with their tokens, in the genesis format of `Nftmart`.
+	pub classes: Vec<ClassConfig<ClassId, AccountId, TokenId>>,
+	/// The unexpired token approvals, as `(owner, spender, class_id, token_id, approval)`.
+	#[serde(default)]
+	pub token_approvals:
+		Vec<(AccountId, AccountId, ClassId, TokenId, TokenApproval<TokenId, BlockNumber>)>,
+	/// The unexpired operator approvals, as `(owner, operator, deadline)`.
+	#[serde(default)]
+	pub operator_approvals: Vec<(AccountId, AccountId, BlockNumber)>,
+	/// The rental listings, as `(class_id, token_id, lender, listing)`.
+	#[serde(default)]
+	pub rental_listings: Vec<(ClassId, TokenId, AccountId, RentalListingOf<Runtime>)>,
+	/// The unexpired user rights of the rented tokens, as `(class_id, token_id, lender, user)`.
+	#[serde(default)]
+	pub token_users: Vec<(ClassId, TokenId, AccountId, UserInfoOf<Runtime>)>,
+}
+
+/// The metadata as text, or hex encoded if it is not UTF-8.
+fn metadata_string(metadata: &[u8]) -> String {
+	match std::str::from_utf8(metadata) {
+		Ok(metadata) => metadata.to_string(),
+		Err(_) => format!("0x{}", HexDisplay::from(&metadata)),
+	}
+}
+
+/// The metadata as text for the genesis, which takes the bytes of the string as they are.
+fn genesis_string(metadata: &[u8], of: impl FnOnce() -> String) -> Result<String, Error> {
+	String::from_utf8(metadata.to_vec()).map_err(|_| {
+		Error::InvalidMetadata(format!(
+			"The metadata of {} is not UTF-8: 0x{}",
+			of(),
+			HexDisplay::from(&metadata)
+		))
+	})
+}
+
+/// Split the key of a map entry, returning the key hashed with a reversible hasher and the rest.
+fn take_key<H: ReversibleStorageHasher, K: Decode>(key: &mut &[u8]) -> Result<K, Error> {
+	*key = H::reverse(key);
+	Ok(K::decode(key)?)
+}
+
+/// The raw storage of a block.
+pub trait StateStorage {
+	/// The value at `key`.
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+	/// The entries whose keys start with `prefix`.
+	fn pairs(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>;
+}
+
+/// The storage of a block read through the client.
+pub struct ClientStorage<'a, B: Block, BE, C> {
+	client: &'a C,
+	at: BlockId<B>,
+	_backend: PhantomData<BE>,
+}
+
+impl<'a, B: Block, BE, C> ClientStorage<'a, B, BE, C> {
+	/// Create new instance reading the storage of block `at`.
+	pub fn new(client: &'a C, at: BlockId<B>) -> Self {
+		Self { client, at, _backend: PhantomData }
+	}
+}
+
+impl<'a, B, BE, C> StateStorage for ClientStorage<'a, B, BE, C>
+where
+	B: Block,
+	BE: Backend<B>,
+	C: StorageProvider<B, BE>,
+{
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+		Ok(self.client.storage(&self.at, &StorageKey(key.to_vec()))?.map(|data| data.0))
+	}
+
+	fn pairs(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+		Ok(self
+			.client
+			.storage_pairs(&self.at, &StorageKey(prefix.to_vec()))?
+			.into_iter()
+			.map(|(key, data)| (key.0, data.0))
+			.collect())
+	}
+}
+
+/// Reads and decodes the NFTMart storage of a block.
+pub struct StateInspector<S> {
+	storage: S,
+}
+
+impl<S: StateStorage> StateInspector<S> {
+	/// Create new instance reading `storage`.
+	pub fn new(storage: S) -> Self {
+		Self { storage }
+	}
+
+	fn value<T: Decode>(&self, key: Vec<u8>) -> Result<Option<T>, Error> {
+		match self.storage.get(&key)? {
+			Some(data) => Ok(Some(T::decode(&mut &data[..])?)),
+			None => Ok(None),
+		}
+	}
+
+	/// The entries under `prefix`, with the keys stripped of the prefix.
+	fn pairs<T: Decode>(&self, prefix: Vec<u8>) -> Result<Vec<(Vec<u8>, T)>, Error> {
+		self.storage
+			.pairs(&prefix)?
+			.into_iter()
+			.map(|(key, data)| Ok((key[prefix.len()..].to_vec(), T::decode(&mut &data[..])?)))
+			.collect()
+	}
+
+	fn listings<T: Decode>(
+		&self,
+		prefix: Vec<u8>,
+		owner: Option<&AccountId>,
+	) -> Result<Vec<Listing<T>>, Error> {
+		let prefix = match owner {
+			Some(owner) => [prefix, Blake2_128Concat::hash(&owner.encode())].concat(),
+			None => prefix,
+		};
+		let mut listings = self
+			.pairs::<T>(prefix)?
+			.into_iter()
+			.map(|(key, listing)| {
+				let mut key = &key[..];
+				let owner = match owner {
+					Some(owner) => owner.clone(),
+					None => take_key::<Blake2_128Concat, _>(&mut key)?,
+				};
+				let id = take_key::<Twox64Concat, _>(&mut key)?;
+				Ok(Listing { owner, id, listing })
+			})
+			.collect::<Result<Vec<_>, Error>>()?;
+		listings.sort_by_key(|l| l.id);
+		Ok(listings)
+	}
+
+	fn auctions<A: Decode, Bid: Decode>(
+		&self,
+		prefix: Vec<u8>,
+		bid_key: impl Fn(GlobalId) -> Vec<u8>,
+		owner: Option<&AccountId>,
+	) -> Result<Vec<Listing<AuctionView<A, Bid>>>, Error> {
+		self.listings::<A>(prefix, owner)?
+			.into_iter()
+			.map(|Listing { owner, id, listing }| {
+				let bid = self.value(bid_key(id))?;
+				Ok(Listing { owner, id, listing: AuctionView { auction: listing, bid } })
+			})
+			.collect()
+	}
+
+	fn class_infos(&self) -> Result<Vec<(ClassId, ClassInfo)>, Error> {
+		let mut classes = self
+			.pairs::<ClassInfo>(orml_nft::Classes::<Runtime>::final_prefix().to_vec())?
+			.into_iter()
+			.map(|(key, class)| Ok((take_key::<Twox64Concat, _>(&mut &key[..])?, class)))
+			.collect::<Result<Vec<_>, Error>>()?;
+		classes.sort_by_key(|(class_id, _)| *class_id);
+		Ok(classes)
+	}
+
+	fn token_infos(&self, class_id: ClassId) -> Result<Vec<(TokenId, TokenInfo)>, Error> {
+		let prefix = [
+			orml_nft::Tokens::<Runtime>::final_prefix().to_vec(),
+			Twox64Concat::hash(&class_id.encode()),
+		]
+		.concat();
+		let mut tokens = self
+			.pairs::<TokenInfo>(prefix)?
+			.into_iter()
+			.map(|(key, token)| Ok((take_key::<Twox64Concat, _>(&mut &key[..])?, token)))
+			.collect::<Result<Vec<_>, Error>>()?;
+		tokens.sort_by_key(|(token_id, _)| *token_id);
+		Ok(tokens)
+	}
+
+	/// All classes, or one class with its tokens.
+	pub fn classes(&self, class_id: Option<ClassId>) -> Result<Vec<ClassView>, Error> {
+		let single = class_id.is_some();
+		let classes = match class_id {
+			Some(class_id) => self
+				.value(orml_nft::Classes::<Runtime>::hashed_key_for(class_id))?
+				.map(|class| vec![(class_id, class)])
+				.ok_or_else(|| Error::NotFound(format!("Could not find class {}", class_id)))?,
+			None => self.class_infos()?,
+		};
+		classes
+			.into_iter()
+			.map(|(class_id, class)| {
+				let tokens = if single { Some(self.tokens(class_id)?) } else { None };
+				Ok(ClassView {
+					class_id,
+					owner: class.owner,
+					metadata: metadata_string(&class.metadata),
+					total_issuance: class.total_issuance,
+					data: class.data,
+					tokens,
+				})
+			})
+			.collect()
+	}
+
+	fn tokens(&self, class_id: ClassId) -> Result<Vec<TokenView>, Error> {
+		Ok(self
+			.token_infos(class_id)?
+			.into_iter()
+			.map(|(token_id, token)| TokenView {
+				class_id,
+				token_id,
+				metadata: metadata_string(&token.metadata),
+				quantity: token.quantity,
+				data: token.data,
+			})
+			.collect())
+	}
+
+	/// The balances, tokens and listings of an account.
+	pub fn account(&self, who: &AccountId) -> Result<AccountView, Error> {
+		let info: frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>> = self
+			.value(frame_system::Account::<Runtime>::hashed_key_for(who))?
+			.unwrap_or_default();
+		let mut balances = vec![BalanceView {
+			currency_id: nftmart_traits::constants_types::NATIVE_CURRENCY_ID,
+			free: info.data.free.to_string(),
+			reserved: info.data.reserved.to_string(),
+		}];
+		let prefix = [
+			orml_tokens::Accounts::<Runtime>::final_prefix().to_vec(),
+			Blake2_128Concat::hash(&who.encode()),
+		]
+		.concat();
+		for (key, data) in self.pairs::<orml_tokens::OrmlAccountData<Balance>>(prefix)? {
+			balances.push(BalanceView {
+				currency_id: take_key::<Twox64Concat, _>(&mut &key[..])?,
+				free: data.free.to_string(),
+				reserved: data.reserved.to_string(),
+			});
+		}
+
+		let prefix = [
+			orml_nft::TokensByOwner::<Runtime>::final_prefix().to_vec(),
+			Twox64Concat::hash(&who.encode()),
+		]
+		.concat();
+		let mut tokens = self
+			.pairs::<AccountToken<TokenId>>(prefix)?
+			.into_iter()
+			.map(|(key, token)| {
+				let (class_id, token_id) = take_key::<Twox64Concat, _>(&mut &key[..])?;
+				Ok(HoldingView {
+					class_id,
+					token_id,
+					quantity: token.quantity,
+					reserved: token.reserved,
+				})
+			})
+			.collect::<Result<Vec<_>, Error>>()?;
+		tokens.sort_by_key(|t| (t.class_id, t.token_id));
+
+		Ok(AccountView {
+			account: who.clone(),
+			nonce: info.nonce,
+			balances,
+			tokens,
+			listings: self.listings_of(Some(who))?,
+		})
+	}
+
+	/// The open listings, of all accounts or of `owner`.
+	pub fn listings_of(&self, owner: Option<&AccountId>) -> Result<ListingsView, Error> {
+		Ok(ListingsView {
+			orders: self
+				.listings(nftmart_order::Orders::<Runtime>::final_prefix().to_vec(), owner)?,
+			offers: self
+				.listings(nftmart_order::Offers::<Runtime>::final_prefix().to_vec(), owner)?,
+			swaps: self
+				.listings(nftmart_order::Swaps::<Runtime>::final_prefix().to_vec(), owner)?,
+			british_auctions: self.auctions(
+				nftmart_auction::BritishAuctions::<Runtime>::final_prefix().to_vec(),
+				|id| nftmart_auction::BritishAuctionBids::<Runtime>::hashed_key_for(id),
+				owner,
+			)?,
+			dutch_auctions: self.auctions(
+				nftmart_auction::DutchAuctions::<Runtime>::final_prefix().to_vec(),
+				|id| nftmart_auction::DutchAuctionBids::<Runtime>::hashed_key_for(id),
+				owner,
+			)?,
+		})
+	}
+
+	fn category_data(&self) -> Result<Vec<(GlobalId, CategoryData)>, Error> {
+		let mut categories = self
+			.pairs::<CategoryData>(nftmart_config::Categories::<Runtime>::final_prefix().to_vec())?
+			.into_iter()
+			.map(|(key, category)| Ok((take_key::<Twox64Concat, _>(&mut &key[..])?, category)))
+			.collect::<Result<Vec<_>, Error>>()?;
+		categories.sort_by_key(|(category_id, _)| *category_id);
+		Ok(categories)
+	}
+
+	/// All categories.
+	pub fn categories(&self) -> Result<Vec<CategoryView>, Error> {
+		Ok(self
+			.category_data()?
+			.into_iter()
+			.map(|(category_id, category)| CategoryView {
+				category_id,
+				metadata: metadata_string(&category.metadata),
+				count: category.count,
+			})
+			.collect())
+	}
+
+	/// The rates and limits of `NftmartConf`.
+	pub fn config(&self) -> Result<ConfigView, Error> {
+		use nftmart_config::{
+			AuctionCloseDelay, FeeDistributionRates, MaxCommissionRewardRate,
+			MinCommissionAgentDeposit, MinOrderDeposit, NextId, PlatformFeeRate, ReaperBountyRate,
+			RoyaltiesRate,
+		};
+		Ok(ConfigView {
+			platform_fee_rate: self.value_or_default(PlatformFeeRate::<Runtime>::hashed_key())?,
+			royalties_rate: self.value_or_default(RoyaltiesRate::<Runtime>::hashed_key())?,
+			max_commission_reward_rate: self
+				.value_or_default(MaxCommissionRewardRate::<Runtime>::hashed_key())?,
+			reaper_bounty_rate: self.value_or_default(ReaperBountyRate::<Runtime>::hashed_key())?,
+			fee_distribution: self
+				.value_or_default(FeeDistributionRates::<Runtime>::hashed_key())?,
+			min_order_deposit: self
+				.value_or_default::<Balance>(MinOrderDeposit::<Runtime>::hashed_key())?
+				.to_string(),
+			min_commission_agent_deposit: self
+				.value_or_default::<Balance>(MinCommissionAgentDeposit::<Runtime>::hashed_key())?
+				.to_string(),
+			auction_close_delay: self
+				.value_or_default(AuctionCloseDelay::<Runtime>::hashed_key())?,
+			next_id: self.value_or_default(NextId::<Runtime>::hashed_key())?,
+		})
+	}
+
+	/// The classes, tokens, holders, approvals and rentals as genesis configuration.
+	///
+	/// The largest holder of a token becomes its `token_owner`, and the reserved quantities are
+	/// counted as held since the orders and auctions are not exported. The rental listings are,
+	/// and reserve their quantities again at genesis. Metadata which is not UTF-8 is an error,
+	/// since the genesis cannot take it.
+	pub fn nft_snapshot(&self) -> Result<NftSnapshot, Error> {
+		let mut holders = BTreeMap::<(ClassId, TokenId), Vec<(AccountId, TokenId)>>::new();
+		let prefix = orml_nft::TokensByOwner::<Runtime>::final_prefix().to_vec();
+		for (key, token) in self.pairs::<AccountToken<TokenId>>(prefix)? {
+			let mut key = &key[..];
+			let who = take_key::<Twox64Concat, AccountId>(&mut key)?;
+			let token_key = take_key::<Twox64Concat, _>(&mut key)?;
+			holders.entry(token_key).or_default().push((who, token.total()));
+		}
+
+		let mut classes = Vec::new();
+		for (class_id, class) in self.class_infos()? {
+			let admins = self
+				.value::<ProxiesOf>(pallet_proxy::Proxies::<Runtime>::hashed_key_for(&class.owner))?
+				.map(|(proxies, _)| proxies.into_iter().map(|p| p.delegate).collect())
+				.unwrap_or_default();
+			let cap: ClassSupplyCap<TokenId> = self
+				.value(nftmart_nft::ClassSupplyCaps::<Runtime>::hashed_key_for(class_id))?
+				.unwrap_or_default();
+
+			let mut tokens = Vec::new();
+			for (token_id, token) in self.token_infos(class_id)? {
+				let mut token_holders = holders.remove(&(class_id, token_id)).unwrap_or_default();
+				token_holders.sort_by(|a, b| b.1.cmp(&a.1));
+				if token_holders.is_empty() {
+					return Err(Error::NotFound(format!(
+						"Could not find the holders of token {}/{}",
+						class_id, token_id
+					)))
+				}
+				let (token_owner, _) = token_holders.remove(0);
+				tokens.push(TokenConfig {
+					token_id,
+					token_metadata: genesis_string(&token.metadata, || {
+						format!("token {}/{}", class_id, token_id)
+					})?,
+					royalty_rate: token.data.royalty_rate,
+					token_owner,
+					token_creator: token.data.creator,
+					royalty_beneficiary: token.data.royalty_beneficiary,
+					quantity: token.quantity,
+					holders: token_holders,
+				});
+			}
+
+			let of_class =
+				|what: &'static str| move || format!("the {} of class {}", what, class_id);
+			classes.push(ClassConfig {
+				class_id,
+				class_metadata: genesis_string(&class.metadata, || format!("class {}", class_id))?,
+				category_ids: class.data.category_ids,
+				name: genesis_string(&class.data.name, of_class("name"))?,
+				description: genesis_string(&class.data.description, of_class("description"))?,
+				royalty_rate: class.data.royalty_rate,
+				properties: class.data.properties.0.bits(),
+				admins,
+				tokens,
+				max_tokens: cap.max_tokens,
+				max_supply: cap.max_supply,
+				min_royalty: self
+					.value(nftmart_nft::ClassMinRoyalties::<Runtime>::hashed_key_for(class_id))?
+					.unwrap_or_default(),
+			});
+		}
+
+		let categories = self
+			.category_data()?
+			.into_iter()
+			.map(|(category_id, category)| {
+				Ok(CategoryView {
+					category_id,
+					metadata: genesis_string(&category.metadata, || {
+						format!("category {}", category_id)
+					})?,
+					count: category.count,
+				})
+			})
+			.collect::<Result<_, Error>>()?;
+
+		let now: BlockNumber =
+			self.value_or_default(frame_system::Number::<Runtime>::hashed_key())?;
+		let left = |deadline: BlockNumber| deadline.checked_sub(now).filter(|left| *left > 0);
+
+		let mut token_approvals = Vec::new();
+		let prefix = nftmart_nft::TokenApprovals::<Runtime>::final_prefix().to_vec();
+		for (key, approval) in self.pairs::<TokenApproval<TokenId, BlockNumber>>(prefix)? {
+			let mut key = &key[..];
+			let owner = take_key::<Blake2_128Concat, _>(&mut key)?;
+			let (spender, class_id, token_id) = take_key::<Blake2_128Concat, _>(&mut key)?;
+			if let Some(deadline) = left(approval.deadline) {
+				token_approvals.push((
+					owner,
+					spender,
+					class_id,
+					token_id,
+					TokenApproval { quantity: approval.quantity, deadline },
+				));
+			}
+		}
+
+		let mut operator_approvals = Vec::new();
+		let prefix = nftmart_nft::OperatorApprovals::<Runtime>::final_prefix().to_vec();
+		for (key, deadline) in self.pairs::<BlockNumber>(prefix)? {
+			let mut key = &key[..];
+			let owner = take_key::<Blake2_128Concat, _>(&mut key)?;
+			let operator = take_key::<Blake2_128Concat, _>(&mut key)?;
+			if let Some(deadline) = left(deadline) {
+				operator_approvals.push((owner, operator, deadline));
+			}
+		}
+
+		let mut rental_listings = Vec::new();
+		let prefix = nftmart_rental::RentalListings::<Runtime>::final_prefix().to_vec();
+		for (key, listing) in self.pairs::<RentalListingOf<Runtime>>(prefix)? {
+			let mut key = &key[..];
+			let (class_id, token_id) = take_key::<Blake2_128Concat, _>(&mut key)?;
+			let lender = take_key::<Blake2_128Concat, _>(&mut key)?;
+			rental_listings.push((class_id, token_id, lender, listing));
+		}
+
+		let mut token_users = Vec::new();
+		let prefix = nftmart_rental::TokenUsers::<Runtime>::final_prefix().to_vec();
+		for (key, user) in self.pairs::<UserInfoOf<Runtime>>(prefix)? {
+			let mut key = &key[..];
+			let (class_id, token_id) = take_key::<Blake2_128Concat, _>(&mut key)?;
+			let lender = take_key::<Blake2_128Concat, _>(&mut key)?;
+			if let Some(expires) = left(user.expires) {
+				token_users.push((class_id, token_id, lender, UserInfo { expires, ..user }));
+			}
+		}
+
+		token_approvals.sort_by(|a, b| (a.2, a.3).cmp(&(b.2, b.3)));
+		rental_listings.sort_by_key(|(class_id, token_id, ..)| (*class_id, *token_id));
+		token_users.sort_by_key(|(class_id, token_id, ..)| (*class_id, *token_id));
+
+		Ok(NftSnapshot {
+			categories,
+			classes,
+			token_approvals,
+			operator_approvals,
+			rental_listings,
+			token_users,
+		})
+	}
+
+	fn value_or_default<T: Decode + Default>(&self, key: [u8; 32]) -> Result<T, Error> {
+		Ok(self.value(key.to_vec())?.unwrap_or_default())
+	}
+}
+
+/// Merge `snapshot` into the genesis of a chain spec which is not raw.
+///
+/// The categories are appended to the `categoryList` of `nftmartConf`, unless already listed, and
+/// category ids of the classes are renumbered to the ids given at genesis, which count from 0 in
+/// the order of the list. The classes and approvals are appended to `nftmart`, and the rental
+/// listings and users to `nftmartRental`.
+pub fn merge_snapshot(spec: &mut Value, snapshot: NftSnapshot) -> Result<(), String> {
+	let runtime = spec
+		.pointer_mut("/genesis/runtime")
+		.ok_or("Expected the genesis of the chain spec not to be raw")?;
+
+	let categories = runtime
+		.pointer_mut("/nftmartConf/categoryList")
+		.and_then(Value::as_array_mut)
+		.ok_or("Could not find the categories of `nftmartConf` in the chain spec")?;
+	let mut category_ids = BTreeMap::new();
+	for category in snapshot.categories {
+		let metadata = Value::from(category.metadata.into_bytes());
+		let id = match categories.iter().position(|c| *c == metadata) {
+			Some(id) => id,
+			None => {
+				categories.push(metadata);
+				categories.len() - 1
+			},
+		};
+		category_ids.insert(category.category_id, id as GlobalId);
+	}
+
+	let classes = runtime
+		.pointer_mut("/nftmart/classes")
+		.and_then(Value::as_array_mut)
+		.ok_or("Could not find the classes of `nftmart` in the chain spec")?;
+	let class_ids: BTreeSet<u64> =
+		classes.iter().filter_map(|c| c.get("class_id")?.as_u64()).collect();
+	for mut class in snapshot.classes {
+		if class_ids.contains(&(class.class_id as u64)) {
+			return Err(format!("Class {} is already in the chain spec", class.class_id))
+		}
+		class.category_ids = class
+			.category_ids
+			.iter()
+			.map(|id| {
+				category_ids.get(id).copied().ok_or_else(|| {
+					format!("Could not find category {} of class {}", id, class.class_id)
+				})
+			})
+			.collect::<Result<_, _>>()?;
+		classes.push(serde_json::to_value(&class).map_err(|e| e.to_string())?);
+	}
+
+	append(runtime, "nftmart", "tokenApprovals", snapshot.token_approvals)?;
+	append(runtime, "nftmart", "operatorApprovals", snapshot.operator_approvals)?;
+	append(runtime, "nftmartRental", "listings", snapshot.rental_listings)?;
+	append(runtime, "nftmartRental", "users", snapshot.token_users)?;
+	Ok(())
+}
+
+/// Append `items` to the list `field` of the genesis of `pallet`, which are added if missing.
+fn append<T: Serialize>(
+	runtime: &mut Value,
+	pallet: &str,
+	field: &str,
+	items: Vec<T>,
+) -> Result<(), String> {
+	let list = runtime
+		.as_object_mut()
+		.and_then(|runtime| runtime.entry(pallet).or_insert_with(|| json!({})).as_object_mut())
+		.and_then(|genesis| genesis.entry(field).or_insert_with(|| json!([])).as_array_mut())
+		.ok_or_else(|| format!("Could not find the {} of `{}` in the chain spec", field, pallet))?;
+	for item in items {
+		list.push(serde_json::to_value(&item).map_err(|e| e.to_string())?);
+	}
+	Ok(())
+}
+
+/// Format `value` as pretty JSON, or with its `Debug` implementation.
+pub fn format<T: Serialize + Debug>(value: &T, json: bool) -> Result<String, Error> {
+	if json {
+		Ok(serde_json::to_string_pretty(value)?)
+	} else {
+		Ok(format!("{:#?}", value))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::traits::{GenesisBuild, Get};
+	use nftmart_rental::RentalListing;
+	use nftmart_traits::ClassProperty;
+	use sp_runtime::traits::AccountIdConversion;
+
+	impl StateStorage for BTreeMap<Vec<u8>, Vec<u8>> {
+		fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+			Ok(BTreeMap::get(self, key).cloned())
+		}
+
+		fn pairs(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+			Ok(self
+				.range(prefix.to_vec()..)
+				.take_while(|(key, _)| key.starts_with(prefix))
+				.map(|(key, value)| (key.clone(), value.clone()))
+				.collect())
+		}
+	}
+
+	fn account(n: u8) -> AccountId {
+		AccountId::new([n; 32])
+	}
+
+	/// Build the genesis of `snapshot` and return its storage at block `now`.
+	fn state_of(snapshot: &NftSnapshot, now: BlockNumber) -> BTreeMap<Vec<u8>, Vec<u8>> {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		// the deposits of the classes are below the existential deposit.
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: snapshot
+				.classes
+				.iter()
+				.map(|c| {
+					let owner = <Runtime as nftmart_nft::Config>::ModuleId::get()
+						.into_sub_account(c.class_id);
+					(owner, node_runtime::ExistentialDeposit::get())
+				})
+				.collect(),
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		nftmart_config::GenesisConfig::<Runtime> {
+			category_list: snapshot
+				.categories
+				.iter()
+				.map(|c| c.metadata.clone().into_bytes())
+				.collect(),
+			..Default::default()
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		nftmart_nft::GenesisConfig::<Runtime> {
+			classes: snapshot.classes.clone(),
+			token_approvals: snapshot.token_approvals.clone(),
+			operator_approvals: snapshot.operator_approvals.clone(),
+			_phantom: Default::default(),
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		nftmart_rental::GenesisConfig::<Runtime> {
+			listings: snapshot.rental_listings.clone(),
+			users: snapshot.token_users.clone(),
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| {
+			frame_system::Pallet::<Runtime>::set_block_number(now);
+			let mut state = BTreeMap::new();
+			let mut key = Vec::new();
+			while let Some(next) = sp_io::storage::next_key(&key) {
+				state.insert(next.clone(), sp_io::storage::get(&next).unwrap());
+				key = next;
+			}
+			state
+		})
+	}
+
+	#[test]
+	fn should_export_snapshot() {
+		let (alice, bob, charlie) = (account(1), account(2), account(3));
+		let genesis = NftSnapshot {
+			categories: vec![CategoryView { category_id: 0, metadata: "art".into(), count: 1 }],
+			classes: vec![ClassConfig {
+				class_id: 0,
+				class_metadata: "class".into(),
+				category_ids: vec![0],
+				name: "name".into(),
+				description: "description".into(),
+				royalty_rate: PerU16::from_percent(5),
+				properties: (ClassProperty::Transferable | ClassProperty::Burnable).bits(),
+				admins: vec![],
+				tokens: vec![TokenConfig {
+					token_id: 0,
+					token_metadata: "token".into(),
+					royalty_rate: PerU16::from_percent(5),
+					token_owner: alice.clone(),
+					token_creator: alice.clone(),
+					royalty_beneficiary: alice.clone(),
+					quantity: 10,
+					holders: vec![(bob.clone(), 4)],
+				}],
+				max_tokens: Some(5),
+				max_supply: None,
+				min_royalty: PerU16::from_percent(2),
+			}],
+			token_approvals: vec![(
+				alice.clone(),
+				bob.clone(),
+				0,
+				0,
+				TokenApproval { quantity: 1, deadline: 10 },
+			)],
+			operator_approvals: vec![(bob.clone(), charlie.clone(), 3)],
+			rental_listings: vec![(
+				0,
+				0,
+				alice.clone(),
+				RentalListing {
+					currency_id: 0,
+					price_per_block: 1,
+					quantity: 2,
+					max_duration: 100,
+				},
+			)],
+			token_users: vec![(
+				0,
+				0,
+				alice.clone(),
+				UserInfo { user: charlie.clone(), expires: 20 },
+			)],
+		};
+		let state = StateInspector::new(state_of(&genesis, 5));
+
+		let classes = state.classes(Some(0)).unwrap();
+		assert_eq!(classes.len(), 1);
+		assert_eq!(classes[0].metadata, "class");
+		assert_eq!(classes[0].total_issuance, 10);
+		assert_eq!(classes[0].tokens.as_ref().unwrap()[0].metadata, "token");
+		let view = state.account(&alice).unwrap();
+		assert_eq!((view.tokens[0].quantity, view.tokens[0].reserved), (4, 2));
+		assert_eq!(state.categories().unwrap()[0].metadata, "art");
+
+		// the deadlines count from block 5 and the expired approval is dropped.
+		let snapshot = state.nft_snapshot().unwrap();
+		assert_eq!(
+			serde_json::to_value(&snapshot.classes).unwrap(),
+			serde_json::to_value(&genesis.classes).unwrap()
+		);
+		assert_eq!(
+			snapshot.token_approvals,
+			vec![(alice.clone(), bob, 0, 0, TokenApproval { quantity: 1, deadline: 5 })]
+		);
+		assert_eq!(snapshot.operator_approvals, vec![]);
+		assert_eq!(snapshot.rental_listings, genesis.rental_listings);
+		assert_eq!(
+			snapshot.token_users,
+			vec![(0, 0, alice, UserInfo { user: charlie, expires: 15 })]
+		);
+
+		// a chain starting from the snapshot exports the same snapshot.
+		let exported = StateInspector::new(state_of(&snapshot, 0)).nft_snapshot().unwrap();
+		assert_eq!(
+			serde_json::to_value(&exported).unwrap(),
+			serde_json::to_value(&snapshot).unwrap()
+		);
+	}
+
+	#[test]
+	fn should_merge_snapshot() {
+		let mut spec = json!({
+			"genesis": { "runtime": {
+				"nftmartConf": { "categoryList": [[97]] },
+				"nftmart": { "classes": [] },
+			}}
+		});
+		let snapshot = NftSnapshot {
+			categories: vec![
+				CategoryView { category_id: 7, metadata: "a".into(), count: 1 },
+				CategoryView { category_id: 9, metadata: "b".into(), count: 1 },
+			],
+			classes: vec![ClassConfig {
+				class_id: 3,
+				category_ids: vec![9, 7],
+				..Default::default()
+			}],
+			operator_approvals: vec![(AccountId::new([1; 32]), AccountId::new([2; 32]), 5)],
+			token_users: vec![(
+				3,
+				0,
+				AccountId::new([1; 32]),
+				UserInfo { user: AccountId::new([2; 32]), expires: 8 },
+			)],
+			..Default::default()
+		};
+
+		assert_eq!(merge_snapshot(&mut spec, snapshot), Ok(()));
+		assert_eq!(spec["genesis"]["runtime"]["nftmartConf"]["categoryList"], json!([[97], [98]]));
+		assert_eq!(spec["genesis"]["runtime"]["nftmart"]["classes"][0]["class_id"], json!(3));
+		assert_eq!(
+			spec["genesis"]["runtime"]["nftmart"]["classes"][0]["category_ids"],
+			json!([1, 0])
+		);
+		assert_eq!(spec["genesis"]["runtime"]["nftmart"]["operatorApprovals"][0][2], json!(5));
+		assert_eq!(spec["genesis"]["runtime"]["nftmart"]["tokenApprovals"], json!([]));
+		assert_eq!(spec["genesis"]["runtime"]["nftmartRental"]["users"][0][3]["expires"], json!(8));
+
+		let snapshot = NftSnapshot {
+			classes: vec![ClassConfig { class_id: 3, ..Default::default() }],
+			..Default::default()
+		};
+		assert_eq!(
+			merge_snapshot(&mut spec, snapshot),
+			Err("Class 3 is already in the chain spec".to_string())
+		);
+		assert!(merge_snapshot(&mut json!({ "genesis": { "raw": {} } }), NftSnapshot::default())
+			.is_err());
+	}
+
+	#[test]
+	fn should_hex_encode_metadata_which_is_not_utf8() {
+		assert_eq!(metadata_string(b"abc"), "abc");
+		assert_eq!(metadata_string(&[0xff, 0x01]), "0xff01");
+		assert_eq!(genesis_string(b"abc", || unreachable!()).unwrap(), "abc");
+		assert_eq!(
+			genesis_string(&[0xff], || "class 1".into()).unwrap_err().to_string(),
+			"The metadata of class 1 is not UTF-8: 0xff"
+		);
+	}
+}
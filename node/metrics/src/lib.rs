@@ -0,0 +1,556 @@
+//! Prometheus metrics of the NFTMart marketplace.
+//!
+//! The counters are fed by the marketplace events of the blocks finalized while the node is
+//! running, and the platform fees by the fees routed in the extrinsics of the trades. The gauges
+//! reflect the listings in the state of the finalized block, refreshed at a throttled interval
+//! since they scan every listing. Prices are in the smallest unit of their currency.
+
+use codec::Decode;
+use frame_support::storage::StoragePrefixedMap;
+use futures::StreamExt;
+use nftmart_indexer::{read_storage, trade_price, Chain, ClientChain, EventRecord, TradeKind};
+use nftmart_rpc::NFTMartRuntimeApi;
+use nftmart_traits::{CurrencyId, GlobalId};
+use node_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Event, Hash, Runtime};
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Gauge, GaugeVec, Opts, PrometheusError, Registry, F64, U64,
+};
+use sc_client_api::{backend::Backend, BlockchainEvents, StorageProvider};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "nftmart-metrics";
+
+/// The metrics registered with the Prometheus registry of the node.
+#[derive(Clone)]
+pub struct MarketplaceMetrics {
+	/// The open listings, by kind.
+	pub open_listings: GaugeVec<U64>,
+	/// The auctions past their deadline which are neither redeemed nor removed, by kind.
+	pub expired_unsettled_auctions: GaugeVec<U64>,
+	/// The trades, by kind.
+	pub trades: CounterVec<U64>,
+	/// The value of the trades, by currency.
+	pub trade_volume: CounterVec<F64>,
+	/// The platform fees taken from the trades, by currency.
+	pub platform_fees: CounterVec<F64>,
+	/// The quantity of the minted NFTs.
+	pub minted_tokens: Counter<U64>,
+	/// The quantity of the burned NFTs.
+	pub burned_tokens: Counter<U64>,
+	/// The last finalized block the metrics are updated to.
+	pub finalized_block: Gauge<U64>,
+}
+
+impl MarketplaceMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			open_listings: register(
+				GaugeVec::new(
+					Opts::new("nftmart_open_listings", "Number of open listings"),
+					&["kind"],
+				)?,
+				registry,
+			)?,
+			expired_unsettled_auctions: register(
+				GaugeVec::new(
+					Opts::new(
+						"nftmart_expired_unsettled_auctions",
+						"Number of auctions past their deadline which are not settled",
+					),
+					&["kind"],
+				)?,
+				registry,
+			)?,
+			trades: register(
+				CounterVec::new(Opts::new("nftmart_trades_total", "Number of trades"), &["kind"])?,
+				registry,
+			)?,
+			trade_volume: register(
+				CounterVec::new(
+					Opts::new("nftmart_trade_volume_total", "Value of the trades"),
+					&["currency_id"],
+				)?,
+				registry,
+			)?,
+			platform_fees: register(
+				CounterVec::new(
+					Opts::new("nftmart_platform_fees_total", "Platform fees taken from the trades"),
+					&["currency_id"],
+				)?,
+				registry,
+			)?,
+			minted_tokens: register(
+				Counter::new("nftmart_minted_tokens_total", "Quantity of the minted NFTs")?,
+				registry,
+			)?,
+			burned_tokens: register(
+				Counter::new("nftmart_burned_tokens_total", "Quantity of the burned NFTs")?,
+				registry,
+			)?,
+			finalized_block: register(
+				Gauge::new(
+					"nftmart_metrics_finalized_block",
+					"Last finalized block the marketplace metrics are updated to",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Updates the [`MarketplaceMetrics`] whenever a block is finalized.
+pub struct MetricsWorker<C, BE> {
+	client: Arc<C>,
+	chain: ClientChain<C, BE>,
+	metrics: MarketplaceMetrics,
+	/// The last block whose events are counted.
+	last_block: BlockNumber,
+	/// The least time between two refreshes of the listing gauges.
+	refresh_interval: Duration,
+	/// When and at which finalized block the listing gauges were last refreshed.
+	last_refresh: Option<(Instant, BlockNumber)>,
+}
+
+impl<C, BE> MetricsWorker<C, BE>
+where
+	BE: Backend<Block> + 'static,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE> + BlockchainEvents<Block>,
+	C: ProvideRuntimeApi<Block> + Send + Sync + 'static,
+	C::Api: NFTMartRuntimeApi<Block>,
+{
+	/// Register the metrics with `registry`, refreshing the listing gauges once per
+	/// `refresh_interval` at most. Only the blocks finalized from now on are counted.
+	pub fn new(
+		client: Arc<C>,
+		registry: &Registry,
+		refresh_interval: Duration,
+	) -> Result<Self, PrometheusError> {
+		let metrics = MarketplaceMetrics::register(registry)?;
+		let last_block = client.info().finalized_number;
+		let chain = ClientChain::new(client.clone());
+		Ok(Self { client, chain, metrics, last_block, refresh_interval, last_refresh: None })
+	}
+
+	/// Catch up with the finalized block whenever the finality advances.
+	pub async fn run(mut self) {
+		let mut notifications = self.client.finality_notification_stream();
+		self.update_logged();
+		while notifications.next().await.is_some() {
+			self.update_logged();
+		}
+	}
+
+	fn update_logged(&mut self) {
+		if let Err(e) = self.update() {
+			log::error!(target: LOG_TARGET, "Failed to update the marketplace metrics: {}", e);
+		}
+	}
+
+	/// Count the events of the blocks finalized since the last update, then refresh the gauges
+	/// from the state of the finalized block if it advanced and the refresh interval is over.
+	pub fn update(&mut self) -> Result<(), String> {
+		let info = self.client.info();
+		while self.last_block < info.finalized_number {
+			count_block(&self.chain, &self.metrics, self.last_block + 1)?;
+			self.last_block += 1;
+			self.metrics.finalized_block.set(self.last_block.into());
+		}
+		let fresh = self.last_refresh.map_or(false, |(at, block)| {
+			block == info.finalized_number || at.elapsed() < self.refresh_interval
+		});
+		if fresh {
+			return Ok(())
+		}
+		self.refresh_listings(info.finalized_hash, info.finalized_number)?;
+		self.last_refresh = Some((Instant::now(), info.finalized_number));
+		Ok(())
+	}
+
+	/// Set the listing gauges from the state of block `at`.
+	fn refresh_listings(&self, at: Hash, number: BlockNumber) -> Result<(), String> {
+		use nftmart_auction::{
+			BritishAuctionBids, BritishAuctions, DutchAuctionBids, DutchAuctions,
+		};
+		use nftmart_order::{Offers, Orders, Swaps};
+
+		for (kind, prefix) in [
+			("order", Orders::<Runtime>::final_prefix()),
+			("offer", Offers::<Runtime>::final_prefix()),
+			("swap", Swaps::<Runtime>::final_prefix()),
+		] {
+			let count = self.keys(at, &prefix)?.len() as u64;
+			self.metrics.open_listings.with_label_values(&[kind]).set(count);
+		}
+
+		let api = self.client.runtime_api();
+		let block_id = BlockId::Hash(at);
+
+		let british: Vec<(GlobalId, nftmart_auction::BritishAuctionOf<Runtime>)> =
+			self.auctions(at, &BritishAuctions::<Runtime>::final_prefix())?;
+		let mut expired = 0u64;
+		for (id, auction) in &british {
+			let bid: Option<nftmart_auction::BritishAuctionBidOf<Runtime>> =
+				read_storage(&*self.client, at, BritishAuctionBids::<Runtime>::hashed_key_for(id))?;
+			let deadline = match bid {
+				Some(bid) if bid.last_bid_account.is_some() => api
+					.get_auction_deadline(
+						&block_id,
+						auction.allow_delay,
+						auction.deadline,
+						bid.last_bid_block,
+						auction.soft_close.clone(),
+					)
+					.map_err(|e| format!("{:?}", e))?,
+				_ => auction.deadline,
+			};
+			if number > deadline {
+				expired += 1;
+			}
+		}
+		self.metrics
+			.open_listings
+			.with_label_values(&["british_auction"])
+			.set(british.len() as u64);
+		self.metrics
+			.expired_unsettled_auctions
+			.with_label_values(&["british_auction"])
+			.set(expired);
+
+		let dutch: Vec<(GlobalId, nftmart_auction::DutchAuctionOf<Runtime>)> =
+			self.auctions(at, &DutchAuctions::<Runtime>::final_prefix())?;
+		let mut expired = 0u64;
+		for (id, auction) in &dutch {
+			let bid: Option<nftmart_auction::DutchAuctionBidOf<Runtime>> =
+				read_storage(&*self.client, at, DutchAuctionBids::<Runtime>::hashed_key_for(id))?;
+			let deadline = match bid {
+				// A bid turns a dutch auction into a british auction which closes after a delay.
+				Some(bid) if bid.last_bid_account.is_some() => api
+					.get_auction_deadline(&block_id, true, 0, bid.last_bid_block, None)
+					.map_err(|e| format!("{:?}", e))?,
+				_ => auction.deadline,
+			};
+			if number > deadline {
+				expired += 1;
+			}
+		}
+		self.metrics
+			.open_listings
+			.with_label_values(&["dutch_auction"])
+			.set(dutch.len() as u64);
+		self.metrics
+			.expired_unsettled_auctions
+			.with_label_values(&["dutch_auction"])
+			.set(expired);
+		Ok(())
+	}
+
+	fn keys(&self, at: Hash, prefix: &[u8]) -> Result<Vec<StorageKey>, String> {
+		self.client
+			.storage_keys(&BlockId::Hash(at), &StorageKey(prefix.to_vec()))
+			.map_err(|e| e.to_string())
+	}
+
+	/// The auctions under `prefix` in the state of block `at`, with their ids.
+	fn auctions<T: Decode>(&self, at: Hash, prefix: &[u8]) -> Result<Vec<(GlobalId, T)>, String> {
+		self.client
+			.storage_pairs(&BlockId::Hash(at), &StorageKey(prefix.to_vec()))
+			.map_err(|e| e.to_string())?
+			.into_iter()
+			.map(|(key, data)| {
+				// prefix ++ blake2_128(owner) ++ owner ++ twox_64(auction_id) ++ auction_id
+				let id =
+					key.0.len().checked_sub(8).map(|start| &key.0[start..]).unwrap_or_default();
+				let id = GlobalId::decode(&mut &id[..]).map_err(|e| e.to_string())?;
+				let auction = T::decode(&mut &data.0[..]).map_err(|e| e.to_string())?;
+				Ok((id, auction))
+			})
+			.collect()
+	}
+}
+
+/// Count the trades, mints and burns of the block at `number` in the best chain.
+pub fn count_block<Ch: Chain>(
+	chain: &Ch,
+	metrics: &MarketplaceMetrics,
+	number: BlockNumber,
+) -> Result<(), String> {
+	let hash = chain.hash(number)?.ok_or_else(|| format!("block #{} not found", number))?;
+	let parent_hash = chain
+		.parent_hash(hash)?
+		.ok_or_else(|| format!("header of block #{} not found", number))?;
+	let mut block = BlockCounter {
+		chain,
+		metrics,
+		number,
+		parent_hash,
+		sellers: HashMap::new(),
+		fees: HashMap::new(),
+	};
+	for record in chain.events(hash)? {
+		block.handle_event(record)?;
+	}
+	Ok(())
+}
+
+/// The state of counting the events of a single block.
+struct BlockCounter<'a, Ch> {
+	chain: &'a Ch,
+	metrics: &'a MarketplaceMetrics,
+	number: BlockNumber,
+	parent_hash: Hash,
+	/// The senders of the NFTs received in each extrinsic, by the receiver.
+	sellers: HashMap<(Option<u32>, AccountId), AccountId>,
+	/// The platform fees routed in each extrinsic which are not attributed to a trade yet.
+	fees: HashMap<Option<u32>, Vec<(CurrencyId, Balance)>>,
+}
+
+impl<'a, Ch: Chain> BlockCounter<'a, Ch> {
+	fn handle_event(&mut self, record: EventRecord) -> Result<(), String> {
+		let phase = match record.phase {
+			frame_system::Phase::ApplyExtrinsic(i) => Some(i),
+			_ => None,
+		};
+		match record.event {
+			Event::Nftmart(event) => self.handle_nft_event(phase, event),
+			Event::NftmartConf(nftmart_config::Event::RoutedFee(_, currency_id, fee)) =>
+				self.fees.entry(phase).or_default().push((currency_id, fee)),
+			Event::NftmartOrder(event) => self.handle_order_event(phase, event)?,
+			Event::NftmartAuction(event) => self.handle_auction_event(phase, event)?,
+			_ => {},
+		}
+		Ok(())
+	}
+
+	fn handle_nft_event(&mut self, phase: Option<u32>, event: nftmart_nft::Event<Runtime>) {
+		use nftmart_nft::Event::*;
+		match event {
+			MintedToken(_, _, _, _, quantity) => self.metrics.minted_tokens.inc_by(quantity),
+			BurnedToken(_, _, _, quantity, _) => self.metrics.burned_tokens.inc_by(quantity),
+			TransferredToken(from, to, ..) => {
+				self.sellers.insert((phase, to), from);
+			},
+			_ => {},
+		}
+	}
+
+	fn handle_order_event(
+		&mut self,
+		phase: Option<u32>,
+		event: nftmart_order::Event<Runtime>,
+	) -> Result<(), String> {
+		use nftmart_order::Event::*;
+		let (kind, owner, id) = match event {
+			TakenOrder(_, owner, id, ..) => (TradeKind::Order, owner, id),
+			TakenOffer(_, owner, id, ..) => (TradeKind::Offer, owner, id),
+			TakenSwap(_, maker, id) => (TradeKind::Swap, maker, id),
+			_ => return Ok(()),
+		};
+		let price = self.trade_price(kind, &owner, id, false)?;
+		self.note_trade(phase, kind, price);
+		Ok(())
+	}
+
+	fn handle_auction_event(
+		&mut self,
+		phase: Option<u32>,
+		event: nftmart_auction::Event<Runtime>,
+	) -> Result<(), String> {
+		use nftmart_auction::Event::*;
+		let (kind, purchaser, id, hammered) = match event {
+			HammerBritishAuction(purchaser, id, ..) =>
+				(TradeKind::BritishAuction, purchaser, id, true),
+			RedeemedBritishAuction(purchaser, id, ..) =>
+				(TradeKind::BritishAuction, purchaser, id, false),
+			RedeemedDutchAuction(purchaser, id, ..) =>
+				(TradeKind::DutchAuction, purchaser, id, false),
+			_ => return Ok(()),
+		};
+		// The auction is keyed by its owner, who sends the NFTs to the purchaser.
+		let price = match self.sellers.get(&(phase, purchaser)).cloned() {
+			Some(seller) => self.trade_price(kind, &seller, id, hammered)?,
+			None => {
+				log::warn!(
+					target: LOG_TARGET,
+					"No NFT was transferred by auction #{} in block #{}",
+					id,
+					self.number,
+				);
+				None
+			},
+		};
+		self.note_trade(phase, kind, price);
+		Ok(())
+	}
+
+	fn trade_price(
+		&self,
+		kind: TradeKind,
+		owner: &AccountId,
+		id: GlobalId,
+		hammered: bool,
+	) -> Result<Option<(CurrencyId, Balance)>, String> {
+		trade_price(self.chain, self.parent_hash, self.number, kind, owner, id, hammered)
+	}
+
+	/// Count a trade of `kind`, its volume when the price is known, and the platform fees routed
+	/// in the extrinsic before it.
+	fn note_trade(
+		&mut self,
+		phase: Option<u32>,
+		kind: TradeKind,
+		price: Option<(CurrencyId, Balance)>,
+	) {
+		let metrics = self.metrics;
+		metrics.trades.with_label_values(&[trade_label(kind)]).inc();
+		if let Some((currency_id, price)) = price {
+			let currency_id = currency_id.to_string();
+			metrics.trade_volume.with_label_values(&[&currency_id]).inc_by(price as f64);
+		}
+		for (currency_id, fee) in self.fees.remove(&phase).unwrap_or_default() {
+			let currency_id = currency_id.to_string();
+			metrics.platform_fees.with_label_values(&[&currency_id]).inc_by(fee as f64);
+		}
+	}
+}
+
+/// The `kind` label of a trade.
+fn trade_label(kind: TradeKind) -> &'static str {
+	match kind {
+		TradeKind::Order => "order",
+		TradeKind::Offer => "offer",
+		TradeKind::BritishAuction => "british_auction",
+		TradeKind::DutchAuction => "dutch_auction",
+		TradeKind::Swap => "swap",
+		TradeKind::Voucher => "voucher",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_system::Phase;
+	use nftmart_order::Orders;
+	use nftmart_traits::OrderItem;
+	use sp_core::crypto::AccountId32;
+	use sp_runtime::PerU16;
+
+	const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+	const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+
+	#[derive(Default)]
+	struct TestChain {
+		/// The hashes of the best chain from block #1.
+		best: Vec<Hash>,
+		parents: HashMap<Hash, Hash>,
+		storage: HashMap<(Hash, Vec<u8>), Vec<u8>>,
+	}
+
+	impl TestChain {
+		fn push(&mut self, id: u8, events: Vec<(u32, Event)>) -> Hash {
+			let hash = Hash::repeat_byte(id);
+			let parent_hash = self.best.last().copied().unwrap_or_default();
+			self.best.push(hash);
+			self.parents.insert(hash, parent_hash);
+			let records: Vec<_> = events
+				.into_iter()
+				.map(|(i, event)| EventRecord {
+					phase: Phase::ApplyExtrinsic(i),
+					event,
+					topics: vec![],
+				})
+				.collect();
+			self.storage.insert((hash, nftmart_indexer::events_key()), records.encode());
+			hash
+		}
+	}
+
+	impl Chain for TestChain {
+		fn best_and_finalized(&self) -> (BlockNumber, BlockNumber) {
+			(self.best.len() as BlockNumber, self.best.len() as BlockNumber)
+		}
+
+		fn hash(&self, number: BlockNumber) -> Result<Option<Hash>, String> {
+			Ok(number.checked_sub(1).and_then(|i| self.best.get(i as usize)).copied())
+		}
+
+		fn parent_hash(&self, hash: Hash) -> Result<Option<Hash>, String> {
+			Ok(self.parents.get(&hash).copied())
+		}
+
+		fn storage(&self, at: Hash, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+			Ok(self.storage.get(&(at, key.to_vec())).cloned())
+		}
+	}
+
+	fn order(price: Balance) -> nftmart_order::OrderOf<Runtime> {
+		nftmart_order::Order {
+			currency_id: 0,
+			deposit: 10,
+			price,
+			deadline: 100,
+			items: vec![OrderItem { class_id: 1, token_id: 2, quantity: 1 }],
+			commission_rate: PerU16::zero(),
+		}
+	}
+
+	fn routed_fee(fee: Balance) -> Event {
+		Event::NftmartConf(nftmart_config::Event::RoutedFee(ALICE, 0, fee))
+	}
+
+	fn taken_order(id: GlobalId) -> Event {
+		Event::NftmartOrder(nftmart_order::Event::TakenOrder(BOB, ALICE, id, None, None))
+	}
+
+	#[test]
+	fn trades_should_be_counted_with_the_routed_fees() {
+		let mut chain = TestChain::default();
+		let hash = chain.push(1, vec![]);
+		for (id, price) in [(7, 100), (8, 300)] {
+			let key = Orders::<Runtime>::hashed_key_for(&ALICE, id);
+			chain.storage.insert((hash, key), order(price).encode());
+		}
+		chain.push(
+			2,
+			vec![
+				(0, routed_fee(3)),
+				(0, taken_order(7)),
+				// The fee of another extrinsic isn't taken from the order of the first one.
+				(1, routed_fee(5)),
+				(1, taken_order(8)),
+				(2, Event::Nftmart(nftmart_nft::Event::MintedToken(ALICE, ALICE, 1, 3, 4))),
+			],
+		);
+
+		let metrics = MarketplaceMetrics::register(&Registry::new()).unwrap();
+		count_block(&chain, &metrics, 2).unwrap();
+
+		assert_eq!(metrics.trades.with_label_values(&["order"]).get(), 2);
+		assert_eq!(metrics.trade_volume.with_label_values(&["0"]).get(), 400.0);
+		assert_eq!(metrics.platform_fees.with_label_values(&["0"]).get(), 8.0);
+		assert_eq!(metrics.minted_tokens.get(), 4);
+	}
+
+	#[test]
+	fn fees_without_a_trade_should_not_be_counted() {
+		let mut chain = TestChain::default();
+		chain.push(1, vec![]);
+		// A fee routed by a drop, and a trade whose listing is unknown.
+		chain.push(2, vec![(0, routed_fee(3)), (1, taken_order(9))]);
+
+		let metrics = MarketplaceMetrics::register(&Registry::new()).unwrap();
+		count_block(&chain, &metrics, 2).unwrap();
+
+		assert_eq!(metrics.trades.with_label_values(&["order"]).get(), 1);
+		assert_eq!(metrics.trade_volume.with_label_values(&["0"]).get(), 0.0);
+		assert_eq!(metrics.platform_fees.with_label_values(&["0"]).get(), 0.0);
+	}
+}
@@ -0,0 +1,431 @@
+//! The `debug_traceTransaction` RPC method, which replays an Ethereum transaction in the EVM
+//! tracer of the runtime.
+
+use fc_db::TransactionMetadata;
+use fc_rpc_core::types::Bytes;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use nftmart_evm_tracing::{CallKind, CallTrace, EvmTracingApi, Step, TraceConfig};
+use node_runtime::{
+	opaque::{Block, Header, UncheckedExtrinsic},
+	Hash,
+};
+use sc_client_api::BlockBackend;
+use serde::{Deserialize, Serialize};
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hexdisplay::HexDisplay, H160, H256, U256};
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use std::{collections::BTreeMap, sync::Arc};
+
+#[rpc]
+pub trait NftmartDebugApi {
+	/// Replay the Ethereum transaction `hash` and return its executed opcodes, in the format of
+	/// the default struct logger of Geth, or its calls with the `callTracer` tracer.
+	#[rpc(name = "debug_traceTransaction")]
+	fn trace_transaction(&self, hash: H256, params: Option<TraceParams>) -> Result<TraceResponse>;
+}
+
+/// The options of `debug_traceTransaction`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceParams {
+	/// Leave the stack out of the opcodes.
+	pub disable_stack: Option<bool>,
+	/// Leave the memory out of the opcodes.
+	pub disable_memory: Option<bool>,
+	/// Leave the storage out of the opcodes.
+	pub disable_storage: Option<bool>,
+	/// `callTracer` to trace the calls only, the opcodes are traced by default.
+	pub tracer: Option<String>,
+}
+
+/// The trace of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum TraceResponse {
+	/// The executed opcodes.
+	Steps(StepsTrace),
+	/// The call frames.
+	Calls(CallFrame),
+}
+
+/// The trace of the default struct logger of Geth.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepsTrace {
+	/// The gas used by the transaction.
+	pub gas: u64,
+	/// Whether the transaction failed.
+	pub failed: bool,
+	/// The returned value, hex encoded without prefix.
+	pub return_value: String,
+	/// The executed opcodes.
+	pub struct_logs: Vec<StructLog>,
+}
+
+/// An executed opcode, the words of the stack, the memory and the storage being hex encoded
+/// without prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+	/// The program counter.
+	pub pc: u64,
+	/// The name of the opcode, such as `PUSH1`.
+	pub op: String,
+	/// The gas left before the opcode.
+	pub gas: u64,
+	/// The gas cost of the opcode.
+	pub gas_cost: u64,
+	/// The depth of the call frame, from 1.
+	pub depth: u32,
+	/// The stack, from the bottom.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stack: Option<Vec<String>>,
+	/// The memory, by words of 32 bytes.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub memory: Option<Vec<String>>,
+	/// The storage of the contract accessed so far, for the opcodes reading or writing it.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub storage: Option<BTreeMap<String, String>>,
+}
+
+/// A call frame of the `callTracer` tracer of Geth.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+	/// `CALL`, `CALLCODE`, `DELEGATECALL`, `STATICCALL`, `CREATE`, `CREATE2` or `SELFDESTRUCT`.
+	#[serde(rename = "type")]
+	pub kind: String,
+	/// The caller.
+	pub from: H160,
+	/// The callee, or the created contract.
+	pub to: H160,
+	/// The value transferred.
+	pub value: U256,
+	/// The gas given to the frame.
+	pub gas: U256,
+	/// The gas used by the frame.
+	pub gas_used: U256,
+	/// The call data, or the init code.
+	pub input: Bytes,
+	/// The returned data, or the code of the created contract.
+	pub output: Bytes,
+	/// Why the frame failed, if it did.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// The subcalls.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub calls: Vec<CallFrame>,
+}
+
+impl From<CallTrace> for CallFrame {
+	fn from(call: CallTrace) -> Self {
+		let kind = match call.kind {
+			CallKind::Call => "CALL",
+			CallKind::CallCode => "CALLCODE",
+			CallKind::DelegateCall => "DELEGATECALL",
+			CallKind::StaticCall => "STATICCALL",
+			CallKind::Create => "CREATE",
+			CallKind::Create2 => "CREATE2",
+			CallKind::SelfDestruct => "SELFDESTRUCT",
+		};
+		Self {
+			kind: kind.into(),
+			from: call.from,
+			to: call.to,
+			value: call.value,
+			gas: call.gas.into(),
+			gas_used: call.gas_used.into(),
+			input: Bytes(call.input),
+			output: Bytes(call.output),
+			error: call.error.map(|e| String::from_utf8_lossy(&e).into_owned()),
+			calls: vec![],
+		}
+	}
+}
+
+/// Nest the calls, entered in this order, under the transaction.
+fn call_tree(calls: Vec<CallTrace>) -> Option<CallFrame> {
+	fn close(frames: &mut Vec<CallFrame>) {
+		if let Some(frame) = frames.pop() {
+			match frames.last_mut() {
+				Some(parent) => parent.calls.push(frame),
+				None => frames.push(frame),
+			}
+		}
+	}
+	let mut frames: Vec<CallFrame> = vec![];
+	for call in calls {
+		while frames.len() > call.trace_address.len() {
+			close(&mut frames);
+		}
+		frames.push(call.into());
+	}
+	while frames.len() > 1 {
+		close(&mut frames);
+	}
+	frames.pop()
+}
+
+fn hex(bytes: &[u8]) -> String {
+	HexDisplay::from(&bytes).to_string()
+}
+
+impl From<Step> for StructLog {
+	fn from(step: Step) -> Self {
+		Self {
+			pc: step.pc,
+			op: opcode_name(step.op),
+			gas: step.gas,
+			gas_cost: step.gas_cost,
+			depth: step.depth,
+			stack: step.stack.map(|stack| stack.iter().map(|word| hex(word.as_bytes())).collect()),
+			memory: step.memory.map(|memory| memory.chunks(32).map(hex).collect()),
+			storage: step.storage.map(|storage| {
+				storage
+					.iter()
+					.map(|(key, value)| (hex(key.as_bytes()), hex(value.as_bytes())))
+					.collect()
+			}),
+		}
+	}
+}
+
+pub(crate) fn tracing_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to trace the transaction.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+/// The header and the extrinsics of the block `hash`, to be replayed on top of its parent.
+pub(crate) fn replayed_block<C>(client: &C, hash: Hash) -> Result<(Header, Vec<UncheckedExtrinsic>)>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block>,
+{
+	let id = BlockId::hash(hash);
+	let header = client.header(id).map_err(tracing_error)?;
+	let extrinsics = client.block_body(&id).map_err(tracing_error)?;
+	match (header, extrinsics) {
+		(Some(header), Some(extrinsics)) => Ok((header, extrinsics)),
+		_ => Err(RpcError::invalid_params(format!("Block {:?} not found", hash))),
+	}
+}
+
+/// Where the Ethereum transaction `hash` is in the canonical chain.
+pub(crate) fn transaction_metadata<C>(
+	client: &C,
+	backend: &fc_db::Backend<Block>,
+	hash: H256,
+) -> Result<Option<TransactionMetadata<Block>>>
+where
+	C: HeaderBackend<Block>,
+{
+	let metadata = backend.mapping().transaction_metadata(&hash).map_err(tracing_error)?;
+	for m in metadata {
+		if let Some(number) = client.number(m.block_hash).map_err(tracing_error)? {
+			if client.hash(number).map_err(tracing_error)? == Some(m.block_hash) {
+				return Ok(Some(m))
+			}
+		}
+	}
+	Ok(None)
+}
+
+/// A struct that implements the [`NftmartDebugApi`].
+pub struct NftmartDebug<C> {
+	client: Arc<C>,
+	backend: Arc<fc_db::Backend<Block>>,
+}
+
+impl<C> NftmartDebug<C> {
+	/// Create new `NftmartDebug` finding the Ethereum transactions in `backend`.
+	pub fn new(client: Arc<C>, backend: Arc<fc_db::Backend<Block>>) -> Self {
+		Self { client, backend }
+	}
+}
+
+impl<C> NftmartDebugApi for NftmartDebug<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: Send + Sync + 'static,
+	C::Api: Core<Block> + EvmTracingApi<Block>,
+{
+	fn trace_transaction(&self, hash: H256, params: Option<TraceParams>) -> Result<TraceResponse> {
+		let params = params.unwrap_or_default();
+		let steps = match params.tracer.as_deref() {
+			None => true,
+			Some("callTracer") => false,
+			Some(tracer) =>
+				return Err(RpcError::invalid_params(format!(
+					"Unsupported tracer `{}`, expected `callTracer` or none",
+					tracer
+				))),
+		};
+		let config = TraceConfig {
+			steps,
+			disable_stack: params.disable_stack.unwrap_or_default(),
+			disable_memory: params.disable_memory.unwrap_or_default(),
+			disable_storage: params.disable_storage.unwrap_or_default(),
+		};
+
+		let block_hash = transaction_metadata(&*self.client, &self.backend, hash)?
+			.ok_or_else(|| RpcError::invalid_params(format!("Transaction {:?} not found", hash)))?
+			.block_hash;
+		let (header, extrinsics) = replayed_block(&*self.client, block_hash)?;
+		let parent = BlockId::hash(*header.parent_hash());
+		let api = self.client.runtime_api();
+		api.initialize_block(&parent, &header).map_err(tracing_error)?;
+		let trace = api
+			.trace_transaction(&parent, extrinsics, hash, config)
+			.map_err(tracing_error)?
+			.map_err(tracing_error)?;
+
+		if !steps {
+			return call_tree(trace.calls)
+				.map(TraceResponse::Calls)
+				.ok_or_else(|| tracing_error("No call traced"))
+		}
+		Ok(TraceResponse::Steps(StepsTrace {
+			gas: trace.gas_used(),
+			failed: trace.failed(),
+			return_value: hex(trace.return_value()),
+			struct_logs: trace.steps.into_iter().map(Into::into).collect(),
+		}))
+	}
+}
+
+/// The mnemonic of an opcode, as printed by Geth.
+fn opcode_name(op: u8) -> String {
+	let name = match op {
+		0x00 => "STOP",
+		0x01 => "ADD",
+		0x02 => "MUL",
+		0x03 => "SUB",
+		0x04 => "DIV",
+		0x05 => "SDIV",
+		0x06 => "MOD",
+		0x07 => "SMOD",
+		0x08 => "ADDMOD",
+		0x09 => "MULMOD",
+		0x0a => "EXP",
+		0x0b => "SIGNEXTEND",
+		0x10 => "LT",
+		0x11 => "GT",
+		0x12 => "SLT",
+		0x13 => "SGT",
+		0x14 => "EQ",
+		0x15 => "ISZERO",
+		0x16 => "AND",
+		0x17 => "OR",
+		0x18 => "XOR",
+		0x19 => "NOT",
+		0x1a => "BYTE",
+		0x1b => "SHL",
+		0x1c => "SHR",
+		0x1d => "SAR",
+		0x20 => "SHA3",
+		0x30 => "ADDRESS",
+		0x31 => "BALANCE",
+		0x32 => "ORIGIN",
+		0x33 => "CALLER",
+		0x34 => "CALLVALUE",
+		0x35 => "CALLDATALOAD",
+		0x36 => "CALLDATASIZE",
+		0x37 => "CALLDATACOPY",
+		0x38 => "CODESIZE",
+		0x39 => "CODECOPY",
+		0x3a => "GASPRICE",
+		0x3b => "EXTCODESIZE",
+		0x3c => "EXTCODECOPY",
+		0x3d => "RETURNDATASIZE",
+		0x3e => "RETURNDATACOPY",
+		0x3f => "EXTCODEHASH",
+		0x40 => "BLOCKHASH",
+		0x41 => "COINBASE",
+		0x42 => "TIMESTAMP",
+		0x43 => "NUMBER",
+		0x44 => "DIFFICULTY",
+		0x45 => "GASLIMIT",
+		0x46 => "CHAINID",
+		0x47 => "SELFBALANCE",
+		0x48 => "BASEFEE",
+		0x50 => "POP",
+		0x51 => "MLOAD",
+		0x52 => "MSTORE",
+		0x53 => "MSTORE8",
+		0x54 => "SLOAD",
+		0x55 => "SSTORE",
+		0x56 => "JUMP",
+		0x57 => "JUMPI",
+		0x58 => "PC",
+		0x59 => "MSIZE",
+		0x5a => "GAS",
+		0x5b => "JUMPDEST",
+		0x60..=0x7f => return format!("PUSH{}", op - 0x5f),
+		0x80..=0x8f => return format!("DUP{}", op - 0x7f),
+		0x90..=0x9f => return format!("SWAP{}", op - 0x8f),
+		0xa0..=0xa4 => return format!("LOG{}", op - 0xa0),
+		0xf0 => "CREATE",
+		0xf1 => "CALL",
+		0xf2 => "CALLCODE",
+		0xf3 => "RETURN",
+		0xf4 => "DELEGATECALL",
+		0xf5 => "CREATE2",
+		0xfa => "STATICCALL",
+		0xfd => "REVERT",
+		0xfe => "INVALID",
+		0xff => "SELFDESTRUCT",
+		_ => return format!("opcode {:#04x} not defined", op),
+	};
+	name.into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn call(trace_address: Vec<u32>, to: u64) -> CallTrace {
+		CallTrace {
+			trace_address,
+			kind: CallKind::Call,
+			from: H160::zero(),
+			to: H160::from_low_u64_be(to),
+			value: U256::zero(),
+			gas: 0,
+			gas_used: 0,
+			input: vec![],
+			output: vec![],
+			error: None,
+			subtraces: 0,
+		}
+	}
+
+	#[test]
+	fn calls_should_be_nested() {
+		let tree = call_tree(vec![
+			call(vec![], 1),
+			call(vec![0], 2),
+			call(vec![0, 0], 3),
+			call(vec![1], 4),
+		])
+		.unwrap();
+		let to = |frame: &CallFrame| frame.to.to_low_u64_be();
+		assert_eq!(to(&tree), 1);
+		assert_eq!(tree.calls.iter().map(to).collect::<Vec<_>>(), vec![2, 4]);
+		assert_eq!(tree.calls[0].calls.iter().map(to).collect::<Vec<_>>(), vec![3]);
+		assert!(tree.calls[1].calls.is_empty());
+	}
+
+	#[test]
+	fn opcodes_should_be_named() {
+		assert_eq!(opcode_name(0x60), "PUSH1");
+		assert_eq!(opcode_name(0x7f), "PUSH32");
+		assert_eq!(opcode_name(0x8f), "DUP16");
+		assert_eq!(opcode_name(0x90), "SWAP1");
+		assert_eq!(opcode_name(0xa4), "LOG4");
+		assert_eq!(opcode_name(0x55), "SSTORE");
+	}
+}
@@ -0,0 +1,74 @@
+//! The `engine_fastForward` RPC method of the development sealing mode.
+
+use futures::{
+	channel::{mpsc, oneshot},
+	FutureExt, SinkExt,
+};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_runtime::Hash;
+use sc_consensus_manual_seal::EngineCommand;
+
+/// The most blocks sealed by a single `engine_fastForward` call.
+pub const MAX_FAST_FORWARD: u32 = 10_000;
+
+#[rpc]
+pub trait NftmartDevApi {
+	/// Seal `blocks` blocks one after the other, finalizing them unless `finalize` is false, and
+	/// return the hash of the last one.
+	///
+	/// Each block moves the timestamp by one slot, so that deadlines and price curves counted in
+	/// blocks are reached in seconds.
+	#[rpc(name = "engine_fastForward")]
+	fn fast_forward(&self, blocks: u32, finalize: Option<bool>) -> BoxFuture<Result<Hash>>;
+}
+
+/// A struct that implements the [`NftmartDevApi`].
+pub struct NftmartDev {
+	command_sink: mpsc::Sender<EngineCommand<Hash>>,
+}
+
+impl NftmartDev {
+	/// Create new `NftmartDev` sending the commands of the sealing task to `command_sink`.
+	pub fn new(command_sink: mpsc::Sender<EngineCommand<Hash>>) -> Self {
+		Self { command_sink }
+	}
+}
+
+fn sealing_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to seal the block.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl NftmartDevApi for NftmartDev {
+	fn fast_forward(&self, blocks: u32, finalize: Option<bool>) -> BoxFuture<Result<Hash>> {
+		let mut command_sink = self.command_sink.clone();
+		async move {
+			if blocks == 0 || blocks > MAX_FAST_FORWARD {
+				return Err(RpcError::invalid_params(format!(
+					"Expected between 1 and {} blocks",
+					MAX_FAST_FORWARD
+				)))
+			}
+			let mut hash = Hash::default();
+			for _ in 0..blocks {
+				let (sender, receiver) = oneshot::channel();
+				command_sink
+					.send(EngineCommand::SealNewBlock {
+						create_empty: true,
+						finalize: finalize.unwrap_or(true),
+						parent_hash: None,
+						sender: Some(sender),
+					})
+					.await
+					.map_err(sealing_error)?;
+				hash = receiver.await.map_err(sealing_error)?.map_err(sealing_error)?.hash;
+			}
+			Ok(hash)
+		}
+		.boxed()
+	}
+}
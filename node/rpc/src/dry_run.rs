@@ -0,0 +1,340 @@
+//! The `nftmart_dryRun` RPC method, which tries a call before it is submitted.
+
+use codec::Decode;
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use frame_support::weights::{DispatchClass, GetDispatchInfo};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use nftmart_rpc::NFTMartDryRunRuntimeApi;
+use nftmart_traits::{Balance, BalanceChange, ClassId, CurrencyId, TokenChange, TokenId};
+use node_inspect::{DebugPrinter, PrettyPrinter};
+use node_runtime::{
+	opaque::Block, AccountId, Call, Event, Hash, RuntimeBlockLength, RuntimeBlockWeights,
+	UncheckedExtrinsic,
+};
+use sc_rpc_api::DenyUnsafe;
+use scale_info::TypeDef;
+use serde::{Deserialize, Serialize};
+use sp_api::{Metadata, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, DispatchError};
+use std::{fmt, fmt::Write, sync::Arc};
+
+#[rpc]
+pub trait NftmartDryRunApi {
+	/// Dispatch a SCALE encoded call from `origin` against the state of the best block, or of
+	/// `at`, as if it were included in the next block. Nothing is committed, and no transaction
+	/// fee is charged.
+	///
+	/// This method is unsafe, and the call must fit in an extrinsic of a normal block, both by
+	/// length and by weight.
+	#[rpc(name = "nftmart_dryRun")]
+	fn dry_run(&self, origin: AccountId, call: Bytes, at: Option<Hash>) -> Result<DryRunResponse>;
+}
+
+/// The error a dry run failed with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunError {
+	/// The pallet which returned the error, if the error is a module error.
+	pub pallet: Option<String>,
+	/// The name of the error, such as `PriceTooLow`.
+	pub error: String,
+	/// The documentation of the error.
+	pub docs: Vec<String>,
+}
+
+/// The balances of an account in a currency changed by a dry run, balances are strings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceDelta {
+	/// The account.
+	pub who: AccountId,
+	/// The currency.
+	pub currency_id: CurrencyId,
+	/// The change of the free balance, such as `-100` or `+20`.
+	pub free: String,
+	/// The change of the reserved balance.
+	pub reserved: String,
+	/// The free balance after the call.
+	pub free_after: String,
+	/// The reserved balance after the call.
+	pub reserved_after: String,
+}
+
+/// The tokens of an account changed by a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDelta {
+	/// The account.
+	pub who: AccountId,
+	/// The class of the token.
+	pub class_id: ClassId,
+	/// The token.
+	pub token_id: TokenId,
+	/// The change of the quantity held, including the reserved quantity.
+	pub quantity: String,
+	/// The change of the reserved quantity.
+	pub reserved: String,
+	/// The quantity held after the call.
+	pub quantity_after: TokenId,
+	/// The reserved quantity after the call.
+	pub reserved_after: TokenId,
+}
+
+/// The outcome of a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResponse {
+	/// Whether the call succeeds.
+	pub success: bool,
+	/// The error the call fails with.
+	pub error: Option<DryRunError>,
+	/// The events the call emits, debug formatted.
+	pub events: Vec<String>,
+	/// The changed balances of the origin and of the accounts in the events.
+	pub balances: Vec<BalanceDelta>,
+	/// The changed tokens of the origin and of the accounts in the events.
+	pub tokens: Vec<TokenDelta>,
+	/// A human-readable report of the call and its outcome.
+	pub report: String,
+}
+
+/// A struct that implements the [`NftmartDryRunApi`].
+pub struct NftmartDryRun<C> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C> NftmartDryRun<C> {
+	/// Create new `NftmartDryRun` with the given reference to the client.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe }
+	}
+}
+
+fn runtime_error(e: impl fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to dry run the call.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn invalid_call(message: &str, data: String) -> RpcError {
+	RpcError { code: ErrorCode::InvalidParams, message: message.into(), data: Some(data.into()) }
+}
+
+/// Decode a call of `encoded` which fits in an extrinsic of a normal block, as a transaction
+/// pool would accept it.
+fn decode_call(encoded: &[u8]) -> Result<Call> {
+	let max_length = *RuntimeBlockLength::get().max.get(DispatchClass::Normal) as usize;
+	if encoded.len() > max_length {
+		return Err(invalid_call(
+			"The call exceeds the maximum extrinsic length.",
+			format!("{} > {}", encoded.len(), max_length),
+		))
+	}
+	let call = Call::decode(&mut &encoded[..])
+		.map_err(|e| invalid_call("Unable to decode the call.", format!("{:?}", e)))?;
+	let weight = call.get_dispatch_info().weight;
+	if let Some(max_weight) = RuntimeBlockWeights::get().get(DispatchClass::Normal).max_extrinsic {
+		if weight > max_weight {
+			return Err(invalid_call(
+				"The call exceeds the maximum extrinsic weight.",
+				format!("{} > {}", weight, max_weight),
+			))
+		}
+	}
+	Ok(call)
+}
+
+/// Format the change from `before` to `after` with a sign.
+fn delta<T: Into<u128>>(before: T, after: T) -> String {
+	let (before, after) = (before.into(), after.into());
+	if after >= before {
+		format!("+{}", after - before)
+	} else {
+		format!("-{}", before - after)
+	}
+}
+
+/// Find the name and the documentation of a module error in the runtime metadata.
+fn module_error(metadata: &[u8], index: u8, error: u8) -> Option<(String, String, Vec<String>)> {
+	let metadata = match RuntimeMetadataPrefixed::decode(&mut &metadata[..]).ok()?.1 {
+		RuntimeMetadata::V14(metadata) => metadata,
+		_ => return None,
+	};
+	let pallet = metadata.pallets.iter().find(|p| p.index == index)?;
+	let ty = metadata.types.resolve(pallet.error.as_ref()?.ty.id())?;
+	match ty.type_def() {
+		TypeDef::Variant(def) =>
+			def.variants().iter().find(|v| v.index() == error).map(|v| {
+				(pallet.name.clone(), v.name().clone(), v.docs().iter().cloned().collect())
+			}),
+		_ => None,
+	}
+}
+
+impl<C> NftmartDryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: Metadata<Block>,
+{
+	fn error(&self, at: &BlockId<Block>, error: DispatchError) -> DryRunError {
+		let named = match error {
+			DispatchError::Module { index, error, .. } => self
+				.client
+				.runtime_api()
+				.metadata(at)
+				.ok()
+				.and_then(|metadata| module_error(&metadata, index, error)),
+			_ => None,
+		};
+		match named {
+			Some((pallet, error, docs)) => DryRunError { pallet: Some(pallet), error, docs },
+			None => DryRunError { pallet: None, error: format!("{:?}", error), docs: vec![] },
+		}
+	}
+}
+
+/// Prints the call with the [`DebugPrinter`] of `node-inspect`.
+struct CallDisplay<'a>(&'a UncheckedExtrinsic);
+
+impl fmt::Display for CallDisplay<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		PrettyPrinter::<node_runtime::Block>::fmt_extrinsic(&DebugPrinter, f, self.0)
+	}
+}
+
+fn report(
+	call: Call,
+	error: &Option<DryRunError>,
+	events: &[Event],
+	balances: &[BalanceChange<AccountId, CurrencyId>],
+	tokens: &[TokenChange<AccountId, ClassId, TokenId>],
+) -> std::result::Result<String, fmt::Error> {
+	let mut out = String::new();
+	writeln!(out, "Call:")?;
+	write!(out, "{}", CallDisplay(&UncheckedExtrinsic::new_unsigned(call)))?;
+	match error {
+		None => writeln!(out, "Result: Ok")?,
+		Some(DryRunError { pallet: Some(pallet), error, .. }) =>
+			writeln!(out, "Result: {}::{}", pallet, error)?,
+		Some(DryRunError { error, .. }) => writeln!(out, "Result: {}", error)?,
+	}
+	writeln!(out, "Events ({}):", events.len())?;
+	for event in events {
+		writeln!(out, " {:?}", event)?;
+	}
+	writeln!(out, "Balances ({}):", balances.len())?;
+	for b in balances {
+		writeln!(
+			out,
+			" {} currency {}: free {} -> {}, reserved {} -> {}",
+			b.who, b.currency_id, b.before.0, b.after.0, b.before.1, b.after.1
+		)?;
+	}
+	writeln!(out, "Tokens ({}):", tokens.len())?;
+	for t in tokens {
+		let (before, after) = (t.before.unwrap_or_default(), t.after.unwrap_or_default());
+		writeln!(
+			out,
+			" {} token {}/{}: quantity {} -> {}, reserved {} -> {}",
+			t.who,
+			t.class_id,
+			t.token_id,
+			before.quantity,
+			after.quantity,
+			before.reserved,
+			after.reserved
+		)?;
+	}
+	Ok(out)
+}
+
+impl<C> NftmartDryRunApi for NftmartDryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: NFTMartDryRunRuntimeApi<Block, Call, Event> + Metadata<Block>,
+{
+	fn dry_run(&self, origin: AccountId, call: Bytes, at: Option<Hash>) -> Result<DryRunResponse> {
+		self.deny_unsafe.check_if_safe()?;
+		let call = decode_call(&call)?;
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let outcome = self
+			.client
+			.runtime_api()
+			.dry_run(&at, origin, call.clone())
+			.map_err(runtime_error)?;
+
+		let error = outcome.result.err().map(|e| self.error(&at, e));
+		let report = report(call, &error, &outcome.events, &outcome.balances, &outcome.tokens)
+			.map_err(runtime_error)?;
+		Ok(DryRunResponse {
+			success: error.is_none(),
+			error,
+			events: outcome.events.iter().map(|e| format!("{:?}", e)).collect(),
+			balances: outcome
+				.balances
+				.into_iter()
+				.map(|b: BalanceChange<AccountId, CurrencyId>| BalanceDelta {
+					free: delta::<Balance>(b.before.0, b.after.0),
+					reserved: delta::<Balance>(b.before.1, b.after.1),
+					free_after: b.after.0.to_string(),
+					reserved_after: b.after.1.to_string(),
+					who: b.who,
+					currency_id: b.currency_id,
+				})
+				.collect(),
+			tokens: outcome
+				.tokens
+				.into_iter()
+				.map(|t| {
+					let (before, after) =
+						(t.before.unwrap_or_default(), t.after.unwrap_or_default());
+					TokenDelta {
+						who: t.who,
+						class_id: t.class_id,
+						token_id: t.token_id,
+						quantity: delta(before.quantity, after.quantity),
+						reserved: delta(before.reserved, after.reserved),
+						quantity_after: after.quantity,
+						reserved_after: after.reserved,
+					}
+				})
+				.collect(),
+			report,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use node_runtime::SystemCall;
+	use sp_runtime::Perbill;
+
+	#[test]
+	fn calls_within_the_limits_should_decode() {
+		let call = Call::System(SystemCall::remark { remark: vec![1; 100] });
+		assert_eq!(decode_call(&call.encode()).unwrap(), call);
+		assert_eq!(decode_call(&[0xff]).unwrap_err().message, "Unable to decode the call.");
+	}
+
+	#[test]
+	fn calls_over_the_limits_should_be_rejected() {
+		let max_length = *RuntimeBlockLength::get().max.get(DispatchClass::Normal) as usize;
+		let call = Call::System(SystemCall::remark { remark: vec![1; max_length] });
+		let error = decode_call(&call.encode()).unwrap_err();
+		assert_eq!(error.code, ErrorCode::InvalidParams);
+		assert_eq!(error.message, "The call exceeds the maximum extrinsic length.");
+
+		let call = Call::System(SystemCall::fill_block { ratio: Perbill::one() });
+		let error = decode_call(&call.encode()).unwrap_err();
+		assert_eq!(error.code, ErrorCode::InvalidParams);
+		assert_eq!(error.message, "The call exceeds the maximum extrinsic weight.");
+	}
+}
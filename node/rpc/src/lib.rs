@@ -30,18 +30,26 @@
 
 #![warn(missing_docs)]
 
+pub mod debug;
+pub mod dev;
+pub mod dry_run;
+pub mod marketplace;
+pub mod trace;
+pub mod txpool;
+
 use std::sync::{Arc, Mutex};
 
 use node_runtime::{
-	opaque::Block, AccountId, Balance, BlockNumber, Hash, Index, TransactionConverter,
+	opaque::Block, AccountId, Balance, BlockNumber, Call, Event, Hash, Index, TransactionConverter,
 };
 use sc_client_api::{
 	backend::{Backend, StateBackend, StorageProvider},
-	AuxStore,
+	AuxStore, BlockBackend, BlockchainEvents,
 };
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRpcHandler;
 use sc_consensus_epochs::SharedEpochChanges;
+use sc_consensus_manual_seal::EngineCommand;
 use sc_finality_grandpa::{
 	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
 };
@@ -88,6 +96,60 @@ pub struct GrandpaDeps<B> {
 	pub finality_provider: Arc<FinalityProofProvider<B, Block>>,
 }
 
+/// An optional group of Ethereum RPC methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EthApiCmd {
+	/// `debug_traceTransaction`.
+	Debug,
+	/// `trace_filter`.
+	Trace,
+	/// `txpool_content`, `txpool_inspect` and `txpool_status`.
+	Txpool,
+}
+
+impl std::str::FromStr for EthApiCmd {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"debug" => Ok(Self::Debug),
+			"trace" => Ok(Self::Trace),
+			"txpool" => Ok(Self::Txpool),
+			_ => Err(format!("Expected `debug`, `trace` or `txpool`, got `{}`", s)),
+		}
+	}
+}
+
+/// The options of the Ethereum RPC methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthConfig {
+	/// The optional groups of methods to enable.
+	pub ethapi: Vec<EthApiCmd>,
+	/// The most logs returned by `eth_getLogs`.
+	pub max_past_logs: u32,
+	/// The most blocks of `eth_feeHistory`, and of its cache.
+	pub fee_history_limit: u64,
+	/// The most traces returned by `trace_filter`.
+	pub trace_max_count: u32,
+	/// The most blocks scanned by one `trace_filter` call.
+	pub trace_max_block_range: u32,
+	/// The number of blocks whose traces are kept for `trace_filter`.
+	pub trace_cache_size: usize,
+}
+
+impl Default for EthConfig {
+	fn default() -> Self {
+		Self {
+			ethapi: vec![],
+			max_past_logs: 1024,
+			fee_history_limit: 2048,
+			trace_max_count: 500,
+			trace_max_block_range: 1000,
+			trace_cache_size: 64,
+		}
+	}
+}
+
 /// Full client dependencies.
 pub struct FullDeps<C, P, SC, B, A: ChainApi> {
 	/// The client instance to use.
@@ -112,6 +174,12 @@ pub struct FullDeps<C, P, SC, B, A: ChainApi> {
 	pub is_authority: bool,
 	/// Graph tool instance.
 	pub graph: Arc<Pool<A>>,
+	/// The marketplace history, if the indexer is enabled.
+	pub indexer: Option<Arc<nftmart_indexer::IndexerDb>>,
+	/// The commands of the sealing task, if blocks are sealed in development mode.
+	pub command_sink: Option<futures::channel::mpsc::Sender<EngineCommand<Hash>>>,
+	/// The options of the Ethereum RPC methods.
+	pub eth: EthConfig,
 }
 
 /// A IO handler that uses all Full RPC extensions.
@@ -124,9 +192,11 @@ where
 	BE: Backend<Block> + 'static,
 	C: ProvideRuntimeApi<Block>
 		+ HeaderBackend<Block>
+		+ BlockBackend<Block>
 		+ AuxStore
 		+ HeaderMetadata<Block, Error = BlockChainError>
 		+ StorageProvider<Block, BE>
+		+ BlockchainEvents<Block>
 		+ Sync
 		+ Send
 		+ 'static,
@@ -134,10 +204,13 @@ where
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: nftmart_rpc::NFTMartRuntimeApi<Block>,
+	C::Api: nftmart_rpc::NFTMartDryRunRuntimeApi<Block, Call, Event>,
+	C::Api: sp_api::Metadata<Block>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
 	C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
 	C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
+	C::Api: nftmart_evm_tracing::EvmTracingApi<Block>,
 	P: TransactionPool<Block = Block> + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
@@ -145,11 +218,19 @@ where
 	BE::State: StateBackend<BlakeTwo256>,
 	A: ChainApi<Block = Block> + 'static,
 {
+	use debug::{NftmartDebug, NftmartDebugApi};
+	use dev::{NftmartDev, NftmartDevApi};
+	use dry_run::{NftmartDryRun, NftmartDryRunApi};
 	use fc_rpc::{EthApi, EthApiServer, NetApi, NetApiServer};
+	use marketplace::{NftmartPubSub, NftmartPubSubApi};
+	use nftmart_indexer::{NftmartHistory, NftmartHistoryApi};
 	use nftmart_rpc::{NFTMart, NFTMartApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+	use trace::{NftmartTrace, NftmartTraceApi};
+	use txpool::{NftmartTxPool, NftmartTxPoolApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
@@ -164,6 +245,9 @@ where
 		backend,
 		is_authority,
 		graph,
+		indexer,
+		command_sink,
+		eth,
 	} = deps;
 
 	let BabeDeps { keystore, babe_config, shared_epoch_changes } = babe;
@@ -186,6 +270,18 @@ where
 	io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 	io.extend_with(NFTMartApi::to_delegate(NFTMart::new(client.clone())));
+	io.extend_with(NftmartDryRunApi::to_delegate(NftmartDryRun::new(client.clone(), deny_unsafe)));
+	io.extend_with(NftmartPubSubApi::to_delegate(NftmartPubSub::<_, BE>::new(
+		client.clone(),
+		subscription_executor.clone(),
+	)));
+	if let Some(db) = indexer {
+		io.extend_with(NftmartHistoryApi::to_delegate(NftmartHistory::new(db)));
+	}
+	if let Some(command_sink) = command_sink {
+		io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink.clone())));
+		io.extend_with(NftmartDevApi::to_delegate(NftmartDev::new(command_sink)));
+	}
 	io.extend_with(sc_consensus_babe_rpc::BabeApi::to_delegate(BabeRpcHandler::new(
 		client.clone(),
 		shared_epoch_changes.clone(),
@@ -224,36 +320,49 @@ where
 		fallback: Box::new(RuntimeApiStorageOverride::new(client.clone())),
 	});
 
-	let fee_history_limit = 2048;
-
 	let fee_history_cache: FeeHistoryCache = Arc::new(Mutex::new(BTreeMap::new()));
 
 	// Nor any signers
 	let signers = Vec::new();
 
-	// Limit the number of queryable logs. In a production chain, this
-	// could be extended back to the CLI. See Moonbeam for example.
-	let max_past_logs = 1024;
-
 	// Reasonable default caching inspired by the frontier template
 	let block_data_cache = Arc::new(EthBlockDataCache::new(50, 50));
 
 	io.extend_with(EthApiServer::to_delegate(EthApi::new(
 		client.clone(),
 		pool.clone(),
-		graph,
+		graph.clone(),
 		Some(TransactionConverter),
 		network.clone(),
 		signers,
 		overrides.clone(),
 		backend.clone(),
 		is_authority,
-		max_past_logs,
+		eth.max_past_logs,
 		block_data_cache.clone(),
 		fc_rpc::format::Geth,
-		fee_history_limit,
+		eth.fee_history_limit,
 		fee_history_cache,
 	)));
 
+	if eth.ethapi.contains(&EthApiCmd::Debug) {
+		io.extend_with(NftmartDebugApi::to_delegate(NftmartDebug::new(
+			client.clone(),
+			backend.clone(),
+		)));
+	}
+	if eth.ethapi.contains(&EthApiCmd::Trace) {
+		io.extend_with(NftmartTraceApi::to_delegate(NftmartTrace::new(
+			client.clone(),
+			backend.clone(),
+			eth.trace_max_count,
+			eth.trace_max_block_range,
+			eth.trace_cache_size,
+		)));
+	}
+	if eth.ethapi.contains(&EthApiCmd::Txpool) {
+		io.extend_with(NftmartTxPoolApi::to_delegate(NftmartTxPool::new(client.clone(), graph)));
+	}
+
 	io
 }
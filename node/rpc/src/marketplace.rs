@@ -0,0 +1,764 @@
+//! Pub/sub RPC methods pushing the marketplace events of imported best blocks.
+
+use futures::{
+	channel::mpsc::{unbounded, UnboundedSender},
+	future, stream,
+	task::SpawnExt,
+	FutureExt, SinkExt, StreamExt,
+};
+use jsonrpc_core::{Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use nftmart_auction::{
+	BritishAuctionBids, BritishAuctions, DutchAuctionBids, DutchAuctions, Event as AuctionEvent,
+};
+use nftmart_indexer::{block_events, read_storage, EventRecord};
+use nftmart_nft::Event as NftEvent;
+use nftmart_order::Event as OrderEvent;
+use nftmart_rpc::NFTMartRuntimeApi;
+use nftmart_traits::{ClassId, GlobalId, TokenId};
+use node_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Event, Hash, Runtime};
+use sc_client_api::{backend::Backend, BlockchainEvents, StorageProvider};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Serialize, Serializer};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use std::{
+	marker::PhantomData,
+	sync::{Arc, Mutex},
+};
+
+type Header = <Block as sp_runtime::traits::Block>::Header;
+
+fn balance_to_string<S: Serializer>(balance: &Balance, s: S) -> Result<S::Ok, S::Error> {
+	s.serialize_str(&balance.to_string())
+}
+
+/// The kind of an auction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuctionKind {
+	/// A british auction.
+	British,
+	/// A dutch auction.
+	Dutch,
+}
+
+/// A decoded marketplace event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum MarketplaceUpdate {
+	/// An order is taken.
+	#[serde(rename_all = "camelCase")]
+	TakenOrder {
+		/// The id of the order.
+		order_id: GlobalId,
+		/// The account buying the NFTs.
+		purchaser: AccountId,
+		/// The owner of the order.
+		owner: AccountId,
+	},
+	/// An offer is accepted.
+	#[serde(rename_all = "camelCase")]
+	TakenOffer {
+		/// The id of the offer.
+		offer_id: GlobalId,
+		/// The account selling the NFTs.
+		token_owner: AccountId,
+		/// The owner of the offer.
+		offer_owner: AccountId,
+	},
+	/// A swap is taken.
+	#[serde(rename_all = "camelCase")]
+	TakenSwap {
+		/// The id of the swap.
+		swap_id: GlobalId,
+		/// The account giving the requested NFTs.
+		taker: AccountId,
+		/// The owner of the swap.
+		maker: AccountId,
+	},
+	/// A lazy minting voucher is redeemed.
+	#[serde(rename_all = "camelCase")]
+	RedeemedVoucher {
+		/// The account buying the minted NFTs.
+		purchaser: AccountId,
+		/// The signer of the voucher.
+		signer: AccountId,
+		/// The class of the minted token.
+		class_id: ClassId,
+		/// The minted token.
+		token_id: TokenId,
+		/// The minted quantity.
+		quantity: TokenId,
+	},
+	/// An auction is hammered or redeemed.
+	#[serde(rename_all = "camelCase")]
+	AuctionSettled {
+		/// The id of the auction.
+		auction_id: GlobalId,
+		/// The kind of the auction.
+		kind: AuctionKind,
+		/// The account receiving the NFTs.
+		purchaser: AccountId,
+	},
+	/// A new highest bid of an auction.
+	#[serde(rename_all = "camelCase")]
+	NewBid {
+		/// The id of the auction.
+		auction_id: GlobalId,
+		/// The kind of the auction.
+		kind: AuctionKind,
+		/// The account placing the bid.
+		bidder: AccountId,
+		/// The price of the bid.
+		#[serde(serialize_with = "balance_to_string")]
+		price: Balance,
+		/// The auction can be redeemed after this block.
+		deadline: BlockNumber,
+	},
+	/// A bid extended the deadline of an auction.
+	#[serde(rename_all = "camelCase")]
+	DeadlineExtended {
+		/// The id of the auction.
+		auction_id: GlobalId,
+		/// The new deadline.
+		deadline: BlockNumber,
+	},
+	/// An auction is removed by its owner.
+	#[serde(rename_all = "camelCase")]
+	AuctionRemoved {
+		/// The id of the auction.
+		auction_id: GlobalId,
+		/// The kind of the auction.
+		kind: AuctionKind,
+	},
+	/// Some of a token are minted, transferred or burned.
+	#[serde(rename_all = "camelCase")]
+	Transfer {
+		/// The class of the token.
+		class_id: ClassId,
+		/// The token.
+		token_id: TokenId,
+		/// The sender, `None` for minting.
+		from: Option<AccountId>,
+		/// The receiver, `None` for burning.
+		to: Option<AccountId>,
+		/// The transferred quantity.
+		quantity: TokenId,
+	},
+}
+
+/// A marketplace event of an imported best block.
+///
+/// The block may be retracted later, subscribers should check `blockHash` against the chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceNotification {
+	/// The number of the block.
+	pub block_number: BlockNumber,
+	/// The hash of the block.
+	pub block_hash: Hash,
+	/// The index of the event in the block.
+	pub event_index: u32,
+	/// The decoded event.
+	#[serde(flatten)]
+	pub update: MarketplaceUpdate,
+}
+
+/// The marketplace pub/sub RPC methods.
+#[rpc]
+pub trait NftmartPubSubApi {
+	/// RPC Metadata
+	type Metadata;
+
+	/// Taken orders, offers and swaps, settled auctions and redeemed vouchers.
+	#[pubsub(subscription = "nftmart_trades", subscribe, name = "nftmart_subscribeTrades")]
+	fn subscribe_trades(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<MarketplaceNotification>,
+	);
+
+	/// Unsubscribe from the trades.
+	#[pubsub(subscription = "nftmart_trades", unsubscribe, name = "nftmart_unsubscribeTrades")]
+	fn unsubscribe_trades(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool>;
+
+	/// New bids, deadline extensions, settlement and removal of an auction of `owner`.
+	#[pubsub(subscription = "nftmart_auction", subscribe, name = "nftmart_subscribeAuction")]
+	fn subscribe_auction(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<MarketplaceNotification>,
+		owner: AccountId,
+		auction_id: GlobalId,
+	);
+
+	/// Unsubscribe from an auction.
+	#[pubsub(subscription = "nftmart_auction", unsubscribe, name = "nftmart_unsubscribeAuction")]
+	fn unsubscribe_auction(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool>;
+
+	/// Mints, transfers and burns of a token.
+	#[pubsub(subscription = "nftmart_token", subscribe, name = "nftmart_subscribeToken")]
+	fn subscribe_token(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<MarketplaceNotification>,
+		class_id: ClassId,
+		token_id: TokenId,
+	);
+
+	/// Unsubscribe from a token.
+	#[pubsub(subscription = "nftmart_token", unsubscribe, name = "nftmart_unsubscribeToken")]
+	fn unsubscribe_token(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool>;
+}
+
+/// What a subscriber is interested in.
+#[derive(Clone)]
+enum Filter {
+	Trades,
+	Auction { auction_id: GlobalId, kind: AuctionKind, owner: AccountId },
+	Token { class_id: ClassId, token_id: TokenId },
+}
+
+/// The events of an imported best block, decoded once for all the subscribers.
+struct DecodedBlock {
+	header: Header,
+	events: Vec<EventRecord>,
+}
+
+type BlockSinks = Arc<Mutex<Vec<UnboundedSender<Arc<DecodedBlock>>>>>;
+
+/// Send a block to the subscribers, decoding it only if there is any, and drop the sinks of the
+/// subscribers which are gone.
+fn publish(sinks: &BlockSinks, decode: impl FnOnce() -> Result<DecodedBlock, String>) {
+	let mut sinks = sinks.lock().expect("the sinks are never poisoned; qed");
+	if sinks.is_empty() {
+		return
+	}
+	match decode() {
+		Ok(block) => {
+			let block = Arc::new(block);
+			sinks.retain(|sink| sink.unbounded_send(block.clone()).is_ok());
+		},
+		Err(e) => log::warn!("Failed to decode the marketplace events: {}", e),
+	}
+}
+
+/// Implements the [`NftmartPubSubApi`].
+pub struct NftmartPubSub<C, BE> {
+	client: Arc<C>,
+	manager: SubscriptionManager,
+	sinks: BlockSinks,
+	_marker: PhantomData<BE>,
+}
+
+impl<C, BE> NftmartPubSub<C, BE>
+where
+	BE: Backend<Block> + 'static,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+	C: BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: NFTMartRuntimeApi<Block>,
+{
+	/// Create new `NftmartPubSub` with the given reference to the client, and start decoding the
+	/// imported best blocks for the subscribers.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		let sinks = BlockSinks::default();
+		let blocks = {
+			let (client, sinks) = (client.clone(), sinks.clone());
+			client
+				.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+				.for_each(move |notification| {
+					publish(&sinks, || decode_block(&*client, notification.hash));
+					future::ready(())
+				})
+		};
+		if let Err(e) = executor.spawn(blocks) {
+			log::error!("Failed to spawn the marketplace events task: {:?}", e);
+		}
+		Self {
+			client,
+			manager: SubscriptionManager::new(Arc::new(executor)),
+			sinks,
+			_marker: PhantomData,
+		}
+	}
+
+	fn subscribe(&self, subscriber: Subscriber<MarketplaceNotification>, filter: Filter) {
+		let (sink, blocks) = unbounded();
+		self.sinks.lock().expect("the sinks are never poisoned; qed").push(sink);
+		let client = self.client.clone();
+		let stream = blocks
+			.map(move |block: Arc<DecodedBlock>| {
+				let notifications =
+					block_notifications(&block, &filter, |id, kind, owner, bidder| {
+						bid_updates(&*client, &block.header, id, kind, owner, bidder)
+					})
+					.unwrap_or_else(|e| {
+						log::warn!("Failed to decode the marketplace events: {}", e);
+						Vec::new()
+					});
+				stream::iter(notifications)
+			})
+			.flatten()
+			.map(|notification| Ok::<_, ()>(Ok(notification)));
+
+		self.manager.add(subscriber, |sink| {
+			stream
+				.forward(sink.sink_map_err(|e| log::warn!("Error sending notifications: {:?}", e)))
+				.map(|_| ())
+		});
+	}
+}
+
+impl<C, BE> NftmartPubSubApi for NftmartPubSub<C, BE>
+where
+	BE: Backend<Block> + 'static,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+	C: BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: NFTMartRuntimeApi<Block>,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe_trades(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<MarketplaceNotification>,
+	) {
+		self.subscribe(subscriber, Filter::Trades);
+	}
+
+	fn unsubscribe_trades(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+
+	fn subscribe_auction(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<MarketplaceNotification>,
+		owner: AccountId,
+		auction_id: GlobalId,
+	) {
+		let best_hash = self.client.info().best_hash;
+		let error = match auction_kind(&*self.client, best_hash, &owner, auction_id) {
+			Ok(Some(kind)) => {
+				self.subscribe(subscriber, Filter::Auction { auction_id, kind, owner });
+				return
+			},
+			Ok(None) => RpcError {
+				code: ErrorCode::InvalidParams,
+				message: "Auction not found.".into(),
+				data: None,
+			},
+			Err(e) => RpcError {
+				code: ErrorCode::ServerError(1),
+				message: "Unable to find the auction.".into(),
+				data: Some(e.into()),
+			},
+		};
+		let _ = subscriber.reject(error);
+	}
+
+	fn unsubscribe_auction(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+
+	fn subscribe_token(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<MarketplaceNotification>,
+		class_id: ClassId,
+		token_id: TokenId,
+	) {
+		self.subscribe(subscriber, Filter::Token { class_id, token_id });
+	}
+
+	fn unsubscribe_token(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+}
+
+/// The kind of an open auction of `owner`.
+fn auction_kind<C, BE>(
+	client: &C,
+	at: Hash,
+	owner: &AccountId,
+	auction_id: GlobalId,
+) -> Result<Option<AuctionKind>, String>
+where
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	for (kind, key) in [
+		(AuctionKind::British, BritishAuctions::<Runtime>::hashed_key_for(owner, auction_id)),
+		(AuctionKind::Dutch, DutchAuctions::<Runtime>::hashed_key_for(owner, auction_id)),
+	] {
+		let exists = client
+			.storage(&BlockId::Hash(at), &StorageKey(key))
+			.map_err(|e| e.to_string())?
+			.is_some();
+		if exists {
+			return Ok(Some(kind))
+		}
+	}
+	Ok(None)
+}
+
+/// The events of block `hash`.
+fn decode_block<C, BE>(client: &C, hash: Hash) -> Result<DecodedBlock, String>
+where
+	BE: Backend<Block>,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+{
+	let header = client
+		.header(BlockId::Hash(hash))
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("header of {:?} not found", hash))?;
+	Ok(DecodedBlock { header, events: block_events(client, hash)? })
+}
+
+fn settled_auction(event: &AuctionEvent<Runtime>) -> Option<(GlobalId, AuctionKind, AccountId)> {
+	match event {
+		AuctionEvent::HammerBritishAuction(purchaser, id, ..) |
+		AuctionEvent::RedeemedBritishAuction(purchaser, id, ..) =>
+			Some((*id, AuctionKind::British, purchaser.clone())),
+		AuctionEvent::RedeemedDutchAuction(purchaser, id, ..) =>
+			Some((*id, AuctionKind::Dutch, purchaser.clone())),
+		_ => None,
+	}
+}
+
+/// The marketplace events of a block a subscriber with `filter` is interested in, with the
+/// updates of a new bid read by `new_bid`.
+fn block_notifications(
+	block: &DecodedBlock,
+	filter: &Filter,
+	mut new_bid: impl FnMut(
+		GlobalId,
+		AuctionKind,
+		&AccountId,
+		AccountId,
+	) -> Result<Vec<MarketplaceUpdate>, String>,
+) -> Result<Vec<MarketplaceNotification>, String> {
+	let (block_number, block_hash) = (*block.header.number(), block.header.hash());
+	let mut notifications = Vec::new();
+	for (index, record) in block.events.iter().enumerate() {
+		let updates = match (filter, record.event.clone()) {
+			(Filter::Trades, Event::NftmartOrder(event)) => match event {
+				OrderEvent::TakenOrder(purchaser, owner, order_id, ..) =>
+					vec![MarketplaceUpdate::TakenOrder { order_id, purchaser, owner }],
+				OrderEvent::TakenOffer(token_owner, offer_owner, offer_id, ..) =>
+					vec![MarketplaceUpdate::TakenOffer { offer_id, token_owner, offer_owner }],
+				OrderEvent::TakenSwap(taker, maker, swap_id) =>
+					vec![MarketplaceUpdate::TakenSwap { swap_id, taker, maker }],
+				OrderEvent::RedeemedVoucher(purchaser, signer, _, class_id, token_id, quantity) =>
+					vec![MarketplaceUpdate::RedeemedVoucher {
+						purchaser,
+						signer,
+						class_id,
+						token_id,
+						quantity,
+					}],
+				_ => vec![],
+			},
+			(Filter::Trades, Event::NftmartAuction(event)) => settled_auction(&event)
+				.map(|(auction_id, kind, purchaser)| MarketplaceUpdate::AuctionSettled {
+					auction_id,
+					kind,
+					purchaser,
+				})
+				.into_iter()
+				.collect(),
+			(Filter::Auction { auction_id, kind, owner }, Event::NftmartAuction(event)) =>
+				match event {
+					AuctionEvent::BidBritishAuction(bidder, id) |
+					AuctionEvent::BidDutchAuction(bidder, id)
+						if id == *auction_id =>
+						new_bid(*auction_id, *kind, owner, bidder)?,
+					AuctionEvent::RemovedBritishAuction(_, id) |
+					AuctionEvent::RemovedDutchAuction(_, id)
+						if id == *auction_id =>
+						vec![MarketplaceUpdate::AuctionRemoved { auction_id: id, kind: *kind }],
+					event =>
+						settled_auction(&event)
+							.filter(|(id, ..)| id == auction_id)
+							.map(|(auction_id, kind, purchaser)| {
+								MarketplaceUpdate::AuctionSettled { auction_id, kind, purchaser }
+							})
+							.into_iter()
+							.collect(),
+				},
+			(Filter::Token { class_id, token_id }, Event::Nftmart(event)) => {
+				let transfer = match event {
+					NftEvent::MintedToken(_, to, c, t, quantity) =>
+						Some((c, t, None, Some(to), quantity)),
+					NftEvent::TransferredToken(from, to, c, t, quantity) =>
+						Some((c, t, Some(from), Some(to), quantity)),
+					NftEvent::BurnedToken(from, c, t, quantity, _) =>
+						Some((c, t, Some(from), None, quantity)),
+					_ => None,
+				};
+				transfer
+					.filter(|(c, t, ..)| c == class_id && t == token_id)
+					.map(|(class_id, token_id, from, to, quantity)| MarketplaceUpdate::Transfer {
+						class_id,
+						token_id,
+						from,
+						to,
+						quantity,
+					})
+					.into_iter()
+					.collect()
+			},
+			_ => vec![],
+		};
+		notifications.extend(updates.into_iter().map(|update| MarketplaceNotification {
+			block_number,
+			block_hash,
+			event_index: index as u32,
+			update,
+		}));
+	}
+	Ok(notifications)
+}
+
+/// The new bid of an auction in the block of `header`, and the deadline extension if any.
+fn bid_updates<C, BE>(
+	client: &C,
+	header: &Header,
+	auction_id: GlobalId,
+	kind: AuctionKind,
+	owner: &AccountId,
+	bidder: AccountId,
+) -> Result<Vec<MarketplaceUpdate>, String>
+where
+	BE: Backend<Block>,
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, BE>,
+	C::Api: NFTMartRuntimeApi<Block>,
+{
+	let (hash, parent_hash) = (header.hash(), *header.parent_hash());
+	let at = BlockId::Hash(hash);
+	let api = client.runtime_api();
+	let (price, deadline, previous_deadline) = match kind {
+		AuctionKind::British => {
+			let auction: Option<nftmart_auction::BritishAuctionOf<Runtime>> = read_storage(
+				client,
+				hash,
+				BritishAuctions::<Runtime>::hashed_key_for(owner, auction_id),
+			)?;
+			let bid_key = BritishAuctionBids::<Runtime>::hashed_key_for(auction_id);
+			let bid: Option<nftmart_auction::BritishAuctionBidOf<Runtime>> =
+				read_storage(client, hash, bid_key.clone())?;
+			let previous_bid: Option<nftmart_auction::BritishAuctionBidOf<Runtime>> =
+				read_storage(client, parent_hash, bid_key)?;
+			let (auction, bid) = match (auction, bid) {
+				(Some(auction), Some(bid)) => (auction, bid),
+				_ => return Ok(Vec::new()),
+			};
+			let deadline_after = |last_bid_block| {
+				api.get_auction_deadline(
+					&at,
+					auction.allow_delay,
+					auction.deadline,
+					last_bid_block,
+					auction.soft_close.clone(),
+				)
+				.map_err(|e| format!("{:?}", e))
+			};
+			let deadline = deadline_after(bid.last_bid_block)?;
+			let previous_deadline = match previous_bid {
+				Some(previous_bid) => deadline_after(previous_bid.last_bid_block)?,
+				None => auction.deadline,
+			};
+			(bid.last_bid_price, deadline, previous_deadline)
+		},
+		AuctionKind::Dutch => {
+			let auction: Option<nftmart_auction::DutchAuctionOf<Runtime>> = read_storage(
+				client,
+				hash,
+				DutchAuctions::<Runtime>::hashed_key_for(owner, auction_id),
+			)?;
+			let bid_key = DutchAuctionBids::<Runtime>::hashed_key_for(auction_id);
+			let bid: Option<nftmart_auction::DutchAuctionBidOf<Runtime>> =
+				read_storage(client, hash, bid_key.clone())?;
+			let previous_bid: Option<nftmart_auction::DutchAuctionBidOf<Runtime>> =
+				read_storage(client, parent_hash, bid_key)?;
+			let (auction, bid) = match (auction, bid) {
+				(Some(auction), Some(bid)) => (auction, bid),
+				_ => return Ok(Vec::new()),
+			};
+			// A bid turns a dutch auction into a british auction which closes after a delay.
+			let deadline_after = |last_bid_block| {
+				api.get_auction_deadline(&at, true, 0, last_bid_block, None)
+					.map_err(|e| format!("{:?}", e))
+			};
+			let deadline = deadline_after(bid.last_bid_block)?;
+			let previous_deadline = match previous_bid {
+				Some(previous_bid) if previous_bid.last_bid_account.is_some() =>
+					deadline_after(previous_bid.last_bid_block)?,
+				_ => auction.deadline,
+			};
+			(bid.last_bid_price, deadline, previous_deadline)
+		},
+	};
+
+	let mut updates = vec![MarketplaceUpdate::NewBid { auction_id, kind, bidder, price, deadline }];
+	if deadline > previous_deadline {
+		updates.push(MarketplaceUpdate::DeadlineExtended { auction_id, deadline });
+	}
+	Ok(updates)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_system::Phase;
+	use futures::channel::mpsc::UnboundedReceiver;
+	use sp_core::crypto::AccountId32;
+	use std::cell::Cell;
+
+	const ALICE: AccountId32 = AccountId32::new([1u8; 32]);
+	const BOB: AccountId32 = AccountId32::new([2u8; 32]);
+
+	fn block(events: Vec<Event>) -> DecodedBlock {
+		let header = Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let events = events
+			.into_iter()
+			.map(|event| EventRecord { phase: Phase::ApplyExtrinsic(0), event, topics: vec![] })
+			.collect();
+		DecodedBlock { header, events }
+	}
+
+	fn updates(block: &DecodedBlock, filter: Filter) -> Vec<(u32, MarketplaceUpdate)> {
+		let new_bid = |auction_id, kind, owner: &AccountId, bidder| {
+			assert_eq!(owner, &ALICE);
+			Ok(vec![MarketplaceUpdate::NewBid { auction_id, kind, bidder, price: 10, deadline: 5 }])
+		};
+		block_notifications(block, &filter, new_bid)
+			.unwrap()
+			.into_iter()
+			.map(|n| {
+				assert_eq!((n.block_number, n.block_hash), (1, block.header.hash()));
+				(n.event_index, n.update)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn notifications_should_match_the_filters() {
+		let block = block(vec![
+			Event::Nftmart(NftEvent::TransferredToken(ALICE, BOB, 1, 2, 3)),
+			Event::NftmartOrder(OrderEvent::TakenOrder(BOB, ALICE, 7, None, None)),
+			Event::NftmartAuction(AuctionEvent::BidBritishAuction(BOB, 9)),
+			Event::NftmartAuction(AuctionEvent::RemovedDutchAuction(ALICE, 8)),
+		]);
+
+		assert_eq!(
+			updates(&block, Filter::Trades),
+			vec![(1, MarketplaceUpdate::TakenOrder { order_id: 7, purchaser: BOB, owner: ALICE })]
+		);
+		assert_eq!(
+			updates(&block, Filter::Token { class_id: 1, token_id: 2 }),
+			vec![(
+				0,
+				MarketplaceUpdate::Transfer {
+					class_id: 1,
+					token_id: 2,
+					from: Some(ALICE),
+					to: Some(BOB),
+					quantity: 3,
+				}
+			)]
+		);
+		assert_eq!(updates(&block, Filter::Token { class_id: 1, token_id: 3 }), vec![]);
+		assert_eq!(
+			updates(
+				&block,
+				Filter::Auction { auction_id: 9, kind: AuctionKind::British, owner: ALICE }
+			),
+			vec![(
+				2,
+				MarketplaceUpdate::NewBid {
+					auction_id: 9,
+					kind: AuctionKind::British,
+					bidder: BOB,
+					price: 10,
+					deadline: 5,
+				}
+			)]
+		);
+		assert_eq!(
+			updates(
+				&block,
+				Filter::Auction { auction_id: 8, kind: AuctionKind::Dutch, owner: ALICE }
+			),
+			vec![(
+				3,
+				MarketplaceUpdate::AuctionRemoved { auction_id: 8, kind: AuctionKind::Dutch }
+			)]
+		);
+	}
+
+	#[test]
+	fn blocks_should_be_decoded_once_for_all_subscribers() {
+		let sinks = BlockSinks::default();
+		let decoded = Cell::new(0);
+		let decode = || {
+			decoded.set(decoded.get() + 1);
+			Ok(block(vec![]))
+		};
+
+		// Nothing is decoded without subscribers.
+		publish(&sinks, decode);
+		assert_eq!(decoded.get(), 0);
+
+		let mut receivers: Vec<UnboundedReceiver<_>> = (0..3)
+			.map(|_| {
+				let (sink, receiver) = unbounded();
+				sinks.lock().unwrap().push(sink);
+				receiver
+			})
+			.collect();
+		drop(receivers.pop());
+
+		publish(&sinks, decode);
+		assert_eq!(decoded.get(), 1);
+		assert_eq!(sinks.lock().unwrap().len(), 2);
+		let received: Vec<_> =
+			receivers.iter_mut().map(|r| r.try_next().unwrap().unwrap()).collect();
+		assert!(Arc::ptr_eq(&received[0], &received[1]));
+	}
+}
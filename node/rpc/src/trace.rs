@@ -0,0 +1,399 @@
+//! The `trace_filter` RPC method, which lists the calls of the Ethereum transactions of a range of
+//! blocks in the format of OpenEthereum.
+
+use crate::debug::{replayed_block, tracing_error, transaction_metadata};
+use fc_rpc_core::types::{BlockNumber, Bytes};
+use fp_rpc::EthereumRuntimeRPCApi;
+use jsonrpc_core::{Error as RpcError, Result};
+use jsonrpc_derive::rpc;
+use nftmart_evm_tracing::{CallKind, CallTrace, EvmTracingApi};
+use node_runtime::{opaque::Block, Hash};
+use sc_client_api::BlockBackend;
+use serde::{Deserialize, Serialize};
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use std::{
+	collections::{HashMap, VecDeque},
+	sync::{Arc, Mutex},
+};
+
+#[rpc]
+pub trait NftmartTraceApi {
+	/// The calls of the Ethereum transactions from `fromBlock` to `toBlock`, both included, made
+	/// by `fromAddress` to `toAddress` if given. The first `after` calls are skipped and `count`
+	/// calls at most are returned. The node bounds both the range of blocks and `count`.
+	#[rpc(name = "trace_filter")]
+	fn filter(&self, filter: TraceFilter) -> Result<Vec<Trace>>;
+}
+
+/// The options of `trace_filter`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFilter {
+	/// The first block, the genesis by default.
+	pub from_block: Option<BlockNumber>,
+	/// The last block, the best block by default.
+	pub to_block: Option<BlockNumber>,
+	/// The callers, any caller if empty.
+	pub from_address: Option<Vec<H160>>,
+	/// The callees, any callee if empty.
+	pub to_address: Option<Vec<H160>>,
+	/// The number of matching calls to skip.
+	pub after: Option<u32>,
+	/// The number of calls to return.
+	pub count: Option<u32>,
+}
+
+/// What a call frame did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Action {
+	/// A message call.
+	Call(CallAction),
+	/// A contract creation.
+	Create(CreateAction),
+	/// A self-destruct.
+	Suicide(SuicideAction),
+}
+
+/// A message call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallAction {
+	/// `call`, `callcode`, `delegatecall` or `staticcall`.
+	pub call_type: String,
+	/// The caller.
+	pub from: H160,
+	/// The callee.
+	pub to: H160,
+	/// The value transferred.
+	pub value: U256,
+	/// The gas given to the call.
+	pub gas: U256,
+	/// The call data.
+	pub input: Bytes,
+}
+
+/// A contract creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAction {
+	/// The creator.
+	pub from: H160,
+	/// The value transferred to the contract.
+	pub value: U256,
+	/// The gas given to the creation.
+	pub gas: U256,
+	/// The init code.
+	pub init: Bytes,
+}
+
+/// A self-destruct.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuicideAction {
+	/// The destroyed contract.
+	pub address: H160,
+	/// The beneficiary of the balance.
+	pub refund_address: H160,
+	/// The balance of the contract.
+	pub balance: U256,
+}
+
+/// The outcome of a successful call frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum TraceResult {
+	/// The outcome of a message call.
+	Call {
+		/// The gas used by the call.
+		#[serde(rename = "gasUsed")]
+		gas_used: U256,
+		/// The returned data.
+		output: Bytes,
+	},
+	/// The outcome of a contract creation.
+	Create {
+		/// The created contract.
+		address: H160,
+		/// The code of the contract.
+		code: Bytes,
+		/// The gas used by the creation.
+		#[serde(rename = "gasUsed")]
+		gas_used: U256,
+	},
+}
+
+/// A call frame of an Ethereum transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+	/// What the frame did.
+	pub action: Action,
+	/// The outcome of the frame, null if it failed or for a self-destruct.
+	pub result: Option<TraceResult>,
+	/// Why the frame failed, if it did.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// The hash of the Ethereum block.
+	pub block_hash: H256,
+	/// The number of the block.
+	pub block_number: u32,
+	/// The number of the direct subcalls.
+	pub subtraces: u32,
+	/// The index of the frame among the subcalls of each of its ancestors.
+	pub trace_address: Vec<u32>,
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The index of the transaction among the Ethereum transactions of the block.
+	pub transaction_position: u32,
+	/// `call`, `create` or `suicide`.
+	#[serde(rename = "type")]
+	pub kind: String,
+}
+
+impl Trace {
+	fn new(call: CallTrace, block: (H256, u32), transaction: (H256, u32)) -> Self {
+		let (gas, gas_used) = (U256::from(call.gas), U256::from(call.gas_used));
+		let (kind, action, result) = match call.kind {
+			CallKind::Create | CallKind::Create2 => (
+				"create",
+				Action::Create(CreateAction {
+					from: call.from,
+					value: call.value,
+					gas,
+					init: Bytes(call.input),
+				}),
+				Some(TraceResult::Create { address: call.to, code: Bytes(call.output), gas_used }),
+			),
+			CallKind::SelfDestruct => (
+				"suicide",
+				Action::Suicide(SuicideAction {
+					address: call.from,
+					refund_address: call.to,
+					balance: call.value,
+				}),
+				None,
+			),
+			kind => (
+				"call",
+				Action::Call(CallAction {
+					call_type: match kind {
+						CallKind::CallCode => "callcode",
+						CallKind::DelegateCall => "delegatecall",
+						CallKind::StaticCall => "staticcall",
+						_ => "call",
+					}
+					.into(),
+					from: call.from,
+					to: call.to,
+					value: call.value,
+					gas,
+					input: Bytes(call.input),
+				}),
+				Some(TraceResult::Call { gas_used, output: Bytes(call.output) }),
+			),
+		};
+		let error = call.error.map(|e| String::from_utf8_lossy(&e).into_owned());
+		Self {
+			action,
+			result: if error.is_some() { None } else { result },
+			error,
+			block_hash: block.0,
+			block_number: block.1,
+			subtraces: call.subtraces,
+			trace_address: call.trace_address,
+			transaction_hash: transaction.0,
+			transaction_position: transaction.1,
+			kind: kind.into(),
+		}
+	}
+
+	/// The account which made the call, or the destroyed contract.
+	fn from(&self) -> H160 {
+		match &self.action {
+			Action::Call(action) => action.from,
+			Action::Create(action) => action.from,
+			Action::Suicide(action) => action.address,
+		}
+	}
+
+	/// The callee, the created contract, or the beneficiary of a self-destruct.
+	fn to(&self) -> Option<H160> {
+		match (&self.action, &self.result) {
+			(Action::Call(action), _) => Some(action.to),
+			(Action::Create(_), Some(TraceResult::Create { address, .. })) => Some(*address),
+			(Action::Create(_), _) => None,
+			(Action::Suicide(action), _) => Some(action.refund_address),
+		}
+	}
+
+	fn matches(&self, from: &[H160], to: &[H160]) -> bool {
+		(from.is_empty() || from.contains(&self.from())) &&
+			(to.is_empty() || matches!(self.to(), Some(address) if to.contains(&address)))
+	}
+}
+
+/// The traces of the most recently traced blocks.
+struct TraceCache {
+	size: usize,
+	blocks: HashMap<Hash, Arc<Vec<Trace>>>,
+	order: VecDeque<Hash>,
+}
+
+impl TraceCache {
+	fn get(&self, hash: &Hash) -> Option<Arc<Vec<Trace>>> {
+		self.blocks.get(hash).cloned()
+	}
+
+	fn insert(&mut self, hash: Hash, traces: Arc<Vec<Trace>>) {
+		if self.size == 0 || self.blocks.insert(hash, traces).is_some() {
+			return
+		}
+		self.order.push_back(hash);
+		if self.order.len() > self.size {
+			if let Some(oldest) = self.order.pop_front() {
+				self.blocks.remove(&oldest);
+			}
+		}
+	}
+}
+
+/// A struct that implements the [`NftmartTraceApi`].
+pub struct NftmartTrace<C> {
+	client: Arc<C>,
+	backend: Arc<fc_db::Backend<Block>>,
+	max_count: u32,
+	max_block_range: u32,
+	cache: Mutex<TraceCache>,
+}
+
+impl<C> NftmartTrace<C> {
+	/// Create new `NftmartTrace` returning `max_count` traces at most and scanning
+	/// `max_block_range` blocks at most per call, and keeping the traces of `cache_size` blocks.
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<fc_db::Backend<Block>>,
+		max_count: u32,
+		max_block_range: u32,
+		cache_size: usize,
+	) -> Self {
+		let cache = TraceCache { size: cache_size, blocks: HashMap::new(), order: VecDeque::new() };
+		Self { client, backend, max_count, max_block_range, cache: Mutex::new(cache) }
+	}
+}
+
+impl<C> NftmartTrace<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: Send + Sync + 'static,
+	C::Api: Core<Block> + EthereumRuntimeRPCApi<Block> + EvmTracingApi<Block>,
+{
+	fn block_number(&self, number: Option<BlockNumber>) -> Result<u32> {
+		let best = self.client.info().best_number;
+		match number {
+			Some(BlockNumber::Num(number)) => Ok(number.min(best as u64) as u32),
+			Some(BlockNumber::Earliest) => Ok(0),
+			Some(BlockNumber::Latest) | Some(BlockNumber::Pending) | None => Ok(best),
+			Some(_) => Err(RpcError::invalid_params("Expected a block number")),
+		}
+	}
+
+	/// The traces of the Ethereum transactions of the block `number`.
+	fn block_traces(&self, number: u32) -> Result<Arc<Vec<Trace>>> {
+		let hash = match self.client.hash(number).map_err(tracing_error)? {
+			Some(hash) => hash,
+			None => return Ok(Default::default()),
+		};
+		if let Some(traces) = self.cache.lock().expect("Poisoned trace cache").get(&hash) {
+			return Ok(traces)
+		}
+
+		let (header, extrinsics) = replayed_block(&*self.client, hash)?;
+		let parent = BlockId::hash(*header.parent_hash());
+		let api = self.client.runtime_api();
+		// Most blocks have no Ethereum transaction and need not be replayed.
+		let transactions =
+			api.extrinsic_filter(&parent, extrinsics.clone()).map_err(tracing_error)?;
+		let mut traces = vec![];
+		if let Some(first) = transactions.first() {
+			let ethereum_block_hash =
+				transaction_metadata(&*self.client, &self.backend, first.hash())?
+					.map(|m| m.ethereum_block_hash)
+					.unwrap_or_default();
+			api.initialize_block(&parent, &header).map_err(tracing_error)?;
+			let calls = api.trace_block(&parent, extrinsics).map_err(tracing_error)?;
+			// A runtime built without the EVM tracer traces no transaction.
+			if calls.len() != transactions.len() {
+				return Err(tracing_error(format!(
+					"The runtime of block {} is built without EVM tracing",
+					number
+				)))
+			}
+			for (position, (transaction_hash, calls)) in calls.into_iter().enumerate() {
+				traces.extend(calls.into_iter().map(|call| {
+					Trace::new(
+						call,
+						(ethereum_block_hash, number),
+						(transaction_hash, position as u32),
+					)
+				}));
+			}
+		}
+
+		let traces = Arc::new(traces);
+		self.cache.lock().expect("Poisoned trace cache").insert(hash, traces.clone());
+		Ok(traces)
+	}
+}
+
+impl<C> NftmartTraceApi for NftmartTrace<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: Send + Sync + 'static,
+	C::Api: Core<Block> + EthereumRuntimeRPCApi<Block> + EvmTracingApi<Block>,
+{
+	fn filter(&self, filter: TraceFilter) -> Result<Vec<Trace>> {
+		let count = filter.count.unwrap_or(self.max_count);
+		if count > self.max_count {
+			return Err(RpcError::invalid_params(format!(
+				"Expected a count of {} traces at most",
+				self.max_count
+			)))
+		}
+		let from_block = self.block_number(filter.from_block)?;
+		let to_block = self.block_number(filter.to_block)?;
+		if to_block.saturating_sub(from_block) >= self.max_block_range {
+			return Err(RpcError::invalid_params(format!(
+				"Expected a range of {} blocks at most",
+				self.max_block_range
+			)))
+		}
+		let from_address = filter.from_address.unwrap_or_default();
+		let to_address = filter.to_address.unwrap_or_default();
+
+		let mut skipped = 0;
+		let mut traces = vec![];
+		if count == 0 {
+			return Ok(traces)
+		}
+		for number in from_block..=to_block {
+			for trace in self.block_traces(number)?.iter() {
+				if !trace.matches(&from_address, &to_address) {
+					continue
+				}
+				if skipped < filter.after.unwrap_or_default() {
+					skipped += 1;
+					continue
+				}
+				traces.push(trace.clone());
+				if traces.len() as u32 == count {
+					return Ok(traces)
+				}
+			}
+		}
+		Ok(traces)
+	}
+}
@@ -0,0 +1,204 @@
+//! The `txpool_*` RPC methods, which list the Ethereum transactions of the transaction pool.
+
+use ethereum::{TransactionAction, TransactionV2 as EthereumTransaction};
+use fc_rpc_core::types::Bytes;
+use fp_rpc::EthereumRuntimeRPCApi;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_runtime::opaque::Block;
+use sc_transaction_pool::{ChainApi, Pool};
+use sc_transaction_pool_api::InPoolTransaction;
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::keccak_256, H160, H256, U256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::{collections::BTreeMap, sync::Arc};
+
+#[rpc]
+pub trait NftmartTxPoolApi {
+	/// The Ethereum transactions of the pool by sender and nonce, `pending` ones being ready to
+	/// be included in a block and `queued` ones waiting for an earlier nonce.
+	#[rpc(name = "txpool_content")]
+	fn content(&self) -> Result<TxPoolResult<TransactionMap<Transaction>>>;
+
+	/// A one-line summary of each Ethereum transaction of the pool, by sender and nonce.
+	#[rpc(name = "txpool_inspect")]
+	fn inspect(&self) -> Result<TxPoolResult<TransactionMap<String>>>;
+
+	/// The number of pending and queued Ethereum transactions in the pool.
+	#[rpc(name = "txpool_status")]
+	fn status(&self) -> Result<TxPoolResult<U256>>;
+}
+
+/// The pending and queued transactions of the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TxPoolResult<T> {
+	/// The transactions ready to be included in a block.
+	pub pending: T,
+	/// The transactions waiting for an earlier nonce.
+	pub queued: T,
+}
+
+/// Transactions by sender and by nonce, the nonce being a decimal string as in Geth.
+pub type TransactionMap<T> = BTreeMap<H160, BTreeMap<String, T>>;
+
+/// A transaction of the pool, in the format of `eth_getTransactionByHash`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+	/// The hash of the transaction.
+	pub hash: H256,
+	/// The nonce of the sender.
+	pub nonce: U256,
+	/// Always null, the transaction is not in a block yet.
+	pub block_hash: Option<H256>,
+	/// Always null, the transaction is not in a block yet.
+	pub block_number: Option<U256>,
+	/// Always null, the transaction is not in a block yet.
+	pub transaction_index: Option<U256>,
+	/// The sender.
+	pub from: H160,
+	/// The recipient, or null for a contract creation.
+	pub to: Option<H160>,
+	/// The value transferred.
+	pub value: U256,
+	/// The gas price, or the max fee per gas of an EIP-1559 transaction.
+	pub gas_price: U256,
+	/// The gas limit.
+	pub gas: U256,
+	/// The call data, or the init code of a contract creation.
+	pub input: Bytes,
+}
+
+impl Transaction {
+	fn new(transaction: &EthereumTransaction, from: H160) -> Self {
+		let (nonce, action, value, gas_price, gas, input) = match transaction {
+			EthereumTransaction::Legacy(t) =>
+				(t.nonce, t.action, t.value, t.gas_price, t.gas_limit, &t.input),
+			EthereumTransaction::EIP2930(t) =>
+				(t.nonce, t.action, t.value, t.gas_price, t.gas_limit, &t.input),
+			EthereumTransaction::EIP1559(t) =>
+				(t.nonce, t.action, t.value, t.max_fee_per_gas, t.gas_limit, &t.input),
+		};
+		Self {
+			hash: transaction.hash(),
+			nonce,
+			block_hash: None,
+			block_number: None,
+			transaction_index: None,
+			from,
+			to: match action {
+				TransactionAction::Call(to) => Some(to),
+				TransactionAction::Create => None,
+			},
+			value,
+			gas_price,
+			gas,
+			input: Bytes(input.clone()),
+		}
+	}
+
+	/// The summary of `txpool_inspect`, such as `0x..: 1 wei + 21000 gas × 1000000000 wei`.
+	fn summary(&self) -> String {
+		let to = match self.to {
+			Some(to) => format!("{:?}", to),
+			None => "contract creation".into(),
+		};
+		format!("{}: {} wei + {} gas × {} wei", to, self.value, self.gas, self.gas_price)
+	}
+}
+
+/// A struct that implements the [`NftmartTxPoolApi`].
+pub struct NftmartTxPool<C, A: ChainApi> {
+	client: Arc<C>,
+	graph: Arc<Pool<A>>,
+}
+
+impl<C, A: ChainApi> NftmartTxPool<C, A> {
+	/// Create new `NftmartTxPool` listing the transactions of `graph`.
+	pub fn new(client: Arc<C>, graph: Arc<Pool<A>>) -> Self {
+		Self { client, graph }
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to read the transaction pool.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+/// The sender of a transaction, `None` if its signature is invalid.
+fn sender(transaction: &EthereumTransaction) -> Option<H160> {
+	let public = fc_rpc::public_key(transaction).ok()?;
+	Some(H160::from_slice(&keccak_256(&public)[12..]))
+}
+
+impl<C, A> NftmartTxPool<C, A>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	A: ChainApi<Block = Block> + 'static,
+{
+	/// The Ethereum transactions of the pool, with their sender.
+	fn transactions(&self) -> Result<TxPoolResult<Vec<Transaction>>> {
+		let pool = self.graph.validated_pool();
+		let ready = pool.ready().map(|tx| tx.data().clone()).collect();
+		let future = pool.futures().into_iter().map(|(_, xt)| xt).collect();
+
+		let at = BlockId::hash(self.client.info().best_hash);
+		let api = self.client.runtime_api();
+		let filter = |xts: Vec<<Block as BlockT>::Extrinsic>| -> Result<Vec<Transaction>> {
+			Ok(api
+				.extrinsic_filter(&at, xts)
+				.map_err(runtime_error)?
+				.iter()
+				.filter_map(|tx| sender(tx).map(|from| Transaction::new(tx, from)))
+				.collect())
+		};
+		Ok(TxPoolResult { pending: filter(ready)?, queued: filter(future)? })
+	}
+}
+
+fn by_sender<T>(
+	transactions: Vec<Transaction>,
+	f: impl Fn(&Transaction) -> T,
+) -> TransactionMap<T> {
+	let mut map = TransactionMap::new();
+	for tx in transactions {
+		map.entry(tx.from)
+			.or_insert_with(BTreeMap::new)
+			.insert(tx.nonce.to_string(), f(&tx));
+	}
+	map
+}
+
+impl<C, A> NftmartTxPoolApi for NftmartTxPool<C, A>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: EthereumRuntimeRPCApi<Block>,
+	A: ChainApi<Block = Block> + 'static,
+{
+	fn content(&self) -> Result<TxPoolResult<TransactionMap<Transaction>>> {
+		let TxPoolResult { pending, queued } = self.transactions()?;
+		Ok(TxPoolResult {
+			pending: by_sender(pending, Transaction::clone),
+			queued: by_sender(queued, Transaction::clone),
+		})
+	}
+
+	fn inspect(&self) -> Result<TxPoolResult<TransactionMap<String>>> {
+		let TxPoolResult { pending, queued } = self.transactions()?;
+		Ok(TxPoolResult {
+			pending: by_sender(pending, Transaction::summary),
+			queued: by_sender(queued, Transaction::summary),
+		})
+	}
+
+	fn status(&self) -> Result<TxPoolResult<U256>> {
+		let TxPoolResult { pending, queued } = self.transactions()?;
+		Ok(TxPoolResult { pending: pending.len().into(), queued: queued.len().into() })
+	}
+}
@@ -0,0 +1,168 @@
+//! Dispatch a call in a throw-away overlay and collect the balances and tokens it changes.
+
+use crate::{AccountId, Call, Currencies, Event, Origin, Runtime, System};
+use frame_support::storage::{with_transaction, TransactionOutcome};
+use nftmart_traits::{
+	constants_types::NATIVE_CURRENCY_ID, AccountToken, Balance, BalanceChange, ClassId, CurrencyId,
+	DryRunOutcome, TokenChange, TokenId,
+};
+use orml_traits::{MultiCurrency, MultiReservableCurrency};
+use sp_runtime::traits::Dispatchable;
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+
+/// The accounts, currencies and tokens a dry run looks at.
+#[derive(Default)]
+struct Touched {
+	accounts: BTreeSet<AccountId>,
+	currencies: BTreeSet<CurrencyId>,
+	tokens: BTreeSet<(AccountId, ClassId, TokenId)>,
+}
+
+impl Touched {
+	fn note_event(&mut self, event: &Event) {
+		match event {
+			Event::System(frame_system::Event::NewAccount(who)) |
+			Event::System(frame_system::Event::KilledAccount(who)) |
+			Event::Balances(pallet_balances::Event::Endowed { account: who, .. }) |
+			Event::Balances(pallet_balances::Event::Reserved { who, .. }) |
+			Event::Balances(pallet_balances::Event::Unreserved { who, .. }) |
+			Event::Balances(pallet_balances::Event::Deposit { who, .. }) |
+			Event::Balances(pallet_balances::Event::Withdraw { who, .. }) |
+			Event::Balances(pallet_balances::Event::Slashed { who, .. }) => {
+				self.accounts.insert(who.clone());
+			},
+			Event::Balances(pallet_balances::Event::Transfer { from, to, .. }) |
+			Event::Balances(pallet_balances::Event::ReserveRepatriated { from, to, .. }) => {
+				self.accounts.insert(from.clone());
+				self.accounts.insert(to.clone());
+			},
+			Event::Tokens(orml_tokens::Event::Transferred(currency_id, from, to, _)) |
+			Event::Currencies(orml_currencies::Event::Transferred(currency_id, from, to, _)) => {
+				self.currencies.insert(*currency_id);
+				self.accounts.insert(from.clone());
+				self.accounts.insert(to.clone());
+			},
+			Event::Tokens(orml_tokens::Event::DustLost(who, currency_id, _)) |
+			Event::Currencies(orml_currencies::Event::BalanceUpdated(currency_id, who, _)) |
+			Event::Currencies(orml_currencies::Event::Deposited(currency_id, who, _)) |
+			Event::Currencies(orml_currencies::Event::Withdrawn(currency_id, who, _)) |
+			Event::NftmartOrder(nftmart_order::Event::RoyaltyPaid(who, currency_id, _)) |
+			Event::NftmartAuction(nftmart_auction::Event::RoyaltyPaid(who, currency_id, _)) => {
+				self.currencies.insert(*currency_id);
+				self.accounts.insert(who.clone());
+			},
+			Event::Nftmart(nftmart_nft::Event::MintedToken(_, to, class_id, token_id, _)) => {
+				self.note_token(to, *class_id, *token_id);
+			},
+			Event::Nftmart(nftmart_nft::Event::TransferredToken(
+				from,
+				to,
+				class_id,
+				token_id,
+				_,
+			)) => {
+				self.note_token(from, *class_id, *token_id);
+				self.note_token(to, *class_id, *token_id);
+			},
+			Event::Nftmart(nftmart_nft::Event::BurnedToken(who, class_id, token_id, _, _)) => {
+				self.note_token(who, *class_id, *token_id);
+			},
+			_ => {},
+		}
+	}
+
+	fn note_token(&mut self, who: &AccountId, class_id: ClassId, token_id: TokenId) {
+		self.accounts.insert(who.clone());
+		self.tokens.insert((who.clone(), class_id, token_id));
+	}
+
+	/// Note the currencies and the tokens held by `who`.
+	fn note_holdings(&mut self, who: &AccountId) {
+		self.accounts.insert(who.clone());
+		self.currencies.insert(NATIVE_CURRENCY_ID);
+		for (currency_id, _) in orml_tokens::Accounts::<Runtime>::iter_prefix(who) {
+			self.currencies.insert(currency_id);
+		}
+		for ((class_id, token_id), _) in orml_nft::TokensByOwner::<Runtime>::iter_prefix(who) {
+			self.tokens.insert((who.clone(), class_id, token_id));
+		}
+	}
+
+	fn balances(&self) -> Vec<(Balance, Balance)> {
+		self.accounts
+			.iter()
+			.flat_map(|who| {
+				self.currencies.iter().map(move |currency_id| {
+					(
+						<Currencies as MultiCurrency<AccountId>>::free_balance(*currency_id, who),
+						<Currencies as MultiReservableCurrency<AccountId>>::reserved_balance(
+							*currency_id,
+							who,
+						),
+					)
+				})
+			})
+			.collect()
+	}
+
+	fn tokens(&self) -> Vec<Option<AccountToken<TokenId>>> {
+		self.tokens
+			.iter()
+			.map(|(who, class_id, token_id)| {
+				orml_nft::TokensByOwner::<Runtime>::get(who, (*class_id, *token_id))
+			})
+			.collect()
+	}
+}
+
+/// Dispatch `call` from `origin` as if it were included in the next block.
+///
+/// The changes are rolled back, and the runtime API overlay they were made in is never committed.
+pub fn dry_run(
+	origin: AccountId,
+	call: Call,
+) -> DryRunOutcome<AccountId, CurrencyId, ClassId, TokenId, Event> {
+	System::set_block_number(System::block_number() + 1);
+	System::reset_events();
+
+	let (result, events, touched, balances_after, tokens_after) = with_transaction(|| {
+		let result = call.dispatch(Origin::signed(origin.clone())).map(|_| ()).map_err(|e| e.error);
+		let events: Vec<Event> = System::events().into_iter().map(|r| r.event).collect();
+
+		let mut touched = Touched::default();
+		touched.note_holdings(&origin);
+		events.iter().for_each(|event| touched.note_event(event));
+		let balances_after = touched.balances();
+		let tokens_after = touched.tokens();
+		TransactionOutcome::Rollback((result, events, touched, balances_after, tokens_after))
+	});
+
+	let balances = touched
+		.accounts
+		.iter()
+		.flat_map(|who| touched.currencies.iter().map(move |currency_id| (who, *currency_id)))
+		.zip(touched.balances().into_iter().zip(balances_after))
+		.filter(|(_, (before, after))| before != after)
+		.map(|((who, currency_id), (before, after))| BalanceChange {
+			who: who.clone(),
+			currency_id,
+			before,
+			after,
+		})
+		.collect();
+	let tokens = touched
+		.tokens
+		.iter()
+		.zip(touched.tokens().into_iter().zip(tokens_after))
+		.filter(|(_, (before, after))| before != after)
+		.map(|((who, class_id, token_id), (before, after))| TokenChange {
+			who: who.clone(),
+			class_id: *class_id,
+			token_id: *token_id,
+			before,
+			after,
+		})
+		.collect();
+
+	DryRunOutcome { result, events, balances, tokens }
+}
@@ -17,8 +17,8 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use crate::{Authorship, Balances, NegativeImbalance};
-use frame_support::traits::{Currency, OnUnbalanced};
+use crate::{Authorship, Balance, Balances, FeeRouter, NegativeImbalance};
+use frame_support::traits::{Currency, ExistenceRequirement, OnUnbalanced, WithdrawReasons};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -27,6 +27,34 @@ impl OnUnbalanced<NegativeImbalance> for Author {
 	}
 }
 
+/// Adds the trading fees in the staking pot to the era payout of validators and nominators.
+///
+/// The pot is burned here, as the payout will be minted again by `pallet_staking`.
+pub struct EraPayoutWithTradingFees<Inner>(sp_std::marker::PhantomData<Inner>);
+impl<Inner: pallet_staking::EraPayout<Balance>> pallet_staking::EraPayout<Balance>
+	for EraPayoutWithTradingFees<Inner>
+{
+	fn era_payout(
+		total_staked: Balance,
+		total_issuance: Balance,
+		era_duration_millis: u64,
+	) -> (Balance, Balance) {
+		let (payout, remainder) =
+			Inner::era_payout(total_staked, total_issuance, era_duration_millis);
+		let pot = FeeRouter::staking_pot_account_id();
+		let fees = Balances::free_balance(&pot).saturating_sub(Balances::minimum_balance());
+		match Balances::withdraw(
+			&pot,
+			fees,
+			WithdrawReasons::TRANSFER,
+			ExistenceRequirement::KeepAlive,
+		) {
+			Ok(_) => (payout.saturating_add(fees), remainder),
+			Err(_) => (payout, remainder),
+		}
+	}
+}
+
 #[cfg(test)]
 mod multiplier_tests {
 	use pallet_transaction_payment::{Multiplier, TargetedFeeAdjustment};
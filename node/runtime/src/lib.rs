@@ -29,7 +29,7 @@ use frame_support::{
 		Currency, EqualPrivilegeOnly, Everything, Imbalance, InstanceFilter, KeyOwnerProofSystem,
 		LockIdentifier, Nothing, OnUnbalanced, U128CurrencyToVote,
 	},
-	weights::{DispatchClass, Weight},
+	weights::{DispatchClass, GetDispatchInfo, Weight},
 	PalletId, RuntimeDebug,
 };
 use frame_system::{
@@ -138,6 +138,9 @@ use sp_runtime::generic::Era;
 
 mod voter_bags;
 
+/// Dry runs of calls in a throw-away overlay.
+mod dry_run;
+
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -162,10 +165,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 280,
+	spec_version: 282,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 2,
+	transaction_version: 3,
 };
 
 /// The BABE epoch configuration at genesis.
@@ -566,7 +569,7 @@ impl pallet_staking::Config for Runtime {
 		pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>,
 	>;
 	type SessionInterface = Self;
-	type EraPayout = pallet_staking::ConvertCurve<RewardCurve>;
+	type EraPayout = impls::EraPayoutWithTradingFees<pallet_staking::ConvertCurve<RewardCurve>>;
 	type NextNewSession = Session;
 	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
 	type OffendingValidatorsThreshold = OffendingValidatorsThreshold;
@@ -1176,6 +1179,9 @@ impl nftmart_config::Config for Runtime {
 	type Event = Event;
 }
 
+pub type FeeRouter =
+	nftmart_config::FeeRouter<Runtime, Currencies, TreasuryPalletId, GetNativeCurrencyId>;
+
 impl nftmart_order::Config for Runtime {
 	type Event = Event;
 	type MultiCurrency = Currencies;
@@ -1184,7 +1190,50 @@ impl nftmart_order::Config for Runtime {
 	type TokenId = nftmart_traits::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = NftmartAgent;
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
+	type OffchainSignature = Signature;
+	type OffchainPublic = <Signature as traits::Verify>::Signer;
+}
+
+parameter_types! {
+	pub const MaxDropPhases: u32 = 8;
+}
+
+impl nftmart_drop::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type Currency = Balances;
+	type ClassId = nftmart_traits::ClassId;
+	type TokenId = nftmart_traits::TokenId;
+	type NFT = Nftmart;
+	type ExtraConfig = NftmartConf;
+	type FeeRouter = FeeRouter;
+	type MaxDropPhases = MaxDropPhases;
+}
+
+parameter_types! {
+	pub const MaxAgentNameLength: u32 = 64;
+	pub const MaxAgentMetadataLength: u32 = 1024;
+}
+
+impl nftmart_agent::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type CurrencyId = nftmart_traits::CurrencyId;
+	type ExtraConfig = NftmartConf;
+	type Slash = Treasury;
+	type MaxNameLength = MaxAgentNameLength;
+	type MaxMetadataLength = MaxAgentMetadataLength;
+}
+
+impl nftmart_rental::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type ClassId = nftmart_traits::ClassId;
+	type TokenId = nftmart_traits::TokenId;
+	type NFT = Nftmart;
 }
 
 impl nftmart_auction::Config for Runtime {
@@ -1195,6 +1244,8 @@ impl nftmart_auction::Config for Runtime {
 	type TokenId = nftmart_traits::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = NftmartAgent;
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
 	type WeightInfo = ();
 }
@@ -1333,6 +1384,9 @@ construct_runtime!(
 		Template: pallet_template::{Pallet, Call, Storage, Event<T>},
 		NopEmitT: pallet_nop::emit_t::{Pallet, Call, Event<T>},
 		EthereumChainId: pallet_ethereum_chain_id::{Pallet, Call, Storage, Config},
+		NftmartDrop: nftmart_drop::{Pallet, Call, Storage, Event<T>},
+		NftmartAgent: nftmart_agent::{Pallet, Call, Storage, Event<T>, Config<T>},
+		NftmartRental: nftmart_rental::{Pallet, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -1370,6 +1424,30 @@ pub type Block = generic::Block<Header, UncheckedExtrinsic>;
 pub type SignedBlock = generic::SignedBlock<Block>;
 /// BlockId type as expected by this runtime.
 pub type BlockId = generic::BlockId<Block>;
+
+/// The fee charged for a signed extrinsic dispatching `call`, as `TransactionPaymentApi` reports
+/// it without a tip. The extrinsic is signed with a placeholder, as only its length counts.
+fn quote_tx_fee(call: Call) -> Balance {
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(Era::mortal(256, System::block_number() as u64)),
+		frame_system::CheckNonce::from(0),
+		frame_system::CheckWeight::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::from(0),
+	);
+	let signature = Signature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64]));
+	let uxt = UncheckedExtrinsic(generic::UncheckedExtrinsic::new_signed(
+		call,
+		Address::Id([0; 32].into()),
+		signature,
+		extra,
+	));
+	let len = uxt.encoded_size() as u32;
+	TransactionPayment::query_info(uxt, len).partial_fee
+}
+
 /// The SignedExtension to the basic transaction logic.
 ///
 /// When you change this, you **MUST** modify [`sign`] in `bin/node/testing/src/keyring.rs`!
@@ -1666,13 +1744,211 @@ impl_runtime_apis! {
 			created_block: BlockNumber,
 			deadline: BlockNumber,
 			current_block: BlockNumber,
+			curve: nftmart_traits::DutchAuctionCurve<BlockNumber>,
 		) -> Balance {
-			nftmart_auction::calc_current_price::<Runtime>(max_price, min_price, created_block, deadline, current_block)
+			nftmart_auction::calc_current_price::<Runtime>(max_price, min_price, created_block, deadline, current_block, &curve)
+		}
+		fn get_dutch_auction_price(
+			auction_owner: AccountId,
+			auction_id: nftmart_traits::GlobalId,
+		) -> Option<Balance> {
+			let auction = NftmartAuction::dutch_auctions(&auction_owner, auction_id)?;
+			Some(NftmartAuction::dutch_auction_price(&auction, System::block_number() + 1))
 		}
 		fn get_auction_deadline(
-			allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber
+			allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber,
+			soft_close: Option<nftmart_traits::SoftClose<BlockNumber>>,
 		) -> BlockNumber {
-			nftmart_auction::get_deadline::<Runtime>(allow_delay, deadline, last_bid_block)
+			nftmart_auction::get_deadline::<Runtime>(allow_delay, deadline, last_bid_block, &soft_close)
+		}
+		fn get_class_supply(
+			class_id: nftmart_traits::ClassId,
+		) -> Option<(nftmart_traits::ClassSupplyCap<nftmart_traits::TokenId>, nftmart_traits::TokenId, nftmart_traits::TokenId)> {
+			Nftmart::class_supply(class_id)
+		}
+		fn get_class_admins(class_id: nftmart_traits::ClassId) -> Vec<AccountId> {
+			Nftmart::class_admins(class_id)
+		}
+		fn get_token_user(
+			class_id: nftmart_traits::ClassId,
+			token_id: nftmart_traits::TokenId,
+			lender: AccountId,
+		) -> Option<(AccountId, BlockNumber)> {
+			NftmartRental::user_of(class_id, token_id, &lender)
+		}
+		fn get_token_users(
+			class_id: nftmart_traits::ClassId,
+			token_id: nftmart_traits::TokenId,
+		) -> Vec<(AccountId, AccountId, BlockNumber)> {
+			NftmartRental::users_of(class_id, token_id)
+		}
+		fn quote_take_order(
+			order_owner: AccountId,
+			order_id: nftmart_traits::GlobalId,
+			commission_agent: Option<AccountId>,
+		) -> Result<nftmart_traits::Quote<AccountId, nftmart_traits::CurrencyId>, sp_runtime::DispatchError> {
+			let mut quote = NftmartOrder::quote_take_order(&order_owner, order_id, commission_agent.clone())?;
+			quote.tx_fee = quote_tx_fee(Call::NftmartOrder(nftmart_order::Call::take_order {
+				order_id,
+				order_owner: order_owner.into(),
+				commission_agent,
+				commission_data: None,
+			}));
+			Ok(quote)
+		}
+		fn quote_submit_order(
+			currency_id: nftmart_traits::CurrencyId,
+			price: Balance,
+			items: Vec<(nftmart_traits::ClassId, nftmart_traits::TokenId, nftmart_traits::TokenId)>,
+			commission_rate: sp_runtime::PerU16,
+			commission_agent: Option<AccountId>,
+		) -> Result<nftmart_traits::Quote<AccountId, nftmart_traits::CurrencyId>, sp_runtime::DispatchError> {
+			let mut quote = NftmartOrder::quote_submit_order(
+				currency_id, price, items.clone(), commission_rate, commission_agent,
+			)?;
+			quote.tx_fee = quote_tx_fee(Call::NftmartOrder(nftmart_order::Call::submit_order {
+				currency_id,
+				deposit: quote.deposit,
+				price,
+				deadline: System::block_number(),
+				items,
+				commission_rate,
+			}));
+			Ok(quote)
+		}
+		fn quote_bid(
+			auction_owner: AccountId,
+			auction_id: nftmart_traits::GlobalId,
+			price: Balance,
+			commission_agent: Option<AccountId>,
+		) -> Result<nftmart_traits::Quote<AccountId, nftmart_traits::CurrencyId>, sp_runtime::DispatchError> {
+			let mut quote = NftmartAuction::quote_bid(&auction_owner, auction_id, price, commission_agent.clone())?;
+			let call = if NftmartAuction::british_auctions(&auction_owner, auction_id).is_some() {
+				nftmart_auction::Call::bid_british_auction {
+					price,
+					auction_owner: auction_owner.into(),
+					auction_id,
+					commission_agent,
+					commission_data: None,
+				}
+			} else {
+				nftmart_auction::Call::bid_dutch_auction {
+					price,
+					auction_owner: auction_owner.into(),
+					auction_id,
+					commission_agent,
+					commission_data: None,
+				}
+			};
+			quote.tx_fee = quote_tx_fee(Call::NftmartAuction(call));
+			Ok(quote)
+		}
+		fn quote_mint(
+			class_id: nftmart_traits::ClassId,
+			metadata_len: u32,
+			quantity: nftmart_traits::TokenId,
+			charge_royalty: Option<sp_runtime::PerU16>,
+		) -> Result<nftmart_traits::Quote<AccountId, nftmart_traits::CurrencyId>, sp_runtime::DispatchError> {
+			let class_info = orml_nft::Pallet::<Runtime>::classes(class_id)
+				.ok_or(nftmart_nft::Error::<Runtime>::ClassIdNotFound)?;
+			let call = Call::Nftmart(nftmart_nft::Call::mint {
+				to: class_info.owner.into(),
+				class_id,
+				metadata: vec![0; metadata_len as usize],
+				quantity,
+				charge_royalty,
+			});
+			Ok(nftmart_traits::Quote {
+				currency_id: nftmart_traits::constants_types::NATIVE_CURRENCY_ID,
+				price: 0,
+				platform_fee: 0,
+				royalty: 0,
+				royalty_beneficiary: None,
+				commission: 0,
+				commission_agent: None,
+				commission_paid: false,
+				deposit: Nftmart::mint_token_deposit(metadata_len),
+				tx_fee: quote_tx_fee(call),
+			})
+		}
+	}
+
+	impl nftmart_rpc_runtime_api::NFTMartDryRunApi<Block, Call, Event> for Runtime {
+		fn dry_run(
+			origin: AccountId,
+			call: Call,
+		) -> nftmart_traits::DryRunOutcome<
+			AccountId,
+			nftmart_traits::CurrencyId,
+			nftmart_traits::ClassId,
+			nftmart_traits::TokenId,
+			Event,
+		> {
+			dry_run::dry_run(origin, call)
+		}
+	}
+
+	impl nftmart_evm_tracing::EvmTracingApi<Block> for Runtime {
+		fn trace_transaction(
+			extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+			transaction_hash: H256,
+			config: nftmart_evm_tracing::TraceConfig,
+		) -> Result<nftmart_evm_tracing::TransactionTrace, sp_runtime::DispatchError> {
+			#[cfg(not(feature = "evm-tracing"))]
+			{
+				let _ = (extrinsics, transaction_hash, config);
+				Err(sp_runtime::DispatchError::Other("The runtime is built without EVM tracing"))
+			}
+			#[cfg(feature = "evm-tracing")]
+			{
+				for xt in extrinsics {
+					let traced = match &xt.0.function {
+						Call::Ethereum(transact { transaction }) =>
+							transaction.hash() == transaction_hash,
+						_ => false,
+					};
+					if traced {
+						let tracer = nftmart_evm_tracing::EvmTracer::new(config);
+						let (_, trace) = tracer.trace(|| Executive::apply_extrinsic(xt));
+						return Ok(trace)
+					}
+					let _ = Executive::apply_extrinsic(xt);
+				}
+				Err(sp_runtime::DispatchError::Other("Ethereum transaction not found in the block"))
+			}
+		}
+
+		fn trace_block(
+			extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<(H256, Vec<nftmart_evm_tracing::CallTrace>)> {
+			#[cfg(not(feature = "evm-tracing"))]
+			{
+				let _ = extrinsics;
+				Vec::new()
+			}
+			#[cfg(feature = "evm-tracing")]
+			{
+				let mut traces = Vec::new();
+				for xt in extrinsics {
+					let transaction_hash = match &xt.0.function {
+						Call::Ethereum(transact { transaction }) => Some(transaction.hash()),
+						_ => None,
+					};
+					match transaction_hash {
+						Some(transaction_hash) => {
+							let tracer = nftmart_evm_tracing::EvmTracer::new(
+								nftmart_evm_tracing::TraceConfig::calls(),
+							);
+							let (_, trace) = tracer.trace(|| Executive::apply_extrinsic(xt));
+							traces.push((transaction_hash, trace.calls));
+						},
+						None => {
+							let _ = Executive::apply_extrinsic(xt);
+						},
+					}
+				}
+				traces
+			}
 		}
 	}
 
@@ -1980,4 +2256,107 @@ mod tests {
 
 		is_submit_signed_transaction::<Runtime>();
 	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	#[test]
+	fn quote_tx_fee_should_match_a_signed_extrinsic() {
+		use sp_core::Pair;
+		new_test_ext().execute_with(|| {
+			let call = Call::System(frame_system::Call::remark { remark: vec![0; 100] });
+			let pair = sp_core::sr25519::Pair::from_string("//Alice", None).unwrap();
+			let extra: SignedExtra = (
+				frame_system::CheckSpecVersion::new(),
+				frame_system::CheckTxVersion::new(),
+				frame_system::CheckGenesis::new(),
+				frame_system::CheckEra::from(Era::mortal(256, System::block_number() as u64)),
+				frame_system::CheckNonce::from(0),
+				frame_system::CheckWeight::new(),
+				pallet_transaction_payment::ChargeTransactionPayment::from(0),
+			);
+			let payload = SignedPayload::new(call.clone(), extra.clone()).unwrap();
+			let signature = payload.using_encoded(|payload| pair.sign(payload));
+			let uxt = UncheckedExtrinsic(generic::UncheckedExtrinsic::new_signed(
+				call.clone(),
+				Address::Id(pair.public().into()),
+				signature.into(),
+				extra,
+			));
+			let len = uxt.encoded_size() as u32;
+
+			let fee = quote_tx_fee(call);
+			assert_eq!(fee, TransactionPayment::query_info(uxt, len).partial_fee);
+			let larger = quote_tx_fee(Call::System(frame_system::Call::remark { remark: vec![0; 200] }));
+			assert_eq!(larger - fee, 100 * TransactionByteFee::get());
+		});
+	}
+
+	#[test]
+	fn quote_mint_should_work() {
+		use nftmart_rpc_runtime_api::runtime_decl_for_NFTMartApi::NFTMartApi;
+		new_test_ext().execute_with(|| {
+			assert_eq!(
+				<Runtime as NFTMartApi<Block>>::quote_mint(0, 10, 1, None),
+				Err(nftmart_nft::Error::<Runtime>::ClassIdNotFound.into()),
+			);
+
+			let owner: AccountId = [1; 32].into();
+			let data = nftmart_traits::ClassData {
+				deposit: 0,
+				properties: Default::default(),
+				name: vec![],
+				description: vec![],
+				create_block: 0,
+				royalty_rate: Default::default(),
+				category_ids: vec![],
+			};
+			let class_id = orml_nft::Pallet::<Runtime>::create_class(&owner, vec![], data).unwrap();
+			let quote = <Runtime as NFTMartApi<Block>>::quote_mint(class_id, 10, 1, None).unwrap();
+			assert_eq!(quote.deposit, Nftmart::mint_token_deposit(10));
+			assert_eq!((quote.price, quote.platform_fee, quote.royalty), (0, 0, 0));
+			assert!(quote.tx_fee > 0);
+			let larger = <Runtime as NFTMartApi<Block>>::quote_mint(class_id, 110, 1, None).unwrap();
+			assert_eq!(larger.tx_fee - quote.tx_fee, 100 * TransactionByteFee::get());
+		});
+	}
+
+	#[test]
+	fn dry_run_should_roll_back() {
+		let (alice, bob): (AccountId, AccountId) = ([1; 32].into(), [2; 32].into());
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: vec![(alice.clone(), 100 * DOLLARS)],
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			let call = Call::Balances(pallet_balances::Call::transfer {
+				dest: Address::Id(bob.clone()),
+				value: 10 * DOLLARS,
+			});
+			let outcome = dry_run::dry_run(alice.clone(), call);
+			assert_eq!(outcome.result, Ok(()));
+			assert!(!outcome.events.is_empty());
+			let change = |who: &AccountId| {
+				outcome.balances.iter().find(|b| &b.who == who).map(|b| (b.before, b.after))
+			};
+			assert_eq!(change(&alice), Some(((100 * DOLLARS, 0), (90 * DOLLARS, 0))));
+			assert_eq!(change(&bob), Some(((0, 0), (10 * DOLLARS, 0))));
+			assert_eq!(Balances::free_balance(&alice), 100 * DOLLARS);
+			assert_eq!(Balances::free_balance(&bob), 0);
+
+			let call = Call::Balances(pallet_balances::Call::transfer {
+				dest: Address::Id(bob.clone()),
+				value: 200 * DOLLARS,
+			});
+			let outcome = dry_run::dry_run(alice.clone(), call);
+			assert_eq!(
+				outcome.result,
+				Err(pallet_balances::Error::<Runtime>::InsufficientBalance.into())
+			);
+			assert!(outcome.balances.is_empty());
+		});
+	}
 }
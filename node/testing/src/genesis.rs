@@ -89,7 +89,9 @@ pub fn config_endowed(code: Option<&[u8]>, extra_endowed: Vec<AccountId>) -> Gen
 		orml_nft: Default::default(),
 		nftmart: Default::default(),
 		nftmart_order: Default::default(),
+		nftmart_agent: Default::default(),
 		nftmart_auction: Default::default(),
+		nftmart_rental: Default::default(),
 		nftmart_conf: Default::default(),
 		evm: Default::default(),
 		ethereum: Default::default(),
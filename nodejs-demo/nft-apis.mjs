@@ -64,11 +64,16 @@ async function classDeposit(metadata, name, description) {
   }
 }
 
-async function getAuctionDeadline(allowDelay, deadline, lastBidBlock) {
+async function getAuctionDeadline(
+  allowDelay,
+  deadline,
+  lastBidBlock,
+  softClose = null
+) {
   try {
     let d = await Global_Api.ws.call(
       "nftmart_getAuctionDeadline",
-      [allowDelay, deadline, lastBidBlock],
+      [allowDelay, deadline, lastBidBlock, softCloseToRpc(softClose)],
       10000
     );
     return bnToBn(d);
@@ -83,13 +88,14 @@ async function getDutchAuctionCurrentPrice(
   minPrice,
   createdBlock,
   deadline,
-  currentBlock
+  currentBlock,
+  curve
 ) {
-  // console.log(maxPrice, minPrice, createdBlock, deadline, currentBlock);
+  // console.log(maxPrice, minPrice, createdBlock, deadline, currentBlock, curve);
   try {
     let price = await Global_Api.ws.call(
       "nftmart_getDutchAuctionCurrentPrice",
-      [maxPrice, minPrice, createdBlock, deadline, currentBlock],
+      [maxPrice, minPrice, createdBlock, deadline, currentBlock, curve],
       10000
     );
     return bnToBn(price);
@@ -99,6 +105,38 @@ async function getDutchAuctionCurrentPrice(
   }
 }
 
+// Converts a stored `DutchAuctionCurve` into the JSON accepted by the RPC.
+function softCloseToRpc(softClose) {
+  if (!softClose) {
+    return null;
+  }
+  return {
+    window: softClose.window,
+    extension: softClose.extension,
+    max_extension: softClose.maxExtension,
+  };
+}
+
+function curveToRpc(curve) {
+  if (curve.isLinear) {
+    return { Linear: { interval: curve.asLinear.interval.toNumber() } };
+  }
+  if (curve.isExponential) {
+    return {
+      Exponential: {
+        interval: curve.asExponential.interval.toNumber(),
+        decay_rate: curve.asExponential.decayRate.toNumber(),
+      },
+    };
+  }
+  return {
+    Breakpoints: curve.asBreakpoints.map(([block, price]) => [
+      block.toNumber(),
+      price.toJSON(),
+    ]),
+  };
+}
+
 function print_nft(classID, tokenID, nft, accountToken) {
   if (nft.isSome) {
     nft = nft.unwrap();
@@ -746,7 +784,8 @@ async function show_british_auction(ws) {
         const actualDeadline = await getAuctionDeadline(
           jsonData.allowDelay,
           jsonData.deadline,
-          bid.lastBidBlock
+          bid.lastBidBlock,
+          jsonData.softClose
         );
         data.actualDeadline = actualDeadline.toString();
         data.lastBidAccount = bid.lastBidAccount.unwrap();
@@ -793,15 +832,28 @@ async function submit_british_auction(
   // For any bidding of an auction,
   // the second bidding should at least to be 1.5 times of the first relative biding.
   const minRaise = float2PerU16(0.5); // 50%
+  // Every bidding should also raise the price by at least 1 NMT.
+  const minIncrement = unit;
+  // If a bidding arrives within 10 minutes before the deadline,
+  // the auction will be closed 10 minutes after the last bidding, at most 1 hour later than the deadline.
+  const softClose = allow_delay
+    ? {
+        window: (10 * 60) / blockTimeSec,
+        extension: (10 * 60) / blockTimeSec,
+        maxExtension: (60 * 60) / blockTimeSec,
+      }
+    : null;
   const commission = float2PerU16(0.1); // 10%
   const call = Global_Api.tx.nftmartAuction.submitBritishAuction(
     NativeCurrencyID,
     hammer_price,
     minRaise,
+    minIncrement,
     min_deposit,
     init_price,
     deadlineBlock,
     allow_delay,
+    softClose,
     tokens,
     commission
   );
@@ -963,7 +1015,8 @@ async function show_dutch_auction(ws) {
         jsonData.minPrice,
         jsonData.createdBlock,
         jsonData.deadline,
-        currentBlock
+        currentBlock,
+        curveToRpc(auction[1].unwrap().curve)
       );
       data.currentPrice = `${currentPrice / unit} NMT`;
     }
@@ -1034,7 +1087,8 @@ async function submit_dutch_auction(
     tokens,
     allow_british_auction,
     minRaise,
-    commission
+    commission,
+    { Linear: { interval: 30 * 60 / blockTimeSec } }
   );
 
   const feeInfo = await call.paymentInfo(account);
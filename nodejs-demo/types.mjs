@@ -63,6 +63,44 @@ const types = {
     reserved: "Compact<TokenId>",
   },
 
+  ClassSupplyCap: {
+    maxTokens: "Option<TokenId>",
+    maxSupply: "Option<TokenId>",
+  },
+
+  TokenApproval: {
+    quantity: "Compact<TokenId>",
+    deadline: "Compact<BlockNumberOf>",
+  },
+
+  LazyMintVoucher: {
+    signer: "AccountId",
+    classId: "Compact<ClassId>",
+    metadata: "NFTMetadata",
+    currencyId: "Compact<CurrencyId>",
+    price: "Compact<Balance>",
+    royaltyRate: "Option<PerU16>",
+    maxQuantity: "Compact<TokenId>",
+    deadline: "Compact<BlockNumberOf>",
+  },
+
+  DropPhase: {
+    start: "Compact<BlockNumberOf>",
+    end: "Compact<BlockNumberOf>",
+    currencyId: "Compact<CurrencyId>",
+    price: "Compact<Balance>",
+    allowList: "Option<Hash>",
+    maxPerAccount: "Option<TokenId>",
+  },
+
+  DropInfo: {
+    creator: "AccountId",
+    classId: "Compact<ClassId>",
+    metadata: "NFTMetadata",
+    royaltyRate: "Option<PerU16>",
+    phases: "Vec<DropPhase>",
+  },
+
   CategoryData: {
     metadata: "NFTMetadata",
     nftCount: "Compact<Balance>",
@@ -91,18 +129,66 @@ const types = {
     commissionRate: "Compact<PerU16>",
   },
 
+  SwapOf: {
+    currencyId: "Compact<CurrencyId>",
+    deposit: "Compact<Balance>",
+    price: "Compact<Balance>",
+    deadline: "Compact<BlockNumberOf>",
+    offeredItems: "Vec<OrderItem>",
+    requestedItems: "Vec<OrderItem>",
+    taker: "Option<AccountId>",
+  },
+
+  FeeDistribution: {
+    burn: "PerU16",
+    staking: "PerU16",
+  },
+
+  AgentInfoOf: {
+    bond: "Compact<Balance>",
+    name: "Vec<u8>",
+    metadata: "NFTMetadata",
+    registeredAt: "Compact<BlockNumberOf>",
+  },
+
+  AgentStats: {
+    volume: "Compact<Balance>",
+    commission: "Compact<Balance>",
+    trades: "Compact<u32>",
+  },
+
+  RentalListingOf: {
+    currencyId: "Compact<CurrencyId>",
+    pricePerBlock: "Compact<Balance>",
+    quantity: "Compact<TokenId>",
+    maxDuration: "Compact<BlockNumberOf>",
+  },
+
+  UserInfoOf: {
+    user: "AccountId",
+    expires: "Compact<BlockNumberOf>",
+  },
+
   BritishAuctionOf: {
     currencyId: "Compact<CurrencyId>",
     hammerPrice: "Compact<Balance>",
     minRaise: "Compact<PerU16>",
+    minIncrement: "Compact<Balance>",
     deposit: "Compact<Balance>",
     initPrice: "Compact<Balance>",
     deadline: "Compact<BlockNumberOf>",
     allowDelay: "bool",
+    softClose: "Option<SoftClose>",
     items: "Vec<OrderItem>",
     commissionRate: "Compact<PerU16>",
   },
 
+  SoftClose: {
+    window: "Compact<BlockNumberOf>",
+    extension: "Compact<BlockNumberOf>",
+    maxExtension: "Option<BlockNumberOf>",
+  },
+
   BritishAuctionBidOf: {
     lastBidPrice: "Compact<Balance>",
     lastBidAccount: "Option<AccountId>",
@@ -122,6 +208,18 @@ const types = {
     allowBritishAuction: "bool",
     minRaise: "Compact<PerU16>",
     commissionRate: "Compact<PerU16>",
+    curve: "DutchAuctionCurve",
+  },
+
+  DutchAuctionCurve: {
+    _enum: {
+      Linear: { interval: "Compact<BlockNumberOf>" },
+      Exponential: {
+        interval: "Compact<BlockNumberOf>",
+        decayRate: "Compact<PerU16>",
+      },
+      Breakpoints: "Vec<(BlockNumberOf, Balance)>",
+    },
   },
 
   DutchAuctionBidOf: "BritishAuctionBidOf",
@@ -0,0 +1,37 @@
+//! Tracing of the Ethereum transactions of a block.
+//!
+//! The runtime replays the extrinsics of a block on top of its parent, listening to the EVM of
+//! the Ethereum transactions with an [`EvmTracer`]. The node formats the traces for the
+//! `debug_traceTransaction` and `trace_filter` RPC methods.
+//!
+//! The [`EvmTracer`] is built with the `tracing` feature only, as the tracing hooks of the EVM
+//! slow down every EVM execution. The production runtime is built without it, and the nodes
+//! serving the tracing RPC methods override it with a runtime built with it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "tracing")]
+mod tracer;
+mod types;
+
+#[cfg(feature = "tracing")]
+pub use tracer::EvmTracer;
+pub use types::*;
+
+use sp_core::H256;
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// The API to replay the Ethereum transactions of a block with the EVM tracer.
+	pub trait EvmTracingApi {
+		/// trace the Ethereum transaction `transaction_hash` after applying the extrinsics before
+		/// it in `extrinsics`. The block must be initialized first.
+		fn trace_transaction(
+			extrinsics: Vec<Block::Extrinsic>, transaction_hash: H256, config: TraceConfig,
+		) -> Result<TransactionTrace, DispatchError>;
+		/// trace the calls of each Ethereum transaction in `extrinsics`, with the hash of the
+		/// transaction. The block must be initialized first.
+		fn trace_block(extrinsics: Vec<Block::Extrinsic>) -> Vec<(H256, Vec<CallTrace>)>;
+	}
+}
@@ -0,0 +1,384 @@
+use crate::types::*;
+use evm::{
+	tracing::{Event as EvmEvent, EventListener as EvmListener},
+	CreateScheme, ExitError, ExitFatal, ExitReason,
+};
+use evm_gasometer::tracing::{Event as GasEvent, EventListener as GasListener};
+use evm_runtime::tracing::{Event as StepEvent, EventListener as StepListener};
+use sp_core::{H160, H256, U256};
+use sp_std::{cell::RefCell, collections::btree_map::BTreeMap, mem, rc::Rc, vec::Vec};
+
+/// Records the calls, and optionally the opcodes, executed by the EVM.
+#[derive(Default)]
+pub struct EvmTracer {
+	config: TraceConfig,
+	trace: TransactionTrace,
+	/// The indices in `trace.calls` of the frames entered but not exited yet.
+	frames: Vec<usize>,
+	/// The gas left in the current frame, as of the last gas event.
+	gas_left: u64,
+	/// The gas left by the frame which just exited, returned to its parent.
+	returned_gas: Option<u64>,
+	/// Whether the gas of the last step is recorded.
+	step_gas_recorded: bool,
+	/// The storage read or written so far, by contract.
+	storage: BTreeMap<H160, BTreeMap<H256, H256>>,
+}
+
+/// Shares an [`EvmTracer`] among the listeners of the EVM, the runtime and the gasometer.
+struct Listener(Rc<RefCell<EvmTracer>>);
+
+impl EvmListener for Listener {
+	fn event(&mut self, event: EvmEvent) {
+		self.0.borrow_mut().evm_event(event);
+	}
+}
+
+impl StepListener for Listener {
+	fn event(&mut self, event: StepEvent) {
+		self.0.borrow_mut().step_event(event);
+	}
+}
+
+impl GasListener for Listener {
+	fn event(&mut self, event: GasEvent) {
+		self.0.borrow_mut().gas_event(event);
+	}
+}
+
+/// Why a frame exited, if it failed.
+fn exit_error(reason: &ExitReason) -> Option<Vec<u8>> {
+	let error: &[u8] = match reason {
+		ExitReason::Succeed(_) => return None,
+		ExitReason::Revert(_) => b"execution reverted",
+		ExitReason::Error(error) | ExitReason::Fatal(ExitFatal::CallErrorAsFatal(error)) =>
+			match error {
+				ExitError::StackUnderflow => b"stack underflow",
+				ExitError::StackOverflow => b"stack overflow",
+				ExitError::InvalidJump => b"invalid jump destination",
+				ExitError::InvalidRange => b"invalid range",
+				ExitError::DesignatedInvalid => b"invalid opcode",
+				ExitError::CallTooDeep => b"max call depth exceeded",
+				ExitError::CreateCollision => b"contract address collision",
+				ExitError::CreateContractLimit => b"max code size exceeded",
+				ExitError::InvalidCode => b"invalid code",
+				ExitError::OutOfOffset => b"out of offset",
+				ExitError::OutOfGas => b"out of gas",
+				ExitError::OutOfFund => b"insufficient balance for transfer",
+				ExitError::PCUnderflow => b"pc underflow",
+				ExitError::CreateEmpty => b"create empty",
+				ExitError::Other(message) => message.as_bytes(),
+			},
+		ExitReason::Fatal(ExitFatal::NotSupported) => b"not supported",
+		ExitReason::Fatal(ExitFatal::UnhandledInterrupt) => b"unhandled interrupt",
+		ExitReason::Fatal(ExitFatal::Other(message)) => message.as_bytes(),
+	};
+	Some(error.to_vec())
+}
+
+impl EvmTracer {
+	pub fn new(config: TraceConfig) -> Self {
+		Self { config, ..Default::default() }
+	}
+
+	/// Run `f` with the tracer listening to the EVM, and return the trace along with the result.
+	pub fn trace<R, F: FnOnce() -> R>(self, f: F) -> (R, TransactionTrace) {
+		let steps = self.config.steps;
+		let tracer = Rc::new(RefCell::new(self));
+		let mut evm_listener = Listener(tracer.clone());
+		let mut gas_listener = Listener(tracer.clone());
+		let result = evm::tracing::using(&mut evm_listener, || {
+			evm_gasometer::tracing::using(&mut gas_listener, || {
+				if steps {
+					let mut step_listener = Listener(tracer.clone());
+					evm_runtime::tracing::using(&mut step_listener, f)
+				} else {
+					f()
+				}
+			})
+		});
+		let trace = mem::take(&mut tracer.borrow_mut().trace);
+		(result, trace)
+	}
+
+	fn evm_event(&mut self, event: EvmEvent) {
+		match event {
+			EvmEvent::Call { code_address, transfer, input, target_gas, is_static, context } => {
+				let value = transfer.as_ref().map(|t| t.value).unwrap_or_default();
+				let (kind, from, value) = if is_static {
+					(CallKind::StaticCall, context.caller, U256::zero())
+				} else if code_address == context.address {
+					(CallKind::Call, context.caller, value)
+				} else if context.caller == context.address {
+					// The code of the callee runs with the storage of the caller.
+					(CallKind::CallCode, context.address, value)
+				} else {
+					// The code of the callee runs in the context of the caller.
+					(CallKind::DelegateCall, context.address, context.apparent_value)
+				};
+				let gas = target_gas.unwrap_or(self.gas_left);
+				self.enter(kind, from, code_address, value, gas, input.to_vec());
+			},
+			EvmEvent::Create { caller, address, scheme, value, init_code, target_gas } => {
+				let kind = match scheme {
+					CreateScheme::Create2 { .. } => CallKind::Create2,
+					_ => CallKind::Create,
+				};
+				let gas = target_gas.unwrap_or(self.gas_left);
+				self.enter(kind, caller, address, value, gas, init_code.to_vec());
+			},
+			EvmEvent::Suicide { address, target, balance } => {
+				self.enter(CallKind::SelfDestruct, address, target, balance, 0, Vec::new());
+				self.frames.pop();
+			},
+			EvmEvent::Exit { reason, return_value } => {
+				// The transaction may fail before entering its call frame.
+				if let Some(index) = self.frames.pop() {
+					let call = &mut self.trace.calls[index];
+					// A frame failing with an error consumes all its gas.
+					let gas_left = match (reason, self.returned_gas) {
+						(ExitReason::Error(_) | ExitReason::Fatal(_), _) => 0,
+						(_, Some(returned_gas)) => returned_gas,
+						(_, None) => self.gas_left,
+					};
+					call.gas_used = call.gas.saturating_sub(gas_left);
+					call.output = return_value.to_vec();
+					call.error = exit_error(reason);
+				}
+			},
+			EvmEvent::TransactCall { gas_limit, .. } |
+			EvmEvent::TransactCreate { gas_limit, .. } |
+			EvmEvent::TransactCreate2 { gas_limit, .. } => self.gas_left = gas_limit,
+		}
+	}
+
+	/// Enter a new call frame, as a subcall of the current one.
+	fn enter(
+		&mut self,
+		kind: CallKind,
+		from: H160,
+		to: H160,
+		value: U256,
+		gas: u64,
+		input: Vec<u8>,
+	) {
+		let trace_address = match self.frames.last() {
+			Some(&parent) => {
+				let parent = &mut self.trace.calls[parent];
+				let mut trace_address = parent.trace_address.clone();
+				trace_address.push(parent.subtraces);
+				parent.subtraces += 1;
+				trace_address
+			},
+			None => Vec::new(),
+		};
+		self.frames.push(self.trace.calls.len());
+		self.trace.calls.push(CallTrace {
+			trace_address,
+			kind,
+			from,
+			to,
+			value,
+			gas,
+			gas_used: 0,
+			input,
+			output: Vec::new(),
+			error: None,
+			subtraces: 0,
+		});
+	}
+
+	fn step_event(&mut self, event: StepEvent) {
+		match event {
+			StepEvent::Step { context: _, opcode, position, stack, memory } => {
+				let config = self.config;
+				self.trace.steps.push(Step {
+					pc: position.as_ref().map_or(0, |pc| *pc as u64),
+					op: opcode.0,
+					depth: self.frames.len() as u32,
+					gas: self.gas_left,
+					gas_cost: 0,
+					stack: (!config.disable_stack).then(|| stack.data().clone()),
+					memory: (!config.disable_memory).then(|| memory.data().clone()),
+					storage: None,
+				});
+				self.step_gas_recorded = false;
+			},
+			StepEvent::SLoad { address, index, value } |
+			StepEvent::SStore { address, index, value } => {
+				if self.config.disable_storage {
+					return
+				}
+				let storage = self.storage.entry(address).or_default();
+				storage.insert(index, value);
+				if let Some(step) = self.trace.steps.last_mut() {
+					step.storage = Some(storage.iter().map(|(k, v)| (*k, *v)).collect());
+				}
+			},
+			StepEvent::StepResult { .. } => {},
+		}
+	}
+
+	fn gas_event(&mut self, event: GasEvent) {
+		self.returned_gas = match event {
+			GasEvent::RecordStipend { stipend, .. } => Some(stipend),
+			GasEvent::RecordRefund { .. } => self.returned_gas,
+			_ => None,
+		};
+		match event {
+			GasEvent::RecordCost { cost, snapshot: Some(snapshot) } =>
+				self.record_cost(snapshot.gas(), cost),
+			GasEvent::RecordDynamicCost {
+				gas_cost, memory_gas, snapshot: Some(snapshot), ..
+			} => {
+				let memory_cost = memory_gas.saturating_sub(snapshot.memory_gas);
+				self.record_cost(snapshot.gas(), gas_cost.saturating_add(memory_cost));
+			},
+			GasEvent::RecordStipend { stipend, snapshot: Some(snapshot) } =>
+				self.gas_left = snapshot.gas().saturating_add(stipend),
+			GasEvent::RecordTransaction { cost, snapshot: Some(snapshot) } =>
+				self.gas_left = snapshot.gas().saturating_sub(cost),
+			_ => {},
+		}
+	}
+
+	/// Record `cost` taken from the `gas_left` of the current frame, for the last step if it is
+	/// not done yet.
+	fn record_cost(&mut self, gas_left: u64, cost: u64) {
+		self.gas_left = gas_left.saturating_sub(cost);
+		if let Some(step) = self.trace.steps.last_mut() {
+			if !self.step_gas_recorded {
+				step.gas = gas_left;
+				self.step_gas_recorded = true;
+			}
+			// The gas passed to a subcall is part of the cost of the calling opcode.
+			step.gas_cost = step.gas_cost.saturating_add(cost);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use evm::{Context, ExitRevert, ExitSucceed, Transfer};
+	use evm_gasometer::Snapshot;
+
+	fn snapshot(gas_limit: u64, used_gas: u64) -> Option<Snapshot> {
+		Some(Snapshot { gas_limit, memory_gas: 0, used_gas, refunded_gas: 0 })
+	}
+
+	fn call(tracer: &mut EvmTracer, caller: H160, address: H160, code_address: H160, gas: u64) {
+		let context = Context { address, caller, apparent_value: 5.into() };
+		let transfer = Some(Transfer { source: caller, target: address, value: 5.into() });
+		tracer.evm_event(EvmEvent::Call {
+			code_address,
+			transfer: &transfer,
+			input: &[1, 2],
+			target_gas: Some(gas),
+			is_static: false,
+			context: &context,
+		});
+	}
+
+	#[test]
+	fn nested_calls_should_be_traced() {
+		let (alice, bob, charlie) =
+			(H160::repeat_byte(1), H160::repeat_byte(2), H160::repeat_byte(3));
+		let mut tracer = EvmTracer::new(TraceConfig::calls());
+		tracer.evm_event(EvmEvent::TransactCall {
+			caller: alice,
+			address: bob,
+			value: 5.into(),
+			data: &[1, 2],
+			gas_limit: 100_000,
+		});
+		tracer.gas_event(GasEvent::RecordTransaction {
+			cost: 21_000,
+			snapshot: snapshot(100_000, 0),
+		});
+		call(&mut tracer, alice, bob, bob, 100_000);
+		tracer.gas_event(GasEvent::RecordCost { cost: 10_000, snapshot: snapshot(79_000, 0) });
+		call(&mut tracer, bob, charlie, charlie, 30_000);
+		tracer.gas_event(GasEvent::RecordCost { cost: 1_000, snapshot: snapshot(30_000, 0) });
+		// The gas left by a subcall is returned to its parent before it exits.
+		tracer.gas_event(GasEvent::RecordStipend {
+			stipend: 29_000,
+			snapshot: snapshot(79_000, 40_000),
+		});
+		let reverted = ExitReason::Revert(ExitRevert::Reverted);
+		tracer.evm_event(EvmEvent::Exit { reason: &reverted, return_value: &[9] });
+		// A delegate call of the code of charlie by bob, running out of gas.
+		call(&mut tracer, alice, bob, charlie, 20_000);
+		tracer.gas_event(GasEvent::RecordCost { cost: 500, snapshot: snapshot(20_000, 0) });
+		let out_of_gas = ExitReason::Error(ExitError::OutOfGas);
+		tracer.evm_event(EvmEvent::Exit { reason: &out_of_gas, return_value: &[] });
+		tracer.gas_event(GasEvent::RecordStipend {
+			stipend: 49_500,
+			snapshot: snapshot(100_000, 100_000),
+		});
+		let stopped = ExitReason::Succeed(ExitSucceed::Stopped);
+		tracer.evm_event(EvmEvent::Exit { reason: &stopped, return_value: &[7] });
+
+		let calls = tracer.trace.calls;
+		assert_eq!(calls.len(), 3);
+		assert_eq!(
+			calls[0],
+			CallTrace {
+				trace_address: vec![],
+				kind: CallKind::Call,
+				from: alice,
+				to: bob,
+				value: 5.into(),
+				gas: 100_000,
+				gas_used: 50_500,
+				input: vec![1, 2],
+				output: vec![7],
+				error: None,
+				subtraces: 2,
+			}
+		);
+		assert_eq!((calls[1].kind, calls[1].trace_address.clone()), (CallKind::Call, vec![0]));
+		assert_eq!((calls[1].from, calls[1].to, calls[1].gas_used), (bob, charlie, 1_000));
+		assert_eq!(calls[1].error, Some(b"execution reverted".to_vec()));
+		assert_eq!(
+			(calls[2].kind, calls[2].trace_address.clone()),
+			(CallKind::DelegateCall, vec![1])
+		);
+		assert_eq!((calls[2].from, calls[2].to, calls[2].gas_used), (bob, charlie, 20_000));
+	}
+
+	#[test]
+	fn step_gas_should_be_recorded() {
+		let mut tracer = EvmTracer::new(TraceConfig { steps: true, ..Default::default() });
+		let (alice, bob) = (H160::repeat_byte(1), H160::repeat_byte(2));
+		call(&mut tracer, alice, bob, bob, 1_000);
+		let context = Context { address: bob, caller: alice, apparent_value: 0.into() };
+		let (stack, memory) = (evm::Stack::new(16), evm::Memory::new(64));
+		for (pc, used_gas, cost) in [(0usize, 0u64, 3u64), (2, 3, 20)] {
+			tracer.step_event(StepEvent::Step {
+				context: &context,
+				opcode: evm::Opcode(0x60),
+				position: &Ok(pc),
+				stack: &stack,
+				memory: &memory,
+			});
+			tracer.gas_event(GasEvent::RecordCost { cost, snapshot: snapshot(1_000, used_gas) });
+		}
+		tracer.step_event(StepEvent::SStore {
+			address: bob,
+			index: H256::repeat_byte(1),
+			value: H256::repeat_byte(2),
+		});
+
+		let steps = tracer.trace.steps;
+		assert_eq!(steps.len(), 2);
+		assert_eq!(
+			(steps[0].pc, steps[0].depth, steps[0].gas, steps[0].gas_cost),
+			(0, 1, 1_000, 3)
+		);
+		assert_eq!((steps[1].pc, steps[1].gas, steps[1].gas_cost), (2, 997, 20));
+		assert_eq!(steps[1].stack, Some(vec![]));
+		assert_eq!(steps[0].storage, None);
+		assert_eq!(steps[1].storage, Some(vec![(H256::repeat_byte(1), H256::repeat_byte(2))]));
+	}
+}
@@ -0,0 +1,105 @@
+use codec::{Decode, Encode};
+use sp_core::{H160, H256, U256};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// What to record while tracing a transaction.
+#[derive(Encode, Decode, Clone, Copy, Default, RuntimeDebug, PartialEq, Eq)]
+pub struct TraceConfig {
+	/// Record the executed opcodes, not only the calls.
+	pub steps: bool,
+	/// Leave the stack out of the recorded opcodes.
+	pub disable_stack: bool,
+	/// Leave the memory out of the recorded opcodes.
+	pub disable_memory: bool,
+	/// Leave the storage out of the recorded opcodes.
+	pub disable_storage: bool,
+}
+
+impl TraceConfig {
+	/// Record the calls only.
+	pub fn calls() -> Self {
+		Self::default()
+	}
+}
+
+/// The kind of a call frame.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub enum CallKind {
+	Call,
+	CallCode,
+	DelegateCall,
+	StaticCall,
+	Create,
+	Create2,
+	SelfDestruct,
+}
+
+/// A call frame of a transaction.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct CallTrace {
+	/// The index of the frame among the subcalls of each of its ancestors, empty for the
+	/// transaction itself.
+	pub trace_address: Vec<u32>,
+	pub kind: CallKind,
+	/// The caller, or the contract destroyed by a self-destruct.
+	pub from: H160,
+	/// The callee, the created contract, or the beneficiary of a self-destruct.
+	pub to: H160,
+	/// The value transferred, or the balance of a destroyed contract.
+	pub value: U256,
+	pub gas: u64,
+	pub gas_used: u64,
+	/// The call data, or the init code of a created contract.
+	pub input: Vec<u8>,
+	pub output: Vec<u8>,
+	/// Why the frame failed, if it did.
+	pub error: Option<Vec<u8>>,
+	/// The number of the direct subcalls.
+	pub subtraces: u32,
+}
+
+/// An executed opcode.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct Step {
+	pub pc: u64,
+	pub op: u8,
+	/// The depth of the call frame, from 1 for the transaction itself.
+	pub depth: u32,
+	/// The gas left before executing the opcode.
+	pub gas: u64,
+	pub gas_cost: u64,
+	pub stack: Option<Vec<H256>>,
+	pub memory: Option<Vec<u8>>,
+	/// The storage of the contract read or written so far, when the opcode accesses it.
+	pub storage: Option<Vec<(H256, H256)>>,
+}
+
+/// The trace of an Ethereum transaction.
+#[derive(Encode, Decode, Clone, Default, RuntimeDebug, PartialEq, Eq)]
+pub struct TransactionTrace {
+	/// The call frames, in the order they are entered.
+	pub calls: Vec<CallTrace>,
+	/// The executed opcodes, if [`TraceConfig::steps`] is set.
+	pub steps: Vec<Step>,
+}
+
+impl TransactionTrace {
+	/// The gas used by the transaction.
+	pub fn gas_used(&self) -> u64 {
+		self.calls.first().map_or(0, |call| call.gas_used)
+	}
+
+	/// Whether the transaction failed.
+	pub fn failed(&self) -> bool {
+		match self.calls.first() {
+			Some(call) => call.error.is_some(),
+			None => true,
+		}
+	}
+
+	/// The value returned by the transaction.
+	pub fn return_value(&self) -> &[u8] {
+		self.calls.first().map_or(&[], |call| &call.output[..])
+	}
+}
@@ -0,0 +1,361 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Currency, OnUnbalanced, ReservableCurrency},
+	transactional,
+};
+use frame_system::pallet_prelude::*;
+pub use nftmart_traits::*;
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{traits::Saturating, RuntimeDebug, SaturatedConversion};
+use sp_std::vec::Vec;
+
+mod mock;
+mod tests;
+
+pub use module::*;
+
+/// The accounts used as commission agents before the registry keep earning commission for this
+/// many blocks after the upgrade adding it, if they hold `MinCommissionAgentDeposit`.
+pub const LEGACY_AGENT_GRACE_PERIOD: BlockNumber = 30 * time::DAYS;
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+enum Releases {
+	V1_0_0,
+	V2_0_0,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1_0_0
+	}
+}
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct AgentInfo<BlockNumber> {
+	/// The balances bonded by the agent.
+	#[codec(compact)]
+	pub bond: Balance,
+	/// The display name of the agent.
+	pub name: Vec<u8>,
+	/// external metadata
+	pub metadata: NFTMetadata,
+	/// The block number when the agent was registered.
+	#[codec(compact)]
+	pub registered_at: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Default, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct AgentStats {
+	/// The total price of trades referred by the agent.
+	#[codec(compact)]
+	pub volume: Balance,
+	/// The total commission earned by the agent.
+	#[codec(compact)]
+	pub commission: Balance,
+	/// The number of trades referred by the agent.
+	#[codec(compact)]
+	pub trades: u32,
+}
+
+pub type BalanceOf<T> =
+	<<T as module::Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub type NegativeImbalanceOf<T> = <<T as module::Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+pub type AgentInfoOf<T> = AgentInfo<BlockNumberFor<T>>;
+
+#[frame_support::pallet]
+pub mod module {
+	use super::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency mechanism.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The currency ID type of trades.
+		type CurrencyId: Parameter + Member + Copy + MaybeSerializeDeserialize;
+
+		/// Extra Configurations
+		type ExtraConfig: NftmartConfig<Self::AccountId, BlockNumberFor<Self>>;
+
+		/// Handler for the slashed bonds.
+		type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		/// The maximum length of an agent's name.
+		#[pallet::constant]
+		type MaxNameLength: Get<u32>;
+
+		/// The maximum length of an agent's metadata.
+		#[pallet::constant]
+		type MaxMetadataLength: Get<u32>;
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// the account is already a registered agent
+		AlreadyRegistered,
+		/// agent not found
+		AgentNotFound,
+		/// the bond is lower than `MinCommissionAgentDeposit`
+		InsufficientBond,
+		/// the name is too long
+		NameTooLong,
+		/// the metadata is too long
+		MetadataTooLong,
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(crate) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// RegisteredAgent \[who, bond\]
+		RegisteredAgent(T::AccountId, Balance),
+		/// UpdatedAgent \[who\]
+		UpdatedAgent(T::AccountId),
+		/// BondedExtra \[who, amount\]
+		BondedExtra(T::AccountId, Balance),
+		/// DeregisteredAgent \[who\]
+		DeregisteredAgent(T::AccountId),
+		/// SlashedAgent \[who, amount\]
+		SlashedAgent(T::AccountId, Balance),
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::<T>::get() == Releases::V1_0_0 {
+				StorageVersion::<T>::put(Releases::V2_0_0);
+				let now = frame_system::Pallet::<T>::block_number();
+				LegacyAgentsUntil::<T>::put(now.saturating_add(LEGACY_AGENT_GRACE_PERIOD.into()));
+				return T::DbWeight::get().reads_writes(1, 2)
+			}
+			0
+		}
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		_phantom: PhantomData<T>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { _phantom: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			<StorageVersion<T>>::put(Releases::V2_0_0);
+		}
+	}
+
+	/// Storage version of the pallet.
+	#[pallet::storage]
+	pub(super) type StorageVersion<T: Config> = StorageValue<_, Releases, ValueQuery>;
+
+	/// Unregistered accounts holding `MinCommissionAgentDeposit` earn commission before this
+	/// block, as they did before the registry.
+	#[pallet::storage]
+	#[pallet::getter(fn legacy_agents_until)]
+	pub type LegacyAgentsUntil<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
+	/// Registered commission agents.
+	#[pallet::storage]
+	#[pallet::getter(fn agents)]
+	pub type Agents<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, AgentInfoOf<T>>;
+
+	/// Lifetime statistics of commission agents by currency, kept after deregistration.
+	#[pallet::storage]
+	#[pallet::getter(fn stats)]
+	pub type Stats<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		T::CurrencyId,
+		AgentStats,
+		ValueQuery,
+	>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register the origin as a commission agent.
+		///
+		/// - `bond`: The balances to be reserved, at least `MinCommissionAgentDeposit`.
+		/// - `name`: display name
+		/// - `metadata`: external metadata
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn register(
+			origin: OriginFor<T>,
+			#[pallet::compact] bond: Balance,
+			name: Vec<u8>,
+			metadata: NFTMetadata,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(!Agents::<T>::contains_key(&who), Error::<T>::AlreadyRegistered);
+			ensure!(
+				bond >= T::ExtraConfig::get_min_commission_agent_deposit(),
+				Error::<T>::InsufficientBond
+			);
+			Self::ensure_valid_info(&name, &metadata)?;
+			T::Currency::reserve(&who, bond.saturated_into())?;
+
+			let info = AgentInfo {
+				bond,
+				name,
+				metadata,
+				registered_at: frame_system::Pallet::<T>::block_number(),
+			};
+			Agents::<T>::insert(&who, info);
+			Self::deposit_event(Event::RegisteredAgent(who, bond));
+			Ok(().into())
+		}
+
+		/// Update the name and metadata of an agent.
+		///
+		/// - `name`: display name
+		/// - `metadata`: external metadata
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn update_agent(
+			origin: OriginFor<T>,
+			name: Vec<u8>,
+			metadata: NFTMetadata,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_valid_info(&name, &metadata)?;
+			Agents::<T>::try_mutate(&who, |maybe_info| -> DispatchResult {
+				let info = maybe_info.as_mut().ok_or(Error::<T>::AgentNotFound)?;
+				info.name = name;
+				info.metadata = metadata;
+				Ok(())
+			})?;
+			Self::deposit_event(Event::UpdatedAgent(who));
+			Ok(().into())
+		}
+
+		/// Bond more balances, e.g. to recover from slashing.
+		///
+		/// - `amount`: The balances to be reserved.
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn bond_extra(
+			origin: OriginFor<T>,
+			#[pallet::compact] amount: Balance,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Agents::<T>::try_mutate(&who, |maybe_info| -> DispatchResult {
+				let info = maybe_info.as_mut().ok_or(Error::<T>::AgentNotFound)?;
+				T::Currency::reserve(&who, amount.saturated_into())?;
+				info.bond = info.bond.saturating_add(amount);
+				Ok(())
+			})?;
+			Self::deposit_event(Event::BondedExtra(who, amount));
+			Ok(().into())
+		}
+
+		/// Deregister the origin and return its bond.
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn deregister(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::do_deregister(&who)?;
+			Ok(().into())
+		}
+
+		/// Deregister an agent by governance, its bond will be returned.
+		///
+		/// - `who`: the agent
+		#[pallet::weight((100_000, DispatchClass::Operational, Pays::Yes))]
+		#[transactional]
+		pub fn force_deregister(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			Self::do_deregister(&who)?;
+			Ok((None, Pays::No).into())
+		}
+
+		/// Slash the bond of an agent by governance.
+		/// The agent earns no more commission until its bond is restored with `bond_extra`.
+		///
+		/// - `who`: the agent
+		/// - `amount`: The balances to be slashed.
+		#[pallet::weight((100_000, DispatchClass::Operational, Pays::Yes))]
+		#[transactional]
+		pub fn slash(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+			#[pallet::compact] amount: Balance,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			let slashed =
+				Agents::<T>::try_mutate(&who, |maybe_info| -> Result<_, DispatchError> {
+					let info = maybe_info.as_mut().ok_or(Error::<T>::AgentNotFound)?;
+					let amount = amount.min(info.bond);
+					let (imbalance, _) = T::Currency::slash_reserved(&who, amount.saturated_into());
+					T::Slash::on_unbalanced(imbalance);
+					info.bond = info.bond.saturating_sub(amount);
+					Ok(amount)
+				})?;
+			Self::deposit_event(Event::SlashedAgent(who, slashed));
+			Ok((None, Pays::No).into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	fn ensure_valid_info(name: &[u8], metadata: &[u8]) -> DispatchResult {
+		ensure!(name.len() as u32 <= T::MaxNameLength::get(), Error::<T>::NameTooLong);
+		ensure!(metadata.len() as u32 <= T::MaxMetadataLength::get(), Error::<T>::MetadataTooLong);
+		Ok(())
+	}
+
+	fn do_deregister(who: &T::AccountId) -> DispatchResult {
+		let info = Agents::<T>::take(who).ok_or(Error::<T>::AgentNotFound)?;
+		let _remain: BalanceOf<T> = T::Currency::unreserve(who, info.bond.saturated_into());
+		Self::deposit_event(Event::DeregisteredAgent(who.clone()));
+		Ok(())
+	}
+}
+
+impl<T: Config> NftmartCommissionAgent<T::AccountId, T::CurrencyId> for Pallet<T> {
+	fn is_registered_agent(who: &T::AccountId) -> bool {
+		let min_deposit = T::ExtraConfig::get_min_commission_agent_deposit();
+		match Self::agents(who) {
+			Some(info) => info.bond >= min_deposit,
+			None =>
+				frame_system::Pallet::<T>::block_number() < Self::legacy_agents_until() &&
+					T::Currency::total_balance(who).saturated_into::<Balance>() >= min_deposit,
+		}
+	}
+
+	fn note_commission(
+		agent: &T::AccountId,
+		currency_id: T::CurrencyId,
+		volume: Balance,
+		commission: Balance,
+	) {
+		Stats::<T>::mutate(agent, currency_id, |stats| {
+			stats.volume = stats.volume.saturating_add(volume);
+			stats.commission = stats.commission.saturating_add(commission);
+			stats.trades = stats.trades.saturating_add(1);
+		});
+	}
+}
@@ -0,0 +1,105 @@
+#![cfg(test)]
+
+use super::*;
+use frame_support::{assert_noop, assert_ok};
+use mock::{Event, *};
+use sp_runtime::DispatchError;
+
+#[test]
+fn register_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			NftmartAgent::register(Origin::signed(ALICE), 49, vec![1], vec![1]),
+			Error::<Runtime>::InsufficientBond
+		);
+		assert_noop!(
+			NftmartAgent::register(Origin::signed(ALICE), 50, vec![1; 9], vec![1]),
+			Error::<Runtime>::NameTooLong
+		);
+		assert_noop!(
+			NftmartAgent::register(Origin::signed(ALICE), 50, vec![1], vec![1; 17]),
+			Error::<Runtime>::MetadataTooLong
+		);
+
+		assert_ok!(NftmartAgent::register(Origin::signed(ALICE), 50, vec![1], vec![2]));
+		assert_eq!(last_event(), Event::NftmartAgent(crate::Event::RegisteredAgent(ALICE, 50)));
+		assert_eq!(
+			NftmartAgent::agents(ALICE),
+			Some(AgentInfo { bond: 50, name: vec![1], metadata: vec![2], registered_at: 1 })
+		);
+		assert_eq!(free_balance(&ALICE), 50);
+		assert!(NftmartAgent::is_registered_agent(&ALICE));
+		assert!(!NftmartAgent::is_registered_agent(&BOB));
+		assert_noop!(
+			NftmartAgent::register(Origin::signed(ALICE), 50, vec![1], vec![2]),
+			Error::<Runtime>::AlreadyRegistered
+		);
+
+		assert_ok!(NftmartAgent::update_agent(Origin::signed(ALICE), vec![3], vec![4]));
+		assert_eq!(last_event(), Event::NftmartAgent(crate::Event::UpdatedAgent(ALICE)));
+		let info = NftmartAgent::agents(ALICE).unwrap();
+		assert_eq!((info.name, info.metadata), (vec![3], vec![4]));
+
+		NftmartAgent::note_commission(&ALICE, NATIVE_CURRENCY_ID, 1000, 10);
+		NftmartAgent::note_commission(&ALICE, NATIVE_CURRENCY_ID, 500, 5);
+		assert_eq!(
+			NftmartAgent::stats(ALICE, NATIVE_CURRENCY_ID),
+			AgentStats { volume: 1500, commission: 15, trades: 2 }
+		);
+
+		assert_ok!(NftmartAgent::deregister(Origin::signed(ALICE)));
+		assert_eq!(last_event(), Event::NftmartAgent(crate::Event::DeregisteredAgent(ALICE)));
+		assert_eq!(NftmartAgent::agents(ALICE), None);
+		assert_eq!(free_balance(&ALICE), 100);
+		assert!(!NftmartAgent::is_registered_agent(&ALICE));
+		// lifetime statistics are kept.
+		assert_eq!(NftmartAgent::stats(ALICE, NATIVE_CURRENCY_ID).trades, 2);
+	});
+}
+
+#[test]
+fn slash_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(NftmartAgent::register(Origin::signed(ALICE), 60, vec![1], vec![2]));
+		assert_noop!(NftmartAgent::slash(Origin::signed(BOB), ALICE, 20), DispatchError::BadOrigin);
+
+		assert_ok!(NftmartAgent::slash(Origin::root(), ALICE, 20));
+		assert_eq!(last_event(), Event::NftmartAgent(crate::Event::SlashedAgent(ALICE, 20)));
+		assert_eq!(NftmartAgent::agents(ALICE).unwrap().bond, 40);
+		assert_eq!(free_balance(&ALICE), 40);
+		assert!(!NftmartAgent::is_registered_agent(&ALICE));
+
+		assert_ok!(NftmartAgent::bond_extra(Origin::signed(ALICE), 10));
+		assert_eq!(last_event(), Event::NftmartAgent(crate::Event::BondedExtra(ALICE, 10)));
+		assert!(NftmartAgent::is_registered_agent(&ALICE));
+
+		assert_ok!(NftmartAgent::force_deregister(Origin::root(), ALICE));
+		assert_eq!(NftmartAgent::agents(ALICE), None);
+		assert_eq!(free_balance(&ALICE), 80);
+	});
+}
+
+#[test]
+fn legacy_agents_should_be_paid_until_the_cutoff() {
+	ExtBuilder::default().build().execute_with(|| {
+		// A new chain has no legacy agents.
+		assert_eq!(NftmartAgent::on_runtime_upgrade(), 0);
+		assert!(!NftmartAgent::is_registered_agent(&BOB));
+
+		StorageVersion::<Runtime>::put(Releases::V1_0_0);
+		NftmartAgent::on_runtime_upgrade();
+		let until = 1 + LEGACY_AGENT_GRACE_PERIOD as u64;
+		assert_eq!(NftmartAgent::legacy_agents_until(), until);
+		assert!(NftmartAgent::is_registered_agent(&BOB));
+
+		// The deposit is still required.
+		assert_ok!(Balances::transfer(Origin::signed(BOB), ALICE, 60));
+		assert!(!NftmartAgent::is_registered_agent(&BOB));
+		assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, 60));
+
+		System::set_block_number(until);
+		assert!(!NftmartAgent::is_registered_agent(&BOB));
+		assert_ok!(NftmartAgent::register(Origin::signed(BOB), 50, vec![1], vec![2]));
+		assert!(NftmartAgent::is_registered_agent(&BOB));
+	});
+}
@@ -47,6 +47,7 @@ benchmarks! {
 			true,
 			PerU16::from_percent(50),
 			PerU16::zero(),
+			Default::default(),
 		)?;
 	}
 	verify {
@@ -1,12 +1,27 @@
 #![cfg(test)]
 
 use super::NATIVE_CURRENCY_ID;
-use crate::{mock::*, utils::test_helper::*};
-use frame_support::assert_ok;
-use nftmart_traits::*;
+use crate::{mock::*, utils::test_helper::*, Error};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::Hooks};
+use nftmart_traits::{
+	notification::WEBHOOK_KEY_TYPE,
+	webhook::{hex, SIGNATURE_HEADER, SIGNER_HEADER},
+	*,
+};
 use orml_nft::AccountToken;
 use paste::paste;
+use sp_core::{
+	ed25519,
+	offchain::{
+		testing::{PendingRequest, TestOffchainExt},
+		OffchainDbExt, OffchainWorkerExt, StorageKind,
+	},
+	Pair,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
 use sp_runtime::{PerU16, SaturatedConversion};
+use std::sync::Arc;
 
 macro_rules! submit_british_auction_should_work {
     ( $(#[$attr: meta])* $test_name: ident, $hammer_price: expr) => {
@@ -34,10 +49,12 @@ macro_rules! submit_british_auction_should_work {
 						NATIVE_CURRENCY_ID,
 						$hammer_price, // hammer_price
 						PerU16::from_percent(50), // min_raise
+						0, // min_increment
 						deposit, // deposit
 						200, // init_price
 						10, // deadline
 						true, // allow_delay
+						None, // soft_close
 						vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)],
 						PerU16::zero(),
 					));
@@ -76,10 +93,12 @@ fn bid_british_auction_should_work_hammer_price() {
 			NATIVE_CURRENCY_ID,
 			hammer,                   // hammer_price
 			PerU16::from_percent(50), // min_raise
+			0,                        // min_increment
 			50,                       // deposit
 			200,                      // init_price
 			10,                       // deadline
 			true,                     // allow_delay
+			None,                     // soft_close
 			vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)],
 			PerU16::zero(),
 		));
@@ -110,6 +129,66 @@ fn bid_british_auction_should_work_hammer_price() {
 	});
 }
 
+#[test]
+fn quote_bid_british_auction_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, None);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 40, Some(PerU16::zero()));
+		let auction_id = current_gid::<Runtime>();
+
+		assert_noop!(
+			NftmartAuction::quote_bid(&BOB, auction_id, 300, None),
+			Error::<Runtime>::DutchAuctionNotFound
+		);
+
+		let hammer = 500;
+		assert_ok!(NftmartAuction::submit_british_auction(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			hammer,                   // hammer_price
+			PerU16::from_percent(50), // min_raise
+			0,                        // min_increment
+			50,                       // deposit
+			200,                      // init_price
+			10,                       // deadline
+			true,                     // allow_delay
+			None,                     // soft_close
+			vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)],
+			PerU16::zero(),
+		));
+
+		// Below the hammer price, the bid is reserved.
+		let quote = NftmartAuction::quote_bid(&BOB, auction_id, 300, None).unwrap();
+		let (platform_fee, _, commission) =
+			sale_fees(300, NftmartConf::platform_fee_rate(), PerU16::zero(), None);
+		assert_eq!(quote.currency_id, NATIVE_CURRENCY_ID);
+		assert_eq!(quote.price, 300);
+		assert_eq!(quote.platform_fee, platform_fee);
+		assert_eq!(quote.commission, commission);
+		assert_eq!(quote.deposit, 300);
+
+		// Reaching the hammer price pays the hammer price, and nothing is reserved.
+		let quote = NftmartAuction::quote_bid(&BOB, auction_id, 600, None).unwrap();
+		let (platform_fee, ..) =
+			sale_fees(hammer, NftmartConf::platform_fee_rate(), PerU16::zero(), None);
+		assert_eq!(quote.price, hammer);
+		assert_eq!(quote.platform_fee, platform_fee);
+		assert_eq!(quote.deposit, 0);
+		assert_eq!(quote.tx_fee, 0);
+
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(CHARLIE),
+			600,
+			BOB,
+			auction_id,
+			None,
+			None,
+		));
+		assert_eq!(free_balance(&CHARLIE), CHARLIE_INIT - quote.price);
+	});
+}
+
 macro_rules! bid_british_auction_should_work {
     ( $(#[$attr: meta])* $test_name: ident, hammer_price $hammer_price: expr, price $price: expr) => {
 		paste! {
@@ -128,10 +207,12 @@ macro_rules! bid_british_auction_should_work {
 						NATIVE_CURRENCY_ID,
 						$hammer_price, // hammer_price
 						raise, // min_raise
+						0, // min_increment
 						50, // deposit
 						200, // init_price
 						10, // deadline
 						true, // allow_delay
+						None, // soft_close
 						vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)],
 						PerU16::zero(),
 					));
@@ -191,10 +272,12 @@ macro_rules! redeem_british_auction_should_work {
 						NATIVE_CURRENCY_ID,
 						500, // hammer_price
 						PerU16::from_percent(50), // min_raise
+						0, // min_increment
 						50, // deposit
 						200, // init_price
 						10, // deadline
 						$allow_delay, // allow_delay
+						None, // soft_close
 						vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)],
 						PerU16::zero(),
 					));
@@ -243,10 +326,12 @@ fn remove_british_auction_should_work() {
 			NATIVE_CURRENCY_ID,
 			hammer,                   // hammer_price
 			PerU16::from_percent(50), // min_raise
+			0,                        // min_increment
 			50,                       // deposit
 			200,                      // init_price
 			10,                       // deadline
 			true,                     // allow_delay
+			None,                     // soft_close
 			vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)],
 			PerU16::zero(),
 		));
@@ -258,6 +343,194 @@ fn remove_british_auction_should_work() {
 	});
 }
 
+#[test]
+fn submit_british_auction_should_fail_with_invalid_soft_close() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, None);
+		for (allow_delay, window, extension) in [(false, 3, 5), (true, 0, 5), (true, 3, 0)] {
+			assert_noop!(
+				NftmartAuction::submit_british_auction(
+					Origin::signed(BOB),
+					NATIVE_CURRENCY_ID,
+					0,                        // hammer_price
+					PerU16::from_percent(10), // min_raise
+					0,                        // min_increment
+					50,                       // deposit
+					200,                      // init_price
+					10,                       // deadline
+					allow_delay,              // allow_delay
+					Some(SoftClose { window, extension, max_extension: None }),
+					vec![(CLASS_ID0, TOKEN_ID0, 10)],
+					PerU16::zero(),
+				),
+				Error::<Runtime>::InvalidSoftClose
+			);
+		}
+	});
+}
+
+#[test]
+fn british_auction_soft_close_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, None);
+		let auction_id = current_gid::<Runtime>();
+		let soft_close = SoftClose { window: 3, extension: 5, max_extension: Some(4) };
+		assert_ok!(NftmartAuction::submit_british_auction(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			0,                        // hammer_price
+			PerU16::from_percent(10), // min_raise
+			50,                       // min_increment
+			50,                       // deposit
+			200,                      // init_price
+			10,                       // deadline
+			true,                     // allow_delay
+			Some(soft_close.clone()), // soft_close
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		let deadline = || {
+			let auction = get_auction(&BOB, auction_id).unwrap();
+			let bid = get_bid(auction_id).unwrap();
+			crate::get_deadline::<Runtime>(
+				auction.allow_delay,
+				auction.deadline,
+				bid.last_bid_block,
+				&auction.soft_close,
+			)
+		};
+
+		// A bid outside the window doesn't extend the deadline.
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(CHARLIE),
+			200,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		assert_eq!(deadline(), 10);
+
+		// `min_increment` is larger than `min_raise` here.
+		assert_noop!(
+			NftmartAuction::bid_british_auction(
+				Origin::signed(DAVE),
+				249,
+				BOB,
+				auction_id,
+				None,
+				None
+			),
+			Error::<Runtime>::PriceTooLow
+		);
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(DAVE),
+			250,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+
+		// A bid within the window extends the deadline.
+		System::set_block_number(8);
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(CHARLIE),
+			300,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		assert_eq!(deadline(), 13);
+
+		// The extension is capped by `max_extension`.
+		System::set_block_number(12);
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(DAVE),
+			350,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		assert_eq!(deadline(), 14);
+
+		System::set_block_number(14);
+		assert_noop!(
+			NftmartAuction::redeem_british_auction(Origin::signed(ALICE), BOB, auction_id),
+			Error::<Runtime>::CannotRedeemAuctionUntilDeadline
+		);
+		System::set_block_number(15);
+		assert_noop!(
+			NftmartAuction::bid_british_auction(
+				Origin::signed(CHARLIE),
+				400,
+				BOB,
+				auction_id,
+				None,
+				None
+			),
+			Error::<Runtime>::BritishAuctionClosed
+		);
+		assert_ok!(NftmartAuction::redeem_british_auction(Origin::signed(ALICE), BOB, auction_id));
+		let event = Event::NftmartAuction(crate::Event::RedeemedBritishAuction(
+			DAVE, auction_id, None, None,
+		));
+		assert_eq!(last_event::<Runtime>(), event);
+	});
+}
+
+#[test]
+fn migrate_british_auctions_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		use crate::migrations::{OldBritishAuction, OldBritishAuctionOf};
+		use frame_support::{generate_storage_alias, Blake2_128Concat, Twox64Concat};
+		generate_storage_alias!(
+			NftmartAuction,
+			BritishAuctions => DoubleMap<
+				(AccountId, Blake2_128Concat),
+				(GlobalId, Twox64Concat),
+				OldBritishAuctionOf<Runtime>
+			>
+		);
+		let old = OldBritishAuction {
+			currency_id: NATIVE_CURRENCY_ID,
+			hammer_price: 500,
+			min_raise: PerU16::from_percent(50),
+			deposit: 50,
+			init_price: 200,
+			deadline: 100,
+			allow_delay: true,
+			items: vec![OrderItem { class_id: CLASS_ID0, token_id: TOKEN_ID0, quantity: 10 }],
+			commission_rate: PerU16::zero(),
+		};
+		BritishAuctions::insert(BOB, 1, old.clone());
+		assert_eq!(NftmartAuction::british_auctions(BOB, 1), None);
+
+		crate::migrations::migrate_british_auctions::<Runtime>();
+		let auction = NftmartAuction::british_auctions(BOB, 1).unwrap();
+		assert_eq!(auction.hammer_price, old.hammer_price);
+		assert_eq!(auction.items, old.items);
+		assert_eq!(auction.min_increment, 0);
+		assert_eq!(auction.soft_close, None);
+	});
+}
+
+#[test]
+fn runtime_upgrade_should_migrate_from_an_unset_storage_version() {
+	ExtBuilder::default().build().execute_with(|| {
+		// A chain which never wrote the storage version is on the first release.
+		crate::StorageVersion::<Runtime>::kill();
+		assert_eq!(crate::StorageVersion::<Runtime>::get(), crate::Releases::V1_0_0);
+
+		NftmartAuction::on_runtime_upgrade();
+		assert_eq!(crate::StorageVersion::<Runtime>::get(), crate::Releases::V3_0_0);
+	});
+}
+
 #[test]
 fn calc_current_price_should_work() {
 	for (x, y) in vec![
@@ -287,7 +560,8 @@ fn calc_current_price_should_work() {
 				100 * ACCURACY,
 				0,
 				(time::MINUTES * 120).saturated_into(),
-				(time::MINUTES * x).saturated_into()
+				(time::MINUTES * x).saturated_into(),
+				&Default::default(),
 			),
 			y * ACCURACY,
 			"x={}, y={}",
@@ -311,6 +585,7 @@ fn calc_current_price_should_work() {
 				0,
 				1,
 				x.saturated_into(),
+				&Default::default(),
 			),
 			y * ACCURACY,
 			"x={}, y={}",
@@ -328,7 +603,14 @@ fn calc_current_price_should_work() {
 		(30 * time::MINUTES, 100),
 	] {
 		assert_eq!(
-			crate::calc_current_price::<Runtime>(101, 100, 0, 1, x.saturated_into(),),
+			crate::calc_current_price::<Runtime>(
+				101,
+				100,
+				0,
+				1,
+				x.saturated_into(),
+				&Default::default()
+			),
 			y,
 			"x={}, y={}",
 			x,
@@ -336,3 +618,90 @@ fn calc_current_price_should_work() {
 		);
 	}
 }
+
+#[test]
+fn offchain_worker_should_post_bids() {
+	const SEED: &str = "//Webhook";
+	const URL: &str = "http://localhost:8080/nftmart";
+
+	let mut ext = ExtBuilder::default().build();
+	let (offchain, state) = TestOffchainExt::with_offchain_db(ext.offchain_db());
+	let keystore = KeyStore::new();
+	SyncCryptoStore::ed25519_generate_new(&keystore, WEBHOOK_KEY_TYPE, Some(SEED)).unwrap();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(KeystoreExt(Arc::new(keystore)));
+
+	let auction_id = ext.execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, None);
+		let auction_id = current_gid::<Runtime>();
+		assert_ok!(NftmartAuction::submit_british_auction(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			0,                        // hammer_price
+			PerU16::from_percent(10), // min_raise
+			0,                        // min_increment
+			50,                       // deposit
+			200,                      // init_price
+			10,                       // deadline
+			true,                     // allow_delay
+			None,                     // soft_close
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(CHARLIE),
+			200,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		assert_ok!(NftmartAuction::bid_british_auction(
+			Origin::signed(DAVE),
+			300,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		auction_id
+	});
+	ext.persist_offchain_overlay();
+
+	ext.execute_with(|| {
+		sp_io::offchain::local_storage_set(
+			StorageKind::PERSISTENT,
+			b"nftmart/webhook/urls",
+			&vec![URL.as_bytes().to_vec()].encode(),
+		);
+		let pair = ed25519::Pair::from_string(SEED, None).unwrap();
+		let header = |bytes: &[u8]| String::from_utf8(hex(bytes)).unwrap();
+		for (bidder, outbid, price) in [(CHARLIE, None, 200), (DAVE, Some(CHARLIE), 300)] {
+			let body = Notification::<AccountId, _>::Bid {
+				auction_id,
+				owner: BOB,
+				bidder,
+				outbid,
+				currency_id: NATIVE_CURRENCY_ID,
+				price,
+			}
+			.to_json(1);
+			state.write().expect_request(PendingRequest {
+				method: "POST".into(),
+				uri: URL.into(),
+				headers: vec![
+					("Content-Type".into(), "application/json".into()),
+					(SIGNER_HEADER.into(), header(pair.public().as_ref())),
+					(SIGNATURE_HEADER.into(), header(pair.sign(&body).as_ref())),
+				],
+				body,
+				response: Some(vec![]),
+				sent: true,
+				..Default::default()
+			});
+		}
+		NftmartConf::offchain_worker(1);
+	});
+}
@@ -24,6 +24,7 @@ fn create_auction(allow_british: bool, max_price: Balance) -> GlobalId {
 		allow_british,
 		PerU16::from_percent(50),
 		PerU16::zero(),
+		Default::default(),
 	));
 	let event = Event::NftmartAuction(crate::Event::CreatedDutchAuction(BOB, auction_id));
 	assert_eq!(last_event::<Runtime>(), event);
@@ -67,6 +68,7 @@ fn submit_dutch_auction_should_fail() {
 				false,
 				PerU16::from_percent(50),
 				PerU16::zero(),
+				Default::default(),
 			),
 			Error::<Runtime>::TooManyTokenChargedRoyalty
 		);
@@ -226,3 +228,196 @@ fn bid_dutch_auction_should_fail() {
 		);
 	});
 }
+
+#[test]
+fn submit_dutch_auction_with_invalid_curve_should_fail() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, None);
+
+		for curve in vec![
+			DutchAuctionCurve::Linear { interval: 0 },
+			DutchAuctionCurve::Exponential { interval: 10, decay_rate: PerU16::zero() },
+			DutchAuctionCurve::Exponential { interval: 0, decay_rate: PerU16::from_percent(10) },
+			DutchAuctionCurve::Breakpoints(vec![]),
+			DutchAuctionCurve::Breakpoints(vec![(10, 100)]),
+			DutchAuctionCurve::Breakpoints(vec![(10, 600)]),
+			DutchAuctionCurve::Breakpoints(vec![(10, 400), (10, 300)]),
+			DutchAuctionCurve::Breakpoints(vec![(10, 300), (20, 400)]),
+		] {
+			assert_noop!(
+				NftmartAuction::submit_dutch_auction(
+					Origin::signed(BOB),
+					NATIVE_CURRENCY_ID,
+					50,  // deposit
+					200, // min_price
+					500, // max_price
+					100, // deadline
+					vec![(CLASS_ID0, TOKEN_ID0, 10)],
+					false,
+					PerU16::from_percent(50),
+					PerU16::zero(),
+					curve,
+				),
+				Error::<Runtime>::InvalidPriceCurve
+			);
+		}
+	});
+}
+
+#[test]
+fn calc_curve_price_should_work() {
+	let curve =
+		DutchAuctionCurve::Exponential { interval: 10, decay_rate: PerU16::from_percent(50) };
+	for (x, y) in
+		vec![(0, 1000), (5, 1000), (10, 600), (25, 400), (30, 300), (100, 200), (101, 200)]
+	{
+		assert_eq!(
+			crate::calc_current_price::<Runtime>(1000, 200, 0, 100, x, &curve),
+			y,
+			"x={}, y={}",
+			x,
+			y,
+		);
+	}
+
+	let curve = DutchAuctionCurve::Breakpoints(vec![(10, 900), (20, 500), (40, 300)]);
+	for (x, y) in
+		vec![(5, 1000), (10, 900), (19, 900), (20, 500), (50, 300), (100, 300), (101, 200)]
+	{
+		assert_eq!(
+			crate::calc_current_price::<Runtime>(1000, 200, 0, 100, x, &curve),
+			y,
+			"x={}, y={}",
+			x,
+			y,
+		);
+	}
+}
+
+#[test]
+fn bid_dutch_auction_with_curve_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, Some(PerU16::zero()));
+
+		let auction_id = current_gid::<Runtime>();
+		assert_ok!(NftmartAuction::submit_dutch_auction(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			50,  // deposit
+			200, // min_price
+			500, // max_price
+			100, // deadline
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			false,
+			PerU16::from_percent(50),
+			PerU16::zero(),
+			DutchAuctionCurve::Breakpoints(vec![(10, 400), (20, 300)]),
+		));
+
+		System::set_block_number(15);
+		let auction = NftmartAuction::dutch_auctions(BOB, auction_id).unwrap();
+		assert_eq!(NftmartAuction::dutch_auction_price(&auction, 15), 400);
+		assert_ok!(NftmartAuction::bid_dutch_auction(
+			Origin::signed(CHARLIE),
+			0,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		assert_eq!(free_balance(&CHARLIE), CHARLIE_INIT - 400);
+	});
+}
+
+#[test]
+fn quote_bid_dutch_auction_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class::<Runtime>(ALICE);
+		add_token::<Runtime>(ALICE, BOB, CLASS_ID0, 20, Some(PerU16::zero()));
+
+		let auction_id = current_gid::<Runtime>();
+		assert_ok!(NftmartAuction::submit_dutch_auction(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			50,  // deposit
+			200, // min_price
+			500, // max_price
+			100, // deadline
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			false,
+			PerU16::from_percent(50),
+			PerU16::zero(),
+			DutchAuctionCurve::Breakpoints(vec![(10, 400), (20, 300)]),
+		));
+
+		// The quote follows the curve, whatever the bid, and nothing is reserved.
+		System::set_block_number(15);
+		let quote = NftmartAuction::quote_bid(&BOB, auction_id, 0, None).unwrap();
+		let (platform_fee, ..) =
+			sale_fees(400, NftmartConf::platform_fee_rate(), PerU16::zero(), None);
+		assert_eq!(quote.price, 400);
+		assert_eq!(quote.platform_fee, platform_fee);
+		assert_eq!(quote.royalty, 0);
+		assert_eq!(quote.deposit, 0);
+
+		assert_ok!(NftmartAuction::bid_dutch_auction(
+			Origin::signed(CHARLIE),
+			0,
+			BOB,
+			auction_id,
+			None,
+			None
+		));
+		assert_eq!(free_balance(&CHARLIE), CHARLIE_INIT - quote.price);
+	});
+	ExtBuilder::default().build().execute_with(|| {
+		// A bid on a dutch auction which allows a british auction is reserved.
+		let auction_id = create_auction(true, 500);
+		let quote = NftmartAuction::quote_bid(&BOB, auction_id, 0, None).unwrap();
+		assert_eq!(quote.price, 500);
+		assert_eq!(quote.deposit, quote.price);
+
+		assert_noop!(
+			NftmartAuction::quote_bid(&BOB, auction_id + 1, 0, None),
+			Error::<Runtime>::DutchAuctionNotFound
+		);
+	});
+}
+
+#[test]
+fn migrate_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		use crate::migrations::{OldDutchAuction, OldDutchAuctionOf};
+		use frame_support::{generate_storage_alias, Blake2_128Concat, Twox64Concat};
+		generate_storage_alias!(
+			NftmartAuction,
+			DutchAuctions => DoubleMap<
+				(AccountId, Blake2_128Concat),
+				(GlobalId, Twox64Concat),
+				OldDutchAuctionOf<Runtime>
+			>
+		);
+		let old = OldDutchAuction {
+			currency_id: NATIVE_CURRENCY_ID,
+			deposit: 50,
+			min_price: 200,
+			max_price: 500,
+			deadline: 100,
+			created_block: 1,
+			items: vec![OrderItem { class_id: CLASS_ID0, token_id: TOKEN_ID0, quantity: 10 }],
+			allow_british_auction: false,
+			min_raise: PerU16::from_percent(50),
+			commission_rate: PerU16::zero(),
+		};
+		DutchAuctions::insert(BOB, 1, old.clone());
+		assert_eq!(NftmartAuction::dutch_auctions(BOB, 1), None);
+
+		crate::migrations::do_migrate::<Runtime>();
+		let auction = NftmartAuction::dutch_auctions(BOB, 1).unwrap();
+		assert_eq!(auction.max_price, old.max_price);
+		assert_eq!(auction.items, old.items);
+		assert_eq!(auction.curve, DutchAuctionCurve::Linear { interval: DESC_INTERVAL.into() });
+	});
+}
@@ -32,9 +32,12 @@ pub use utils::*;
 mod types;
 pub use types::*;
 
+pub mod migrations;
+
 pub use module::*;
 
 pub const MAX_TOKEN_PER_AUCTION: u32 = 100;
+pub const MAX_CURVE_BREAKPOINTS: u32 = 100;
 
 #[frame_support::pallet]
 pub mod module {
@@ -74,6 +77,18 @@ pub mod module {
 		/// Extra Configurations
 		type ExtraConfig: NftmartConfig<Self::AccountId, BlockNumberFor<Self>>;
 
+		/// Registry of commission agents
+		type CommissionAgent: NftmartCommissionAgent<
+			Self::AccountId,
+			<Self::MultiCurrency as MultiCurrency<Self::AccountId>>::CurrencyId,
+		>;
+
+		/// Router of trading fees
+		type FeeRouter: NftmartFeeRouter<
+			Self::AccountId,
+			<Self::MultiCurrency as MultiCurrency<Self::AccountId>>::CurrencyId,
+		>;
+
 		/// The treasury's pallet id, used for deriving its sovereign account ID.
 		#[pallet::constant]
 		type TreasuryPalletId: Get<frame_support::PalletId>;
@@ -108,6 +123,8 @@ pub mod module {
 		EmptyTokenList,
 		InvalidCommissionRate,
 		SenderTakeCommission,
+		InvalidPriceCurve,
+		InvalidSoftClose,
 	}
 
 	#[pallet::event]
@@ -139,6 +156,8 @@ pub mod module {
 			Option<(bool, T::AccountId, PerU16)>,
 			Option<Vec<u8>>,
 		),
+		/// RoyaltyPaid \[beneficiary, currency_id, amount\]
+		RoyaltyPaid(T::AccountId, CurrencyIdOf<T>, Balance),
 	}
 
 	#[pallet::pallet]
@@ -147,7 +166,16 @@ pub mod module {
 	#[pallet::hooks]
 	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
 		fn on_runtime_upgrade() -> Weight {
-			0
+			let mut weight: Weight = 0;
+			if StorageVersion::<T>::get() == Releases::V1_0_0 {
+				StorageVersion::<T>::put(Releases::V2_0_0);
+				weight = weight.saturating_add(migrations::do_migrate::<T>());
+			}
+			if StorageVersion::<T>::get() == Releases::V2_0_0 {
+				StorageVersion::<T>::put(Releases::V3_0_0);
+				weight = weight.saturating_add(migrations::migrate_british_auctions::<T>());
+			}
+			weight
 		}
 
 		fn integrity_test() {}
@@ -168,7 +196,7 @@ pub mod module {
 	#[pallet::genesis_build]
 	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
 		fn build(&self) {
-			<StorageVersion<T>>::put(Releases::default());
+			<StorageVersion<T>>::put(Releases::V3_0_0);
 		}
 	}
 
@@ -228,6 +256,7 @@ pub mod module {
 			allow_british_auction: bool,
 			#[pallet::compact] min_raise: PerU16,
 			#[pallet::compact] commission_rate: PerU16,
+			curve: DutchAuctionCurve<BlockNumberOf<T>>,
 		) -> DispatchResultWithPostInfo {
 			let who: T::AccountId = ensure_signed(origin)?;
 			ensure!(!items.is_empty(), Error::<T>::EmptyTokenList);
@@ -251,6 +280,10 @@ pub mod module {
 			// check min price and max price
 			ensure!(0 < min_price, Error::<T>::InvalidDutchMinPrice);
 			ensure!(min_price < max_price, Error::<T>::MaxPriceShouldBeGreaterThanMinPrice);
+			ensure!(
+				is_valid_curve::<T>(&curve, min_price, max_price),
+				Error::<T>::InvalidPriceCurve
+			);
 
 			let mut auction: DutchAuctionOf<T> = DutchAuction {
 				currency_id,
@@ -263,6 +296,7 @@ pub mod module {
 				allow_british_auction,
 				min_raise,
 				commission_rate,
+				curve,
 			};
 
 			ensure_one_royalty!(items);
@@ -319,13 +353,8 @@ pub mod module {
 					let current_block: BlockNumberOf<T> = frame_system::Pallet::<T>::block_number();
 					ensure!(auction.deadline >= current_block, Error::<T>::DutchAuctionClosed);
 					// get price
-					let current_price: Balance = calc_current_price::<T>(
-						auction.max_price,
-						auction.min_price,
-						auction.created_block,
-						auction.deadline,
-						current_block,
-					);
+					let current_price: Balance = Self::dutch_auction_price(&auction, current_block);
+					let currency_id = auction.currency_id;
 					Self::save_dutch_bid(
 						auction_bid,
 						auction,
@@ -336,6 +365,14 @@ pub mod module {
 						commission_data,
 					)?;
 
+					Self::notify(Notification::Bid {
+						auction_id,
+						owner: auction_owner,
+						bidder: purchaser.clone(),
+						outbid: None,
+						currency_id,
+						price: current_price,
+					});
 					Self::deposit_event(Event::BidDutchAuction(purchaser, auction_id));
 				},
 				(None, false) => {
@@ -343,30 +380,40 @@ pub mod module {
 					let current_block: BlockNumberOf<T> = frame_system::Pallet::<T>::block_number();
 					ensure!(auction.deadline >= current_block, Error::<T>::DutchAuctionClosed);
 					// get price
-					let current_price: Balance = calc_current_price::<T>(
-						auction.max_price,
-						auction.min_price,
-						auction.created_block,
-						auction.deadline,
-						current_block,
-					);
+					let current_price: Balance = Self::dutch_auction_price(&auction, current_block);
 					// delete auction
 					Self::delete_dutch_auction(&auction_owner, auction_id)?;
 					// swap
 					let (items, commission_agent) = to_item_vec!(auction, commission_agent);
 					let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
-					swap_assets::<T::MultiCurrency, T::NFT, _, _, _, _>(
+					let royalty = swap_assets::<
+						T::MultiCurrency,
+						T::NFT,
+						T::CommissionAgent,
+						T::FeeRouter,
+						_,
+						_,
+						_,
+						_,
+					>(
 						&purchaser,
 						&auction_owner,
 						auction.currency_id,
 						current_price,
 						&items,
-						&Self::treasury_account_id(),
 						T::ExtraConfig::get_platform_fee_rate(),
 						&beneficiary,
 						royalty_rate,
 						&commission_agent,
 					)?;
+					Self::note_royalty(&beneficiary, auction.currency_id, royalty);
+					Self::notify(Notification::AuctionWon {
+						auction_id,
+						owner: auction_owner,
+						winner: purchaser.clone(),
+						currency_id: auction.currency_id,
+						price: current_price,
+					});
 					Self::deposit_event(Event::RedeemedDutchAuction(
 						purchaser,
 						auction_id,
@@ -377,10 +424,12 @@ pub mod module {
 				(Some(_), true) => {
 					// check deadline
 					ensure!(
-						get_deadline::<T>(true, Zero::zero(), auction_bid.last_bid_block) >=
+						get_deadline::<T>(true, Zero::zero(), auction_bid.last_bid_block, &None) >=
 							frame_system::Pallet::<T>::block_number(),
 						Error::<T>::DutchAuctionClosed,
 					);
+					let outbid = auction_bid.last_bid_account.clone();
+					let currency_id = auction.currency_id;
 					Self::save_dutch_bid(
 						auction_bid,
 						auction,
@@ -391,6 +440,14 @@ pub mod module {
 						commission_data,
 					)?;
 
+					Self::notify(Notification::Bid {
+						auction_id,
+						owner: auction_owner,
+						bidder: purchaser.clone(),
+						outbid,
+						currency_id,
+						price,
+					});
 					Self::deposit_event(Event::BidDutchAuction(purchaser, auction_id));
 				},
 				_ => return Err(Error::<T>::DutchAuctionClosed.into()),
@@ -410,7 +467,7 @@ pub mod module {
 			let auction_owner = T::Lookup::lookup(auction_owner)?;
 			let (auction, auction_bid) = Self::delete_dutch_auction(&auction_owner, auction_id)?;
 			ensure!(
-				get_deadline::<T>(true, Zero::zero(), auction_bid.last_bid_block) <
+				get_deadline::<T>(true, Zero::zero(), auction_bid.last_bid_block, &None) <
 					frame_system::Pallet::<T>::block_number(),
 				Error::<T>::CannotRedeemAuctionUntilDeadline
 			);
@@ -420,19 +477,35 @@ pub mod module {
 			let commission_agent = auction_bid.commission_agent.clone();
 			let (items, commission_agent) = to_item_vec!(auction, commission_agent);
 			let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
-			swap_assets::<T::MultiCurrency, T::NFT, _, _, _, _>(
+			let royalty = swap_assets::<
+				T::MultiCurrency,
+				T::NFT,
+				T::CommissionAgent,
+				T::FeeRouter,
+				_,
+				_,
+				_,
+				_,
+			>(
 				&purchaser,
 				&auction_owner,
 				auction.currency_id,
 				auction_bid.last_bid_price,
 				&items,
-				&Self::treasury_account_id(),
 				T::ExtraConfig::get_platform_fee_rate(),
 				&beneficiary,
 				royalty_rate,
 				&commission_agent,
 			)?;
+			Self::note_royalty(&beneficiary, auction.currency_id, royalty);
 
+			Self::notify(Notification::AuctionWon {
+				auction_id,
+				owner: auction_owner,
+				winner: purchaser.clone(),
+				currency_id: auction.currency_id,
+				price: auction_bid.last_bid_price,
+			});
 			Self::deposit_event(Event::RedeemedDutchAuction(
 				purchaser,
 				auction_id,
@@ -480,10 +553,12 @@ pub mod module {
 		/// - `currency_id`: Currency Id
 		/// - `hammer_price`: If somebody offer this price, the auction will be finished. Set to zero to disable.
 		/// - `min_raise`: The next price of bid should be larger than old_price * ( 1 + min_raise )
+		/// - `min_increment`: The next price of bid should be at least old_price + min_increment
 		/// - `deposit`: A higher deposit will be good for the display of the auction in the market.
 		/// - `init_price`: The initial price for the auction to kick off.
 		/// - `deadline`: A block number which represents the end of the auction activity.
 		/// - `allow_delay`: If ture, in some cases the deadline will be extended.
+		/// - `soft_close`: Anti-sniping policy, `None` to extend the deadline by `AuctionCloseDelay`.
 		/// - `items`: Nft list.
 		#[pallet::weight(100_000)]
 		#[transactional]
@@ -493,10 +568,12 @@ pub mod module {
 			#[pallet::compact] currency_id: CurrencyIdOf<T>,
 			#[pallet::compact] hammer_price: Balance,
 			#[pallet::compact] min_raise: PerU16,
+			#[pallet::compact] min_increment: Balance,
 			#[pallet::compact] deposit: Balance,
 			#[pallet::compact] init_price: Balance,
 			#[pallet::compact] deadline: BlockNumberOf<T>,
 			allow_delay: bool,
+			soft_close: Option<SoftClose<BlockNumberOf<T>>>,
 			items: Vec<(ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>)>,
 			#[pallet::compact] commission_rate: PerU16,
 		) -> DispatchResultWithPostInfo {
@@ -524,14 +601,24 @@ pub mod module {
 				ensure!(hammer_price > init_price, Error::<T>::InvalidHammerPrice);
 			}
 
+			// check anti-sniping policy
+			if let Some(soft_close) = &soft_close {
+				ensure!(
+					allow_delay && is_valid_soft_close(soft_close),
+					Error::<T>::InvalidSoftClose
+				);
+			}
+
 			let mut auction: BritishAuctionOf<T> = BritishAuction {
 				currency_id,
 				hammer_price,
 				min_raise,
+				min_increment,
 				deposit,
 				init_price,
 				deadline,
 				allow_delay,
+				soft_close,
 				items: Vec::with_capacity(items.len()),
 				commission_rate,
 			};
@@ -588,7 +675,8 @@ pub mod module {
 				get_deadline::<T>(
 					auction.allow_delay,
 					auction.deadline,
-					auction_bid.last_bid_block
+					auction_bid.last_bid_block,
+					&auction.soft_close,
 				) >= frame_system::Pallet::<T>::block_number(),
 				Error::<T>::BritishAuctionClosed,
 			);
@@ -600,19 +688,35 @@ pub mod module {
 
 				let (items, commission_agent) = to_item_vec!(auction, commission_agent);
 				let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
-				swap_assets::<T::MultiCurrency, T::NFT, _, _, _, _>(
+				let royalty = swap_assets::<
+					T::MultiCurrency,
+					T::NFT,
+					T::CommissionAgent,
+					T::FeeRouter,
+					_,
+					_,
+					_,
+					_,
+				>(
 					&purchaser,
 					&auction_owner,
 					auction.currency_id,
 					auction.hammer_price,
 					&items,
-					&Self::treasury_account_id(),
 					T::ExtraConfig::get_platform_fee_rate(),
 					&beneficiary,
 					royalty_rate,
 					&commission_agent,
 				)?;
+				Self::note_royalty(&beneficiary, auction.currency_id, royalty);
 
+				Self::notify(Notification::AuctionWon {
+					auction_id,
+					owner: auction_owner,
+					winner: purchaser.clone(),
+					currency_id: auction.currency_id,
+					price: auction.hammer_price,
+				});
 				Self::deposit_event(Event::HammerBritishAuction(
 					purchaser,
 					auction_id,
@@ -623,8 +727,15 @@ pub mod module {
 			} else {
 				if auction_bid.last_bid_account.is_none() {
 					ensure!(price >= auction.init_price, Error::<T>::PriceTooLow);
+				} else {
+					ensure!(
+						price >= auction_bid.last_bid_price.saturating_add(auction.min_increment),
+						Error::<T>::PriceTooLow
+					);
 				}
 
+				let outbid = auction_bid.last_bid_account.clone();
+				let currency_id = auction.currency_id;
 				Self::save_british_bid(
 					auction_bid,
 					auction,
@@ -635,6 +746,14 @@ pub mod module {
 					commission_data,
 				)?;
 
+				Self::notify(Notification::Bid {
+					auction_id,
+					owner: auction_owner,
+					bidder: purchaser.clone(),
+					outbid,
+					currency_id,
+					price,
+				});
 				Self::deposit_event(Event::BidBritishAuction(purchaser, auction_id));
 				Ok(().into())
 			}
@@ -655,7 +774,8 @@ pub mod module {
 				get_deadline::<T>(
 					auction.allow_delay,
 					auction.deadline,
-					auction_bid.last_bid_block
+					auction_bid.last_bid_block,
+					&auction.soft_close,
 				) < frame_system::Pallet::<T>::block_number(),
 				Error::<T>::CannotRedeemAuctionUntilDeadline
 			);
@@ -665,19 +785,35 @@ pub mod module {
 			let commission_agent = auction_bid.commission_agent.clone();
 			let (items, commission_agent) = to_item_vec!(auction, commission_agent);
 			let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
-			swap_assets::<T::MultiCurrency, T::NFT, _, _, _, _>(
+			let royalty = swap_assets::<
+				T::MultiCurrency,
+				T::NFT,
+				T::CommissionAgent,
+				T::FeeRouter,
+				_,
+				_,
+				_,
+				_,
+			>(
 				&purchaser,
 				&auction_owner,
 				auction.currency_id,
 				auction_bid.last_bid_price,
 				&items,
-				&Self::treasury_account_id(),
 				T::ExtraConfig::get_platform_fee_rate(),
 				&beneficiary,
 				royalty_rate,
 				&commission_agent,
 			)?;
+			Self::note_royalty(&beneficiary, auction.currency_id, royalty);
 
+			Self::notify(Notification::AuctionWon {
+				auction_id,
+				owner: auction_owner,
+				winner: purchaser.clone(),
+				currency_id: auction.currency_id,
+				price: auction_bid.last_bid_price,
+			});
 			Self::deposit_event(Event::RedeemedBritishAuction(
 				purchaser,
 				auction_id,
@@ -723,6 +859,84 @@ pub mod module {
 }
 
 impl<T: Config> Pallet<T> {
+	/// Record a notification going with the next event, see [`nftmart_traits::notification`].
+	fn notify(notification: Notification<T::AccountId, CurrencyIdOf<T>>) {
+		notification::note_notification(
+			frame_system::Pallet::<T>::block_number().saturated_into(),
+			frame_system::Pallet::<T>::parent_hash().as_ref(),
+			frame_system::Pallet::<T>::event_count(),
+			&notification,
+		);
+	}
+
+	fn note_royalty(beneficiary: &T::AccountId, currency_id: CurrencyIdOf<T>, royalty: Balance) {
+		if !royalty.is_zero() {
+			Self::deposit_event(Event::RoyaltyPaid(beneficiary.clone(), currency_id, royalty));
+		}
+	}
+
+	/// The price of the dutch auction `auction` at block `now` on its curve, paid by the first
+	/// bid.
+	pub fn dutch_auction_price(auction: &DutchAuctionOf<T>, now: BlockNumberOf<T>) -> Balance {
+		calc_current_price::<T>(
+			auction.max_price,
+			auction.min_price,
+			auction.created_block,
+			auction.deadline,
+			now,
+			&auction.curve,
+		)
+	}
+
+	/// Quote bidding `price` on the auction `auction_id` of `auction_owner` through
+	/// `commission_agent`.
+	///
+	/// A bid reaching the hammer price or redeeming a dutch auction is quoted as an immediate
+	/// sale, otherwise the bid price is reserved as the deposit and the fees are those charged if
+	/// the bid wins.
+	pub fn quote_bid(
+		auction_owner: &T::AccountId,
+		auction_id: GlobalId,
+		price: Balance,
+		commission_agent: Option<T::AccountId>,
+	) -> Result<QuoteOf<T>, DispatchError> {
+		let current_block: BlockNumberOf<T> = frame_system::Pallet::<T>::block_number();
+		let (price, reserved, items, commission_agent, currency_id) =
+			if let Some(auction) = Self::british_auctions(auction_owner, auction_id) {
+				let reached = !auction.hammer_price.is_zero() && price >= auction.hammer_price;
+				let price = if reached { auction.hammer_price } else { price };
+				let (items, commission_agent) = to_item_vec!(auction, commission_agent);
+				(price, !reached, items, commission_agent, auction.currency_id)
+			} else {
+				let auction: DutchAuctionOf<T> = Self::dutch_auctions(auction_owner, auction_id)
+					.ok_or(Error::<T>::DutchAuctionNotFound)?;
+				let auction_bid: DutchAuctionBidOf<T> = Self::dutch_auction_bids(auction_id)
+					.ok_or(Error::<T>::DutchAuctionBidNotFound)?;
+				let (price, reserved) = match auction_bid.last_bid_account {
+					Some(_) => (price, true),
+					None => (
+						Self::dutch_auction_price(&auction, current_block),
+						auction.allow_british_auction,
+					),
+				};
+				let (items, commission_agent) = to_item_vec!(auction, commission_agent);
+				(price, reserved, items, commission_agent, auction.currency_id)
+			};
+		let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
+		let mut quote = quote_sale(
+			currency_id,
+			price,
+			T::ExtraConfig::get_platform_fee_rate(),
+			beneficiary,
+			royalty_rate,
+			commission_agent,
+		);
+		if reserved {
+			quote.deposit = price;
+		}
+		Ok(quote)
+	}
+
 	pub fn treasury_account_id() -> T::AccountId {
 		sp_runtime::traits::AccountIdConversion::<T::AccountId>::into_account(
 			&T::TreasuryPalletId::get(),
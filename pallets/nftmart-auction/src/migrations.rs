@@ -0,0 +1,93 @@
+use crate::*;
+
+pub type OldDutchAuctionOf<T> =
+	OldDutchAuction<CurrencyIdOf<T>, BlockNumberFor<T>, ClassIdOf<T>, TokenIdOf<T>>;
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, scale_info::TypeInfo)]
+pub struct OldDutchAuction<CurrencyId, BlockNumber, ClassId, TokenId> {
+	#[codec(compact)]
+	pub currency_id: CurrencyId,
+	#[codec(compact)]
+	pub deposit: Balance,
+	#[codec(compact)]
+	pub min_price: Balance,
+	#[codec(compact)]
+	pub max_price: Balance,
+	#[codec(compact)]
+	pub deadline: BlockNumber,
+	#[codec(compact)]
+	pub created_block: BlockNumber,
+	pub items: Vec<OrderItem<ClassId, TokenId>>,
+	pub allow_british_auction: bool,
+	#[codec(compact)]
+	pub min_raise: PerU16,
+	#[codec(compact)]
+	pub commission_rate: PerU16,
+}
+
+pub type OldBritishAuctionOf<T> =
+	OldBritishAuction<CurrencyIdOf<T>, BlockNumberFor<T>, ClassIdOf<T>, TokenIdOf<T>>;
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, scale_info::TypeInfo)]
+pub struct OldBritishAuction<CurrencyId, BlockNumber, ClassId, TokenId> {
+	#[codec(compact)]
+	pub currency_id: CurrencyId,
+	#[codec(compact)]
+	pub hammer_price: Balance,
+	#[codec(compact)]
+	pub min_raise: PerU16,
+	#[codec(compact)]
+	pub deposit: Balance,
+	#[codec(compact)]
+	pub init_price: Balance,
+	#[codec(compact)]
+	pub deadline: BlockNumber,
+	pub allow_delay: bool,
+	pub items: Vec<OrderItem<ClassId, TokenId>>,
+	#[codec(compact)]
+	pub commission_rate: PerU16,
+}
+
+pub fn do_migrate<T: Config>() -> Weight {
+	// Dutch auctions created before price curves decline linearly every 30 minutes.
+	let mut count: Weight = 0;
+	DutchAuctions::<T>::translate::<OldDutchAuctionOf<T>, _>(|_, _, p| {
+		count = count.saturating_add(1);
+		Some(DutchAuction {
+			currency_id: p.currency_id,
+			deposit: p.deposit,
+			min_price: p.min_price,
+			max_price: p.max_price,
+			deadline: p.deadline,
+			created_block: p.created_block,
+			items: p.items,
+			allow_british_auction: p.allow_british_auction,
+			min_raise: p.min_raise,
+			commission_rate: p.commission_rate,
+			curve: Default::default(),
+		})
+	});
+	T::DbWeight::get().reads_writes(count, count)
+}
+
+pub fn migrate_british_auctions<T: Config>() -> Weight {
+	// British auctions created before anti-sniping policies keep the default delay rule.
+	let mut count: Weight = 0;
+	BritishAuctions::<T>::translate::<OldBritishAuctionOf<T>, _>(|_, _, p| {
+		count = count.saturating_add(1);
+		Some(BritishAuction {
+			currency_id: p.currency_id,
+			hammer_price: p.hammer_price,
+			min_raise: p.min_raise,
+			min_increment: Zero::zero(),
+			deposit: p.deposit,
+			init_price: p.init_price,
+			deadline: p.deadline,
+			allow_delay: p.allow_delay,
+			soft_close: None,
+			items: p.items,
+			commission_rate: p.commission_rate,
+		})
+	});
+	T::DbWeight::get().reads_writes(count, count)
+}
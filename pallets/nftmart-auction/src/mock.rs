@@ -210,7 +210,11 @@ impl nftmart_order::Config for Runtime {
 	type TokenId = nftmart_traits::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = ();
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
+	type OffchainSignature = sp_runtime::MultiSignature;
+	type OffchainPublic = sp_runtime::MultiSigner;
 }
 
 impl nftmart_config::Config for Runtime {
@@ -221,6 +225,9 @@ parameter_types! {
 	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
 }
 
+pub type FeeRouter =
+	nftmart_config::FeeRouter<Runtime, Currencies, TreasuryPalletId, GetNativeCurrencyId>;
+
 impl nftmart_auction::Config for Runtime {
 	type Event = Event;
 	type MultiCurrency = Currencies;
@@ -229,6 +236,8 @@ impl nftmart_auction::Config for Runtime {
 	type TokenId = nftmart_traits::constants_types::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = ();
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
 	type WeightInfo = ();
 }
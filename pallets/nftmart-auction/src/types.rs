@@ -13,6 +13,9 @@ pub struct BritishAuction<CurrencyId, BlockNumber, ClassId, TokenId> {
 	/// if Some(min_raise), min_raise > 0.
 	#[codec(compact)]
 	pub min_raise: PerU16,
+	/// The new price offered should also meet `new_price>=old_price+min_increment`.
+	#[codec(compact)]
+	pub min_increment: Balance,
 	/// The auction owner/creator should deposit some balances to create an auction.
 	/// After this auction finishing or deleting, this balances
 	/// will be returned to the auction owner.
@@ -26,6 +29,8 @@ pub struct BritishAuction<CurrencyId, BlockNumber, ClassId, TokenId> {
 	pub deadline: BlockNumber,
 	/// If true, the real deadline will be max(deadline, last_bid_block + delay).
 	pub allow_delay: bool,
+	/// If Some, it replaces the default delay rule of `allow_delay`.
+	pub soft_close: Option<SoftClose<BlockNumber>>,
 	/// nft list
 	pub items: Vec<OrderItem<ClassId, TokenId>>,
 	/// commission rate
@@ -70,6 +75,8 @@ pub struct DutchAuction<CurrencyId, BlockNumber, ClassId, TokenId> {
 	/// commission rate
 	#[codec(compact)]
 	pub commission_rate: PerU16,
+	/// How the price declines from `max_price` to `min_price`.
+	pub curve: DutchAuctionCurve<BlockNumber>,
 }
 
 pub type DutchAuctionBid<AccountId, BlockNumber> = BritishAuctionBid<AccountId, BlockNumber>;
@@ -77,6 +84,8 @@ pub type DutchAuctionBid<AccountId, BlockNumber> = BritishAuctionBid<AccountId,
 #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo)]
 pub enum Releases {
 	V1_0_0,
+	V2_0_0,
+	V3_0_0,
 }
 
 impl Default for Releases {
@@ -100,4 +109,4 @@ pub type BritishAuctionBidOf<T> = BritishAuctionBid<AccountIdOf<T>, BlockNumberF
 pub type DutchAuctionOf<T> =
 	DutchAuction<CurrencyIdOf<T>, BlockNumberFor<T>, ClassIdOf<T>, TokenIdOf<T>>;
 pub type DutchAuctionBidOf<T> = DutchAuctionBid<AccountIdOf<T>, BlockNumberFor<T>>;
-pub const DESC_INTERVAL: BlockNumber = time::MINUTES * 30;
+pub type QuoteOf<T> = Quote<AccountIdOf<T>, CurrencyIdOf<T>>;
@@ -1,4 +1,5 @@
 use crate::*;
+use sp_runtime::PerThing;
 
 #[macro_export]
 macro_rules! save_bid {
@@ -84,6 +85,7 @@ pub fn calc_current_price<T: Config>(
 	created_block: BlockNumberOf<T>,
 	deadline: BlockNumberOf<T>,
 	current_block: BlockNumberOf<T>,
+	curve: &DutchAuctionCurve<BlockNumberOf<T>>,
 ) -> Balance {
 	if current_block <= created_block {
 		return max_price
@@ -91,16 +93,58 @@ pub fn calc_current_price<T: Config>(
 		return min_price
 	}
 
-	let created_block: BlockNumber = created_block.saturated_into();
+	match curve {
+		DutchAuctionCurve::Linear { interval } => calc_linear_price(
+			max_price,
+			min_price,
+			created_block.saturated_into(),
+			deadline.saturated_into(),
+			current_block.saturated_into(),
+			(*interval).saturated_into(),
+		),
+		DutchAuctionCurve::Exponential { interval, decay_rate } => {
+			let steps: BlockNumber = current_block
+				.saturating_sub(created_block)
+				.checked_div(interval)
+				.unwrap_or_else(Zero::zero)
+				.saturated_into();
+			let remaining = FixedU128::saturating_from_rational(
+				decay_rate.left_from_one().deconstruct(),
+				PerU16::ACCURACY,
+			)
+			.saturating_pow(steps as usize);
+			remaining
+				.saturating_mul_int(max_price.saturating_sub(min_price))
+				.saturating_add(min_price)
+		},
+		DutchAuctionCurve::Breakpoints(breakpoints) => {
+			let elapsed = current_block.saturating_sub(created_block);
+			let price = breakpoints
+				.iter()
+				.take_while(|(block, _)| *block <= elapsed)
+				.last()
+				.map_or(max_price, |(_, price)| *price);
+			price.max(min_price).min(max_price)
+		},
+	}
+}
+
+fn calc_linear_price(
+	max_price: Balance,
+	min_price: Balance,
+	created_block: BlockNumber,
+	deadline: BlockNumber,
+	current_block: BlockNumber,
+	interval: BlockNumber,
+) -> Balance {
 	let aligned_block: BlockNumber = current_block
-		.saturated_into::<BlockNumber>()
 		.saturating_sub(created_block) // >= 0
-		.checked_div(DESC_INTERVAL) // >= 0
-		.map(|x| x.saturating_mul(DESC_INTERVAL)) // >= 0
+		.checked_div(interval) // >= 0
+		.map(|x| x.saturating_mul(interval)) // >= 0
 		.map(|x| x.saturating_add(created_block)) // >= created_block
 		.unwrap_or(created_block); // >= created_block
 
-	let deadline: FixedU128 = (deadline.saturated_into::<BlockNumber>(), 1).into();
+	let deadline: FixedU128 = (deadline, 1).into();
 	let created_block: FixedU128 = (created_block, 1).into();
 	let current_block: FixedU128 = (aligned_block, 1).into();
 	let max_price: FixedU128 = (max_price, ACCURACY).into();
@@ -118,17 +162,55 @@ pub fn calc_current_price<T: Config>(
 	current_price
 }
 
+pub fn is_valid_curve<T: Config>(
+	curve: &DutchAuctionCurve<BlockNumberOf<T>>,
+	min_price: Balance,
+	max_price: Balance,
+) -> bool {
+	match curve {
+		DutchAuctionCurve::Linear { interval } => !interval.is_zero(),
+		DutchAuctionCurve::Exponential { interval, decay_rate } =>
+			!interval.is_zero() && !decay_rate.is_zero(),
+		DutchAuctionCurve::Breakpoints(breakpoints) =>
+			!breakpoints.is_empty() &&
+				breakpoints.len() as u32 <= MAX_CURVE_BREAKPOINTS &&
+				breakpoints.iter().all(|(_, price)| min_price <= *price && *price <= max_price) &&
+				breakpoints.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 >= w[1].1),
+	}
+}
+
 pub fn get_deadline<T: Config>(
 	allow_delay: bool,
 	deadline: BlockNumberOf<T>,
 	last_bid_block: BlockNumberOf<T>,
+	soft_close: &Option<SoftClose<BlockNumberOf<T>>>,
 ) -> BlockNumberFor<T> {
-	if allow_delay {
-		let delay = last_bid_block.saturating_add(T::ExtraConfig::auction_close_delay());
-		core::cmp::max(deadline, delay)
-	} else {
-		deadline
+	if !allow_delay {
+		return deadline
 	}
+	match soft_close {
+		None => {
+			let delay = last_bid_block.saturating_add(T::ExtraConfig::auction_close_delay());
+			core::cmp::max(deadline, delay)
+		},
+		Some(SoftClose { window, extension, max_extension }) => {
+			// Bids are placed in order, so the auction is in soft-close
+			// if and only if the last bid is placed within the window.
+			if last_bid_block.is_zero() || last_bid_block.saturating_add(*window) < deadline {
+				return deadline
+			}
+			let delay = core::cmp::max(deadline, last_bid_block.saturating_add(*extension));
+			match max_extension {
+				Some(max_extension) =>
+					core::cmp::min(delay, deadline.saturating_add(*max_extension)),
+				None => delay,
+			}
+		},
+	}
+}
+
+pub fn is_valid_soft_close<BlockNumber: Zero>(soft_close: &SoftClose<BlockNumber>) -> bool {
+	!soft_close.window.is_zero() && !soft_close.extension.is_zero()
 }
 
 #[cfg(any(test, feature = "runtime-benchmarks"))]
@@ -1,7 +1,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{pallet_prelude::*, transactional};
+use frame_support::{pallet_prelude::*, transactional, PalletId};
 use frame_system::pallet_prelude::*;
+use orml_traits::MultiCurrency;
 use sp_std::vec::Vec;
 
 mod mock;
@@ -9,12 +10,12 @@ mod tests;
 
 pub use module::*;
 use nftmart_traits::{
-	constants_types::{Balance, GlobalId, ACCURACY},
-	time, CategoryData, NFTMetadata, NftmartConfig,
+	constants_types::{Balance, CurrencyId, GlobalId, ACCURACY},
+	time, CategoryData, FeeDistribution, NFTMetadata, NftmartConfig, NftmartFeeRouter,
 };
 use sp_runtime::{
-	traits::{One, Zero},
-	PerU16,
+	traits::{AccountIdConversion, One, Zero},
+	PerU16, SaturatedConversion,
 };
 
 #[frame_support::pallet]
@@ -32,6 +33,8 @@ pub mod module {
 		NoAvailableId,
 		/// category not found
 		CategoryNotFound,
+		/// the parts of fee distribution exceed 100%
+		InvalidFeeDistribution,
 	}
 
 	#[pallet::event]
@@ -45,6 +48,8 @@ pub mod module {
 		CreatedCategory(GlobalId),
 		/// Updated NFT common category. \[category_id\]
 		UpdatedCategory(GlobalId),
+		/// Routed a platform fee paid by `payer`. \[payer, currency_id, fee\]
+		RoutedFee(T::AccountId, CurrencyId, Balance),
 	}
 
 	#[pallet::pallet]
@@ -58,6 +63,8 @@ pub mod module {
 		pub min_commission_agent_deposit: Balance,
 		pub min_order_deposit: Balance,
 		pub auction_close_delay: BlockNumberFor<T>,
+		pub reaper_bounty_rate: PerU16,
+		pub fee_distribution: FeeDistribution,
 		pub white_list: Vec<T::AccountId>,
 		pub category_list: Vec<NFTMetadata>,
 		pub _phantom: PhantomData<T>,
@@ -73,6 +80,8 @@ pub mod module {
 				min_commission_agent_deposit: ACCURACY,
 				min_order_deposit: ACCURACY,
 				auction_close_delay: time::MINUTES.into(),
+				reaper_bounty_rate: PerU16::from_percent(10),
+				fee_distribution: Default::default(),
 				white_list: vec![],
 				category_list: vec![b"saaaa1".to_vec(), b"saaaa2".to_vec(), b"saaaa3".to_vec()],
 				_phantom: Default::default(),
@@ -85,6 +94,18 @@ pub mod module {
 		fn on_runtime_upgrade() -> Weight {
 			0
 		}
+
+		/// Post the marketplace notifications of the block to the webhooks, for all the pallets
+		/// recording them, see [`nftmart_traits::notification`].
+		fn offchain_worker(n: T::BlockNumber) {
+			// The parent hash is the one of the header of block `n` in the offchain worker.
+			nftmart_traits::notification::post_notifications(
+				n.saturated_into(),
+				frame_system::Pallet::<T>::parent_hash().as_ref(),
+				frame_system::Pallet::<T>::event_count(),
+			);
+		}
+
 		fn integrity_test() {}
 	}
 
@@ -97,6 +118,9 @@ pub mod module {
 			MinCommissionAgentDeposit::<T>::put(self.min_commission_agent_deposit);
 			MinOrderDeposit::<T>::put(self.min_order_deposit);
 			AuctionCloseDelay::<T>::put(self.auction_close_delay);
+			ReaperBountyRate::<T>::put(self.reaper_bounty_rate);
+			assert!(self.fee_distribution.is_valid(), "invalid fee distribution");
+			FeeDistributionRates::<T>::put(self.fee_distribution);
 			for a in &self.white_list {
 				<Pallet<T> as NftmartConfig<T::AccountId, T::BlockNumber>>::do_add_whitelist(a);
 			}
@@ -148,7 +172,7 @@ pub mod module {
 	/// min reference deposit
 	///
 	/// The secondary retailer who will get reward from helping selling
-	/// should keep at least `MinReferenceDeposit` balances.
+	/// should bond at least `MinReferenceDeposit` balances as a registered agent.
 	#[pallet::storage]
 	#[pallet::getter(fn min_commission_agent_deposit)]
 	pub type MinCommissionAgentDeposit<T: Config> = StorageValue<_, Balance, ValueQuery>;
@@ -158,6 +182,26 @@ pub mod module {
 	#[pallet::getter(fn min_order_deposit)]
 	pub type MinOrderDeposit<T: Config> = StorageValue<_, Balance, ValueQuery>;
 
+	/// Bounty rate for removing expired orders and offers.
+	///
+	/// Bounty = Deposit * `ReaperBountyRate` for an order, in the native currency.
+	/// Bounty = Price * `ReaperBountyRate` for an offer, in the currency of the offer.
+	/// It will be paid to the account who removes an expired order or offer.
+	#[pallet::storage]
+	#[pallet::getter(fn reaper_bounty_rate)]
+	pub type ReaperBountyRate<T: Config> = StorageValue<_, PerU16, ValueQuery>;
+
+	/// How the native currency trading fees are split between burning, stakers and the treasury.
+	///
+	/// Burn = PlatformFee * `burn`
+	/// Staking = PlatformFee * `staking`
+	/// Treasury = PlatformFee - Burn - Staking
+	///
+	/// Trading fees in other currencies are accumulated in a separate account.
+	#[pallet::storage]
+	#[pallet::getter(fn fee_distribution)]
+	pub type FeeDistributionRates<T: Config> = StorageValue<_, FeeDistribution, ValueQuery>;
+
 	/// Next available global ID.
 	#[pallet::storage]
 	#[pallet::getter(fn next_id)]
@@ -240,6 +284,32 @@ pub mod module {
 			AuctionCloseDelay::<T>::set(delay);
 			Ok((None, Pays::No).into())
 		}
+
+		#[pallet::weight((100_000, DispatchClass::Operational, Pays::Yes))]
+		#[transactional]
+		pub fn update_reaper_bounty_rate(
+			origin: OriginFor<T>,
+			rate: PerU16,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			ReaperBountyRate::<T>::set(rate);
+			Ok((None, Pays::No).into())
+		}
+
+		/// Update how the native currency trading fees are split.
+		///
+		/// - `distribution`: the parts to be burned and paid to stakers
+		#[pallet::weight((100_000, DispatchClass::Operational, Pays::Yes))]
+		#[transactional]
+		pub fn update_fee_distribution(
+			origin: OriginFor<T>,
+			distribution: FeeDistribution,
+		) -> DispatchResultWithPostInfo {
+			ensure_root(origin)?;
+			ensure!(distribution.is_valid(), Error::<T>::InvalidFeeDistribution);
+			FeeDistributionRates::<T>::set(distribution);
+			Ok((None, Pays::No).into())
+		}
 	}
 }
 
@@ -315,4 +385,96 @@ impl<T: Config> NftmartConfig<T::AccountId, BlockNumberFor<T>> for Pallet<T> {
 	fn get_min_commission_agent_deposit() -> Balance {
 		Self::min_commission_agent_deposit()
 	}
+
+	fn get_reaper_bounty_rate() -> PerU16 {
+		Self::reaper_bounty_rate()
+	}
+
+	fn get_fee_distribution() -> FeeDistribution {
+		Self::fee_distribution()
+	}
+}
+
+/// The stakers take their share of the trading fees, paid out from the staking pot.
+pub struct WithStaking;
+impl Get<bool> for WithStaking {
+	fn get() -> bool {
+		true
+	}
+}
+
+/// There are no stakers, their share of the trading fees goes to the treasury.
+pub struct WithoutStaking;
+impl Get<bool> for WithoutStaking {
+	fn get() -> bool {
+		false
+	}
+}
+
+/// Routes trading fees according to `FeeDistributionRates`.
+///
+/// Native currency fees are burned, paid into the staking pot or the treasury. The staking share
+/// goes to the treasury unless `Staking` is [`WithStaking`].
+/// Fees in other currencies are accumulated in the foreign fee account.
+pub struct FeeRouter<T, Currency, TreasuryPalletId, NativeCurrencyId, Staking = WithStaking>(
+	PhantomData<(T, Currency, TreasuryPalletId, NativeCurrencyId, Staking)>,
+);
+
+impl<T, Currency, TreasuryPalletId, NativeCurrencyId, Staking>
+	FeeRouter<T, Currency, TreasuryPalletId, NativeCurrencyId, Staking>
+where
+	T: Config,
+	TreasuryPalletId: Get<PalletId>,
+{
+	pub fn treasury_account_id() -> T::AccountId {
+		TreasuryPalletId::get().into_account()
+	}
+
+	/// The account holding the fees to be paid to stakers in the next era payout.
+	pub fn staking_pot_account_id() -> T::AccountId {
+		TreasuryPalletId::get().into_sub_account(b"staking")
+	}
+
+	/// The account accumulating the fees paid in non-native currencies.
+	pub fn foreign_fee_account_id() -> T::AccountId {
+		TreasuryPalletId::get().into_sub_account(b"foreign")
+	}
+}
+
+impl<T, Currency, TreasuryPalletId, NativeCurrencyId, Staking>
+	NftmartFeeRouter<T::AccountId, CurrencyId>
+	for FeeRouter<T, Currency, TreasuryPalletId, NativeCurrencyId, Staking>
+where
+	T: Config,
+	Currency: MultiCurrency<T::AccountId, CurrencyId = CurrencyId, Balance = Balance>,
+	TreasuryPalletId: Get<PalletId>,
+	NativeCurrencyId: Get<CurrencyId>,
+	Staking: Get<bool>,
+{
+	fn route_fee(payer: &T::AccountId, currency_id: CurrencyId, fee: Balance) -> DispatchResult {
+		if !fee.is_zero() {
+			Pallet::<T>::deposit_event(Event::RoutedFee(payer.clone(), currency_id, fee));
+		}
+		if currency_id != NativeCurrencyId::get() {
+			return Currency::transfer(currency_id, payer, &Self::foreign_fee_account_id(), fee)
+		}
+
+		let distribution = Pallet::<T>::fee_distribution();
+		let burn = distribution.burn.mul_floor(fee);
+		let mut staking =
+			if Staking::get() { distribution.staking.mul_floor(fee) } else { Zero::zero() };
+		let staking_pot = Self::staking_pot_account_id();
+		// Leave dust to the treasury rather than failing to create the staking pot.
+		if Currency::free_balance(currency_id, &staking_pot).saturating_add(staking) <
+			Currency::minimum_balance(currency_id)
+		{
+			staking = Zero::zero();
+		}
+		let treasury = fee.saturating_sub(burn).saturating_sub(staking);
+
+		Currency::withdraw(currency_id, payer, burn)?;
+		Currency::transfer(currency_id, payer, &staking_pot, staking)?;
+		Currency::transfer(currency_id, payer, &Self::treasury_account_id(), treasury)?;
+		Ok(())
+	}
 }
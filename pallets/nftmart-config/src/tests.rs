@@ -92,3 +92,25 @@ fn create_category_should_fail() {
 		);
 	});
 }
+
+#[test]
+fn update_fee_distribution_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(NftmartConf::fee_distribution(), FeeDistribution::default());
+		let distribution =
+			FeeDistribution { burn: PerU16::from_percent(60), staking: PerU16::from_percent(40) };
+		assert_noop!(
+			NftmartConf::update_fee_distribution(Origin::signed(ALICE), distribution),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(NftmartConf::update_fee_distribution(Origin::root(), distribution));
+		assert_eq!(NftmartConf::fee_distribution(), distribution);
+
+		let distribution =
+			FeeDistribution { burn: PerU16::from_percent(60), staking: PerU16::from_percent(41) };
+		assert_noop!(
+			NftmartConf::update_fee_distribution(Origin::root(), distribution),
+			Error::<Runtime>::InvalidFeeDistribution
+		);
+	});
+}
@@ -0,0 +1,344 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Currency, ExistenceRequirement},
+	transactional,
+};
+use frame_system::pallet_prelude::*;
+pub use nftmart_traits::*;
+use orml_traits::MultiCurrency;
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, CheckedAdd, Hash, Zero},
+	ArithmeticError, PerU16, RuntimeDebug, SaturatedConversion,
+};
+use sp_std::vec::Vec;
+
+mod mock;
+mod tests;
+
+pub use module::*;
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct DropPhase<CurrencyId, BlockNumber, TokenId, Hash> {
+	/// This phase starts at `start` block number.
+	#[codec(compact)]
+	pub start: BlockNumber,
+	/// This phase ends before `end` block number.
+	#[codec(compact)]
+	pub end: BlockNumber,
+	/// currency ID.
+	#[codec(compact)]
+	pub currency_id: CurrencyId,
+	/// Price of each unit of the token.
+	#[codec(compact)]
+	pub price: Balance,
+	/// Merkle root of the allow-list, `None` for a public phase.
+	///
+	/// Each leaf is `hash_of(&(account, max_quantity))`.
+	pub allow_list: Option<Hash>,
+	/// The maximum quantity each account can claim in a public phase, `None` for no limit.
+	pub max_per_account: Option<TokenId>,
+}
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct DropInfo<AccountId, CurrencyId, BlockNumber, ClassId, TokenId, Hash> {
+	/// The class admin who created this drop and receives the proceeds.
+	pub creator: AccountId,
+	/// class id
+	#[codec(compact)]
+	pub class_id: ClassId,
+	/// external metadata of the tokens to be minted.
+	pub metadata: NFTMetadata,
+	/// Charge royalty, `None` for the class's royalty rate.
+	pub royalty_rate: Option<PerU16>,
+	/// Sale phases, sorted by block number and not overlapping.
+	pub phases: Vec<DropPhase<CurrencyId, BlockNumber, TokenId, Hash>>,
+}
+
+pub type TokenIdOf<T> = <T as module::Config>::TokenId;
+pub type ClassIdOf<T> = <T as module::Config>::ClassId;
+pub type BalanceOf<T> =
+	<<T as module::Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+pub type CurrencyIdOf<T> = <<T as module::Config>::MultiCurrency as MultiCurrency<
+	<T as frame_system::Config>::AccountId,
+>>::CurrencyId;
+pub type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+pub type DropPhaseOf<T> =
+	DropPhase<CurrencyIdOf<T>, BlockNumberOf<T>, TokenIdOf<T>, <T as frame_system::Config>::Hash>;
+pub type DropOf<T> = DropInfo<
+	<T as frame_system::Config>::AccountId,
+	CurrencyIdOf<T>,
+	BlockNumberOf<T>,
+	ClassIdOf<T>,
+	TokenIdOf<T>,
+	<T as frame_system::Config>::Hash,
+>;
+
+/// The leaf of an allow-list, which allows `who` to claim at most `max_quantity` in a phase.
+pub fn allow_list_leaf<T: Config>(who: &T::AccountId, max_quantity: TokenIdOf<T>) -> T::Hash {
+	T::Hashing::hash_of(&(who, max_quantity))
+}
+
+/// Verify a Merkle proof of `leaf` against `root`, each pair of nodes is hashed in sorted order.
+pub fn verify_merkle_proof<T: Config>(root: &T::Hash, leaf: T::Hash, proof: &[T::Hash]) -> bool {
+	let computed = proof.iter().fold(leaf, |node, sibling| {
+		let (a, b) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+		T::Hashing::hash(&[a.as_ref(), b.as_ref()].concat())
+	});
+	&computed == root
+}
+
+#[frame_support::pallet]
+pub mod module {
+	use super::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// MultiCurrency type for trading
+		type MultiCurrency: MultiCurrency<Self::AccountId, Balance = Balance>;
+
+		/// The currency mechanism.
+		type Currency: Currency<Self::AccountId>;
+
+		/// The class ID type
+		type ClassId: Parameter
+			+ Member
+			+ AtLeast32BitUnsigned
+			+ Default
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ codec::FullCodec;
+
+		/// The token ID type
+		type TokenId: Parameter
+			+ Member
+			+ AtLeast32BitUnsigned
+			+ Default
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ codec::FullCodec;
+
+		/// NFTMart nft
+		type NFT: NftmartNft<Self::AccountId, Self::ClassId, Self::TokenId>;
+
+		/// Extra Configurations
+		type ExtraConfig: NftmartConfig<Self::AccountId, BlockNumberFor<Self>>;
+
+		/// Router of trading fees
+		type FeeRouter: NftmartFeeRouter<
+			Self::AccountId,
+			<Self::MultiCurrency as MultiCurrency<Self::AccountId>>::CurrencyId,
+		>;
+
+		/// The maximum number of phases in a drop.
+		#[pallet::constant]
+		type MaxDropPhases: Get<u32>;
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// drop not found
+		DropNotFound,
+		/// phase not found
+		PhaseNotFound,
+		/// only class admins can create drops
+		NoPermission,
+		/// phases are empty, too many, overlapping or have an invalid limit
+		InvalidPhases,
+		/// the phase is not active at the current block
+		PhaseNotActive,
+		/// the account is not in the allow-list of the phase
+		NotInAllowList,
+		/// the claimed quantity exceeds the account's limit of the phase
+		ExceedAccountLimit,
+		/// claim with zero quantity
+		InvalidQuantity,
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(crate) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// CreatedDrop \[creator, drop_id, class_id\]
+		CreatedDrop(T::AccountId, GlobalId, ClassIdOf<T>),
+		/// RemovedDrop \[creator, drop_id\]
+		RemovedDrop(T::AccountId, GlobalId),
+		/// ClaimedDrop \[who, drop_id, phase, class_id, token_id, quantity\]
+		ClaimedDrop(T::AccountId, GlobalId, u32, ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>),
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+	/// Drops by drop id.
+	#[pallet::storage]
+	#[pallet::getter(fn drops)]
+	pub type Drops<T: Config> = StorageMap<_, Twox64Concat, GlobalId, DropOf<T>>;
+
+	/// The quantity claimed by each account in each phase of a drop.
+	#[pallet::storage]
+	#[pallet::getter(fn claimed)]
+	pub type Claimed<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		GlobalId,
+		Blake2_128Concat,
+		(u32, T::AccountId),
+		TokenIdOf<T>,
+		ValueQuery,
+	>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Create a drop, the caller must be an admin of the class.
+		///
+		/// - `class_id`: class id
+		/// - `metadata`: external metadata of the tokens to be minted
+		/// - `royalty_rate`: charge royalty, `None` for the class's royalty rate
+		/// - `phases`: sale phases, sorted by block number and not overlapping
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn create_drop(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			metadata: NFTMetadata,
+			royalty_rate: Option<PerU16>,
+			phases: Vec<DropPhaseOf<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(T::NFT::is_class_admin(&who, class_id), Error::<T>::NoPermission);
+			ensure!(Self::valid_phases(&phases), Error::<T>::InvalidPhases);
+
+			let drop_id = T::ExtraConfig::get_then_inc_id()?;
+			Drops::<T>::insert(
+				drop_id,
+				DropOf::<T> { creator: who.clone(), class_id, metadata, royalty_rate, phases },
+			);
+			Self::deposit_event(Event::CreatedDrop(who, drop_id, class_id));
+			Ok(().into())
+		}
+
+		/// Remove a drop by its creator.
+		///
+		/// - `drop_id`: drop id
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn remove_drop(
+			origin: OriginFor<T>,
+			#[pallet::compact] drop_id: GlobalId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let drop = Self::drops(drop_id).ok_or(Error::<T>::DropNotFound)?;
+			ensure!(drop.creator == who, Error::<T>::NoPermission);
+
+			Drops::<T>::remove(drop_id);
+			let _ = Claimed::<T>::remove_prefix(drop_id, None);
+			Self::deposit_event(Event::RemovedDrop(who, drop_id));
+			Ok(().into())
+		}
+
+		/// Claim tokens from an active phase of a drop.
+		///
+		/// A new token is minted to the caller, who pays the mint deposit and the price.
+		/// The platform fee goes to the treasury, and the rest goes to the creator.
+		///
+		/// - `drop_id`: drop id
+		/// - `phase`: the index of the phase
+		/// - `quantity`: quantity of the token to be minted
+		/// - `allowance`: `(max_quantity, proof)` of the caller in the allow-list, only required
+		///   by allow-list phases
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn claim(
+			origin: OriginFor<T>,
+			#[pallet::compact] drop_id: GlobalId,
+			#[pallet::compact] phase: u32,
+			#[pallet::compact] quantity: TokenIdOf<T>,
+			allowance: Option<(TokenIdOf<T>, Vec<T::Hash>)>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(!quantity.is_zero(), Error::<T>::InvalidQuantity);
+			let drop = Self::drops(drop_id).ok_or(Error::<T>::DropNotFound)?;
+			let drop_phase = drop.phases.get(phase as usize).ok_or(Error::<T>::PhaseNotFound)?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(drop_phase.start <= now && now < drop_phase.end, Error::<T>::PhaseNotActive);
+
+			let limit = match drop_phase.allow_list {
+				Some(ref root) => {
+					let (max_quantity, proof) = allowance.ok_or(Error::<T>::NotInAllowList)?;
+					let leaf = allow_list_leaf::<T>(&who, max_quantity);
+					ensure!(
+						verify_merkle_proof::<T>(root, leaf, &proof),
+						Error::<T>::NotInAllowList
+					);
+					Some(max_quantity)
+				},
+				None => drop_phase.max_per_account,
+			};
+			Claimed::<T>::try_mutate(drop_id, (phase, who.clone()), |claimed| -> DispatchResult {
+				*claimed = claimed
+					.checked_add(&quantity)
+					.filter(|c| limit.map_or(true, |l| c <= &l))
+					.ok_or(Error::<T>::ExceedAccountLimit)?;
+				Ok(())
+			})?;
+
+			// The caller pays the mint deposit, which is refunded to whom burns the token.
+			let deposit = T::NFT::mint_token_deposit(drop.metadata.len().saturated_into());
+			<T as Config>::Currency::transfer(
+				&who,
+				&drop.creator,
+				deposit.saturated_into(),
+				ExistenceRequirement::KeepAlive,
+			)?;
+			let (_, _, class_id, token_id, quantity) = T::NFT::proxy_mint(
+				&drop.creator,
+				&who,
+				drop.class_id,
+				drop.metadata.clone(),
+				quantity,
+				drop.royalty_rate,
+			)?;
+
+			let price = drop_phase
+				.price
+				.checked_mul(quantity.saturated_into())
+				.ok_or(ArithmeticError::Overflow)?;
+			let platform_fee = T::ExtraConfig::get_platform_fee_rate().mul_ceil(price);
+			T::FeeRouter::route_fee(&who, drop_phase.currency_id, platform_fee)?;
+			T::MultiCurrency::transfer(
+				drop_phase.currency_id,
+				&who,
+				&drop.creator,
+				price.saturating_sub(platform_fee),
+			)?;
+
+			Self::deposit_event(Event::ClaimedDrop(
+				who, drop_id, phase, class_id, token_id, quantity,
+			));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	fn valid_phases(phases: &[DropPhaseOf<T>]) -> bool {
+		!phases.is_empty() &&
+			phases.len() <= T::MaxDropPhases::get() as usize &&
+			phases
+				.iter()
+				.all(|p| p.start < p.end && p.max_per_account != Some(Zero::zero())) &&
+			phases.windows(2).all(|w| w[0].end <= w[1].start)
+	}
+}
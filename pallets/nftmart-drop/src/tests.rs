@@ -0,0 +1,174 @@
+#![cfg(test)]
+
+use super::*;
+use crate::mock::{
+	add_class, current_gid, ensure_account, free_balance, last_event, Event, ExtBuilder,
+	NftmartDrop, Origin, Runtime, System, ALICE, BOB, CHARLIE, CLASS_ID0, DAVE, TOKEN_ID0,
+	TOKEN_ID1,
+};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+
+fn hash_pair(a: H256, b: H256) -> H256 {
+	let (a, b) = if a <= b { (a, b) } else { (b, a) };
+	BlakeTwo256::hash(&[a.as_ref(), b.as_ref()].concat())
+}
+
+fn phase(start: u64, end: u64, price: Balance) -> DropPhaseOf<Runtime> {
+	DropPhase {
+		start,
+		end,
+		currency_id: NATIVE_CURRENCY_ID,
+		price,
+		allow_list: None,
+		max_per_account: None,
+	}
+}
+
+#[test]
+fn claim_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+
+		let bob_leaf = allow_list_leaf::<Runtime>(&BOB, 2);
+		let charlie_leaf = allow_list_leaf::<Runtime>(&CHARLIE, 1);
+		let root = hash_pair(bob_leaf, charlie_leaf);
+		let phases = vec![
+			DropPhase { allow_list: Some(root), ..phase(1, 10, 10) },
+			DropPhase { max_per_account: Some(1), ..phase(10, 20, 20) },
+		];
+
+		let drop_id = current_gid();
+		assert_ok!(NftmartDrop::create_drop(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			vec![1],
+			None,
+			phases
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartDrop(crate::Event::CreatedDrop(ALICE, drop_id, CLASS_ID0))
+		);
+
+		// allow-list phase
+		let alice_balance = free_balance(&ALICE);
+		let bob_balance = free_balance(&BOB);
+		assert_ok!(NftmartDrop::claim(
+			Origin::signed(BOB),
+			drop_id,
+			0,
+			2,
+			Some((2, vec![charlie_leaf]))
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartDrop(crate::Event::ClaimedDrop(BOB, drop_id, 0, CLASS_ID0, TOKEN_ID0, 2))
+		);
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID0, 0, 2);
+		assert_eq!(NftmartDrop::claimed(drop_id, (0, BOB)), 2);
+		// price 20, platform fee 1.
+		assert_eq!(free_balance(&BOB), bob_balance - 20);
+		assert_eq!(free_balance(&ALICE), alice_balance + 19);
+		assert_eq!(free_balance(&FeeRouter::treasury_account_id()), 1);
+
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(BOB), drop_id, 0, 1, Some((2, vec![charlie_leaf]))),
+			Error::<Runtime>::ExceedAccountLimit
+		);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(CHARLIE), drop_id, 0, 1, Some((2, vec![bob_leaf]))),
+			Error::<Runtime>::NotInAllowList
+		);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(DAVE), drop_id, 0, 1, None),
+			Error::<Runtime>::NotInAllowList
+		);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(DAVE), drop_id, 1, 1, None),
+			Error::<Runtime>::PhaseNotActive
+		);
+
+		// public phase
+		System::set_block_number(10);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(CHARLIE), drop_id, 0, 1, Some((1, vec![bob_leaf]))),
+			Error::<Runtime>::PhaseNotActive
+		);
+		assert_ok!(NftmartDrop::claim(Origin::signed(DAVE), drop_id, 1, 1, None));
+		ensure_account(&DAVE, CLASS_ID0, TOKEN_ID1, 0, 1);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(DAVE), drop_id, 1, 1, None),
+			Error::<Runtime>::ExceedAccountLimit
+		);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(DAVE), drop_id, 2, 1, None),
+			Error::<Runtime>::PhaseNotFound
+		);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(BOB), drop_id, 1, 0, None),
+			Error::<Runtime>::InvalidQuantity
+		);
+	});
+}
+
+#[test]
+fn create_drop_should_fail() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+
+		assert_noop!(
+			NftmartDrop::create_drop(
+				Origin::signed(BOB),
+				CLASS_ID0,
+				vec![1],
+				None,
+				vec![phase(1, 10, 10)]
+			),
+			Error::<Runtime>::NoPermission
+		);
+		for phases in [
+			vec![],
+			vec![phase(10, 10, 10)],
+			vec![phase(1, 10, 10), phase(9, 20, 10)],
+			vec![DropPhase { max_per_account: Some(0), ..phase(1, 10, 10) }],
+			(0..5).map(|i| phase(i * 10, i * 10 + 10, 10)).collect(),
+		] {
+			assert_noop!(
+				NftmartDrop::create_drop(Origin::signed(ALICE), CLASS_ID0, vec![1], None, phases),
+				Error::<Runtime>::InvalidPhases
+			);
+		}
+	});
+}
+
+#[test]
+fn remove_drop_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+
+		let drop_id = current_gid();
+		assert_ok!(NftmartDrop::create_drop(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			vec![1],
+			None,
+			vec![phase(1, 10, 10)]
+		));
+		assert_ok!(NftmartDrop::claim(Origin::signed(BOB), drop_id, 0, 1, None));
+
+		assert_noop!(
+			NftmartDrop::remove_drop(Origin::signed(BOB), drop_id),
+			Error::<Runtime>::NoPermission
+		);
+		assert_ok!(NftmartDrop::remove_drop(Origin::signed(ALICE), drop_id));
+		assert_eq!(last_event(), Event::NftmartDrop(crate::Event::RemovedDrop(ALICE, drop_id)));
+		assert_eq!(NftmartDrop::drops(drop_id), None);
+		assert_eq!(NftmartDrop::claimed(drop_id, (0, BOB)), 0);
+		assert_noop!(
+			NftmartDrop::claim(Origin::signed(BOB), drop_id, 0, 1, None),
+			Error::<Runtime>::DropNotFound
+		);
+	});
+}
@@ -1,9 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::Codec;
 pub use nftmart_traits::*;
+use sp_runtime::{DispatchError, PerU16};
+use sp_std::vec::Vec;
 
 sp_api::decl_runtime_apis! {
 	/// The helper API to calculate deposit.
+	///
+	/// Version 2 added the class supply, class admin, token user and quote methods.
+	/// Version 3 added `get_dutch_auction_price`, the `curve` of `get_dutch_auction_current_price`
+	/// and the `soft_close` of `get_auction_deadline`.
+	#[api_version(3)]
 	pub trait NFTMartApi {
 		/// mint_token_deposit
 		fn mint_token_deposit(metadata_len: u32) -> Balance;
@@ -11,16 +19,65 @@ sp_api::decl_runtime_apis! {
 		fn add_class_admin_deposit(admin_count: u32) -> Balance;
 		/// create_class_deposit
 		fn create_class_deposit(metadata_len: u32, name_len: u32, description_len: u32) -> (Balance, Balance);
+		/// get the current price of a Dutch auction on the linear curve.
+		#[changed_in(3)]
+		fn get_dutch_auction_current_price(
+			max_price: Balance, min_price: Balance,
+			created_block: BlockNumber,
+			deadline: BlockNumber,
+			current_block: BlockNumber,
+		) -> Balance;
 		/// get the current price of a Dutch auction.
 		fn get_dutch_auction_current_price(
 			max_price: Balance, min_price: Balance,
 			created_block: BlockNumber,
 			deadline: BlockNumber,
 			current_block: BlockNumber,
+			curve: DutchAuctionCurve<BlockNumber>,
 		) -> Balance;
+		/// get the price the first bid pays for the Dutch auction `auction_id` of `auction_owner`
+		/// in the next block, on the curve of the auction.
+		fn get_dutch_auction_price(auction_owner: AccountId, auction_id: GlobalId) -> Option<Balance>;
+		/// get the deadline of an auction, extended by `AuctionCloseDelay` after the last bid.
+		#[changed_in(3)]
+		fn get_auction_deadline(allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber) -> BlockNumber;
 		/// get the deadline of an auction.
 		fn get_auction_deadline(
-			allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber
+			allow_delay: bool, deadline: BlockNumber, last_bid_block: BlockNumber,
+			soft_close: Option<SoftClose<BlockNumber>>,
 		) -> BlockNumber;
+		/// get the supply cap of a class, with the number of minted tokens and the total issuance.
+		fn get_class_supply(class_id: ClassId) -> Option<(ClassSupplyCap<TokenId>, TokenId, TokenId)>;
+		/// get the admins of a class.
+		fn get_class_admins(class_id: ClassId) -> Vec<AccountId>;
+		/// get the user of a token lent by `lender`, with the block number the user right expires at.
+		fn get_token_user(class_id: ClassId, token_id: TokenId, lender: AccountId) -> Option<(AccountId, BlockNumber)>;
+		/// get all the users of a token, as `(lender, user, expires)`.
+		fn get_token_users(class_id: ClassId, token_id: TokenId) -> Vec<(AccountId, AccountId, BlockNumber)>;
+		/// quote taking an order through `commission_agent`, with the fee of the transaction.
+		fn quote_take_order(
+			order_owner: AccountId, order_id: GlobalId, commission_agent: Option<AccountId>,
+		) -> Result<Quote<AccountId, CurrencyId>, DispatchError>;
+		/// quote submitting an order, with the fee of the transaction.
+		fn quote_submit_order(
+			currency_id: CurrencyId, price: Balance,
+			items: Vec<(ClassId, TokenId, TokenId)>,
+			commission_rate: PerU16, commission_agent: Option<AccountId>,
+		) -> Result<Quote<AccountId, CurrencyId>, DispatchError>;
+		/// quote bidding on a British or Dutch auction through `commission_agent`, with the fee of the transaction.
+		fn quote_bid(
+			auction_owner: AccountId, auction_id: GlobalId, price: Balance,
+			commission_agent: Option<AccountId>,
+		) -> Result<Quote<AccountId, CurrencyId>, DispatchError>;
+		/// quote minting tokens with a metadata of `metadata_len` bytes, with the fee of the transaction.
+		fn quote_mint(
+			class_id: ClassId, metadata_len: u32, quantity: TokenId, charge_royalty: Option<PerU16>,
+		) -> Result<Quote<AccountId, CurrencyId>, DispatchError>;
+	}
+
+	/// The API to try calls before submitting them.
+	pub trait NFTMartDryRunApi<Call, Event> where Call: Codec, Event: Codec {
+		/// dispatch `call` from `origin` in a throw-away overlay, as if it were included in the next block.
+		fn dry_run(origin: AccountId, call: Call) -> DryRunOutcome<AccountId, CurrencyId, ClassId, TokenId, Event>;
 	}
 }
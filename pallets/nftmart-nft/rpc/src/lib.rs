@@ -1,10 +1,13 @@
 use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
 use jsonrpc_derive::rpc;
-pub use nftmart_rpc_runtime_api::NFTMartApi as NFTMartRuntimeApi;
 use nftmart_rpc_runtime_api::*;
-use sp_api::ProvideRuntimeApi;
+pub use nftmart_rpc_runtime_api::{
+	NFTMartApi as NFTMartRuntimeApi, NFTMartDryRunApi as NFTMartDryRunRuntimeApi,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, DispatchError, PerU16};
 use std::sync::Arc;
 
 #[rpc]
@@ -23,6 +26,9 @@ pub trait NFTMartApi {
 	#[rpc(name = "nftmart_addClassAdminDeposit")]
 	fn add_class_admin_deposit(&self, admin_count: u32) -> Result<String>;
 
+	/// Deprecated: the price curve of the auction is linear with a 30-minute step if `curve` is
+	/// omitted, whatever the curve of a listed auction is. Use `nftmart_getDutchAuctionPrice` for
+	/// the price of a listed auction on its own curve.
 	#[rpc(name = "nftmart_getDutchAuctionCurrentPrice")]
 	fn get_dutch_auction_current_price(
 		&self,
@@ -31,15 +37,136 @@ pub trait NFTMartApi {
 		created_block: BlockNumber,
 		deadline: BlockNumber,
 		current_block: BlockNumber,
+		curve: Option<DutchAuctionCurve<BlockNumber>>,
 	) -> Result<String>;
 
+	/// The price the first bid pays for the Dutch auction `auction_id` of `auction_owner` in the
+	/// next block, on the curve of the auction the same as `bid_dutch_auction` does. `null` if the
+	/// auction is not found.
+	#[rpc(name = "nftmart_getDutchAuctionPrice")]
+	fn get_dutch_auction_price(
+		&self,
+		auction_owner: AccountId,
+		auction_id: GlobalId,
+	) -> Result<Option<String>>;
+
+	/// The deadline is extended by `AuctionCloseDelay` after the last bid if `soft_close` is omitted.
 	#[rpc(name = "nftmart_getAuctionDeadline")]
 	fn get_auction_deadline(
 		&self,
 		allow_delay: bool,
 		deadline: BlockNumber,
 		last_bid_block: BlockNumber,
+		soft_close: Option<SoftClose<BlockNumber>>,
 	) -> Result<String>;
+
+	#[rpc(name = "nftmart_getClassSupply")]
+	fn get_class_supply(
+		&self,
+		class_id: ClassId,
+	) -> Result<Option<(ClassSupplyCap<TokenId>, TokenId, TokenId)>>;
+
+	#[rpc(name = "nftmart_getClassAdmins")]
+	fn get_class_admins(&self, class_id: ClassId) -> Result<Vec<AccountId>>;
+
+	#[rpc(name = "nftmart_getTokenUser")]
+	fn get_token_user(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		lender: AccountId,
+	) -> Result<Option<(AccountId, BlockNumber)>>;
+
+	#[rpc(name = "nftmart_getTokenUsers")]
+	fn get_token_users(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+	) -> Result<Vec<(AccountId, AccountId, BlockNumber)>>;
+
+	/// Quote taking an order through `commission_agent`.
+	#[rpc(name = "nftmart_quoteTakeOrder")]
+	fn quote_take_order(
+		&self,
+		order_owner: AccountId,
+		order_id: GlobalId,
+		commission_agent: Option<AccountId>,
+	) -> Result<QuoteResponse>;
+
+	/// Quote submitting an order, the commission rate is zero if `commission_rate` is omitted.
+	#[rpc(name = "nftmart_quoteSubmitOrder")]
+	fn quote_submit_order(
+		&self,
+		currency_id: CurrencyId,
+		price: Balance,
+		items: Vec<(ClassId, TokenId, TokenId)>,
+		commission_rate: Option<PerU16>,
+		commission_agent: Option<AccountId>,
+	) -> Result<QuoteResponse>;
+
+	/// Quote bidding `price` on a British or Dutch auction through `commission_agent`.
+	#[rpc(name = "nftmart_quoteBid")]
+	fn quote_bid(
+		&self,
+		auction_owner: AccountId,
+		auction_id: GlobalId,
+		price: Balance,
+		commission_agent: Option<AccountId>,
+	) -> Result<QuoteResponse>;
+
+	/// Quote minting `quantity` tokens with a metadata of `metadata_len` bytes.
+	#[rpc(name = "nftmart_quoteMint")]
+	fn quote_mint(
+		&self,
+		class_id: ClassId,
+		metadata_len: u32,
+		quantity: TokenId,
+		charge_royalty: Option<PerU16>,
+	) -> Result<QuoteResponse>;
+}
+
+/// The breakdown of the balances paid by a marketplace action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteResponse {
+	/// The currency of the price, the fees and the commission.
+	pub currency_id: CurrencyId,
+	/// The price paid by the purchaser.
+	pub price: String,
+	/// The platform fee taken from the price.
+	pub platform_fee: String,
+	/// The royalty taken from the price.
+	pub royalty: String,
+	/// The beneficiary of the royalty, if any royalty is charged.
+	pub royalty_beneficiary: Option<AccountId>,
+	/// The commission at the rate of the listing, paid only if `commission_paid`.
+	pub commission: String,
+	/// The commission agent.
+	pub commission_agent: Option<AccountId>,
+	/// Whether the commission is paid to the agent.
+	pub commission_paid: bool,
+	/// The balances reserved from the sender, in the native currency for orders and mints and in
+	/// the auction currency for bids.
+	pub deposit: String,
+	/// The transaction fee in the native currency.
+	pub tx_fee: String,
+}
+
+impl From<Quote<AccountId, CurrencyId>> for QuoteResponse {
+	fn from(quote: Quote<AccountId, CurrencyId>) -> Self {
+		Self {
+			currency_id: quote.currency_id,
+			price: format!("{}", quote.price),
+			platform_fee: format!("{}", quote.platform_fee),
+			royalty: format!("{}", quote.royalty),
+			royalty_beneficiary: quote.royalty_beneficiary,
+			commission: format!("{}", quote.commission),
+			commission_agent: quote.commission_agent,
+			commission_paid: quote.commission_paid,
+			deposit: format!("{}", quote.deposit),
+			tx_fee: format!("{}", quote.tx_fee),
+		}
+	}
 }
 
 /// A struct that implements the [`NFTMartApi`].
@@ -61,6 +188,8 @@ pub enum Error {
 	DecodeError,
 	/// The call to runtime failed.
 	RuntimeError,
+	/// The quoted action would fail.
+	QuoteError,
 }
 
 impl From<Error> for i64 {
@@ -68,10 +197,55 @@ impl From<Error> for i64 {
 		match e {
 			Error::RuntimeError => 1,
 			Error::DecodeError => 2,
+			Error::QuoteError => 3,
 		}
 	}
 }
 
+/// Whether the runtime at `at` implements a version of `NFTMartApi` older than 3, which has neither
+/// the `curve` of `get_dutch_auction_current_price` nor the `soft_close` of `get_auction_deadline`.
+fn before_version_3<Block, Api>(api: &Api, at: &BlockId<Block>) -> Result<bool>
+where
+	Block: BlockT,
+	Api: ApiExt<Block> + NFTMartRuntimeApi<Block>,
+{
+	api.api_version::<dyn NFTMartRuntimeApi<Block>>(at)
+		.map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query the runtime api version.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+		.map(|version| version.map_or(true, |version| version < 3))
+}
+
+fn unsupported_by_runtime(param: &str) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: format!("`{}` is not supported by the runtime.", param),
+		data: None,
+	}
+}
+
+fn into_quote_response<E: std::fmt::Debug>(
+	result: std::result::Result<
+		std::result::Result<Quote<AccountId, CurrencyId>, DispatchError>,
+		E,
+	>,
+) -> Result<QuoteResponse> {
+	result
+		.map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?
+		.map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::QuoteError.into()),
+			message: "The quoted action would fail.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+		.map(Into::into)
+}
+
 impl<C, Block> NFTMartApi for NFTMart<C, Block>
 where
 	Block: BlockT,
@@ -138,23 +312,61 @@ where
 		created_block: BlockNumber,
 		deadline: BlockNumber,
 		current_block: BlockNumber,
+		curve: Option<DutchAuctionCurve<BlockNumber>>,
 	) -> Result<String> {
+		log::warn!(
+			"nftmart_getDutchAuctionCurrentPrice is deprecated and ignores the curve of the auction, \
+			 use nftmart_getDutchAuctionPrice instead."
+		);
 		let api = self.client.runtime_api();
 		let at = BlockId::hash(self.client.info().best_hash);
-		api.get_dutch_auction_current_price(
-			&at,
-			max_price,
-			min_price,
-			created_block,
-			deadline,
-			current_block,
-		)
-		.map_err(|e| RpcError {
-			code: ErrorCode::ServerError(Error::RuntimeError.into()),
-			message: "Unable to query dispatch info.".into(),
-			data: Some(format!("{:?}", e).into()),
-		})
-		.map(|x| format!("{}", x))
+		let price = if before_version_3(&*api, &at)? {
+			if curve.map_or(false, |curve| curve != Default::default()) {
+				return Err(unsupported_by_runtime("curve"))
+			}
+			#[allow(deprecated)]
+			api.get_dutch_auction_current_price_before_version_3(
+				&at,
+				max_price,
+				min_price,
+				created_block,
+				deadline,
+				current_block,
+			)
+		} else {
+			api.get_dutch_auction_current_price(
+				&at,
+				max_price,
+				min_price,
+				created_block,
+				deadline,
+				current_block,
+				curve.unwrap_or_default(),
+			)
+		};
+		price
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to query dispatch info.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+			.map(|x| format!("{}", x))
+	}
+
+	fn get_dutch_auction_price(
+		&self,
+		auction_owner: AccountId,
+		auction_id: GlobalId,
+	) -> Result<Option<String>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		api.get_dutch_auction_price(&at, auction_owner, auction_id)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to query dispatch info.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+			.map(|price| price.map(|x| format!("{}", x)))
 	}
 
 	fn get_auction_deadline(
@@ -162,10 +374,20 @@ where
 		allow_delay: bool,
 		deadline: BlockNumber,
 		last_bid_block: BlockNumber,
+		soft_close: Option<SoftClose<BlockNumber>>,
 	) -> Result<String> {
 		let api = self.client.runtime_api();
 		let at = BlockId::hash(self.client.info().best_hash);
-		api.get_auction_deadline(&at, allow_delay, deadline, last_bid_block)
+		let deadline = if before_version_3(&*api, &at)? {
+			if soft_close.is_some() {
+				return Err(unsupported_by_runtime("soft_close"))
+			}
+			#[allow(deprecated)]
+			api.get_auction_deadline_before_version_3(&at, allow_delay, deadline, last_bid_block)
+		} else {
+			api.get_auction_deadline(&at, allow_delay, deadline, last_bid_block, soft_close)
+		};
+		deadline
 			.map_err(|e| RpcError {
 				code: ErrorCode::ServerError(Error::RuntimeError.into()),
 				message: "Unable to query dispatch info.".into(),
@@ -173,4 +395,110 @@ where
 			})
 			.map(|x| format!("{}", x))
 	}
+
+	fn get_class_supply(
+		&self,
+		class_id: ClassId,
+	) -> Result<Option<(ClassSupplyCap<TokenId>, TokenId, TokenId)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		api.get_class_supply(&at, class_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn get_class_admins(&self, class_id: ClassId) -> Result<Vec<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		api.get_class_admins(&at, class_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn get_token_user(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+		lender: AccountId,
+	) -> Result<Option<(AccountId, BlockNumber)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		api.get_token_user(&at, class_id, token_id, lender).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn get_token_users(
+		&self,
+		class_id: ClassId,
+		token_id: TokenId,
+	) -> Result<Vec<(AccountId, AccountId, BlockNumber)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		api.get_token_users(&at, class_id, token_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn quote_take_order(
+		&self,
+		order_owner: AccountId,
+		order_id: GlobalId,
+		commission_agent: Option<AccountId>,
+	) -> Result<QuoteResponse> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		into_quote_response(api.quote_take_order(&at, order_owner, order_id, commission_agent))
+	}
+
+	fn quote_submit_order(
+		&self,
+		currency_id: CurrencyId,
+		price: Balance,
+		items: Vec<(ClassId, TokenId, TokenId)>,
+		commission_rate: Option<PerU16>,
+		commission_agent: Option<AccountId>,
+	) -> Result<QuoteResponse> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		into_quote_response(api.quote_submit_order(
+			&at,
+			currency_id,
+			price,
+			items,
+			commission_rate.unwrap_or_default(),
+			commission_agent,
+		))
+	}
+
+	fn quote_bid(
+		&self,
+		auction_owner: AccountId,
+		auction_id: GlobalId,
+		price: Balance,
+		commission_agent: Option<AccountId>,
+	) -> Result<QuoteResponse> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		into_quote_response(api.quote_bid(&at, auction_owner, auction_id, price, commission_agent))
+	}
+
+	fn quote_mint(
+		&self,
+		class_id: ClassId,
+		metadata_len: u32,
+		quantity: TokenId,
+		charge_royalty: Option<PerU16>,
+	) -> Result<QuoteResponse> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		into_quote_response(api.quote_mint(&at, class_id, metadata_len, quantity, charge_royalty))
+	}
 }
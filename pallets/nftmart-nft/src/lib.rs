@@ -11,7 +11,8 @@ use orml_traits::{MultiCurrency, MultiReservableCurrency};
 use scale_info::TypeInfo;
 use sp_runtime::{
 	traits::{
-		AccountIdConversion, AtLeast32BitUnsigned, Bounded, CheckedAdd, One, StaticLookup, Zero,
+		AccountIdConversion, AtLeast32BitUnsigned, Bounded, CheckedAdd, CheckedSub, One,
+		StaticLookup, Zero,
 	},
 	PerU16, RuntimeDebug, SaturatedConversion,
 };
@@ -33,6 +34,8 @@ pub type CurrencyIdOf<T> = <<T as module::Config>::MultiCurrency as MultiCurrenc
 	<T as frame_system::Config>::AccountId,
 >>::CurrencyId;
 pub type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+pub type TokenApprovalOf<T> = TokenApproval<TokenIdOf<T>, BlockNumberOf<T>>;
+pub type ClassSupplyCapOf<T> = ClassSupplyCap<TokenIdOf<T>>;
 
 #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
 enum Releases {
@@ -217,6 +220,24 @@ pub mod module {
 		CategoryOutOfBound,
 		DuplicatedCategories,
 		RoyaltyRateTooHigh,
+		/// Cannot approve oneself
+		ApproveSelf,
+		/// Approval not found
+		ApprovalNotFound,
+		/// Approval has expired
+		ApprovalExpired,
+		/// The approved quantity is not enough
+		InsufficientAllowance,
+		/// The class has reached its maximum number of tokens
+		MaxTokensExceeded,
+		/// The class has reached its maximum supply
+		MaxSupplyExceeded,
+		/// Supply cap can only be reduced and never below the minted amount
+		InvalidSupplyCap,
+		/// Cannot remove the last admin of a class
+		LastClassAdmin,
+		/// The royalty rate is lower than the minimum royalty of the class
+		RoyaltyRateTooLow,
 	}
 
 	#[pallet::event]
@@ -242,6 +263,36 @@ pub mod module {
 		BurnedToken(T::AccountId, ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>, Balance),
 		/// Destroyed NFT class. \[owner, class_id, dest\]
 		DestroyedClass(T::AccountId, ClassIdOf<T>, T::AccountId),
+		/// Approved NFT token. \[owner, spender, class_id, token_id, quantity, deadline\]
+		Approved(
+			T::AccountId,
+			T::AccountId,
+			ClassIdOf<T>,
+			TokenIdOf<T>,
+			TokenIdOf<T>,
+			BlockNumberOf<T>,
+		),
+		/// Revoked NFT token approval. \[owner, spender, class_id, token_id\]
+		RevokedApproval(T::AccountId, T::AccountId, ClassIdOf<T>, TokenIdOf<T>),
+		/// Approved an operator for all tokens. \[owner, operator, deadline\]
+		ApprovedForAll(T::AccountId, T::AccountId, BlockNumberOf<T>),
+		/// Revoked an operator. \[owner, operator\]
+		RevokedApprovalForAll(T::AccountId, T::AccountId),
+		/// Added NFT class admin. \[who, class_id, admin\]
+		AddedClassAdmin(T::AccountId, ClassIdOf<T>, T::AccountId),
+		/// Removed NFT class admin. \[who, class_id, admin\]
+		RemovedClassAdmin(T::AccountId, ClassIdOf<T>, T::AccountId),
+		/// Transferred NFT class ownership. \[from, class_id, to\]
+		TransferredClassOwnership(T::AccountId, ClassIdOf<T>, T::AccountId),
+		/// Updated NFT class supply cap. \[owner, class_id, max_tokens, max_supply\]
+		UpdatedClassSupplyCap(
+			T::AccountId,
+			ClassIdOf<T>,
+			Option<TokenIdOf<T>>,
+			Option<TokenIdOf<T>>,
+		),
+		/// Updated NFT class minimum royalty. \[owner, class_id, min_royalty\]
+		UpdatedClassMinRoyalty(T::AccountId, ClassIdOf<T>, PerU16),
 	}
 
 	#[pallet::pallet]
@@ -264,13 +315,25 @@ pub mod module {
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub classes: Vec<ClassConfig<ClassIdOf<T>, T::AccountId, TokenIdOf<T>>>,
+		/// owner, spender, class_id, token_id, approval
+		#[serde(default)]
+		pub token_approvals:
+			Vec<(T::AccountId, T::AccountId, ClassIdOf<T>, TokenIdOf<T>, TokenApprovalOf<T>)>,
+		/// owner, operator, deadline
+		#[serde(default)]
+		pub operator_approvals: Vec<(T::AccountId, T::AccountId, BlockNumberOf<T>)>,
 		pub _phantom: PhantomData<T>,
 	}
 
 	#[cfg(feature = "std")]
 	impl<T: Config> Default for GenesisConfig<T> {
 		fn default() -> Self {
-			Self { classes: Default::default(), _phantom: Default::default() }
+			Self {
+				classes: Default::default(),
+				token_approvals: Default::default(),
+				operator_approvals: Default::default(),
+				_phantom: Default::default(),
+			}
 		}
 	}
 
@@ -290,6 +353,9 @@ pub mod module {
 				properties,
 				admins,
 				tokens,
+				max_tokens,
+				max_supply,
+				min_royalty,
 			} in &self.classes
 			{
 				let class_metadata: NFTMetadata = class_metadata.as_bytes().to_vec();
@@ -344,6 +410,7 @@ pub mod module {
 					token_creator,
 					royalty_beneficiary,
 					quantity,
+					holders,
 				} in tokens
 				{
 					assert!(
@@ -371,6 +438,15 @@ pub mod module {
 						*quantity,
 					)
 					.unwrap();
+					for (holder, quantity) in holders {
+						orml_nft::Pallet::<T>::transfer(
+							token_owner,
+							holder,
+							(*class_id, *token_id),
+							*quantity,
+						)
+						.unwrap();
+					}
 
 					if max_token_id < *token_id {
 						max_token_id = *token_id;
@@ -380,8 +456,43 @@ pub mod module {
 					*class_id,
 					max_token_id.checked_add(&One::one()).unwrap(),
 				);
+				if max_tokens.is_some() || max_supply.is_some() {
+					let next_token_id = orml_nft::Pallet::<T>::next_token_id(*class_id);
+					let total_issuance =
+						orml_nft::Pallet::<T>::classes(*class_id).unwrap().total_issuance;
+					assert!(
+						max_tokens.map_or(true, |n| !n.is_zero() && next_token_id <= n),
+						"Class has more tokens than its max_tokens"
+					);
+					assert!(
+						max_supply.map_or(true, |n| !n.is_zero() && total_issuance <= n),
+						"Class has a larger supply than its max_supply"
+					);
+					ClassSupplyCaps::<T>::insert(
+						*class_id,
+						ClassSupplyCap { max_tokens: *max_tokens, max_supply: *max_supply },
+					);
+				}
+				if !min_royalty.is_zero() {
+					ClassMinRoyalties::<T>::insert(*class_id, *min_royalty);
+				}
 			}
 			orml_nft::NextClassId::<T>::set(max_class_id.checked_add(&One::one()).unwrap());
+
+			for (owner, spender, class_id, token_id, approval) in &self.token_approvals {
+				assert!(
+					orml_nft::Tokens::<T>::contains_key(*class_id, *token_id),
+					"Approval of unknown token"
+				);
+				TokenApprovals::<T>::insert(
+					owner,
+					(spender.clone(), *class_id, *token_id),
+					approval.clone(),
+				);
+			}
+			for (owner, operator, deadline) in &self.operator_approvals {
+				OperatorApprovals::<T>::insert(owner, operator, *deadline);
+			}
 		}
 	}
 
@@ -389,6 +500,71 @@ pub mod module {
 	#[pallet::storage]
 	pub(super) type StorageVersion<T: Config> = StorageValue<_, Releases, ValueQuery>;
 
+	/// Token allowances granted by owners.
+	///
+	/// owner -> (spender, class_id, token_id) -> approval
+	#[pallet::storage]
+	#[pallet::getter(fn token_approvals)]
+	pub type TokenApprovals<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		(T::AccountId, ClassIdOf<T>, TokenIdOf<T>),
+		TokenApprovalOf<T>,
+	>;
+
+	/// Operators allowed to transfer any token of an owner.
+	///
+	/// owner -> operator -> deadline
+	#[pallet::storage]
+	#[pallet::getter(fn operator_approvals)]
+	pub type OperatorApprovals<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		BlockNumberOf<T>,
+	>;
+
+	/// Supply caps of classes, absent means unbounded.
+	///
+	/// class_id -> supply cap
+	#[pallet::storage]
+	#[pallet::getter(fn class_supply_caps)]
+	pub type ClassSupplyCaps<T: Config> =
+		StorageMap<_, Twox64Concat, ClassIdOf<T>, ClassSupplyCapOf<T>>;
+
+	/// The quantity ever minted in a class, which burning does not lower. Absent for a class which
+	/// has neither minted nor burned since this was added, whose total issuance stands for it.
+	///
+	/// class_id -> minted quantity
+	#[pallet::storage]
+	#[pallet::getter(fn class_minted)]
+	pub type ClassMinted<T: Config> = StorageMap<_, Twox64Concat, ClassIdOf<T>, TokenIdOf<T>>;
+
+	/// The minimum royalty of tokens in a class, which token-level updates cannot go below.
+	#[pallet::storage]
+	#[pallet::getter(fn class_min_royalties)]
+	pub type ClassMinRoyalties<T: Config> =
+		StorageMap<_, Twox64Concat, ClassIdOf<T>, PerU16, ValueQuery>;
+
+	/// The account which paid the deposit of a class admin, refunded when the admin is removed.
+	/// The deposits of the admins added before this storage are kept by the class.
+	///
+	/// class_id, admin -> (depositor, deposit)
+	#[pallet::storage]
+	#[pallet::getter(fn class_admin_deposits)]
+	pub type ClassAdminDeposits<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		ClassIdOf<T>,
+		Blake2_128Concat,
+		T::AccountId,
+		(T::AccountId, Balance),
+	>;
+
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Create NFT class, tokens belong to the class.
@@ -417,6 +593,7 @@ pub mod module {
 				royalty_rate,
 				properties,
 				category_ids,
+				Default::default(),
 			)?;
 			Ok(().into())
 		}
@@ -454,6 +631,139 @@ pub mod module {
 			Ok(().into())
 		}
 
+		/// Reduce the supply cap of NFT class. A cap can never be increased or removed.
+		///
+		/// - `class_id`: class id
+		/// - `max_tokens`: the new maximum number of tokens, `None` for unchanged if unbounded.
+		/// - `max_supply`: the new maximum total issuance, `None` for unchanged if unbounded.
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn reduce_class_supply_cap(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			max_tokens: Option<TokenIdOf<T>>,
+			max_supply: Option<TokenIdOf<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::do_reduce_class_supply_cap(
+				&who,
+				class_id,
+				ClassSupplyCap { max_tokens, max_supply },
+			)?;
+			Ok(().into())
+		}
+
+		/// Create NFT class with a supply cap, see `create_class`.
+		///
+		/// - `max_tokens`: the maximum number of tokens can be minted, `None` for unbounded.
+		/// - `max_supply`: the maximum total issuance of the class, `None` for unbounded.
+		#[pallet::weight(100_000)]
+		#[transactional]
+		#[allow(clippy::too_many_arguments)]
+		pub fn create_class_with_supply_cap(
+			origin: OriginFor<T>,
+			metadata: NFTMetadata,
+			name: Vec<u8>,
+			description: Vec<u8>,
+			#[pallet::compact] royalty_rate: PerU16,
+			properties: Properties,
+			category_ids: Vec<GlobalId>,
+			max_tokens: Option<TokenIdOf<T>>,
+			max_supply: Option<TokenIdOf<T>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::do_create_class(
+				&who,
+				metadata,
+				name,
+				description,
+				royalty_rate,
+				properties,
+				category_ids,
+				ClassSupplyCap { max_tokens, max_supply },
+			)?;
+			Ok(().into())
+		}
+
+		/// Set the minimum royalty of NFT class, it also applies to the tokens already minted.
+		///
+		/// - `class_id`: class id
+		/// - `min_royalty`: the minimum royalty rate
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn set_class_min_royalty(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			#[pallet::compact] min_royalty: PerU16,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let class_info: ClassInfoOf<T> =
+				orml_nft::Pallet::<T>::classes(class_id).ok_or(Error::<T>::ClassIdNotFound)?;
+			ensure!(who == class_info.owner, Error::<T>::NoPermission);
+			ensure!(
+				T::ExtraConfig::get_royalties_rate() >= min_royalty,
+				Error::<T>::RoyaltyRateTooHigh
+			);
+			ClassMinRoyalties::<T>::insert(class_id, min_royalty);
+			Self::deposit_event(Event::UpdatedClassMinRoyalty(who, class_id, min_royalty));
+			Ok(().into())
+		}
+
+		/// Add an admin to NFT class, the origin pays the proxy deposit.
+		///
+		/// - `origin`: an admin of the class
+		/// - `class_id`: class id
+		/// - `admin`: the new admin
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn add_class_admin(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			admin: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let admin = T::Lookup::lookup(admin)?;
+			Self::do_add_class_admin(&who, class_id, admin)?;
+			Ok(().into())
+		}
+
+		/// Remove an admin from NFT class, the proxy deposit is returned to the origin.
+		///
+		/// - `origin`: an admin of the class
+		/// - `class_id`: class id
+		/// - `admin`: the admin to be removed
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn remove_class_admin(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			admin: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let admin = T::Lookup::lookup(admin)?;
+			Self::do_remove_class_admin(&who, class_id, admin)?;
+			Ok(().into())
+		}
+
+		/// Replace all admins of NFT class with `to`, the extra proxy deposits are returned to
+		/// the origin.
+		///
+		/// - `origin`: an admin of the class
+		/// - `class_id`: class id
+		/// - `to`: the new sole admin
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn transfer_class_ownership(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			to: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let to = T::Lookup::lookup(to)?;
+			Self::do_transfer_class_ownership(&who, class_id, to)?;
+			Ok(().into())
+		}
+
 		/// Update token royalty.
 		#[pallet::weight(100_000)]
 		#[transactional]
@@ -481,14 +791,13 @@ pub mod module {
 						);
 					}
 
-					token_info.data.royalty_rate = charge_royalty
-						.ok_or_else(|| -> Result<PerU16, DispatchError> {
-							let class_info: ClassInfoOf<T> =
-								orml_nft::Pallet::<T>::classes(class_id)
-									.ok_or(Error::<T>::ClassIdNotFound)?;
-							Ok(class_info.data.royalty_rate)
-						})
-						.or_else(core::convert::identity)?;
+					let class_info: ClassInfoOf<T> = orml_nft::Pallet::<T>::classes(class_id)
+						.ok_or(Error::<T>::ClassIdNotFound)?;
+					token_info.data.royalty_rate = Self::checked_royalty_rate(
+						class_id,
+						class_info.data.royalty_rate,
+						charge_royalty,
+					)?;
 					Ok(().into())
 				},
 			)?;
@@ -586,19 +895,28 @@ pub mod module {
 						Error::<T>::NoPermission
 					);
 
-					token_info.data.royalty_rate = charge_royalty
-						.ok_or_else(|| -> Result<PerU16, DispatchError> {
-							let class_info: ClassInfoOf<T> =
-								orml_nft::Pallet::<T>::classes(class_id)
-									.ok_or(Error::<T>::ClassIdNotFound)?;
-							Ok(class_info.data.royalty_rate)
-						})
-						.or_else(core::convert::identity)?;
+					let class_info: ClassInfoOf<T> = orml_nft::Pallet::<T>::classes(class_id)
+						.ok_or(Error::<T>::ClassIdNotFound)?;
+					token_info.data.royalty_rate = Self::checked_royalty_rate(
+						class_id,
+						class_info.data.royalty_rate,
+						charge_royalty,
+					)?;
 
 					// update metadata
 					token_info.metadata = metadata;
 
 					// update quantity
+					if quantity > token_info.quantity {
+						let minted = Self::minted_supply(class_id, class_info.total_issuance)
+							.saturating_add(quantity - token_info.quantity);
+						Self::ensure_within_supply_cap(
+							class_id,
+							orml_nft::Pallet::<T>::next_token_id(class_id),
+							minted,
+						)?;
+						ClassMinted::<T>::insert(class_id, minted);
+					}
 					token_info.quantity = quantity;
 
 					Ok(().into())
@@ -683,6 +1001,104 @@ pub mod module {
 			Ok(().into())
 		}
 
+		/// Allow `spender` to transfer `quantity` of a token on behalf of the origin.
+		///
+		/// - `spender`: the account being approved
+		/// - `class_id`: class id
+		/// - `token_id`: token id
+		/// - `quantity`: quantity the spender is allowed to transfer
+		/// - `deadline`: this approval will be invalidated after `deadline` block number
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn approve(
+			origin: OriginFor<T>,
+			spender: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			#[pallet::compact] token_id: TokenIdOf<T>,
+			#[pallet::compact] quantity: TokenIdOf<T>,
+			#[pallet::compact] deadline: BlockNumberOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let spender = T::Lookup::lookup(spender)?;
+			Self::do_approve(&who, &spender, class_id, token_id, quantity, deadline)?;
+			Ok(().into())
+		}
+
+		/// Revoke an approval granted to `spender`.
+		///
+		/// - `spender`: the approved account
+		/// - `class_id`: class id
+		/// - `token_id`: token id
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn revoke(
+			origin: OriginFor<T>,
+			spender: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			#[pallet::compact] token_id: TokenIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let spender = T::Lookup::lookup(spender)?;
+			Self::do_revoke(&who, &spender, class_id, token_id)?;
+			Ok(().into())
+		}
+
+		/// Allow `operator` to transfer any token of the origin.
+		///
+		/// - `operator`: the account being approved
+		/// - `deadline`: this approval will be invalidated after `deadline` block number
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn approve_for_all(
+			origin: OriginFor<T>,
+			operator: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] deadline: BlockNumberOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let operator = T::Lookup::lookup(operator)?;
+			Self::do_approve_for_all(&who, &operator, deadline)?;
+			Ok(().into())
+		}
+
+		/// Revoke an operator.
+		///
+		/// - `operator`: the approved account
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn revoke_for_all(
+			origin: OriginFor<T>,
+			operator: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let operator = T::Lookup::lookup(operator)?;
+			Self::do_revoke_for_all(&who, &operator)?;
+			Ok(().into())
+		}
+
+		/// Transfer NFT tokens of `from` to another account by an approved spender.
+		///
+		/// - `from`: the token owner's account
+		/// - `to`: the receiver's account
+		/// - `items`: a list of `(class_id, token_id, quantity)`
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn transfer_from(
+			origin: OriginFor<T>,
+			from: <T::Lookup as StaticLookup>::Source,
+			to: <T::Lookup as StaticLookup>::Source,
+			items: Vec<(ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>)>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let from = T::Lookup::lookup(from)?;
+			let to = T::Lookup::lookup(to)?;
+			for (class_id, token_id, quantity) in items {
+				if quantity > Zero::zero() {
+					Self::do_transfer_from(&who, &from, &to, class_id, token_id, quantity)?;
+				}
+			}
+			Ok(().into())
+		}
+
 		/// Burn NFT token
 		///
 		/// - `class_id`: class id
@@ -703,6 +1119,13 @@ pub mod module {
 			T::OrderConfig::burn_orders(&who, class_id, token_id)?;
 			T::OrderConfig::burn_offers(&who, class_id, token_id)?;
 
+			// The minted quantity is kept before burning lowers the total issuance.
+			if !ClassMinted::<T>::contains_key(class_id) {
+				if let Some(class_info) = orml_nft::Pallet::<T>::classes(class_id) {
+					ClassMinted::<T>::insert(class_id, class_info.total_issuance);
+				}
+			}
+
 			if let Some(token_info) =
 				orml_nft::Pallet::<T>::burn(&who, (class_id, token_id), quantity)?
 			{
@@ -769,6 +1192,10 @@ pub mod module {
 
 			// transfer all free from origin to dest
 			orml_nft::Pallet::<T>::destroy_class(&who, class_id)?;
+			ClassSupplyCaps::<T>::remove(class_id);
+			ClassMinted::<T>::remove(class_id);
+			ClassMinRoyalties::<T>::remove(class_id);
+			let _ = ClassAdminDeposits::<T>::remove_prefix(class_id, None);
 
 			Self::deposit_event(Event::DestroyedClass(who, class_id, dest));
 			Ok(().into())
@@ -830,7 +1257,11 @@ impl<T: Config> Pallet<T> {
 		let data: TokenData<T::AccountId, BlockNumberOf<T>> = TokenData {
 			deposit,
 			create_block: <frame_system::Pallet<T>>::block_number(),
-			royalty_rate: charge_royalty.unwrap_or(class_info.data.royalty_rate),
+			royalty_rate: Self::checked_royalty_rate(
+				class_id,
+				class_info.data.royalty_rate,
+				charge_royalty,
+			)?,
 			creator: to.clone(),
 			royalty_beneficiary: to.clone(),
 		};
@@ -840,8 +1271,19 @@ impl<T: Config> Pallet<T> {
 			Error::<T>::RoyaltyRateTooHigh
 		);
 
+		let next_token_id = orml_nft::Pallet::<T>::next_token_id(class_id);
+		let minted = Self::minted_supply(class_id, class_info.total_issuance)
+			.checked_add(&quantity)
+			.ok_or(Error::<T>::MaxSupplyExceeded)?;
+		Self::ensure_within_supply_cap(
+			class_id,
+			next_token_id.checked_add(&One::one()).ok_or(Error::<T>::MaxTokensExceeded)?,
+			minted,
+		)?;
+
 		let token_id: TokenIdOf<T> =
 			orml_nft::Pallet::<T>::mint(to, class_id, metadata, data, quantity)?;
+		ClassMinted::<T>::insert(class_id, minted);
 
 		Self::deposit_event(Event::MintedToken(
 			who.clone(),
@@ -854,6 +1296,7 @@ impl<T: Config> Pallet<T> {
 	}
 
 	#[transactional]
+	#[allow(clippy::too_many_arguments)]
 	pub fn do_create_class(
 		who: &T::AccountId,
 		metadata: NFTMetadata,
@@ -862,8 +1305,14 @@ impl<T: Config> Pallet<T> {
 		royalty_rate: PerU16,
 		properties: Properties,
 		category_ids: Vec<GlobalId>,
+		supply_cap: ClassSupplyCapOf<T>,
 	) -> ResultPost<(T::AccountId, ClassIdOf<T>)> {
 		ensure!(T::ExtraConfig::is_in_whitelist(who), Error::<T>::AccountNotInWhitelist);
+		ensure!(
+			supply_cap.max_tokens.map_or(true, |n| !n.is_zero()) &&
+				supply_cap.max_supply.map_or(true, |n| !n.is_zero()),
+			Error::<T>::InvalidSupplyCap
+		);
 		ensure!(category_ids.len() <= MAX_CATEGORY_PER_CLASS, Error::<T>::CategoryOutOfBound);
 		ensure!(category_ids.len() >= 1, Error::<T>::CategoryOutOfBound);
 		if category_ids.len() == 2 {
@@ -910,6 +1359,12 @@ impl<T: Config> Pallet<T> {
 		};
 
 		orml_nft::Pallet::<T>::create_class(&owner, metadata, data)?;
+		if supply_cap != ClassSupplyCap::default() {
+			ClassSupplyCaps::<T>::insert(next_id, supply_cap);
+		}
+		// The first admin is paid with the class deposit.
+		let admin_deposit = Self::add_class_admin_deposit(1);
+		ClassAdminDeposits::<T>::insert(next_id, who, (who.clone(), admin_deposit));
 
 		Self::deposit_event(Event::CreatedClass(owner.clone(), next_id));
 		Ok((owner, next_id))
@@ -967,6 +1422,179 @@ impl<T: Config> Pallet<T> {
 		Ok((owner, class_id))
 	}
 
+	#[transactional]
+	pub fn do_add_class_admin(
+		who: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		admin: T::AccountId,
+	) -> DispatchResult {
+		let class_info =
+			orml_nft::Pallet::<T>::classes(class_id).ok_or(Error::<T>::ClassIdNotFound)?;
+		let owner = class_info.owner;
+		let _ = pallet_proxy::Pallet::<T>::find_proxy(&owner, who, None)?;
+
+		let deposit = Self::add_class_admin_deposit(1);
+		<T as Config>::Currency::transfer(who, &owner, deposit.saturated_into(), KeepAlive)?;
+		<pallet_proxy::Pallet<T>>::add_proxy_delegate(
+			&owner,
+			admin.clone(),
+			Default::default(),
+			Zero::zero(),
+		)?;
+		ClassAdminDeposits::<T>::insert(class_id, &admin, (who.clone(), deposit));
+
+		Self::deposit_event(Event::AddedClassAdmin(who.clone(), class_id, admin));
+		Ok(())
+	}
+
+	#[transactional]
+	pub fn do_remove_class_admin(
+		who: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		admin: T::AccountId,
+	) -> DispatchResult {
+		let class_info =
+			orml_nft::Pallet::<T>::classes(class_id).ok_or(Error::<T>::ClassIdNotFound)?;
+		let owner = class_info.owner;
+		let _ = pallet_proxy::Pallet::<T>::find_proxy(&owner, who, None)?;
+		ensure!(pallet_proxy::Pallet::<T>::proxies(&owner).0.len() > 1, Error::<T>::LastClassAdmin);
+
+		<pallet_proxy::Pallet<T>>::remove_proxy_delegate(
+			&owner,
+			admin.clone(),
+			Default::default(),
+			Zero::zero(),
+		)?;
+		Self::refund_class_admin_deposit(&owner, class_id, &admin)?;
+
+		Self::deposit_event(Event::RemovedClassAdmin(who.clone(), class_id, admin));
+		Ok(())
+	}
+
+	#[transactional]
+	pub fn do_transfer_class_ownership(
+		who: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		to: T::AccountId,
+	) -> DispatchResult {
+		let class_info =
+			orml_nft::Pallet::<T>::classes(class_id).ok_or(Error::<T>::ClassIdNotFound)?;
+		let owner = class_info.owner;
+		let _ = pallet_proxy::Pallet::<T>::find_proxy(&owner, who, None)?;
+
+		// The deposit of the caller pays for the new owner, the others are refunded.
+		let kept_deposit = ClassAdminDeposits::<T>::take(class_id, who);
+		let (proxies, _) = pallet_proxy::Pallet::<T>::proxies(&owner);
+		for p in proxies {
+			<pallet_proxy::Pallet<T>>::remove_proxy_delegate(
+				&owner,
+				p.delegate.clone(),
+				p.proxy_type,
+				p.delay,
+			)?;
+			Self::refund_class_admin_deposit(&owner, class_id, &p.delegate)?;
+		}
+		<pallet_proxy::Pallet<T>>::add_proxy_delegate(
+			&owner,
+			to.clone(),
+			Default::default(),
+			Zero::zero(),
+		)?;
+		if let Some(deposit) = kept_deposit {
+			ClassAdminDeposits::<T>::insert(class_id, &to, deposit);
+		}
+
+		Self::deposit_event(Event::TransferredClassOwnership(who.clone(), class_id, to));
+		Ok(())
+	}
+
+	#[transactional]
+	pub fn do_reduce_class_supply_cap(
+		who: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		supply_cap: ClassSupplyCapOf<T>,
+	) -> DispatchResult {
+		let class_info =
+			orml_nft::Pallet::<T>::classes(class_id).ok_or(Error::<T>::ClassIdNotFound)?;
+		ensure!(who == &class_info.owner, Error::<T>::NoPermission);
+
+		let old = ClassSupplyCaps::<T>::get(class_id).unwrap_or_default();
+		let supply_cap = ClassSupplyCap {
+			max_tokens: Self::reduce_cap(
+				old.max_tokens,
+				supply_cap.max_tokens,
+				orml_nft::Pallet::<T>::next_token_id(class_id),
+			)?,
+			max_supply: Self::reduce_cap(
+				old.max_supply,
+				supply_cap.max_supply,
+				Self::minted_supply(class_id, class_info.total_issuance),
+			)?,
+		};
+
+		ClassSupplyCaps::<T>::insert(class_id, &supply_cap);
+		Self::deposit_event(Event::UpdatedClassSupplyCap(
+			who.clone(),
+			class_id,
+			supply_cap.max_tokens,
+			supply_cap.max_supply,
+		));
+		Ok(())
+	}
+
+	/// The royalty rate of a token cannot go below the minimum royalty of its class, `None`
+	/// falls back to the class royalty.
+	fn checked_royalty_rate(
+		class_id: ClassIdOf<T>,
+		class_royalty: PerU16,
+		charge_royalty: Option<PerU16>,
+	) -> Result<PerU16, DispatchError> {
+		let min_royalty = Self::class_min_royalties(class_id);
+		match charge_royalty {
+			Some(rate) => {
+				ensure!(rate >= min_royalty, Error::<T>::RoyaltyRateTooLow);
+				Ok(rate)
+			},
+			None => Ok(class_royalty.max(min_royalty)),
+		}
+	}
+
+	/// A new cap must not exceed the old one nor fall below the `minted` amount, `None` keeps
+	/// the old one.
+	fn reduce_cap(
+		old: Option<TokenIdOf<T>>,
+		new: Option<TokenIdOf<T>>,
+		minted: TokenIdOf<T>,
+	) -> Result<Option<TokenIdOf<T>>, DispatchError> {
+		match new {
+			None => Ok(old),
+			Some(new) => {
+				ensure!(old.map_or(true, |old| new <= old), Error::<T>::InvalidSupplyCap);
+				ensure!(new >= minted, Error::<T>::InvalidSupplyCap);
+				Ok(Some(new))
+			},
+		}
+	}
+
+	/// The quantity ever minted in a class with `total_issuance`, see [`ClassMinted`].
+	fn minted_supply(class_id: ClassIdOf<T>, total_issuance: TokenIdOf<T>) -> TokenIdOf<T> {
+		ClassMinted::<T>::get(class_id).unwrap_or(total_issuance)
+	}
+
+	/// Ensure the class stays within its supply cap with `tokens` minted tokens and `minted`
+	/// quantity ever minted.
+	fn ensure_within_supply_cap(
+		class_id: ClassIdOf<T>,
+		tokens: TokenIdOf<T>,
+		minted: TokenIdOf<T>,
+	) -> DispatchResult {
+		if let Some(cap) = ClassSupplyCaps::<T>::get(class_id) {
+			ensure!(cap.max_tokens.map_or(true, |n| tokens <= n), Error::<T>::MaxTokensExceeded);
+			ensure!(cap.max_supply.map_or(true, |n| minted <= n), Error::<T>::MaxSupplyExceeded);
+		}
+		Ok(())
+	}
+
 	#[transactional]
 	pub fn do_transfer(
 		from: &T::AccountId,
@@ -989,8 +1617,160 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
+	/// Transfer tokens of `from` by `spender`, consuming the allowance unless `spender` is
+	/// the owner or an operator of `from`.
+	#[transactional]
+	pub fn do_transfer_from(
+		spender: &T::AccountId,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		token_id: TokenIdOf<T>,
+		quantity: TokenIdOf<T>,
+	) -> DispatchResult {
+		if spender != from && !Self::is_approved_for_all(from, spender) {
+			TokenApprovals::<T>::try_mutate_exists(
+				from,
+				(spender.clone(), class_id, token_id),
+				|maybe_approval| -> DispatchResult {
+					let approval: &mut TokenApprovalOf<T> =
+						maybe_approval.as_mut().ok_or(Error::<T>::ApprovalNotFound)?;
+					ensure!(
+						frame_system::Pallet::<T>::block_number() < approval.deadline,
+						Error::<T>::ApprovalExpired
+					);
+					approval.quantity = approval
+						.quantity
+						.checked_sub(&quantity)
+						.ok_or(Error::<T>::InsufficientAllowance)?;
+					if approval.quantity.is_zero() {
+						*maybe_approval = None;
+					}
+					Ok(())
+				},
+			)?;
+		}
+		Self::do_transfer(from, to, class_id, token_id, quantity)
+	}
+
+	pub fn do_approve(
+		owner: &T::AccountId,
+		spender: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		token_id: TokenIdOf<T>,
+		quantity: TokenIdOf<T>,
+		deadline: BlockNumberOf<T>,
+	) -> DispatchResult {
+		ensure!(owner != spender, Error::<T>::ApproveSelf);
+		ensure!(quantity >= One::one(), Error::<T>::InvalidQuantity);
+		ensure!(frame_system::Pallet::<T>::block_number() < deadline, Error::<T>::InvalidDeadline);
+		ensure!(
+			orml_nft::Tokens::<T>::contains_key(class_id, token_id),
+			Error::<T>::TokenIdNotFound
+		);
+
+		TokenApprovals::<T>::insert(
+			owner,
+			(spender.clone(), class_id, token_id),
+			TokenApproval { quantity, deadline },
+		);
+		Self::deposit_event(Event::Approved(
+			owner.clone(),
+			spender.clone(),
+			class_id,
+			token_id,
+			quantity,
+			deadline,
+		));
+		Ok(())
+	}
+
+	pub fn do_revoke(
+		owner: &T::AccountId,
+		spender: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		token_id: TokenIdOf<T>,
+	) -> DispatchResult {
+		let key = (spender.clone(), class_id, token_id);
+		ensure!(TokenApprovals::<T>::contains_key(owner, &key), Error::<T>::ApprovalNotFound);
+		TokenApprovals::<T>::remove(owner, key);
+		Self::deposit_event(Event::RevokedApproval(
+			owner.clone(),
+			spender.clone(),
+			class_id,
+			token_id,
+		));
+		Ok(())
+	}
+
+	pub fn do_approve_for_all(
+		owner: &T::AccountId,
+		operator: &T::AccountId,
+		deadline: BlockNumberOf<T>,
+	) -> DispatchResult {
+		ensure!(owner != operator, Error::<T>::ApproveSelf);
+		ensure!(frame_system::Pallet::<T>::block_number() < deadline, Error::<T>::InvalidDeadline);
+		OperatorApprovals::<T>::insert(owner, operator, deadline);
+		Self::deposit_event(Event::ApprovedForAll(owner.clone(), operator.clone(), deadline));
+		Ok(())
+	}
+
+	pub fn do_revoke_for_all(owner: &T::AccountId, operator: &T::AccountId) -> DispatchResult {
+		ensure!(
+			OperatorApprovals::<T>::contains_key(owner, operator),
+			Error::<T>::ApprovalNotFound
+		);
+		OperatorApprovals::<T>::remove(owner, operator);
+		Self::deposit_event(Event::RevokedApprovalForAll(owner.clone(), operator.clone()));
+		Ok(())
+	}
+
 	// ################## read only ##################
 
+	/// The quantity `spender` can still transfer on behalf of `owner`, zero if expired.
+	pub fn allowance(
+		owner: &T::AccountId,
+		spender: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		token_id: TokenIdOf<T>,
+	) -> TokenIdOf<T> {
+		TokenApprovals::<T>::get(owner, (spender.clone(), class_id, token_id))
+			.filter(|a| frame_system::Pallet::<T>::block_number() < a.deadline)
+			.map(|a| a.quantity)
+			.unwrap_or_default()
+	}
+
+	pub fn is_approved_for_all(owner: &T::AccountId, operator: &T::AccountId) -> bool {
+		OperatorApprovals::<T>::get(owner, operator)
+			.map_or(false, |deadline| frame_system::Pallet::<T>::block_number() < deadline)
+	}
+
+	/// The admins of a class, empty if the class does not exist.
+	pub fn class_admins(class_id: ClassIdOf<T>) -> Vec<T::AccountId> {
+		orml_nft::Pallet::<T>::classes(class_id)
+			.map(|class_info| {
+				pallet_proxy::Pallet::<T>::proxies(&class_info.owner)
+					.0
+					.into_iter()
+					.map(|p| p.delegate)
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	/// The supply cap of a class, with the number of minted tokens and the total issuance.
+	pub fn class_supply(
+		class_id: ClassIdOf<T>,
+	) -> Option<(ClassSupplyCapOf<T>, TokenIdOf<T>, TokenIdOf<T>)> {
+		orml_nft::Pallet::<T>::classes(class_id).map(|class_info| {
+			(
+				ClassSupplyCaps::<T>::get(class_id).unwrap_or_default(),
+				orml_nft::Pallet::<T>::next_token_id(class_id),
+				class_info.total_issuance,
+			)
+		})
+	}
+
 	pub fn contract_tokens(
 		class_id: ClassIdOf<T>,
 		token_id: TokenIdOf<T>,
@@ -1066,6 +1846,23 @@ impl<T: Config> Pallet<T> {
 		Ok(data.properties.0.contains(ClassProperty::Burnable))
 	}
 
+	/// Refund the deposit of a removed class admin to whom paid it.
+	fn refund_class_admin_deposit(
+		owner: &T::AccountId,
+		class_id: ClassIdOf<T>,
+		admin: &T::AccountId,
+	) -> DispatchResult {
+		if let Some((depositor, deposit)) = ClassAdminDeposits::<T>::take(class_id, admin) {
+			<T as Config>::Currency::transfer(
+				owner,
+				&depositor,
+				deposit.saturated_into(),
+				KeepAlive,
+			)?;
+		}
+		Ok(())
+	}
+
 	pub fn add_class_admin_deposit(admin_count: u32) -> Balance {
 		let proxy_deposit_before: Balance = <pallet_proxy::Pallet<T>>::deposit(1).saturated_into();
 		let proxy_deposit_after: Balance =
@@ -1152,7 +1949,7 @@ impl<T: Config> nftmart_traits::NftmartNft<T::AccountId, ClassIdOf<T>, TokenIdOf
 		let token: TokenInfoOf<T> =
 			orml_nft::Tokens::<T>::get(class_id, token_id).ok_or(Error::<T>::TokenIdNotFound)?;
 		let data: TokenData<T::AccountId, T::BlockNumber> = token.data;
-		Ok((data.royalty_beneficiary, data.royalty_rate))
+		Ok((data.royalty_beneficiary, data.royalty_rate.max(Self::class_min_royalties(class_id))))
 	}
 
 	fn create_class(
@@ -1172,6 +1969,7 @@ impl<T: Config> nftmart_traits::NftmartNft<T::AccountId, ClassIdOf<T>, TokenIdOf
 			royalty_rate,
 			properties,
 			category_ids,
+			Default::default(),
 		)
 	}
 
@@ -1208,4 +2006,14 @@ impl<T: Config> nftmart_traits::NftmartNft<T::AccountId, ClassIdOf<T>, TokenIdOf
 	) -> ResultPost<(T::AccountId, T::AccountId, ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>)> {
 		Self::do_proxy_mint(delegate, to, class_id, metadata, quantity, charge_royalty)
 	}
+
+	fn mint_token_deposit(metadata_len: u32) -> Balance {
+		Pallet::<T>::mint_token_deposit(metadata_len)
+	}
+
+	fn is_class_admin(who: &T::AccountId, class_id: ClassIdOf<T>) -> bool {
+		orml_nft::Pallet::<T>::classes(class_id).map_or(false, |class_info| {
+			pallet_proxy::Pallet::<T>::find_proxy(&class_info.owner, who, None).is_ok()
+		})
+	}
 }
@@ -212,13 +212,20 @@ impl nftmart_order::Config for Runtime {
 	type TokenId = nftmart_traits::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = ();
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
+	type OffchainSignature = sp_runtime::MultiSignature;
+	type OffchainPublic = sp_runtime::MultiSigner;
 }
 
 parameter_types! {
 	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
 }
 
+pub type FeeRouter =
+	nftmart_config::FeeRouter<Runtime, Currencies, TreasuryPalletId, GetNativeCurrencyId>;
+
 impl nftmart_config::Config for Runtime {
 	type Event = Event;
 }
@@ -601,3 +601,516 @@ fn destroy_class_should_fail() {
 		assert_ok!(Nftmart::destroy_class(Origin::signed(class_id_account()), CLASS_ID, BOB));
 	});
 }
+
+#[test]
+fn approve_and_transfer_from_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 3, None);
+
+		assert_ok!(Nftmart::approve(Origin::signed(BOB), ALICE, CLASS_ID, TOKEN_ID, 2, 10));
+		let event = Event::Nftmart(crate::Event::Approved(BOB, ALICE, CLASS_ID, TOKEN_ID, 2, 10));
+		assert_eq!(last_event(), event);
+		assert_eq!(Nftmart::allowance(&BOB, &ALICE, CLASS_ID, TOKEN_ID), 2);
+
+		assert_ok!(Nftmart::transfer_from(
+			Origin::signed(ALICE),
+			BOB,
+			ALICE,
+			vec![(CLASS_ID, TOKEN_ID, 1)]
+		));
+		let event =
+			Event::Nftmart(crate::Event::TransferredToken(BOB, ALICE, CLASS_ID, TOKEN_ID, 1));
+		assert_eq!(last_event(), event);
+		assert_eq!(Nftmart::allowance(&BOB, &ALICE, CLASS_ID, TOKEN_ID), 1);
+		assert_eq!(orml_nft::Pallet::<Runtime>::total_count(&BOB, (CLASS_ID, TOKEN_ID)), 2);
+
+		assert_ok!(Nftmart::transfer_from(
+			Origin::signed(ALICE),
+			BOB,
+			ALICE,
+			vec![(CLASS_ID, TOKEN_ID, 1)]
+		));
+		assert_eq!(Nftmart::token_approvals(&BOB, (ALICE, CLASS_ID, TOKEN_ID)), None);
+		assert_eq!(orml_nft::Pallet::<Runtime>::total_count(&ALICE, (CLASS_ID, TOKEN_ID)), 2);
+
+		// operator
+		assert_ok!(Nftmart::approve_for_all(Origin::signed(BOB), ALICE, 10));
+		assert!(Nftmart::is_approved_for_all(&BOB, &ALICE));
+		assert_ok!(Nftmart::transfer_from(
+			Origin::signed(ALICE),
+			BOB,
+			ALICE,
+			vec![(CLASS_ID, TOKEN_ID, 1)]
+		));
+		assert_eq!(orml_nft::Pallet::<Runtime>::total_count(&BOB, (CLASS_ID, TOKEN_ID)), 0);
+		assert_ok!(Nftmart::revoke_for_all(Origin::signed(BOB), ALICE));
+		assert!(!Nftmart::is_approved_for_all(&BOB, &ALICE));
+	});
+}
+
+#[test]
+fn transfer_from_should_fail() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 3, None);
+
+		assert_noop!(
+			Nftmart::approve(Origin::signed(BOB), BOB, CLASS_ID, TOKEN_ID, 2, 10),
+			Error::<Runtime>::ApproveSelf
+		);
+		assert_noop!(
+			Nftmart::approve(Origin::signed(BOB), ALICE, CLASS_ID, TOKEN_ID, 2, 1),
+			Error::<Runtime>::InvalidDeadline
+		);
+		assert_noop!(
+			Nftmart::approve(Origin::signed(BOB), ALICE, CLASS_ID, TOKEN_ID_NOT_EXIST, 2, 10),
+			Error::<Runtime>::TokenIdNotFound
+		);
+		assert_noop!(
+			Nftmart::transfer_from(
+				Origin::signed(ALICE),
+				BOB,
+				ALICE,
+				vec![(CLASS_ID, TOKEN_ID, 1)]
+			),
+			Error::<Runtime>::ApprovalNotFound
+		);
+
+		assert_ok!(Nftmart::approve(Origin::signed(BOB), ALICE, CLASS_ID, TOKEN_ID, 2, 10));
+		assert_noop!(
+			Nftmart::transfer_from(
+				Origin::signed(ALICE),
+				BOB,
+				ALICE,
+				vec![(CLASS_ID, TOKEN_ID, 3)]
+			),
+			Error::<Runtime>::InsufficientAllowance
+		);
+
+		System::set_block_number(10);
+		assert_eq!(Nftmart::allowance(&BOB, &ALICE, CLASS_ID, TOKEN_ID), 0);
+		assert_noop!(
+			Nftmart::transfer_from(
+				Origin::signed(ALICE),
+				BOB,
+				ALICE,
+				vec![(CLASS_ID, TOKEN_ID, 1)]
+			),
+			Error::<Runtime>::ApprovalExpired
+		);
+
+		assert_ok!(Nftmart::revoke(Origin::signed(BOB), ALICE, CLASS_ID, TOKEN_ID));
+		assert_noop!(
+			Nftmart::revoke(Origin::signed(BOB), ALICE, CLASS_ID, TOKEN_ID),
+			Error::<Runtime>::ApprovalNotFound
+		);
+	});
+}
+
+#[test]
+fn supply_cap_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		assert_eq!(Nftmart::class_supply(CLASS_ID), Some((Default::default(), 0, 0)));
+		assert_noop!(
+			Nftmart::reduce_class_supply_cap(Origin::signed(ALICE), CLASS_ID, Some(2), Some(5)),
+			Error::<Runtime>::NoPermission
+		);
+		assert_ok!(Nftmart::reduce_class_supply_cap(
+			Origin::signed(class_id_account()),
+			CLASS_ID,
+			Some(2),
+			Some(5)
+		));
+		let event = Event::Nftmart(crate::Event::UpdatedClassSupplyCap(
+			class_id_account(),
+			CLASS_ID,
+			Some(2),
+			Some(5),
+		));
+		assert_eq!(last_event(), event);
+
+		add_token(BOB, 3, None);
+		{
+			let deposit = Nftmart::mint_token_deposit(METADATA.len() as u32);
+			assert_eq!(Balances::deposit_into_existing(&class_id_account(), deposit).is_ok(), true);
+		}
+		assert_noop!(
+			Nftmart::mint(Origin::signed(class_id_account()), BOB, CLASS_ID, vec![1], 3, None),
+			Error::<Runtime>::MaxSupplyExceeded
+		);
+		assert_noop!(
+			Nftmart::update_token(Origin::signed(BOB), BOB, CLASS_ID, TOKEN_ID, 6, vec![1], None),
+			Error::<Runtime>::MaxSupplyExceeded
+		);
+		add_token(BOB, 2, None);
+		assert_eq!(
+			Nftmart::class_supply(CLASS_ID),
+			Some((ClassSupplyCap { max_tokens: Some(2), max_supply: Some(5) }, 2, 5))
+		);
+
+		{
+			let deposit = Nftmart::mint_token_deposit(METADATA.len() as u32);
+			assert_eq!(Balances::deposit_into_existing(&class_id_account(), deposit).is_ok(), true);
+		}
+		assert_noop!(
+			Nftmart::mint(Origin::signed(class_id_account()), BOB, CLASS_ID, vec![1], 1, None),
+			Error::<Runtime>::MaxTokensExceeded
+		);
+
+		// Burning does not give room to mint again.
+		assert_ok!(Nftmart::burn(Origin::signed(BOB), CLASS_ID, TOKEN_ID, 1));
+		assert_eq!(Nftmart::class_minted(CLASS_ID), Some(5));
+		assert_eq!(
+			Nftmart::class_supply(CLASS_ID),
+			Some((ClassSupplyCap { max_tokens: Some(2), max_supply: Some(5) }, 2, 4))
+		);
+		assert_noop!(
+			Nftmart::update_token(Origin::signed(BOB), BOB, CLASS_ID, TOKEN_ID, 3, vec![1], None),
+			Error::<Runtime>::MaxSupplyExceeded
+		);
+
+		// caps can never be increased nor reduced below the minted amount.
+		assert_noop!(
+			Nftmart::reduce_class_supply_cap(
+				Origin::signed(class_id_account()),
+				CLASS_ID,
+				Some(3),
+				None
+			),
+			Error::<Runtime>::InvalidSupplyCap
+		);
+		assert_noop!(
+			Nftmart::reduce_class_supply_cap(
+				Origin::signed(class_id_account()),
+				CLASS_ID,
+				None,
+				Some(4)
+			),
+			Error::<Runtime>::InvalidSupplyCap
+		);
+		assert_ok!(Nftmart::reduce_class_supply_cap(
+			Origin::signed(class_id_account()),
+			CLASS_ID,
+			None,
+			None
+		));
+		assert_eq!(
+			Nftmart::class_supply_caps(CLASS_ID),
+			Some(ClassSupplyCap { max_tokens: Some(2), max_supply: Some(5) })
+		);
+	});
+}
+
+#[test]
+fn create_class_with_supply_cap_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let cate_id1 = get_cid();
+		add_category();
+		assert_noop!(
+			Nftmart::create_class_with_supply_cap(
+				Origin::signed(ALICE),
+				METADATA.to_vec(),
+				METADATA.to_vec(),
+				METADATA.to_vec(),
+				PerU16::from_percent(5),
+				Properties(ClassProperty::Transferable | ClassProperty::Burnable),
+				vec![cate_id1],
+				Some(0),
+				None
+			),
+			Error::<Runtime>::InvalidSupplyCap
+		);
+		assert_ok!(Nftmart::create_class_with_supply_cap(
+			Origin::signed(ALICE),
+			METADATA.to_vec(),
+			METADATA.to_vec(),
+			METADATA.to_vec(),
+			PerU16::from_percent(5),
+			Properties(ClassProperty::Transferable | ClassProperty::Burnable),
+			vec![cate_id1],
+			Some(1),
+			None
+		));
+		assert_eq!(
+			Nftmart::class_supply_caps(CLASS_ID),
+			Some(ClassSupplyCap { max_tokens: Some(1), max_supply: None })
+		);
+		add_token(BOB, 10, None);
+		assert_noop!(
+			Nftmart::proxy_mint(Origin::signed(ALICE), BOB, CLASS_ID, vec![1], 1, None),
+			Error::<Runtime>::MaxTokensExceeded
+		);
+	});
+}
+
+#[test]
+fn class_admin_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		ensure_bob_balances(ACCURACY * 4);
+		assert_eq!(Nftmart::class_admins(CLASS_ID), vec![ALICE]);
+		assert_noop!(
+			Nftmart::add_class_admin(Origin::signed(BOB), CLASS_ID, BOB),
+			pallet_proxy::Error::<Runtime>::NotProxy
+		);
+
+		let deposit = Nftmart::add_class_admin_deposit(1);
+		let alice_balance = free_balance(&ALICE);
+		let reserved = reserved_balance(&class_id_account());
+		assert_ok!(Nftmart::add_class_admin(Origin::signed(ALICE), CLASS_ID, BOB));
+		let event = Event::Nftmart(crate::Event::AddedClassAdmin(ALICE, CLASS_ID, BOB));
+		assert_eq!(last_event(), event);
+		assert_eq!(Nftmart::class_admins(CLASS_ID), vec![ALICE, BOB]);
+		assert_eq!(free_balance(&ALICE), alice_balance - deposit);
+		assert_eq!(reserved_balance(&class_id_account()), reserved + deposit);
+
+		// the new admin is able to mint.
+		assert_ok!(Nftmart::proxy_mint(Origin::signed(BOB), BOB, CLASS_ID, vec![1], 1, None));
+
+		// The deposit is refunded to the creator who paid it, not to the caller.
+		let (alice_balance, bob_balance) = (free_balance(&ALICE), free_balance(&BOB));
+		assert_ok!(Nftmart::remove_class_admin(Origin::signed(BOB), CLASS_ID, ALICE));
+		let event = Event::Nftmart(crate::Event::RemovedClassAdmin(BOB, CLASS_ID, ALICE));
+		assert_eq!(last_event(), event);
+		assert_eq!(Nftmart::class_admins(CLASS_ID), vec![BOB]);
+		assert_eq!(free_balance(&ALICE), alice_balance + deposit);
+		assert_eq!(free_balance(&BOB), bob_balance);
+		assert_eq!(Nftmart::class_admin_deposits(CLASS_ID, ALICE), None);
+		assert_eq!(Nftmart::class_admin_deposits(CLASS_ID, BOB), Some((ALICE, deposit)));
+		assert_eq!(reserved_balance(&class_id_account()), reserved);
+		assert_noop!(
+			Nftmart::remove_class_admin(Origin::signed(BOB), CLASS_ID, BOB),
+			Error::<Runtime>::LastClassAdmin
+		);
+		assert_noop!(
+			Nftmart::remove_class_admin(Origin::signed(ALICE), CLASS_ID, BOB),
+			pallet_proxy::Error::<Runtime>::NotProxy
+		);
+	});
+}
+
+#[test]
+fn transfer_class_ownership_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		ensure_bob_balances(ACCURACY * 4);
+		assert_ok!(Nftmart::add_class_admin(Origin::signed(ALICE), CLASS_ID, BOB));
+		assert_eq!(Nftmart::class_admins(CLASS_ID), vec![ALICE, BOB]);
+
+		let deposit = Nftmart::add_class_admin_deposit(1);
+		let alice_balance = free_balance(&ALICE);
+		let reserved = reserved_balance(&class_id_account());
+		assert_ok!(Nftmart::transfer_class_ownership(Origin::signed(ALICE), CLASS_ID, BOB));
+		let event = Event::Nftmart(crate::Event::TransferredClassOwnership(ALICE, CLASS_ID, BOB));
+		assert_eq!(last_event(), event);
+		assert_eq!(Nftmart::class_admins(CLASS_ID), vec![BOB]);
+		assert_eq!(free_balance(&ALICE), alice_balance + deposit);
+		assert_eq!(reserved_balance(&class_id_account()), reserved - deposit);
+		// The deposit of ALICE pays for BOB as the only admin.
+		assert_eq!(Nftmart::class_admin_deposits(CLASS_ID, ALICE), None);
+		assert_eq!(Nftmart::class_admin_deposits(CLASS_ID, BOB), Some((ALICE, deposit)));
+
+		assert_noop!(
+			Nftmart::proxy_mint(Origin::signed(ALICE), ALICE, CLASS_ID, vec![1], 1, None),
+			pallet_proxy::Error::<Runtime>::NotProxy
+		);
+		assert_noop!(
+			Nftmart::transfer_class_ownership(Origin::signed(ALICE), CLASS_ID_NOT_EXIST, ALICE),
+			Error::<Runtime>::ClassIdNotFound
+		);
+	});
+}
+
+#[test]
+fn class_min_royalty_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		assert_noop!(
+			Nftmart::set_class_min_royalty(
+				Origin::signed(ALICE),
+				CLASS_ID,
+				PerU16::from_percent(8)
+			),
+			Error::<Runtime>::NoPermission
+		);
+		assert_noop!(
+			Nftmart::set_class_min_royalty(
+				Origin::signed(class_id_account()),
+				CLASS_ID,
+				PerU16::from_percent(100)
+			),
+			Error::<Runtime>::RoyaltyRateTooHigh
+		);
+		assert_ok!(Nftmart::set_class_min_royalty(
+			Origin::signed(class_id_account()),
+			CLASS_ID,
+			PerU16::from_percent(8)
+		));
+		assert_eq!(
+			last_event(),
+			Event::Nftmart(crate::Event::UpdatedClassMinRoyalty(
+				class_id_account(),
+				CLASS_ID,
+				PerU16::from_percent(8)
+			))
+		);
+
+		// the class royalty 5% is lower than the minimum.
+		add_token(BOB, 1, None);
+		assert_eq!(
+			orml_nft::Tokens::<Runtime>::get(CLASS_ID, TOKEN_ID).unwrap().data.royalty_rate,
+			PerU16::from_percent(8)
+		);
+		{
+			let deposit = Nftmart::mint_token_deposit(METADATA.len() as u32);
+			assert_eq!(Balances::deposit_into_existing(&class_id_account(), deposit).is_ok(), true);
+		}
+		assert_noop!(
+			Nftmart::mint(
+				Origin::signed(class_id_account()),
+				BOB,
+				CLASS_ID,
+				METADATA.to_vec(),
+				1,
+				Some(PerU16::from_percent(7))
+			),
+			Error::<Runtime>::RoyaltyRateTooLow
+		);
+
+		assert_noop!(
+			Nftmart::update_token_royalty(
+				Origin::signed(BOB),
+				CLASS_ID,
+				TOKEN_ID,
+				Some(PerU16::zero())
+			),
+			Error::<Runtime>::RoyaltyRateTooLow
+		);
+		assert_noop!(
+			Nftmart::update_token(
+				Origin::signed(BOB),
+				BOB,
+				CLASS_ID,
+				TOKEN_ID,
+				1,
+				vec![1],
+				Some(PerU16::zero())
+			),
+			Error::<Runtime>::RoyaltyRateTooLow
+		);
+		assert_ok!(Nftmart::update_token_royalty(Origin::signed(BOB), CLASS_ID, TOKEN_ID, None));
+		assert_eq!(
+			orml_nft::Tokens::<Runtime>::get(CLASS_ID, TOKEN_ID).unwrap().data.royalty_rate,
+			PerU16::from_percent(8)
+		);
+
+		// a raised minimum applies to the tokens already minted.
+		assert_ok!(Nftmart::set_class_min_royalty(
+			Origin::signed(class_id_account()),
+			CLASS_ID,
+			PerU16::from_percent(10)
+		));
+		assert_eq!(
+			<Nftmart as NftmartNft<_, _, _>>::token_charged_royalty(CLASS_ID, TOKEN_ID),
+			Ok((BOB, PerU16::from_percent(10)))
+		);
+	});
+}
+
+#[test]
+fn genesis_holders_should_work() {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	nftmart_config::GenesisConfig::<Runtime>::default()
+		.assimilate_storage(&mut t)
+		.unwrap();
+	crate::GenesisConfig::<Runtime> {
+		classes: vec![ClassConfig {
+			class_id: CLASS_ID,
+			class_metadata: "class".into(),
+			category_ids: vec![0],
+			name: "name".into(),
+			description: "description".into(),
+			royalty_rate: PerU16::from_percent(5),
+			properties: (ClassProperty::Transferable | ClassProperty::Burnable).bits(),
+			admins: vec![ALICE],
+			tokens: vec![TokenConfig {
+				token_id: TOKEN_ID,
+				token_metadata: "token".into(),
+				royalty_rate: PerU16::from_percent(5),
+				token_owner: ALICE,
+				token_creator: ALICE,
+				royalty_beneficiary: ALICE,
+				quantity: 10,
+				holders: vec![(BOB, 4)],
+			}],
+			max_tokens: None,
+			max_supply: None,
+			min_royalty: PerU16::from_percent(2),
+		}],
+		token_approvals: vec![(
+			BOB,
+			ALICE,
+			CLASS_ID,
+			TOKEN_ID,
+			TokenApproval { quantity: 2, deadline: 10 },
+		)],
+		operator_approvals: vec![(ALICE, BOB, 20)],
+		_phantom: Default::default(),
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	sp_io::TestExternalities::new(t).execute_with(|| {
+		assert_eq!(orml_nft::Tokens::<Runtime>::get(CLASS_ID, TOKEN_ID).unwrap().quantity, 10);
+		assert_eq!(OrmlNFT::total_count(&ALICE, (CLASS_ID, TOKEN_ID)), 6);
+		assert_eq!(OrmlNFT::total_count(&BOB, (CLASS_ID, TOKEN_ID)), 4);
+		assert_eq!(
+			pallet_proxy::Pallet::<Runtime>::proxies(class_id_account()).0[0].delegate,
+			ALICE
+		);
+		assert_eq!(Nftmart::class_min_royalties(CLASS_ID), PerU16::from_percent(2));
+		assert_eq!(Nftmart::allowance(&BOB, &ALICE, CLASS_ID, TOKEN_ID), 2);
+		assert!(Nftmart::is_approved_for_all(&ALICE, &BOB));
+	});
+}
+
+#[test]
+#[should_panic(expected = "Class has a larger supply than its max_supply")]
+fn genesis_should_reject_supply_over_the_cap() {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	nftmart_config::GenesisConfig::<Runtime>::default()
+		.assimilate_storage(&mut t)
+		.unwrap();
+	crate::GenesisConfig::<Runtime> {
+		classes: vec![ClassConfig {
+			class_id: CLASS_ID,
+			class_metadata: "class".into(),
+			category_ids: vec![0],
+			name: "name".into(),
+			description: "description".into(),
+			royalty_rate: PerU16::from_percent(5),
+			properties: (ClassProperty::Transferable | ClassProperty::Burnable).bits(),
+			admins: vec![ALICE],
+			tokens: vec![TokenConfig {
+				token_id: TOKEN_ID,
+				token_metadata: "token".into(),
+				royalty_rate: PerU16::from_percent(5),
+				token_owner: ALICE,
+				token_creator: ALICE,
+				royalty_beneficiary: ALICE,
+				quantity: 10,
+				holders: vec![(BOB, 4)],
+			}],
+			max_tokens: None,
+			max_supply: Some(5),
+			min_royalty: PerU16::from_percent(0),
+		}],
+		token_approvals: vec![],
+		operator_approvals: vec![],
+		_phantom: Default::default(),
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+}
@@ -2,7 +2,8 @@
 
 use frame_support::{
 	pallet_prelude::*,
-	traits::{Currency, ReservableCurrency},
+	storage::{with_transaction, TransactionOutcome},
+	traits::{Currency, ExistenceRequirement, ReservableCurrency},
 	transactional,
 };
 use frame_system::pallet_prelude::*;
@@ -12,16 +13,28 @@ use scale_info::TypeInfo;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 use sp_runtime::{
-	traits::{AtLeast32BitUnsigned, StaticLookup},
-	PerU16, RuntimeDebug, SaturatedConversion,
+	traits::{
+		AtLeast32BitUnsigned, BadOrigin, CheckedAdd, Hash, IdentifyAccount, StaticLookup, Verify,
+		Zero,
+	},
+	ArithmeticError, PerU16, RuntimeDebug, SaturatedConversion,
 };
-use sp_std::vec::Vec;
+use sp_std::{vec, vec::Vec};
 
 mod mock;
 mod tests;
 
 pub use module::*;
 
+pub const MAX_TAKE_ORDERS: u32 = 50;
+pub const REAP_WEIGHT: Weight = 100_000;
+/// Orders and offers expire this many blocks after the upgrade adding the deadlines of orders
+/// and the sweeper, so that the orders already past their deadlines can still be taken and their
+/// owners can remove them before anyone reaps them.
+pub const SWEEP_GRACE_PERIOD: BlockNumber = 7 * time::DAYS;
+/// Prefixed to the vouchers signed for lazy minting.
+pub const VOUCHER_SIGNING_CONTEXT: &[u8] = b"nftmart/voucher";
+
 #[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct Order<CurrencyId, BlockNumber, ClassId, TokenId> {
@@ -63,9 +76,59 @@ pub struct Offer<CurrencyId, BlockNumber, ClassId, TokenId> {
 	pub commission_rate: PerU16,
 }
 
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Swap<AccountId, CurrencyId, BlockNumber, ClassId, TokenId> {
+	/// currency ID.
+	#[codec(compact)]
+	pub currency_id: CurrencyId,
+	/// The balances to create a swap
+	#[codec(compact)]
+	pub deposit: Balance,
+	/// The amount of `currency_id` paid by the maker in addition to `offered_items`.
+	#[codec(compact)]
+	pub price: Balance,
+	/// This swap will be invalidated after `deadline` block number.
+	#[codec(compact)]
+	pub deadline: BlockNumber,
+	/// nft list offered by the maker
+	pub offered_items: Vec<OrderItem<ClassId, TokenId>>,
+	/// nft list requested from the taker
+	pub requested_items: Vec<OrderItem<ClassId, TokenId>>,
+	/// Only this account can take the swap, `None` for anyone.
+	pub taker: Option<AccountId>,
+}
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct LazyMintVoucher<AccountId, CurrencyId, BlockNumber, ClassId, TokenId> {
+	/// The class admin who signed this voucher.
+	pub signer: AccountId,
+	/// class id
+	#[codec(compact)]
+	pub class_id: ClassId,
+	/// external metadata of the token to be minted.
+	pub metadata: NFTMetadata,
+	/// currency ID.
+	#[codec(compact)]
+	pub currency_id: CurrencyId,
+	/// Price of each unit of the token.
+	#[codec(compact)]
+	pub price: Balance,
+	/// Charge royalty, `None` for the class's royalty rate.
+	pub royalty_rate: Option<PerU16>,
+	/// The maximum quantity can be redeemed from this voucher.
+	#[codec(compact)]
+	pub max_quantity: TokenId,
+	/// This voucher will be invalidated after `deadline` block number.
+	#[codec(compact)]
+	pub deadline: BlockNumber,
+}
+
 #[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
 enum Releases {
 	V1_0_0,
+	V2_0_0,
 }
 
 impl Default for Releases {
@@ -84,6 +147,21 @@ pub type CurrencyIdOf<T> = <<T as module::Config>::MultiCurrency as MultiCurrenc
 pub type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
 pub type OrderOf<T> = Order<CurrencyIdOf<T>, BlockNumberOf<T>, ClassIdOf<T>, TokenIdOf<T>>;
 pub type OfferOf<T> = Offer<CurrencyIdOf<T>, BlockNumberOf<T>, ClassIdOf<T>, TokenIdOf<T>>;
+pub type SwapOf<T> = Swap<
+	<T as frame_system::Config>::AccountId,
+	CurrencyIdOf<T>,
+	BlockNumberOf<T>,
+	ClassIdOf<T>,
+	TokenIdOf<T>,
+>;
+pub type QuoteOf<T> = Quote<<T as frame_system::Config>::AccountId, CurrencyIdOf<T>>;
+pub type LazyMintVoucherOf<T> = LazyMintVoucher<
+	<T as frame_system::Config>::AccountId,
+	CurrencyIdOf<T>,
+	BlockNumberOf<T>,
+	ClassIdOf<T>,
+	TokenIdOf<T>,
+>;
 
 #[frame_support::pallet]
 pub mod module {
@@ -123,9 +201,27 @@ pub mod module {
 		/// Extra Configurations
 		type ExtraConfig: NftmartConfig<Self::AccountId, BlockNumberFor<Self>>;
 
+		/// Registry of commission agents
+		type CommissionAgent: NftmartCommissionAgent<
+			Self::AccountId,
+			<Self::MultiCurrency as MultiCurrency<Self::AccountId>>::CurrencyId,
+		>;
+
+		/// Router of trading fees
+		type FeeRouter: NftmartFeeRouter<
+			Self::AccountId,
+			<Self::MultiCurrency as MultiCurrency<Self::AccountId>>::CurrencyId,
+		>;
+
 		/// The treasury's pallet id, used for deriving its sovereign account ID.
 		#[pallet::constant]
 		type TreasuryPalletId: Get<frame_support::PalletId>;
+
+		/// Off-chain signature type of lazy-mint vouchers.
+		type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+
+		/// Off-chain public key which identifies the signer of lazy-mint vouchers.
+		type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
 	}
 
 	#[pallet::error]
@@ -146,6 +242,32 @@ pub mod module {
 		TakeOwnOffer,
 		InvalidCommissionRate,
 		SenderTakeCommission,
+		/// the signature of voucher is invalid
+		InvalidVoucherSignature,
+		/// voucher has expired
+		VoucherExpired,
+		/// the remaining quantity of voucher is not enough
+		VoucherExhausted,
+		/// cannot redeem one's own voucher
+		RedeemOwnVoucher,
+		/// the price of the order is higher than the price limit
+		OrderPriceTooHigh,
+		/// take an empty list of orders
+		EmptyOrderList,
+		/// take too many orders at once
+		TooManyOrders,
+		/// the order or offer has not expired yet
+		NotExpired,
+		/// swap not found
+		SwapNotFound,
+		/// cannot take one's own swap
+		TakeOwnSwap,
+		/// the swap can only be taken by the specified taker
+		NotSwapTaker,
+		/// either side of the swap has no nft
+		EmptySwapItems,
+		/// the price of the offer is lower than the price limit
+		OfferPriceTooLow,
 	}
 
 	#[pallet::event]
@@ -174,6 +296,35 @@ pub mod module {
 		),
 		/// CreatedOffer \[who, order_id\]
 		CreatedOffer(T::AccountId, GlobalId),
+		/// RedeemedVoucher \[purchaser, signer, voucher_hash, class_id, token_id, quantity\]
+		RedeemedVoucher(
+			T::AccountId,
+			T::AccountId,
+			T::Hash,
+			ClassIdOf<T>,
+			TokenIdOf<T>,
+			TokenIdOf<T>,
+		),
+		/// CancelledVoucher \[signer, voucher_hash\]
+		CancelledVoucher(T::AccountId, T::Hash),
+		/// TakenOrders \[purchaser, taken_order_ids, skipped_order_ids\]
+		TakenOrders(T::AccountId, Vec<GlobalId>, Vec<GlobalId>),
+		/// UpdatedOrder \[who, order_id\]
+		UpdatedOrder(T::AccountId, GlobalId),
+		/// UpdatedOffer \[who, offer_id\]
+		UpdatedOffer(T::AccountId, GlobalId),
+		/// ReapedOrder \[order_owner, order_id, reaper_and_bounty\]
+		ReapedOrder(T::AccountId, GlobalId, Option<(T::AccountId, Balance)>),
+		/// ReapedOffer \[offer_owner, offer_id, reaper_and_bounty\]
+		ReapedOffer(T::AccountId, GlobalId, Option<(T::AccountId, Balance)>),
+		/// CreatedSwap \[who, swap_id\]
+		CreatedSwap(T::AccountId, GlobalId),
+		/// RemovedSwap \[who, swap_id\]
+		RemovedSwap(T::AccountId, GlobalId),
+		/// TakenSwap \[taker, maker, swap_id\]
+		TakenSwap(T::AccountId, T::AccountId, GlobalId),
+		/// RoyaltyPaid \[beneficiary, currency_id, amount\]
+		RoyaltyPaid(T::AccountId, CurrencyIdOf<T>, Balance),
 	}
 
 	#[pallet::pallet]
@@ -182,9 +333,24 @@ pub mod module {
 	#[pallet::hooks]
 	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
 		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::<T>::get() == Releases::V1_0_0 {
+				StorageVersion::<T>::put(Releases::V2_0_0);
+				let now = frame_system::Pallet::<T>::block_number();
+				SweepStartBlock::<T>::put(now.saturating_add(SWEEP_GRACE_PERIOD.into()));
+				return T::DbWeight::get().reads_writes(1, 2)
+			}
 			0
 		}
 
+		fn on_idle(n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			if n < Self::sweep_start_block() {
+				return T::DbWeight::get().reads(1)
+			}
+			// Share the remaining weight between orders and offers.
+			let used = Self::sweep_expired_orders(remaining_weight / 2);
+			used.saturating_add(Self::sweep_expired_offers(remaining_weight.saturating_sub(used)))
+		}
+
 		fn integrity_test() {}
 	}
 
@@ -203,7 +369,7 @@ pub mod module {
 	#[pallet::genesis_build]
 	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
 		fn build(&self) {
-			<StorageVersion<T>>::put(Releases::default());
+			<StorageVersion<T>>::put(Releases::V2_0_0);
 		}
 	}
 
@@ -222,12 +388,37 @@ pub mod module {
 	pub type Orders<T: Config> =
 		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, GlobalId, OrderOf<T>>;
 
+	/// The block from which expired orders and offers are swept by `on_idle`.
+	#[pallet::storage]
+	#[pallet::getter(fn sweep_start_block)]
+	pub type SweepStartBlock<T: Config> = StorageValue<_, BlockNumberOf<T>, ValueQuery>;
+
+	/// The raw key of the last order visited by the sweeper of expired orders.
+	#[pallet::storage]
+	pub(super) type OrderReapCursor<T: Config> = StorageValue<_, Vec<u8>>;
+
+	/// The raw key of the last offer visited by the sweeper of expired offers.
+	#[pallet::storage]
+	pub(super) type OfferReapCursor<T: Config> = StorageValue<_, Vec<u8>>;
+
 	/// Index/store offers by account as primary key and order id as secondary key.
 	#[pallet::storage]
 	#[pallet::getter(fn offers)]
 	pub type Offers<T: Config> =
 		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, GlobalId, OfferOf<T>>;
 
+	/// Index/store swaps by maker as primary key and swap id as secondary key.
+	#[pallet::storage]
+	#[pallet::getter(fn swaps)]
+	pub type Swaps<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, GlobalId, SwapOf<T>>;
+
+	/// The quantity consumed from each lazy-mint voucher, keyed by the hash of the voucher.
+	#[pallet::storage]
+	#[pallet::getter(fn voucher_consumed)]
+	pub type VoucherConsumed<T: Config> =
+		StorageMap<_, Twox64Concat, T::Hash, TokenIdOf<T>, ValueQuery>;
+
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Create an order.
@@ -300,40 +491,78 @@ pub mod module {
 			let purchaser = ensure_signed(origin)?;
 			let order_owner = T::Lookup::lookup(order_owner)?;
 
-			// Simplify the logic, to make life easier.
-			ensure!(purchaser != order_owner, Error::<T>::TakeOwnOrder);
-
 			if let Some(c) = &commission_agent {
 				ensure!(&purchaser != c, Error::<T>::SenderTakeCommission);
 			}
 
-			let order: OrderOf<T> = Self::delete_order(&order_owner, order_id)?;
-
-			// Skip check deadline of orders
-			// Orders are supposed to be valid until taken or cancelled
-
-			let (items, commission_agent) = to_item_vec!(order, commission_agent);
-			let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
-			swap_assets::<T::MultiCurrency, T::NFT, _, _, _, _>(
+			Self::do_take_order(
 				&purchaser,
-				&order_owner,
-				order.currency_id,
-				order.price,
-				&items,
-				&Self::treasury_account_id(),
-				T::ExtraConfig::get_platform_fee_rate(),
-				&beneficiary,
-				royalty_rate,
-				&commission_agent,
-			)?;
-
-			Self::deposit_event(Event::TakenOrder(
-				purchaser,
 				order_owner,
 				order_id,
+				None,
 				commission_agent,
 				commission_data,
-			));
+			)?;
+			Ok(().into())
+		}
+
+		/// Take many NFT orders in one call.
+		///
+		/// - `orders`: a list of `(order_owner, order_id, max_price)`
+		/// - `skip_failed`: skip the orders which cannot be taken, otherwise all orders must be
+		///   taken or none of them.
+		#[pallet::weight(100_000u64.saturating_mul(orders.len() as u64))]
+		#[transactional]
+		pub fn take_orders(
+			origin: OriginFor<T>,
+			orders: Vec<(<T::Lookup as StaticLookup>::Source, GlobalId, Balance)>,
+			skip_failed: bool,
+			commission_agent: Option<T::AccountId>,
+			commission_data: Option<Vec<u8>>,
+		) -> DispatchResultWithPostInfo {
+			let purchaser = ensure_signed(origin)?;
+			ensure!(!orders.is_empty(), Error::<T>::EmptyOrderList);
+			ensure!(orders.len() as u32 <= MAX_TAKE_ORDERS, Error::<T>::TooManyOrders);
+
+			if let Some(c) = &commission_agent {
+				ensure!(&purchaser != c, Error::<T>::SenderTakeCommission);
+			}
+
+			let mut taken = Vec::with_capacity(orders.len());
+			let mut skipped = Vec::new();
+			for (order_owner, order_id, max_price) in orders {
+				let order_owner = T::Lookup::lookup(order_owner)?;
+				let take = || {
+					Self::do_take_order(
+						&purchaser,
+						order_owner,
+						order_id,
+						Some(max_price),
+						commission_agent.clone(),
+						commission_data.clone(),
+					)
+				};
+				if skip_failed {
+					// Roll back whatever the failed order has changed.
+					let result = with_transaction(|| {
+						let result = take();
+						if result.is_ok() {
+							TransactionOutcome::Commit(result)
+						} else {
+							TransactionOutcome::Rollback(result)
+						}
+					});
+					if result.is_err() {
+						skipped.push(order_id);
+						continue
+					}
+				} else {
+					take()?;
+				}
+				taken.push(order_id);
+			}
+
+			Self::deposit_event(Event::TakenOrders(purchaser, taken, skipped));
 			Ok(().into())
 		}
 
@@ -367,6 +596,113 @@ pub mod module {
 			Ok(().into())
 		}
 
+		/// Remove an expired order by anyone, who will get a bounty from the order deposit.
+		///
+		/// - `order_owner`: order owner
+		/// - `order_id`: order id
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn reap_expired_order(
+			origin: OriginFor<T>,
+			order_owner: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] order_id: GlobalId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let order_owner = T::Lookup::lookup(order_owner)?;
+			Self::reap_order(&order_owner, order_id, Some(who))?;
+			Ok(().into())
+		}
+
+		/// Remove an expired offer by anyone, who will get a bounty from the reserved balances.
+		///
+		/// - `offer_owner`: offer owner
+		/// - `offer_id`: offer id
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn reap_expired_offer(
+			origin: OriginFor<T>,
+			offer_owner: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] offer_id: GlobalId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let offer_owner = T::Lookup::lookup(offer_owner)?;
+			Self::reap_offer(&offer_owner, offer_id, Some(who))?;
+			Ok(().into())
+		}
+
+		/// Update the price, deadline and commission rate of an order by order owner.
+		///
+		/// - `order_id`: order id
+		/// - `new_price`: nfts' new price
+		/// - `new_deadline`: new deadline
+		/// - `new_commission_rate`: new commission rate
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn update_order(
+			origin: OriginFor<T>,
+			#[pallet::compact] order_id: GlobalId,
+			#[pallet::compact] new_price: Balance,
+			#[pallet::compact] new_deadline: BlockNumberOf<T>,
+			#[pallet::compact] new_commission_rate: PerU16,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_valid_update(new_deadline, new_commission_rate)?;
+
+			Orders::<T>::try_mutate(&who, order_id, |maybe_order| -> DispatchResult {
+				let order = maybe_order.as_mut().ok_or(Error::<T>::OrderNotFound)?;
+				order.price = new_price;
+				order.deadline = new_deadline;
+				order.commission_rate = new_commission_rate;
+				Ok(())
+			})?;
+			Self::deposit_event(Event::UpdatedOrder(who, order_id));
+			Ok(().into())
+		}
+
+		/// Update the price, deadline and commission rate of an offer by offer owner.
+		///
+		/// The reserved balances are adjusted to the new price.
+		///
+		/// - `offer_id`: offer id
+		/// - `new_price`: nfts' new price
+		/// - `new_deadline`: new deadline
+		/// - `new_commission_rate`: new commission rate
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn update_offer(
+			origin: OriginFor<T>,
+			#[pallet::compact] offer_id: GlobalId,
+			#[pallet::compact] new_price: Balance,
+			#[pallet::compact] new_deadline: BlockNumberOf<T>,
+			#[pallet::compact] new_commission_rate: PerU16,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_valid_update(new_deadline, new_commission_rate)?;
+
+			Offers::<T>::try_mutate(&who, offer_id, |maybe_offer| -> DispatchResult {
+				let offer = maybe_offer.as_mut().ok_or(Error::<T>::OfferNotFound)?;
+				if new_price > offer.price {
+					T::MultiCurrency::reserve(
+						offer.currency_id,
+						&who,
+						new_price.saturating_sub(offer.price),
+					)?;
+				} else {
+					let _remain: Balance = T::MultiCurrency::unreserve(
+						offer.currency_id,
+						&who,
+						offer.price.saturating_sub(new_price),
+					);
+				}
+				offer.price = new_price;
+				offer.deadline = new_deadline;
+				offer.commission_rate = new_commission_rate;
+				Ok(())
+			})?;
+			Self::deposit_event(Event::UpdatedOffer(who, offer_id));
+			Ok(().into())
+		}
+
 		#[pallet::weight(100_000)]
 		#[transactional]
 		pub fn submit_offer(
@@ -423,50 +759,533 @@ pub mod module {
 		) -> DispatchResultWithPostInfo {
 			let token_owner = ensure_signed(origin)?;
 			let offer_owner = T::Lookup::lookup(offer_owner)?;
+			Self::do_take_offer(
+				token_owner,
+				offer_owner,
+				offer_id,
+				None,
+				commission_agent,
+				commission_data,
+			)?;
+			Ok(().into())
+		}
 
-			// Simplify the logic, to make life easier.
-			ensure!(offer_owner != token_owner, Error::<T>::TakeOwnOffer);
+		/// Redeem a lazy-mint voucher signed by a class admin. The token is minted to the
+		/// signer and then sold to the origin, who also pays the mint deposit.
+		///
+		/// - `voucher`: the voucher signed by `voucher.signer`
+		/// - `signature`: the signature of the [`Pallet::voucher_payload`] of the voucher
+		/// - `quantity`: the quantity to be minted and purchased
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn redeem_voucher(
+			origin: OriginFor<T>,
+			voucher: LazyMintVoucherOf<T>,
+			signature: T::OffchainSignature,
+			#[pallet::compact] quantity: TokenIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let purchaser = ensure_signed(origin)?;
+			let signer = voucher.signer.clone();
+			ensure!(purchaser != signer, Error::<T>::RedeemOwnVoucher);
+			ensure!(
+				signature.verify(&Self::voucher_payload(&voucher)[..], &signer),
+				Error::<T>::InvalidVoucherSignature
+			);
+			ensure!(
+				frame_system::Pallet::<T>::block_number() < voucher.deadline,
+				Error::<T>::VoucherExpired
+			);
 
-			if let Some(c) = &commission_agent {
-				ensure!(&token_owner != c, Error::<T>::SenderTakeCommission);
-			}
+			let voucher_hash = T::Hashing::hash_of(&voucher);
+			VoucherConsumed::<T>::try_mutate(voucher_hash, |consumed| -> DispatchResult {
+				*consumed = consumed
+					.checked_add(&quantity)
+					.filter(|c| c <= &voucher.max_quantity)
+					.ok_or(Error::<T>::VoucherExhausted)?;
+				Ok(())
+			})?;
+
+			// The purchaser pays the mint deposit, which is refunded to whom burns the token.
+			let deposit = T::NFT::mint_token_deposit(voucher.metadata.len().saturated_into());
+			<T as Config>::Currency::transfer(
+				&purchaser,
+				&signer,
+				deposit.saturated_into(),
+				ExistenceRequirement::KeepAlive,
+			)?;
+			let (_, _, class_id, token_id, quantity) = T::NFT::proxy_mint(
+				&signer,
+				&signer,
+				voucher.class_id,
+				voucher.metadata,
+				quantity,
+				voucher.royalty_rate,
+			)?;
+
+			let price = voucher
+				.price
+				.checked_mul(quantity.saturated_into())
+				.ok_or(ArithmeticError::Overflow)?;
+			let items = vec![(class_id, token_id, quantity)];
+			let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
+			let royalty = swap_assets::<
+				T::MultiCurrency,
+				T::NFT,
+				T::CommissionAgent,
+				T::FeeRouter,
+				_,
+				_,
+				_,
+				_,
+			>(
+				&purchaser,
+				&signer,
+				voucher.currency_id,
+				price,
+				&items,
+				T::ExtraConfig::get_platform_fee_rate(),
+				&beneficiary,
+				royalty_rate,
+				&None,
+			)?;
+			Self::note_royalty(&beneficiary, voucher.currency_id, royalty);
 
-			let offer: OfferOf<T> = Self::delete_offer(&offer_owner, offer_id)?;
+			Self::deposit_event(Event::RedeemedVoucher(
+				purchaser,
+				signer,
+				voucher_hash,
+				class_id,
+				token_id,
+				quantity,
+			));
+			Ok(().into())
+		}
+
+		/// Cancel a lazy-mint voucher by its signer, no more token can be redeemed from it.
+		///
+		/// - `voucher`: the voucher to be cancelled
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn cancel_voucher(
+			origin: OriginFor<T>,
+			voucher: LazyMintVoucherOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(who == voucher.signer, BadOrigin);
+			let voucher_hash = T::Hashing::hash_of(&voucher);
+			VoucherConsumed::<T>::insert(voucher_hash, voucher.max_quantity);
+			Self::deposit_event(Event::CancelledVoucher(who, voucher_hash));
+			Ok(().into())
+		}
+
+		/// Create a swap, which trades `offered_items` plus `price` for `requested_items`.
+		///
+		/// - `currency_id`: currency id
+		/// - `deposit`: The balances to create a swap
+		/// - `price`: The amount of `currency_id` paid by the maker, can be zero
+		/// - `deadline`: deadline
+		/// - `offered_items`: nft list of the maker
+		/// - `requested_items`: nft list requested from the taker
+		/// - `taker`: Only this account can take the swap, `None` for anyone
+		#[pallet::weight(100_000)]
+		#[transactional]
+		#[allow(clippy::too_many_arguments)]
+		pub fn submit_swap(
+			origin: OriginFor<T>,
+			#[pallet::compact] currency_id: CurrencyIdOf<T>,
+			#[pallet::compact] deposit: Balance,
+			#[pallet::compact] price: Balance,
+			#[pallet::compact] deadline: BlockNumberOf<T>,
+			offered_items: Vec<(ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>)>,
+			requested_items: Vec<(ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>)>,
+			taker: Option<T::AccountId>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				!offered_items.is_empty() && !requested_items.is_empty(),
+				Error::<T>::EmptySwapItems
+			);
+			ensure!(taker.as_ref() != Some(&who), Error::<T>::TakeOwnSwap);
+
+			ensure!(
+				deposit >= T::ExtraConfig::get_min_order_deposit(),
+				Error::<T>::SubmitWithInvalidDeposit
+			);
+			<T as Config>::Currency::reserve(&who, deposit.saturated_into())?;
 
-			// Check deadline of this offer
 			ensure!(
-				frame_system::Pallet::<T>::block_number() < offer.deadline,
+				frame_system::Pallet::<T>::block_number() < deadline,
+				Error::<T>::SubmitWithInvalidDeadline
+			);
+
+			// Reserve balances of `currency_id` for the taker to accept this swap.
+			T::MultiCurrency::reserve(currency_id, &who, price)?;
+
+			let mut swap = Swap {
+				currency_id,
+				deposit,
+				price,
+				deadline,
+				offered_items: Vec::with_capacity(offered_items.len()),
+				requested_items: Vec::with_capacity(requested_items.len()),
+				taker,
+			};
+
+			ensure_one_royalty!(requested_items);
+			reserve_and_push_tokens::<_, _, _, T::NFT>(
+				Some(&who),
+				&offered_items,
+				&mut swap.offered_items,
+			)?;
+			reserve_and_push_tokens::<_, _, _, T::NFT>(
+				None,
+				&requested_items,
+				&mut swap.requested_items,
+			)?;
+
+			let swap_id = T::ExtraConfig::get_then_inc_id()?;
+			Swaps::<T>::insert(&who, swap_id, swap);
+			Self::deposit_event(Event::CreatedSwap(who, swap_id));
+			Ok(().into())
+		}
+
+		/// Take a swap.
+		///
+		/// The price paid by the maker is charged with the platform fee and the royalty of
+		/// `requested_items`. No royalty is charged for `offered_items` which are paid by nfts.
+		///
+		/// - `swap_id`: swap id
+		/// - `swap_owner`: the maker
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn take_swap(
+			origin: OriginFor<T>,
+			#[pallet::compact] swap_id: GlobalId,
+			swap_owner: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResultWithPostInfo {
+			let taker = ensure_signed(origin)?;
+			let swap_owner = T::Lookup::lookup(swap_owner)?;
+			ensure!(taker != swap_owner, Error::<T>::TakeOwnSwap);
+
+			let swap: SwapOf<T> = Self::delete_swap(&swap_owner, swap_id)?;
+			if let Some(t) = &swap.taker {
+				ensure!(t == &taker, Error::<T>::NotSwapTaker);
+			}
+
+			// Check deadline of this swap
+			ensure!(
+				frame_system::Pallet::<T>::block_number() < swap.deadline,
 				Error::<T>::TakeExpiredOrderOrOffer
 			);
 
-			let (items, commission_agent) = to_item_vec!(offer, commission_agent);
-			let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
-			swap_assets::<T::MultiCurrency, T::NFT, _, _, _, _>(
-				&offer_owner,
-				&token_owner,
-				offer.currency_id,
-				offer.price,
-				&items,
-				&Self::treasury_account_id(),
+			// Transferring the requested tokens to the maker fails if the taker doesn't own them.
+			let requested_items: Vec<_> = swap
+				.requested_items
+				.iter()
+				.map(|item| (item.class_id, item.token_id, item.quantity))
+				.collect();
+
+			let (beneficiary, royalty_rate) = ensure_one_royalty!(requested_items);
+			let royalty = swap_assets::<
+				T::MultiCurrency,
+				T::NFT,
+				T::CommissionAgent,
+				T::FeeRouter,
+				_,
+				_,
+				_,
+				_,
+			>(
+				&swap_owner,
+				&taker,
+				swap.currency_id,
+				swap.price,
+				&requested_items,
 				T::ExtraConfig::get_platform_fee_rate(),
 				&beneficiary,
 				royalty_rate,
-				&commission_agent,
+				&None,
 			)?;
+			Self::note_royalty(&beneficiary, swap.currency_id, royalty);
+			for item in &swap.offered_items {
+				T::NFT::transfer(&swap_owner, &taker, item.class_id, item.token_id, item.quantity)?;
+			}
 
-			Self::deposit_event(Event::TakenOffer(
+			Self::notify(Notification::SwapTaken {
+				swap_id,
+				owner: swap_owner.clone(),
+				taker: taker.clone(),
+			});
+			Self::deposit_event(Event::TakenSwap(taker, swap_owner, swap_id));
+			Ok(().into())
+		}
+
+		/// remove a swap by the maker.
+		///
+		/// - `swap_id`: swap id
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn remove_swap(
+			origin: OriginFor<T>,
+			#[pallet::compact] swap_id: GlobalId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::delete_swap(&who, swap_id)?;
+			Self::deposit_event(Event::RemovedSwap(who, swap_id));
+			Ok(().into())
+		}
+
+		/// Take a NFT order, which fails if the order has been updated to a price higher than
+		/// `max_price`.
+		///
+		/// - `order_id`: order id
+		/// - `order_owner`: token owner
+		/// - `max_price`: the highest price the purchaser accepts
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn take_order_with_max_price(
+			origin: OriginFor<T>,
+			#[pallet::compact] order_id: GlobalId,
+			order_owner: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] max_price: Balance,
+			commission_agent: Option<T::AccountId>,
+			commission_data: Option<Vec<u8>>,
+		) -> DispatchResultWithPostInfo {
+			let purchaser = ensure_signed(origin)?;
+			let order_owner = T::Lookup::lookup(order_owner)?;
+
+			if let Some(c) = &commission_agent {
+				ensure!(&purchaser != c, Error::<T>::SenderTakeCommission);
+			}
+
+			Self::do_take_order(
+				&purchaser,
+				order_owner,
+				order_id,
+				Some(max_price),
+				commission_agent,
+				commission_data,
+			)?;
+			Ok(().into())
+		}
+
+		/// Take a NFT offer, which fails if the offer has been updated to a price lower than
+		/// `min_price`.
+		///
+		/// - `offer_id`: offer id
+		/// - `offer_owner`: token owner
+		/// - `min_price`: the lowest price the token owner accepts
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn take_offer_with_min_price(
+			origin: OriginFor<T>,
+			#[pallet::compact] offer_id: GlobalId,
+			offer_owner: <T::Lookup as StaticLookup>::Source,
+			#[pallet::compact] min_price: Balance,
+			commission_agent: Option<T::AccountId>,
+			commission_data: Option<Vec<u8>>,
+		) -> DispatchResultWithPostInfo {
+			let token_owner = ensure_signed(origin)?;
+			let offer_owner = T::Lookup::lookup(offer_owner)?;
+			Self::do_take_offer(
 				token_owner,
 				offer_owner,
 				offer_id,
+				Some(min_price),
 				commission_agent,
 				commission_data,
-			));
+			)?;
 			Ok(().into())
 		}
 	}
 }
 
 impl<T: Config> Pallet<T> {
+	/// The SCALE encoded `(VOUCHER_SIGNING_CONTEXT, genesis_hash, voucher)` signed by the signer
+	/// of a voucher, so a signature is valid for vouchers of this chain only.
+	pub fn voucher_payload(voucher: &LazyMintVoucherOf<T>) -> Vec<u8> {
+		let genesis_hash = frame_system::Pallet::<T>::block_hash(BlockNumberOf::<T>::zero());
+		(VOUCHER_SIGNING_CONTEXT, genesis_hash, voucher).encode()
+	}
+
+	/// Quote taking the order `order_id` of `order_owner` through `commission_agent`.
+	pub fn quote_take_order(
+		order_owner: &T::AccountId,
+		order_id: GlobalId,
+		commission_agent: Option<T::AccountId>,
+	) -> Result<QuoteOf<T>, DispatchError> {
+		let order: OrderOf<T> =
+			Self::orders(order_owner, order_id).ok_or(Error::<T>::OrderNotFound)?;
+		let (items, commission_agent) = to_item_vec!(order, commission_agent);
+		let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
+		Ok(quote_sale(
+			order.currency_id,
+			order.price,
+			T::ExtraConfig::get_platform_fee_rate(),
+			beneficiary,
+			royalty_rate,
+			commission_agent,
+		))
+	}
+
+	/// Quote submitting an order of `items` at `price`, the deposit is the minimum order deposit.
+	pub fn quote_submit_order(
+		currency_id: CurrencyIdOf<T>,
+		price: Balance,
+		items: Vec<(ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>)>,
+		commission_rate: PerU16,
+		commission_agent: Option<T::AccountId>,
+	) -> Result<QuoteOf<T>, DispatchError> {
+		ensure!(
+			commission_rate <= T::ExtraConfig::get_max_commission_reward_rate(),
+			Error::<T>::InvalidCommissionRate
+		);
+		let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
+		let commission_agent = commission_agent.map(|ca| {
+			let paid = T::CommissionAgent::is_registered_agent(&ca) && !commission_rate.is_zero();
+			(paid, ca, commission_rate)
+		});
+		let mut quote = quote_sale(
+			currency_id,
+			price,
+			T::ExtraConfig::get_platform_fee_rate(),
+			beneficiary,
+			royalty_rate,
+			commission_agent,
+		);
+		quote.deposit = T::ExtraConfig::get_min_order_deposit();
+		Ok(quote)
+	}
+
+	fn note_royalty(beneficiary: &T::AccountId, currency_id: CurrencyIdOf<T>, royalty: Balance) {
+		if !royalty.is_zero() {
+			Self::deposit_event(Event::RoyaltyPaid(beneficiary.clone(), currency_id, royalty));
+		}
+	}
+
+	fn ensure_valid_update(deadline: BlockNumberOf<T>, commission_rate: PerU16) -> DispatchResult {
+		ensure!(
+			frame_system::Pallet::<T>::block_number() < deadline,
+			Error::<T>::SubmitWithInvalidDeadline
+		);
+		ensure!(
+			commission_rate <= T::ExtraConfig::get_max_commission_reward_rate(),
+			Error::<T>::InvalidCommissionRate
+		);
+		Ok(())
+	}
+
+	fn do_take_order(
+		purchaser: &T::AccountId,
+		order_owner: T::AccountId,
+		order_id: GlobalId,
+		max_price: Option<Balance>,
+		commission_agent: Option<T::AccountId>,
+		commission_data: Option<Vec<u8>>,
+	) -> DispatchResult {
+		// Simplify the logic, to make life easier.
+		ensure!(purchaser != &order_owner, Error::<T>::TakeOwnOrder);
+
+		let order: OrderOf<T> = Self::delete_order(&order_owner, order_id)?;
+		if let Some(max_price) = max_price {
+			ensure!(order.price <= max_price, Error::<T>::OrderPriceTooHigh);
+		}
+
+		// Check deadline of this order
+		ensure!(!Self::is_expired(order.deadline), Error::<T>::TakeExpiredOrderOrOffer);
+
+		let (items, commission_agent) = to_item_vec!(order, commission_agent);
+		let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
+		let royalty =
+			swap_assets::<T::MultiCurrency, T::NFT, T::CommissionAgent, T::FeeRouter, _, _, _, _>(
+				purchaser,
+				&order_owner,
+				order.currency_id,
+				order.price,
+				&items,
+				T::ExtraConfig::get_platform_fee_rate(),
+				&beneficiary,
+				royalty_rate,
+				&commission_agent,
+			)?;
+		Self::note_royalty(&beneficiary, order.currency_id, royalty);
+
+		Self::notify(Notification::OrderTaken {
+			order_id,
+			owner: order_owner.clone(),
+			taker: purchaser.clone(),
+			currency_id: order.currency_id,
+			price: order.price,
+		});
+		Self::deposit_event(Event::TakenOrder(
+			purchaser.clone(),
+			order_owner,
+			order_id,
+			commission_agent,
+			commission_data,
+		));
+		Ok(())
+	}
+
+	fn do_take_offer(
+		token_owner: T::AccountId,
+		offer_owner: T::AccountId,
+		offer_id: GlobalId,
+		min_price: Option<Balance>,
+		commission_agent: Option<T::AccountId>,
+		commission_data: Option<Vec<u8>>,
+	) -> DispatchResult {
+		// Simplify the logic, to make life easier.
+		ensure!(offer_owner != token_owner, Error::<T>::TakeOwnOffer);
+
+		if let Some(c) = &commission_agent {
+			ensure!(&token_owner != c, Error::<T>::SenderTakeCommission);
+		}
+
+		let offer: OfferOf<T> = Self::delete_offer(&offer_owner, offer_id)?;
+		if let Some(min_price) = min_price {
+			ensure!(offer.price >= min_price, Error::<T>::OfferPriceTooLow);
+		}
+
+		// Check deadline of this offer
+		ensure!(
+			frame_system::Pallet::<T>::block_number() < offer.deadline,
+			Error::<T>::TakeExpiredOrderOrOffer
+		);
+
+		let (items, commission_agent) = to_item_vec!(offer, commission_agent);
+		let (beneficiary, royalty_rate) = ensure_one_royalty!(items);
+		let royalty =
+			swap_assets::<T::MultiCurrency, T::NFT, T::CommissionAgent, T::FeeRouter, _, _, _, _>(
+				&offer_owner,
+				&token_owner,
+				offer.currency_id,
+				offer.price,
+				&items,
+				T::ExtraConfig::get_platform_fee_rate(),
+				&beneficiary,
+				royalty_rate,
+				&commission_agent,
+			)?;
+		Self::note_royalty(&beneficiary, offer.currency_id, royalty);
+
+		Self::notify(Notification::OfferTaken {
+			offer_id,
+			owner: offer_owner.clone(),
+			taker: token_owner.clone(),
+			currency_id: offer.currency_id,
+			price: offer.price,
+		});
+		Self::deposit_event(Event::TakenOffer(
+			token_owner,
+			offer_owner,
+			offer_id,
+			commission_agent,
+			commission_data,
+		));
+		Ok(())
+	}
+
 	fn delete_order(who: &T::AccountId, order_id: GlobalId) -> Result<OrderOf<T>, DispatchError> {
 		Orders::<T>::try_mutate_exists(who, order_id, |maybe_order| {
 			let order: OrderOf<T> = maybe_order.as_mut().ok_or(Error::<T>::OrderNotFound)?.clone();
@@ -496,6 +1315,160 @@ impl<T: Config> Pallet<T> {
 		})
 	}
 
+	fn delete_swap(who: &T::AccountId, swap_id: GlobalId) -> Result<SwapOf<T>, DispatchError> {
+		Swaps::<T>::try_mutate_exists(who, swap_id, |maybe_swap| {
+			let swap: SwapOf<T> = maybe_swap.as_mut().ok_or(Error::<T>::SwapNotFound)?.clone();
+
+			let _remain: BalanceOf<T> =
+				<T as Config>::Currency::unreserve(who, swap.deposit.saturated_into());
+			let _remain: Balance = T::MultiCurrency::unreserve(swap.currency_id, who, swap.price);
+
+			for item in &swap.offered_items {
+				T::NFT::unreserve_tokens(who, item.class_id, item.token_id, item.quantity)?;
+			}
+
+			*maybe_swap = None;
+			Ok(swap)
+		})
+	}
+
+	/// Record a notification going with the next event, see [`nftmart_traits::notification`].
+	fn notify(notification: Notification<T::AccountId, CurrencyIdOf<T>>) {
+		notification::note_notification(
+			frame_system::Pallet::<T>::block_number().saturated_into(),
+			frame_system::Pallet::<T>::parent_hash().as_ref(),
+			frame_system::Pallet::<T>::event_count(),
+			&notification,
+		);
+	}
+
+	/// Orders and offers expire from the start block of the sweeper, the orders already past
+	/// their deadlines at the upgrade adding it can still be taken until then.
+	fn is_expired(deadline: BlockNumberOf<T>) -> bool {
+		let now = frame_system::Pallet::<T>::block_number();
+		now >= deadline && now >= Self::sweep_start_block()
+	}
+
+	/// Remove an expired order, `reaper` gets a bounty from the order deposit.
+	#[transactional]
+	fn reap_order(
+		who: &T::AccountId,
+		order_id: GlobalId,
+		reaper: Option<T::AccountId>,
+	) -> DispatchResult {
+		let order: OrderOf<T> = Self::orders(who, order_id).ok_or(Error::<T>::OrderNotFound)?;
+		ensure!(Self::is_expired(order.deadline), Error::<T>::NotExpired);
+		Self::delete_order(who, order_id)?;
+
+		let reaper = match reaper {
+			Some(reaper) => {
+				let bounty = T::ExtraConfig::get_reaper_bounty_rate().mul_floor(order.deposit);
+				<T as Config>::Currency::transfer(
+					who,
+					&reaper,
+					bounty.saturated_into(),
+					ExistenceRequirement::AllowDeath,
+				)?;
+				Some((reaper, bounty))
+			},
+			None => None,
+		};
+
+		Self::notify(Notification::OrderExpired { order_id, owner: who.clone() });
+		Self::deposit_event(Event::ReapedOrder(who.clone(), order_id, reaper));
+		Ok(())
+	}
+
+	/// Remove an expired offer, `reaper` gets a bounty from the reserved price, in the currency of
+	/// the offer.
+	#[transactional]
+	fn reap_offer(
+		who: &T::AccountId,
+		offer_id: GlobalId,
+		reaper: Option<T::AccountId>,
+	) -> DispatchResult {
+		let offer: OfferOf<T> = Self::offers(who, offer_id).ok_or(Error::<T>::OfferNotFound)?;
+		ensure!(Self::is_expired(offer.deadline), Error::<T>::NotExpired);
+		Self::delete_offer(who, offer_id)?;
+
+		let reaper = match reaper {
+			Some(reaper) => {
+				let bounty = T::ExtraConfig::get_reaper_bounty_rate().mul_floor(offer.price);
+				T::MultiCurrency::transfer(offer.currency_id, who, &reaper, bounty)?;
+				Some((reaper, bounty))
+			},
+			None => None,
+		};
+
+		Self::notify(Notification::OfferExpired { offer_id, owner: who.clone() });
+		Self::deposit_event(Event::ReapedOffer(who.clone(), offer_id, reaper));
+		Ok(())
+	}
+
+	/// Remove expired orders within `max_weight`, starting from where the last sweep stopped.
+	fn sweep_expired_orders(max_weight: Weight) -> Weight {
+		let mut used = T::DbWeight::get().reads_writes(1, 1);
+		if used > max_weight {
+			return 0
+		}
+		let cursor = OrderReapCursor::<T>::get();
+		let mut iter = match cursor.clone() {
+			Some(cursor) => Orders::<T>::iter_from(cursor),
+			None => Orders::<T>::iter(),
+		};
+		let next_cursor = loop {
+			if used.saturating_add(REAP_WEIGHT) > max_weight {
+				break Some(iter.last_raw_key().to_vec())
+			}
+			match iter.next() {
+				Some((who, order_id, order)) => {
+					used = used.saturating_add(T::DbWeight::get().reads(1));
+					if Self::is_expired(order.deadline) {
+						used = used.saturating_add(REAP_WEIGHT);
+						let _ = Self::reap_order(&who, order_id, None);
+					}
+				},
+				None => break None,
+			}
+		};
+		if next_cursor != cursor {
+			OrderReapCursor::<T>::set(next_cursor);
+		}
+		used
+	}
+
+	/// Remove expired offers within `max_weight`, starting from where the last sweep stopped.
+	fn sweep_expired_offers(max_weight: Weight) -> Weight {
+		let mut used = T::DbWeight::get().reads_writes(1, 1);
+		if used > max_weight {
+			return 0
+		}
+		let cursor = OfferReapCursor::<T>::get();
+		let mut iter = match cursor.clone() {
+			Some(cursor) => Offers::<T>::iter_from(cursor),
+			None => Offers::<T>::iter(),
+		};
+		let next_cursor = loop {
+			if used.saturating_add(REAP_WEIGHT) > max_weight {
+				break Some(iter.last_raw_key().to_vec())
+			}
+			match iter.next() {
+				Some((who, offer_id, offer)) => {
+					used = used.saturating_add(T::DbWeight::get().reads(1));
+					if Self::is_expired(offer.deadline) {
+						used = used.saturating_add(REAP_WEIGHT);
+						let _ = Self::reap_offer(&who, offer_id, None);
+					}
+				},
+				None => break None,
+			}
+		};
+		if next_cursor != cursor {
+			OfferReapCursor::<T>::set(next_cursor);
+		}
+		used
+	}
+
 	pub fn treasury_account_id() -> T::AccountId {
 		sp_runtime::traits::AccountIdConversion::<T::AccountId>::into_account(
 			&T::TreasuryPalletId::get(),
@@ -15,7 +15,7 @@ use sp_core::{crypto::AccountId32, H256};
 use sp_runtime::{
 	testing::Header,
 	traits::{AccountIdConversion, BlakeTwo256, IdentityLookup},
-	PerU16,
+	MultiSignature, MultiSigner, PerU16,
 };
 
 parameter_types! {
@@ -209,10 +209,28 @@ impl nftmart_config::Config for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const MaxAgentNameLength: u32 = 32;
+	pub const MaxAgentMetadataLength: u32 = 256;
+}
+
+impl nftmart_agent::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type CurrencyId = CurrencyId;
+	type ExtraConfig = NftmartConf;
+	type Slash = ();
+	type MaxNameLength = MaxAgentNameLength;
+	type MaxMetadataLength = MaxAgentMetadataLength;
+}
+
 parameter_types! {
 	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
 }
 
+pub type FeeRouter =
+	nftmart_config::FeeRouter<Runtime, Currencies, TreasuryPalletId, GetNativeCurrencyId>;
+
 impl nftmart_order::Config for Runtime {
 	type Event = Event;
 	type MultiCurrency = Currencies;
@@ -221,7 +239,11 @@ impl nftmart_order::Config for Runtime {
 	type TokenId = nftmart_traits::constants_types::TokenId;
 	type NFT = Nftmart;
 	type ExtraConfig = NftmartConf;
+	type CommissionAgent = NftmartAgent;
+	type FeeRouter = FeeRouter;
 	type TreasuryPalletId = TreasuryPalletId;
+	type OffchainSignature = MultiSignature;
+	type OffchainPublic = MultiSigner;
 }
 
 use frame_system::Call as SystemCall;
@@ -245,6 +267,7 @@ construct_runtime!(
 		NftmartConf: nftmart_config::{Pallet, Call, Event<T>},
 		Nftmart: nftmart_nft::{Pallet, Call, Event<T>},
 		NftmartOrder: nftmart_order::{Pallet, Call, Event<T>},
+		NftmartAgent: nftmart_agent::{Pallet, Call, Storage, Event<T>},
 	}
 );
 
@@ -273,9 +296,13 @@ impl ExtBuilder {
 		.assimilate_storage(&mut t)
 		.unwrap();
 
-		nftmart_config::GenesisConfig::<Runtime> { min_order_deposit: 10, ..Default::default() }
-			.assimilate_storage(&mut t)
-			.unwrap();
+		nftmart_config::GenesisConfig::<Runtime> {
+			min_order_deposit: 10,
+			min_commission_agent_deposit: 10,
+			..Default::default()
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
 
 		let mut ext = sp_io::TestExternalities::new(t);
 		ext.execute_with(|| {
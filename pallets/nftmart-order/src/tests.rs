@@ -1,14 +1,35 @@
 #![cfg(test)]
 
-use super::NATIVE_CURRENCY_ID;
+use super::{FeeDistribution, Quote, NATIVE_CURRENCY_ID};
 use crate::mock::{
 	add_class, add_token, all_offers, all_orders, all_tokens_by, current_gid, ensure_account,
-	free_balance, last_event, Event, ExtBuilder, Nftmart, NftmartOrder, Origin, ALICE, BOB,
-	CHARLIE, CLASS_ID0, DAVE, TOKEN_ID0, TOKEN_ID1,
+	free_balance, last_event, AccountId, Balances, Currencies, Event, ExtBuilder, FeeRouter,
+	Nftmart, NftmartAgent, NftmartConf, NftmartOrder, Origin, Runtime, System, ALICE, BOB, CHARLIE,
+	CLASS_ID0, DAVE, TOKEN_ID0, TOKEN_ID1,
+};
+use codec::Encode;
+use frame_support::{assert_noop, assert_ok, traits::Hooks, weights::Weight};
+use nftmart_traits::{
+	notification::{self, WEBHOOK_KEY_TYPE},
+	webhook::{hex, SIGNATURE_HEADER, SIGNER_HEADER},
+	Notification,
 };
-use frame_support::assert_ok;
 use orml_nft::AccountToken;
-use sp_runtime::PerU16;
+use orml_traits::MultiCurrency;
+use parking_lot::RwLock;
+use sp_core::{
+	ed25519,
+	offchain::{
+		testing::{OffchainState, PendingRequest, TestOffchainExt},
+		OffchainDbExt, OffchainWorkerExt, StorageKind,
+	},
+	sr25519, Pair, H256,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
+use sp_runtime::{
+	traits::{BadOrigin, Hash},
+	MultiSignature, PerThing, PerU16,
+};
 
 #[test]
 fn submit_order_should_work() {
@@ -78,6 +99,7 @@ fn take_order_should_work() {
 			PerU16::from_percent(10),
 		));
 		assert_eq!(1, all_orders().len());
+		assert_ok!(NftmartAgent::register(Origin::signed(DAVE), 10, vec![], vec![]));
 		assert_ok!(NftmartOrder::take_order(
 			Origin::signed(ALICE),
 			order_id,
@@ -85,6 +107,10 @@ fn take_order_should_work() {
 			Some(DAVE),
 			Some(vec![1, 2, 4])
 		));
+		assert_eq!(
+			NftmartAgent::stats(DAVE, NATIVE_CURRENCY_ID),
+			nftmart_agent::AgentStats { volume: 100, commission: 8, trades: 1 }
+		);
 		assert_eq!(0, all_orders().len());
 
 		assert_eq!(98, free_balance(&ALICE));
@@ -105,6 +131,83 @@ fn take_order_should_work() {
 				Some(vec![1, 2, 4]),
 			)),
 		);
+		assert!(System::events().iter().any(|r| r.event ==
+			Event::NftmartOrder(crate::Event::RoyaltyPaid(CHARLIE, NATIVE_CURRENCY_ID, 20))));
+	});
+}
+
+#[test]
+fn quote_take_order_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, Some(PerU16::from_percent(20)));
+		add_token(BOB, 40, Some(PerU16::zero()));
+		assert_ok!(Nftmart::update_token_royalty_beneficiary(
+			Origin::signed(BOB),
+			CLASS_ID0,
+			TOKEN_ID0,
+			CHARLIE
+		));
+		let items = vec![(CLASS_ID0, TOKEN_ID0, 10), (CLASS_ID0, TOKEN_ID1, 20)];
+
+		let quote = NftmartOrder::quote_submit_order(
+			NATIVE_CURRENCY_ID,
+			100,
+			items.clone(),
+			PerU16::from_percent(10),
+			None,
+		)
+		.unwrap();
+		assert_eq!(quote.deposit, NftmartConf::min_order_deposit());
+		assert_eq!((quote.platform_fee, quote.royalty), (1, 20));
+		assert_eq!(quote.royalty_beneficiary, Some(CHARLIE));
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			items,
+			PerU16::from_percent(10),
+		));
+
+		// The commission is not paid to an unregistered agent.
+		let quote = NftmartOrder::quote_take_order(&BOB, order_id, Some(DAVE)).unwrap();
+		assert_eq!((quote.commission, quote.commission_paid), (8, false));
+
+		assert_ok!(NftmartAgent::register(Origin::signed(DAVE), 10, vec![], vec![]));
+		let quote = NftmartOrder::quote_take_order(&BOB, order_id, Some(DAVE)).unwrap();
+		assert_eq!(
+			quote,
+			Quote {
+				currency_id: NATIVE_CURRENCY_ID,
+				price: 100,
+				platform_fee: 1,
+				royalty: 20,
+				royalty_beneficiary: Some(CHARLIE),
+				commission: 8,
+				commission_agent: Some(DAVE),
+				commission_paid: true,
+				deposit: 0,
+				tx_fee: 0,
+			}
+		);
+
+		assert_ok!(NftmartOrder::take_order(
+			Origin::signed(ALICE),
+			order_id,
+			BOB,
+			Some(DAVE),
+			None
+		));
+		assert_eq!(100 + 20, free_balance(&CHARLIE));
+		assert_eq!(200 - 1 - 20 - 8, free_balance(&BOB));
+		assert_noop!(
+			NftmartOrder::quote_take_order(&BOB, order_id, None),
+			crate::Error::<Runtime>::OrderNotFound
+		);
 	});
 }
 
@@ -180,3 +283,950 @@ fn take_offer_should_work() {
 		ensure_account(&CHARLIE, CLASS_ID0, TOKEN_ID1, 0, 20);
 	});
 }
+
+#[test]
+fn redeem_voucher_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let pair = sr25519::Pair::from_seed(&[9u8; 32]);
+		let signer: AccountId = pair.public().into();
+		assert_ok!(Balances::transfer(Origin::signed(DAVE), signer.clone(), 100));
+		assert_ok!(NftmartConf::add_whitelist(Origin::root(), signer.clone()));
+		add_class(signer.clone());
+
+		let voucher = crate::LazyMintVoucher {
+			signer: signer.clone(),
+			class_id: CLASS_ID0,
+			metadata: vec![1],
+			currency_id: NATIVE_CURRENCY_ID,
+			price: 10,
+			royalty_rate: None,
+			max_quantity: 3,
+			deadline: 10,
+		};
+		let signature = MultiSignature::from(pair.sign(&NftmartOrder::voucher_payload(&voucher)));
+		let voucher_hash = <Runtime as frame_system::Config>::Hashing::hash_of(&voucher);
+
+		let deposit = Nftmart::mint_token_deposit(1);
+		let alice_balance = free_balance(&ALICE);
+		let signer_balance = free_balance(&signer);
+		assert_ok!(NftmartOrder::redeem_voucher(
+			Origin::signed(ALICE),
+			voucher.clone(),
+			signature.clone(),
+			2
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::RedeemedVoucher(
+				ALICE,
+				signer.clone(),
+				voucher_hash,
+				CLASS_ID0,
+				TOKEN_ID0,
+				2,
+			)),
+		);
+		ensure_account(&ALICE, CLASS_ID0, TOKEN_ID0, 0, 2);
+		ensure_account(&signer, CLASS_ID0, TOKEN_ID0, 0, 0);
+		assert_eq!(NftmartOrder::voucher_consumed(voucher_hash), 2);
+		// price 20, platform fee 1, the royalty goes to the signer who is the creator.
+		assert_eq!(free_balance(&ALICE), alice_balance - 20 - deposit);
+		assert_eq!(free_balance(&signer), signer_balance + 19);
+
+		assert_noop!(
+			NftmartOrder::redeem_voucher(
+				Origin::signed(BOB),
+				voucher.clone(),
+				signature.clone(),
+				2
+			),
+			crate::Error::<Runtime>::VoucherExhausted
+		);
+		assert_noop!(
+			NftmartOrder::redeem_voucher(
+				Origin::signed(signer.clone()),
+				voucher.clone(),
+				signature.clone(),
+				1
+			),
+			crate::Error::<Runtime>::RedeemOwnVoucher
+		);
+		assert_noop!(
+			NftmartOrder::redeem_voucher(
+				Origin::signed(BOB),
+				crate::LazyMintVoucher { price: 1, ..voucher.clone() },
+				signature.clone(),
+				1
+			),
+			crate::Error::<Runtime>::InvalidVoucherSignature
+		);
+
+		assert_ok!(NftmartOrder::redeem_voucher(
+			Origin::signed(BOB),
+			voucher.clone(),
+			signature.clone(),
+			1
+		));
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID1, 0, 1);
+		assert_eq!(NftmartOrder::voucher_consumed(voucher_hash), 3);
+	});
+}
+
+#[test]
+fn redeem_voucher_should_fail() {
+	ExtBuilder::default().build().execute_with(|| {
+		let pair = sr25519::Pair::from_seed(&[9u8; 32]);
+		let signer: AccountId = pair.public().into();
+		assert_ok!(Balances::transfer(Origin::signed(DAVE), signer.clone(), 100));
+		assert_ok!(NftmartConf::add_whitelist(Origin::root(), signer.clone()));
+		add_class(signer.clone());
+
+		let voucher = crate::LazyMintVoucher {
+			signer: signer.clone(),
+			class_id: CLASS_ID0,
+			metadata: vec![1],
+			currency_id: NATIVE_CURRENCY_ID,
+			price: 10,
+			royalty_rate: None,
+			max_quantity: 3,
+			deadline: 10,
+		};
+		let signature = MultiSignature::from(pair.sign(&NftmartOrder::voucher_payload(&voucher)));
+
+		// A signature of the voucher alone is valid for any chain, so it's rejected.
+		assert_noop!(
+			NftmartOrder::redeem_voucher(
+				Origin::signed(ALICE),
+				voucher.clone(),
+				MultiSignature::from(pair.sign(&voucher.encode())),
+				1
+			),
+			crate::Error::<Runtime>::InvalidVoucherSignature
+		);
+
+		assert_noop!(NftmartOrder::cancel_voucher(Origin::signed(BOB), voucher.clone()), BadOrigin);
+		assert_ok!(NftmartOrder::cancel_voucher(Origin::signed(signer.clone()), voucher.clone()));
+		assert_noop!(
+			NftmartOrder::redeem_voucher(
+				Origin::signed(ALICE),
+				voucher.clone(),
+				signature.clone(),
+				1
+			),
+			crate::Error::<Runtime>::VoucherExhausted
+		);
+
+		let voucher = crate::LazyMintVoucher { deadline: 5, ..voucher };
+		let signature = MultiSignature::from(pair.sign(&NftmartOrder::voucher_payload(&voucher)));
+		System::set_block_number(5);
+		assert_noop!(
+			NftmartOrder::redeem_voucher(Origin::signed(ALICE), voucher, signature, 1),
+			crate::Error::<Runtime>::VoucherExpired
+		);
+	});
+}
+
+#[test]
+fn take_orders_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, Some(PerU16::zero()));
+		add_token(CHARLIE, 20, Some(PerU16::zero()));
+
+		let order_id0 = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			30,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		let order_id1 = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(CHARLIE),
+			NATIVE_CURRENCY_ID,
+			10,
+			50,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID1, 10)],
+			PerU16::zero(),
+		));
+
+		assert_noop!(
+			NftmartOrder::take_orders(Origin::signed(DAVE), vec![], false, None, None),
+			crate::Error::<Runtime>::EmptyOrderList
+		);
+		assert_noop!(
+			NftmartOrder::take_orders(
+				Origin::signed(DAVE),
+				vec![(BOB, order_id0, 30); crate::MAX_TAKE_ORDERS as usize + 1],
+				false,
+				None,
+				None
+			),
+			crate::Error::<Runtime>::TooManyOrders
+		);
+		// all or nothing
+		assert_noop!(
+			NftmartOrder::take_orders(
+				Origin::signed(DAVE),
+				vec![(BOB, order_id0, 30), (CHARLIE, order_id1, 40)],
+				false,
+				None,
+				None
+			),
+			crate::Error::<Runtime>::OrderPriceTooHigh
+		);
+
+		// skip failed
+		let dave_balance = free_balance(&DAVE);
+		assert_ok!(NftmartOrder::take_orders(
+			Origin::signed(DAVE),
+			vec![(BOB, order_id0, 30), (CHARLIE, order_id1, 40)],
+			true,
+			None,
+			None
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::TakenOrders(DAVE, vec![order_id0], vec![order_id1])),
+		);
+		assert_eq!(1, all_orders().len());
+		assert_eq!(free_balance(&DAVE), dave_balance - 30);
+		ensure_account(&DAVE, CLASS_ID0, TOKEN_ID0, 0, 10);
+		ensure_account(&CHARLIE, CLASS_ID0, TOKEN_ID1, 10, 10);
+
+		assert_ok!(NftmartOrder::take_orders(
+			Origin::signed(DAVE),
+			vec![(CHARLIE, order_id1, 50), (BOB, order_id0, 30)],
+			true,
+			None,
+			None
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::TakenOrders(DAVE, vec![order_id1], vec![order_id0])),
+		);
+		assert!(System::events().iter().any(|r| r.event ==
+			Event::NftmartOrder(crate::Event::TakenOrder(
+				DAVE, CHARLIE, order_id1, None, None
+			))));
+		assert_eq!(0, all_orders().len());
+		assert_eq!(free_balance(&DAVE), dave_balance - 80);
+		ensure_account(&DAVE, CLASS_ID0, TOKEN_ID1, 0, 10);
+	});
+}
+
+#[test]
+fn update_order_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		assert_noop!(
+			NftmartOrder::update_order(Origin::signed(ALICE), order_id, 50, 5, PerU16::zero()),
+			crate::Error::<Runtime>::OrderNotFound
+		);
+		assert_noop!(
+			NftmartOrder::update_order(Origin::signed(BOB), order_id, 50, 1, PerU16::zero()),
+			crate::Error::<Runtime>::SubmitWithInvalidDeadline
+		);
+
+		let bob_balance = free_balance(&BOB);
+		assert_ok!(NftmartOrder::update_order(
+			Origin::signed(BOB),
+			order_id,
+			50,
+			5,
+			PerU16::from_percent(5)
+		));
+		assert_eq!(last_event(), Event::NftmartOrder(crate::Event::UpdatedOrder(BOB, order_id)),);
+		let order = NftmartOrder::orders(BOB, order_id).unwrap();
+		assert_eq!(order.price, 50);
+		assert_eq!(order.deadline, 5);
+		assert_eq!(order.commission_rate, PerU16::from_percent(5));
+		assert_eq!(order.deposit, 10);
+		assert_eq!(free_balance(&BOB), bob_balance);
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID0, 10, 10);
+		assert_eq!(current_gid(), order_id + 1);
+
+		// A purchaser expecting the previous price isn't charged more.
+		assert_ok!(NftmartOrder::update_order(
+			Origin::signed(BOB),
+			order_id,
+			150,
+			5,
+			PerU16::zero()
+		));
+		assert_noop!(
+			NftmartOrder::take_order_with_max_price(
+				Origin::signed(ALICE),
+				order_id,
+				BOB,
+				50,
+				None,
+				None
+			),
+			crate::Error::<Runtime>::OrderPriceTooHigh
+		);
+		assert_ok!(NftmartOrder::take_order_with_max_price(
+			Origin::signed(DAVE),
+			order_id,
+			BOB,
+			150,
+			None,
+			None
+		));
+		assert_eq!(0, all_orders().len());
+	});
+}
+
+#[test]
+fn update_offer_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let offer_id = current_gid();
+		assert_ok!(NftmartOrder::submit_offer(
+			Origin::signed(CHARLIE),
+			NATIVE_CURRENCY_ID,
+			60,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		assert_eq!(40, free_balance(&CHARLIE));
+
+		assert_noop!(
+			NftmartOrder::update_offer(Origin::signed(CHARLIE), offer_id, 200, 5, PerU16::zero()),
+			pallet_balances::Error::<Runtime>::InsufficientBalance
+		);
+		assert_ok!(NftmartOrder::update_offer(
+			Origin::signed(CHARLIE),
+			offer_id,
+			90,
+			5,
+			PerU16::zero()
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::UpdatedOffer(CHARLIE, offer_id)),
+		);
+		assert_eq!(10, free_balance(&CHARLIE));
+
+		assert_ok!(NftmartOrder::update_offer(
+			Origin::signed(CHARLIE),
+			offer_id,
+			20,
+			5,
+			PerU16::zero()
+		));
+		assert_eq!(80, free_balance(&CHARLIE));
+		let offer = NftmartOrder::offers(CHARLIE, offer_id).unwrap();
+		assert_eq!(offer.price, 20);
+		assert_eq!(offer.deadline, 5);
+
+		// A seller expecting the previous price isn't paid less.
+		assert_noop!(
+			NftmartOrder::take_offer_with_min_price(
+				Origin::signed(BOB),
+				offer_id,
+				CHARLIE,
+				90,
+				None,
+				None
+			),
+			crate::Error::<Runtime>::OfferPriceTooLow
+		);
+		assert_ok!(NftmartOrder::take_offer_with_min_price(
+			Origin::signed(BOB),
+			offer_id,
+			CHARLIE,
+			20,
+			None,
+			None
+		));
+		assert_eq!(0, all_offers().len());
+	});
+}
+
+#[test]
+fn reap_expired_order_and_offer_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		let offer_id = current_gid();
+		assert_ok!(NftmartOrder::submit_offer(
+			Origin::signed(CHARLIE),
+			NATIVE_CURRENCY_ID,
+			60,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		assert_noop!(
+			NftmartOrder::reap_expired_order(Origin::signed(ALICE), BOB, order_id),
+			crate::Error::<Runtime>::NotExpired
+		);
+		assert_noop!(
+			NftmartOrder::reap_expired_offer(Origin::signed(DAVE), CHARLIE, offer_id),
+			crate::Error::<Runtime>::NotExpired
+		);
+
+		System::set_block_number(2);
+		assert_noop!(
+			NftmartOrder::take_order(Origin::signed(DAVE), order_id, BOB, None, None),
+			crate::Error::<Runtime>::TakeExpiredOrderOrOffer
+		);
+
+		// 10% of the order deposit goes to the reaper.
+		let alice_balance = free_balance(&ALICE);
+		let bob_balance = free_balance(&BOB);
+		assert_ok!(NftmartOrder::reap_expired_order(Origin::signed(ALICE), BOB, order_id));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::ReapedOrder(BOB, order_id, Some((ALICE, 1)))),
+		);
+		assert_eq!(0, all_orders().len());
+		assert_eq!(free_balance(&ALICE), alice_balance + 1);
+		assert_eq!(free_balance(&BOB), bob_balance + 10 - 1);
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID0, 0, 20);
+
+		// 10% of the offer price goes to the reaper.
+		let dave_balance = free_balance(&DAVE);
+		assert_ok!(NftmartOrder::reap_expired_offer(Origin::signed(DAVE), CHARLIE, offer_id));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::ReapedOffer(CHARLIE, offer_id, Some((DAVE, 6)))),
+		);
+		assert_eq!(0, all_offers().len());
+		assert_eq!(free_balance(&DAVE), dave_balance + 6);
+		assert_eq!(free_balance(&CHARLIE), 100 - 6);
+	});
+}
+
+#[test]
+fn reap_expired_offer_should_pay_the_bounty_in_the_offer_currency() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let currency_id = NATIVE_CURRENCY_ID + 1;
+		assert_ok!(<Currencies as MultiCurrency<AccountId>>::deposit(currency_id, &CHARLIE, 1_000));
+		let offer_id = current_gid();
+		assert_ok!(NftmartOrder::submit_offer(
+			Origin::signed(CHARLIE),
+			currency_id,
+			500,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		System::set_block_number(2);
+		let dave_balance = free_balance(&DAVE);
+		assert_ok!(NftmartOrder::reap_expired_offer(Origin::signed(DAVE), CHARLIE, offer_id));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::ReapedOffer(CHARLIE, offer_id, Some((DAVE, 50)))),
+		);
+		assert_eq!(<Currencies as MultiCurrency<AccountId>>::free_balance(currency_id, &DAVE), 50);
+		assert_eq!(
+			<Currencies as MultiCurrency<AccountId>>::free_balance(currency_id, &CHARLIE),
+			1_000 - 50
+		);
+		// No native balance is taken from the offer owner.
+		assert_eq!(free_balance(&DAVE), dave_balance);
+		assert_eq!(free_balance(&CHARLIE), 100);
+	});
+}
+
+#[test]
+fn on_idle_should_reap_expired_orders_and_offers() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		let offer_id = current_gid();
+		assert_ok!(NftmartOrder::submit_offer(
+			Origin::signed(CHARLIE),
+			NATIVE_CURRENCY_ID,
+			60,
+			3,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		// Nothing will be reaped without enough weight.
+		System::set_block_number(2);
+		assert_eq!(NftmartOrder::on_idle(2, 0), 0);
+		assert_eq!(1, all_orders().len());
+
+		let bob_balance = free_balance(&BOB);
+		NftmartOrder::on_idle(2, Weight::max_value());
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::ReapedOrder(BOB, order_id, None)),
+		);
+		assert_eq!(0, all_orders().len());
+		assert_eq!(1, all_offers().len());
+		assert_eq!(free_balance(&BOB), bob_balance + 10);
+
+		System::set_block_number(3);
+		NftmartOrder::on_idle(3, Weight::max_value());
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::ReapedOffer(CHARLIE, offer_id, None)),
+		);
+		assert_eq!(0, all_offers().len());
+		assert_eq!(free_balance(&CHARLIE), 100);
+	});
+}
+
+#[test]
+fn on_idle_should_wait_for_the_grace_period_after_the_upgrade() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		// The order expired before the upgrade adding the sweeper.
+		System::set_block_number(5);
+		NftmartOrder::on_runtime_upgrade();
+		let start = 5 + crate::SWEEP_GRACE_PERIOD as u64;
+		assert_eq!(NftmartOrder::sweep_start_block(), start);
+		// The migration runs only once.
+		System::set_block_number(6);
+		assert_eq!(NftmartOrder::on_runtime_upgrade(), 0);
+		assert_eq!(NftmartOrder::sweep_start_block(), start);
+
+		NftmartOrder::on_idle(start - 1, Weight::max_value());
+		assert_eq!(1, all_orders().len());
+
+		// The sweeper reaps it from the start block.
+		System::set_block_number(start);
+		NftmartOrder::on_idle(start, Weight::max_value());
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::ReapedOrder(BOB, order_id, None)),
+		);
+		assert_eq!(0, all_orders().len());
+	});
+}
+
+#[test]
+fn orders_past_their_deadlines_at_the_upgrade_should_be_takeable_until_the_sweeper_starts() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		let offer_id = current_gid();
+		assert_ok!(NftmartOrder::submit_offer(
+			Origin::signed(CHARLIE),
+			NATIVE_CURRENCY_ID,
+			60,
+			3,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		System::set_block_number(5);
+		NftmartOrder::on_runtime_upgrade();
+		let start = 5 + crate::SWEEP_GRACE_PERIOD as u64;
+
+		// Neither can be reaped during the grace period.
+		assert_noop!(
+			NftmartOrder::reap_expired_order(Origin::signed(DAVE), BOB, order_id),
+			crate::Error::<Runtime>::NotExpired
+		);
+		assert_noop!(
+			NftmartOrder::reap_expired_offer(Origin::signed(DAVE), CHARLIE, offer_id),
+			crate::Error::<Runtime>::NotExpired
+		);
+
+		// The order submitted before the upgrade is still sold.
+		assert_ok!(NftmartOrder::take_order(Origin::signed(DAVE), order_id, BOB, None, None));
+		assert_eq!(0, all_orders().len());
+		ensure_account(&DAVE, CLASS_ID0, TOKEN_ID0, 0, 10);
+
+		// From the start block, expired orders are refused and reaped.
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			start,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		System::set_block_number(start);
+		assert_noop!(
+			NftmartOrder::take_order(Origin::signed(DAVE), order_id, BOB, None, None),
+			crate::Error::<Runtime>::TakeExpiredOrderOrOffer
+		);
+		assert_ok!(NftmartOrder::reap_expired_order(Origin::signed(DAVE), BOB, order_id));
+		assert_ok!(NftmartOrder::reap_expired_offer(Origin::signed(DAVE), CHARLIE, offer_id));
+	});
+}
+
+#[test]
+fn swap_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+		add_token(CHARLIE, 40, Some(PerU16::from_percent(20)));
+		assert_ok!(Nftmart::update_token_royalty_beneficiary(
+			Origin::signed(CHARLIE),
+			CLASS_ID0,
+			TOKEN_ID1,
+			DAVE
+		));
+
+		assert_noop!(
+			NftmartOrder::submit_swap(
+				Origin::signed(BOB),
+				NATIVE_CURRENCY_ID,
+				10,
+				50,
+				2,
+				vec![],
+				vec![(CLASS_ID0, TOKEN_ID1, 20)],
+				None,
+			),
+			crate::Error::<Runtime>::EmptySwapItems
+		);
+
+		// BOB offers 10 of TOKEN_ID0 plus 50 for 20 of TOKEN_ID1.
+		let swap_id = current_gid();
+		assert_ok!(NftmartOrder::submit_swap(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			50,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			vec![(CLASS_ID0, TOKEN_ID1, 20)],
+			Some(CHARLIE),
+		));
+		assert_eq!(last_event(), Event::NftmartOrder(crate::Event::CreatedSwap(BOB, swap_id)));
+		assert_eq!(40, free_balance(&BOB));
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID0, 10, 10);
+
+		assert_noop!(
+			NftmartOrder::take_swap(Origin::signed(DAVE), swap_id, BOB),
+			crate::Error::<Runtime>::NotSwapTaker
+		);
+
+		let dave_balance = free_balance(&DAVE);
+		assert_ok!(NftmartOrder::take_swap(Origin::signed(CHARLIE), swap_id, BOB));
+		assert_eq!(
+			last_event(),
+			Event::NftmartOrder(crate::Event::TakenSwap(CHARLIE, BOB, swap_id))
+		);
+		assert!(NftmartOrder::swaps(BOB, swap_id).is_none());
+
+		// The royalty of TOKEN_ID1 and the platform fee are charged from the price.
+		let platform_fee = NftmartConf::platform_fee_rate().mul_ceil(50);
+		assert_eq!(50, free_balance(&BOB));
+		assert_eq!(100 + 50 - platform_fee - 10, free_balance(&CHARLIE));
+		assert_eq!(dave_balance + 10, free_balance(&DAVE));
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID0, 0, 10);
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID1, 0, 20);
+		ensure_account(&CHARLIE, CLASS_ID0, TOKEN_ID0, 0, 10);
+		ensure_account(&CHARLIE, CLASS_ID0, TOKEN_ID1, 0, 20);
+
+		// CHARLIE doesn't have enough TOKEN_ID0 for this swap.
+		let swap_id = current_gid();
+		assert_ok!(NftmartOrder::submit_swap(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			0,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID1, 20)],
+			vec![(CLASS_ID0, TOKEN_ID0, 20)],
+			None,
+		));
+		assert!(NftmartOrder::take_swap(Origin::signed(CHARLIE), swap_id, BOB).is_err());
+
+		assert_ok!(NftmartOrder::remove_swap(Origin::signed(BOB), swap_id));
+		assert_eq!(last_event(), Event::NftmartOrder(crate::Event::RemovedSwap(BOB, swap_id)));
+		assert_eq!(50, free_balance(&BOB));
+		ensure_account(&BOB, CLASS_ID0, TOKEN_ID1, 0, 20);
+	});
+}
+
+#[test]
+fn trading_fee_should_be_routed() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, Some(PerU16::zero()));
+		assert_ok!(NftmartConf::update_fee_distribution(
+			Origin::root(),
+			FeeDistribution { burn: PerU16::from_percent(50), staking: PerU16::from_percent(30) },
+		));
+
+		let price = 1000;
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			price,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 20)],
+			PerU16::zero(),
+		));
+
+		let treasury = FeeRouter::treasury_account_id();
+		let staking_pot = FeeRouter::staking_pot_account_id();
+		let treasury_balance = free_balance(&treasury);
+		let issuance = Balances::total_issuance();
+		assert_ok!(NftmartOrder::take_order(Origin::signed(DAVE), order_id, BOB, None, None));
+
+		let platform_fee = NftmartConf::platform_fee_rate().mul_ceil(price);
+		let burn = platform_fee / 2;
+		let staking = platform_fee * 3 / 10;
+		assert_eq!(issuance - burn, Balances::total_issuance());
+		assert_eq!(staking, free_balance(&staking_pot));
+		assert_eq!(treasury_balance + platform_fee - burn - staking, free_balance(&treasury));
+		assert_eq!(100 + price - platform_fee, free_balance(&BOB));
+		assert!(System::events().iter().any(|r| r.event ==
+			Event::NftmartConf(nftmart_config::Event::RoutedFee(
+				BOB,
+				NATIVE_CURRENCY_ID,
+				platform_fee
+			))));
+	});
+}
+
+#[test]
+fn staking_share_should_go_to_the_treasury_without_staking() {
+	use crate::mock::{Currencies, GetNativeCurrencyId, TreasuryPalletId};
+	use nftmart_traits::NftmartFeeRouter;
+	type Router = nftmart_config::FeeRouter<
+		Runtime,
+		Currencies,
+		TreasuryPalletId,
+		GetNativeCurrencyId,
+		nftmart_config::WithoutStaking,
+	>;
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(NftmartConf::update_fee_distribution(
+			Origin::root(),
+			FeeDistribution { burn: PerU16::from_percent(50), staking: PerU16::from_percent(30) },
+		));
+
+		let treasury = Router::treasury_account_id();
+		let treasury_balance = free_balance(&treasury);
+		let issuance = Balances::total_issuance();
+		assert_ok!(Router::route_fee(&DAVE, NATIVE_CURRENCY_ID, 100));
+
+		assert_eq!(issuance - 50, Balances::total_issuance());
+		assert_eq!(0, free_balance(&Router::staking_pot_account_id()));
+		assert_eq!(treasury_balance + 50, free_balance(&treasury));
+	});
+}
+
+const WEBHOOK_SEED: &str = "//Webhook";
+const WEBHOOK_URL: &str = "http://localhost:8080/nftmart";
+
+/// Build the externalities with a webhook and a signing key, whose offchain workers post to a
+/// local HTTP stand-in.
+fn new_offchain_ext() -> (sp_io::TestExternalities, std::sync::Arc<RwLock<OffchainState>>) {
+	let mut ext = ExtBuilder::default().build();
+	let (offchain, state) = TestOffchainExt::with_offchain_db(ext.offchain_db());
+	let keystore = KeyStore::new();
+	SyncCryptoStore::ed25519_generate_new(&keystore, WEBHOOK_KEY_TYPE, Some(WEBHOOK_SEED)).unwrap();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext.register_extension(KeystoreExt(std::sync::Arc::new(keystore)));
+	ext.execute_with(|| {
+		sp_io::offchain::local_storage_set(
+			StorageKind::PERSISTENT,
+			b"nftmart/webhook/urls",
+			&vec![WEBHOOK_URL.as_bytes().to_vec()].encode(),
+		);
+	});
+	(ext, state)
+}
+
+fn expect_post(state: &RwLock<OffchainState>, body: Vec<u8>) {
+	let pair = ed25519::Pair::from_string(WEBHOOK_SEED, None).unwrap();
+	let header = |bytes: &[u8]| String::from_utf8(hex(bytes)).unwrap();
+	state.write().expect_request(PendingRequest {
+		method: "POST".into(),
+		uri: WEBHOOK_URL.into(),
+		headers: vec![
+			("Content-Type".into(), "application/json".into()),
+			(SIGNER_HEADER.into(), header(pair.public().as_ref())),
+			(SIGNATURE_HEADER.into(), header(pair.sign(&body).as_ref())),
+		],
+		body,
+		response: Some(vec![]),
+		sent: true,
+		..Default::default()
+	});
+}
+
+#[test]
+fn offchain_worker_should_post_taken_and_expired_orders() {
+	let (mut ext, state) = new_offchain_ext();
+	let (order_id, offer_id) = ext.execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		let offer_id = current_gid();
+		assert_ok!(NftmartOrder::submit_offer(
+			Origin::signed(CHARLIE),
+			NATIVE_CURRENCY_ID,
+			60,
+			2,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+		assert_ok!(NftmartOrder::take_order(Origin::signed(ALICE), order_id, BOB, None, None));
+
+		System::set_block_number(2);
+		assert_ok!(NftmartOrder::reap_expired_offer(Origin::signed(DAVE), CHARLIE, offer_id));
+		(order_id, offer_id)
+	});
+	ext.persist_offchain_overlay();
+
+	ext.execute_with(|| {
+		let taken = Notification::<AccountId, _>::OrderTaken {
+			order_id,
+			owner: BOB,
+			taker: ALICE,
+			currency_id: NATIVE_CURRENCY_ID,
+			price: 100,
+		};
+		expect_post(&state, taken.to_json(1));
+		NftmartConf::offchain_worker(1);
+
+		let expired = Notification::<AccountId, _>::OfferExpired { offer_id, owner: CHARLIE };
+		expect_post(&state, expired.to_json(2));
+		NftmartConf::offchain_worker(2);
+
+		// The notifications are only posted once.
+		NftmartConf::offchain_worker(2);
+		assert!(notification::take_notifications(
+			2,
+			System::parent_hash().as_ref(),
+			System::event_count()
+		)
+		.is_empty());
+	});
+}
+
+#[test]
+fn offchain_worker_should_only_post_the_notifications_of_its_fork() {
+	let (mut ext, state) = new_offchain_ext();
+	let order_id = ext.execute_with(|| {
+		add_class(ALICE);
+		add_token(BOB, 20, None);
+
+		let order_id = current_gid();
+		assert_ok!(NftmartOrder::submit_order(
+			Origin::signed(BOB),
+			NATIVE_CURRENCY_ID,
+			10,
+			100,
+			10,
+			vec![(CLASS_ID0, TOKEN_ID0, 10)],
+			PerU16::zero(),
+		));
+
+		// The order is taken in block 2 of a fork.
+		System::set_block_number(2);
+		System::set_parent_hash(H256::repeat_byte(1));
+		assert_ok!(NftmartOrder::take_order(Origin::signed(ALICE), order_id, BOB, None, None));
+		order_id
+	});
+	ext.persist_offchain_overlay();
+
+	ext.execute_with(|| {
+		// Block 2 of another fork has nothing to post.
+		System::set_parent_hash(H256::repeat_byte(2));
+		NftmartConf::offchain_worker(2);
+		assert!(state.read().requests.is_empty());
+
+		let taken = Notification::<AccountId, _>::OrderTaken {
+			order_id,
+			owner: BOB,
+			taker: ALICE,
+			currency_id: NATIVE_CURRENCY_ID,
+			price: 100,
+		};
+		expect_post(&state, taken.to_json(2));
+		System::set_parent_hash(H256::repeat_byte(1));
+		NftmartConf::offchain_worker(2);
+	});
+}
@@ -0,0 +1,366 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{pallet_prelude::*, transactional};
+use frame_system::pallet_prelude::*;
+pub use nftmart_traits::*;
+use orml_traits::MultiCurrency;
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, CheckedAdd, Saturating, Zero},
+	ArithmeticError, RuntimeDebug, SaturatedConversion,
+};
+use sp_std::vec::Vec;
+
+mod mock;
+mod tests;
+
+pub use module::*;
+
+/// Weight of removing an expired user right by the sweeper.
+pub const REAP_WEIGHT: Weight = 10_000;
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct RentalListing<CurrencyId, BlockNumber, TokenId> {
+	/// currency ID.
+	#[codec(compact)]
+	pub currency_id: CurrencyId,
+	/// Price of each block of a rental.
+	#[codec(compact)]
+	pub price_per_block: Balance,
+	/// The quantity of the token reserved for renting.
+	#[codec(compact)]
+	pub quantity: TokenId,
+	/// The maximum number of blocks a renter can hold the user right.
+	#[codec(compact)]
+	pub max_duration: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct UserInfo<AccountId, BlockNumber> {
+	/// The account holding the user right.
+	pub user: AccountId,
+	/// The user right is revoked at `expires` block number.
+	#[codec(compact)]
+	pub expires: BlockNumber,
+}
+
+pub type TokenIdOf<T> = <T as module::Config>::TokenId;
+pub type ClassIdOf<T> = <T as module::Config>::ClassId;
+pub type CurrencyIdOf<T> = <<T as module::Config>::MultiCurrency as MultiCurrency<
+	<T as frame_system::Config>::AccountId,
+>>::CurrencyId;
+pub type BlockNumberOf<T> = <T as frame_system::Config>::BlockNumber;
+pub type RentalListingOf<T> = RentalListing<CurrencyIdOf<T>, BlockNumberOf<T>, TokenIdOf<T>>;
+pub type UserInfoOf<T> = UserInfo<<T as frame_system::Config>::AccountId, BlockNumberOf<T>>;
+
+#[frame_support::pallet]
+pub mod module {
+	use super::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// MultiCurrency type for paying rents
+		type MultiCurrency: MultiCurrency<Self::AccountId, Balance = Balance>;
+
+		/// The class ID type
+		type ClassId: Parameter
+			+ Member
+			+ AtLeast32BitUnsigned
+			+ Default
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ codec::FullCodec;
+
+		/// The token ID type
+		type TokenId: Parameter
+			+ Member
+			+ AtLeast32BitUnsigned
+			+ Default
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ codec::FullCodec;
+
+		/// NFTMart nft
+		type NFT: NftmartNft<Self::AccountId, Self::ClassId, Self::TokenId>;
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// the token has been listed for rent by the lender
+		AlreadyListed,
+		/// rental listing not found
+		ListingNotFound,
+		/// the token is still rented out
+		TokenRented,
+		/// list with zero quantity
+		InvalidQuantity,
+		/// the duration is zero or longer than the listing allows
+		InvalidDuration,
+		/// the rent is higher than the price limit
+		RentPriceTooHigh,
+		/// cannot rent one's own token
+		RentOwnToken,
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(crate) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// ListedForRent \[lender, class_id, token_id, quantity\]
+		ListedForRent(T::AccountId, ClassIdOf<T>, TokenIdOf<T>, TokenIdOf<T>),
+		/// CancelledRentalListing \[lender, class_id, token_id\]
+		CancelledRentalListing(T::AccountId, ClassIdOf<T>, TokenIdOf<T>),
+		/// UpdatedUser \[user, lender, class_id, token_id, expires\]
+		UpdatedUser(T::AccountId, T::AccountId, ClassIdOf<T>, TokenIdOf<T>, BlockNumberOf<T>),
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+		fn on_idle(_n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			Self::sweep_expired_users(remaining_weight)
+		}
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// class_id, token_id, lender, listing
+		pub listings: Vec<(ClassIdOf<T>, TokenIdOf<T>, T::AccountId, RentalListingOf<T>)>,
+		/// class_id, token_id, lender, user
+		pub users: Vec<(ClassIdOf<T>, TokenIdOf<T>, T::AccountId, UserInfoOf<T>)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { listings: Default::default(), users: Default::default() }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			for (class_id, token_id, lender, listing) in &self.listings {
+				T::NFT::reserve_tokens(lender, *class_id, *token_id, listing.quantity).unwrap();
+				RentalListings::<T>::insert((*class_id, *token_id), lender, listing.clone());
+			}
+			for (class_id, token_id, lender, user) in &self.users {
+				assert!(
+					RentalListings::<T>::contains_key((*class_id, *token_id), lender),
+					"User of an unlisted token"
+				);
+				TokenUsers::<T>::insert((*class_id, *token_id), lender, user.clone());
+			}
+		}
+	}
+
+	/// Rental listings.
+	///
+	/// (class_id, token_id), lender => RentalListing
+	#[pallet::storage]
+	#[pallet::getter(fn rental_listings)]
+	pub type RentalListings<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(ClassIdOf<T>, TokenIdOf<T>),
+		Blake2_128Concat,
+		T::AccountId,
+		RentalListingOf<T>,
+		OptionQuery,
+	>;
+
+	/// The user rights of rented tokens, which may have expired until swept by `on_idle`.
+	///
+	/// (class_id, token_id), lender => UserInfo
+	#[pallet::storage]
+	#[pallet::getter(fn token_users)]
+	pub type TokenUsers<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(ClassIdOf<T>, TokenIdOf<T>),
+		Blake2_128Concat,
+		T::AccountId,
+		UserInfoOf<T>,
+		OptionQuery,
+	>;
+
+	/// The raw key of the last user right visited by the sweeper of expired user rights.
+	#[pallet::storage]
+	pub(super) type UserReapCursor<T: Config> = StorageValue<_, Vec<u8>>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// List a token for rent. The listed quantity is reserved, the lender can neither transfer
+		/// nor sell it until the listing is cancelled.
+		///
+		/// - `price_per_block`: rent of each block
+		/// - `max_duration`: the maximum number of blocks of a rental
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn list_for_rent(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			#[pallet::compact] token_id: TokenIdOf<T>,
+			#[pallet::compact] quantity: TokenIdOf<T>,
+			#[pallet::compact] currency_id: CurrencyIdOf<T>,
+			#[pallet::compact] price_per_block: Balance,
+			#[pallet::compact] max_duration: BlockNumberOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!quantity.is_zero(), Error::<T>::InvalidQuantity);
+			ensure!(!max_duration.is_zero(), Error::<T>::InvalidDuration);
+			ensure!(
+				!RentalListings::<T>::contains_key((class_id, token_id), &who),
+				Error::<T>::AlreadyListed
+			);
+
+			T::NFT::reserve_tokens(&who, class_id, token_id, quantity)?;
+			RentalListings::<T>::insert(
+				(class_id, token_id),
+				&who,
+				RentalListing { currency_id, price_per_block, quantity, max_duration },
+			);
+			Self::deposit_event(Event::ListedForRent(who, class_id, token_id, quantity));
+			Ok(().into())
+		}
+
+		/// Cancel a rental listing and unreserve the token, only if it is not rented out.
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn cancel_rental_listing(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			#[pallet::compact] token_id: TokenIdOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let listing = RentalListings::<T>::take((class_id, token_id), &who)
+				.ok_or(Error::<T>::ListingNotFound)?;
+			ensure!(Self::user_of(class_id, token_id, &who).is_none(), Error::<T>::TokenRented);
+
+			TokenUsers::<T>::remove((class_id, token_id), &who);
+			T::NFT::unreserve_tokens(&who, class_id, token_id, listing.quantity)?;
+			Self::deposit_event(Event::CancelledRentalListing(who, class_id, token_id));
+			Ok(().into())
+		}
+
+		/// Rent a listed token for `duration` blocks. The current user can extend the rental.
+		///
+		/// - `lender`: the owner of the listing
+		/// - `max_price_per_block`: the renter is not willing to pay more than this
+		#[pallet::weight(100_000)]
+		#[transactional]
+		pub fn rent(
+			origin: OriginFor<T>,
+			#[pallet::compact] class_id: ClassIdOf<T>,
+			#[pallet::compact] token_id: TokenIdOf<T>,
+			lender: T::AccountId,
+			#[pallet::compact] duration: BlockNumberOf<T>,
+			#[pallet::compact] max_price_per_block: Balance,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(who != lender, Error::<T>::RentOwnToken);
+
+			let listing = RentalListings::<T>::get((class_id, token_id), &lender)
+				.ok_or(Error::<T>::ListingNotFound)?;
+			ensure!(listing.price_per_block <= max_price_per_block, Error::<T>::RentPriceTooHigh);
+			ensure!(!duration.is_zero(), Error::<T>::InvalidDuration);
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let starts = match Self::user_of(class_id, token_id, &lender) {
+				Some((user, expires)) => {
+					ensure!(user == who, Error::<T>::TokenRented);
+					expires
+				},
+				None => now,
+			};
+			let expires = starts.checked_add(&duration).ok_or(ArithmeticError::Overflow)?;
+			ensure!(
+				expires.saturating_sub(now) <= listing.max_duration,
+				Error::<T>::InvalidDuration
+			);
+
+			let rent = listing
+				.price_per_block
+				.checked_mul(duration.saturated_into::<Balance>())
+				.ok_or(ArithmeticError::Overflow)?;
+			T::MultiCurrency::transfer(listing.currency_id, &who, &lender, rent)?;
+
+			TokenUsers::<T>::insert(
+				(class_id, token_id),
+				&lender,
+				UserInfo { user: who.clone(), expires },
+			);
+			Self::deposit_event(Event::UpdatedUser(who, lender, class_id, token_id, expires));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The user of a token lent by `lender` and the block number the user right expires at,
+	/// `None` if the token is not rented out.
+	pub fn user_of(
+		class_id: ClassIdOf<T>,
+		token_id: TokenIdOf<T>,
+		lender: &T::AccountId,
+	) -> Option<(T::AccountId, BlockNumberOf<T>)> {
+		let now = frame_system::Pallet::<T>::block_number();
+		TokenUsers::<T>::get((class_id, token_id), lender)
+			.filter(|info| now < info.expires)
+			.map(|info| (info.user, info.expires))
+	}
+
+	/// All the unexpired user rights of a token, as `(lender, user, expires)`.
+	pub fn users_of(
+		class_id: ClassIdOf<T>,
+		token_id: TokenIdOf<T>,
+	) -> Vec<(T::AccountId, T::AccountId, BlockNumberOf<T>)> {
+		let now = frame_system::Pallet::<T>::block_number();
+		TokenUsers::<T>::iter_prefix((class_id, token_id))
+			.filter(|(_lender, info)| now < info.expires)
+			.map(|(lender, info)| (lender, info.user, info.expires))
+			.collect()
+	}
+
+	/// Remove expired user rights within `max_weight`, starting from where the last sweep stopped.
+	fn sweep_expired_users(max_weight: Weight) -> Weight {
+		let mut used = T::DbWeight::get().reads_writes(2, 1);
+		if used > max_weight {
+			return 0
+		}
+		let now = frame_system::Pallet::<T>::block_number();
+		let cursor = UserReapCursor::<T>::get();
+		let mut iter = match cursor.clone() {
+			Some(cursor) => TokenUsers::<T>::iter_from(cursor),
+			None => TokenUsers::<T>::iter(),
+		};
+		let next_cursor = loop {
+			if used.saturating_add(REAP_WEIGHT) > max_weight {
+				break Some(iter.last_raw_key().to_vec())
+			}
+			match iter.next() {
+				Some((token, lender, info)) => {
+					used = used.saturating_add(T::DbWeight::get().reads(1));
+					if now >= info.expires {
+						used = used.saturating_add(REAP_WEIGHT);
+						TokenUsers::<T>::remove(token, lender);
+					}
+				},
+				None => break None,
+			}
+		};
+		if next_cursor != cursor {
+			UserReapCursor::<T>::set(next_cursor);
+		}
+		used
+	}
+}
@@ -0,0 +1,262 @@
+#![cfg(test)]
+
+use super::*;
+use crate as nftmart_rental;
+use frame_support::{assert_ok, construct_runtime, parameter_types, traits::Currency, PalletId};
+use nftmart_traits::{ClassProperty, Properties};
+use orml_currencies::BasicCurrencyAdapter;
+use orml_nft::AccountToken;
+use sp_core::{crypto::AccountId32, H256};
+use sp_runtime::{
+	testing::Header,
+	traits::{AccountIdConversion, BlakeTwo256, IdentityLookup},
+	MultiSignature, MultiSigner, PerU16,
+};
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+}
+
+pub type AccountId = AccountId32;
+
+impl frame_system::Config for Runtime {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = Call;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type DbWeight = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+parameter_types! {
+	pub const ExistentialDeposit: Balance = 1;
+}
+impl pallet_balances::Config for Runtime {
+	type Balance = Balance;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = frame_system::Pallet<Runtime>;
+	type MaxLocks = ();
+	type WeightInfo = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = ();
+}
+
+orml_traits::parameter_type_with_key! {
+	pub ExistentialDeposits: |currency_id: nftmart_traits::constants_types::CurrencyId| -> Balance {
+		if currency_id == &nftmart_traits::constants_types::NATIVE_CURRENCY_ID {
+			ExistentialDeposit::get()
+		} else  {
+			Default::default()
+		}
+	};
+}
+
+impl orml_tokens::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type Amount = Amount;
+	type CurrencyId = CurrencyId;
+	type WeightInfo = ();
+	type ExistentialDeposits = ExistentialDeposits;
+	type OnDust = ();
+}
+
+parameter_types! {
+	pub const GetNativeCurrencyId: nftmart_traits::constants_types::CurrencyId = nftmart_traits::constants_types::NATIVE_CURRENCY_ID;
+}
+
+pub type AdaptedBasicCurrency = BasicCurrencyAdapter<
+	Runtime,
+	Balances,
+	nftmart_traits::constants_types::Amount,
+	nftmart_traits::constants_types::Moment,
+>;
+
+impl orml_currencies::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Tokens;
+	type NativeCurrency = AdaptedBasicCurrency;
+	type GetNativeCurrencyId = GetNativeCurrencyId;
+	type WeightInfo = ();
+}
+
+impl orml_nft::Config for Runtime {
+	type ClassId = ClassId;
+	type TokenId = TokenId;
+	type ClassData = ClassData<BlockNumberOf<Self>>;
+	type TokenData = TokenData<<Self as frame_system::Config>::AccountId, BlockNumberOf<Self>>;
+}
+
+parameter_types! {
+	pub const CreateClassDeposit: Balance = 0;
+	pub const CreateTokenDeposit: Balance = 0;
+	pub const MetaDataByteDeposit: Balance = 0;
+	pub const NftModuleId: PalletId = PalletId(*b"nftmart*");
+}
+
+impl nftmart_nft::Config for Runtime {
+	type Event = Event;
+	type ExtraConfig = NftmartConf;
+	type OrderConfig = NftmartOrder;
+	type CreateClassDeposit = CreateClassDeposit;
+	type MetaDataByteDeposit = MetaDataByteDeposit;
+	type CreateTokenDeposit = CreateTokenDeposit;
+	type ModuleId = NftModuleId;
+	type Currency = Balances;
+	type MultiCurrency = Currencies;
+}
+
+impl nftmart_config::Config for Runtime {
+	type Event = Event;
+}
+
+parameter_types! {
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+}
+
+pub type FeeRouter =
+	nftmart_config::FeeRouter<Runtime, Currencies, TreasuryPalletId, GetNativeCurrencyId>;
+
+impl nftmart_order::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type Currency = Balances;
+	type ClassId = nftmart_traits::constants_types::ClassId;
+	type TokenId = nftmart_traits::constants_types::TokenId;
+	type NFT = Nftmart;
+	type ExtraConfig = NftmartConf;
+	type CommissionAgent = ();
+	type FeeRouter = FeeRouter;
+	type TreasuryPalletId = TreasuryPalletId;
+	type OffchainSignature = MultiSignature;
+	type OffchainPublic = MultiSigner;
+}
+
+impl nftmart_rental::Config for Runtime {
+	type Event = Event;
+	type MultiCurrency = Currencies;
+	type ClassId = nftmart_traits::constants_types::ClassId;
+	type TokenId = nftmart_traits::constants_types::TokenId;
+	type NFT = Nftmart;
+}
+
+pub type Block = sp_runtime::generic::Block<Header, UncheckedExtrinsic>;
+pub type UncheckedExtrinsic = sp_runtime::generic::UncheckedExtrinsic<u32, Call, u32, ()>;
+
+construct_runtime!(
+	pub enum Runtime where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Tokens: orml_tokens::{Pallet, Storage, Event<T>, Config<T>},
+		Currencies: orml_currencies::{Pallet, Call, Event<T>},
+		OrmlNFT: orml_nft::{Pallet, Storage, Config<T>},
+		NftmartConf: nftmart_config::{Pallet, Call, Event<T>},
+		Nftmart: nftmart_nft::{Pallet, Call, Event<T>},
+		NftmartOrder: nftmart_order::{Pallet, Call, Event<T>},
+		NftmartRental: nftmart_rental::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+pub const ALICE: AccountId = AccountId::new([1u8; 32]);
+pub const BOB: AccountId = AccountId::new([2u8; 32]);
+pub const CHARLIE: AccountId = AccountId::new([3u8; 32]);
+pub const CLASS_ID0: <Runtime as orml_nft::Config>::ClassId = 0;
+pub const TOKEN_ID0: <Runtime as orml_nft::Config>::TokenId = 0;
+
+pub struct ExtBuilder;
+impl Default for ExtBuilder {
+	fn default() -> Self {
+		ExtBuilder
+	}
+}
+
+impl ExtBuilder {
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: vec![(ALICE, 100), (BOB, 100), (CHARLIE, 100)],
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+		nftmart_config::GenesisConfig::<Runtime> { min_order_deposit: 10, ..Default::default() }
+			.assimilate_storage(&mut t)
+			.unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| {
+			System::set_block_number(1);
+			NftmartConf::add_whitelist(Origin::root(), ALICE).unwrap();
+			NftmartConf::add_whitelist(Origin::root(), BOB).unwrap();
+		});
+		ext
+	}
+}
+
+#[allow(dead_code)]
+pub fn last_event() -> Event {
+	frame_system::Pallet::<Runtime>::events().pop().expect("Event expected").event
+}
+
+pub fn class_id0_account() -> AccountId {
+	<Runtime as nftmart_nft::Config>::ModuleId::get().into_sub_account(CLASS_ID0)
+}
+
+pub fn add_class_and_token(who: AccountId, quantity: TokenId) {
+	let cate_id = nftmart_config::Pallet::<Runtime>::next_id();
+	assert_ok!(NftmartConf::create_category(Origin::root(), vec![1]));
+	assert_ok!(Nftmart::create_class(
+		Origin::signed(who.clone()),
+		vec![1],
+		vec![1],
+		vec![1],
+		PerU16::from_percent(5),
+		Properties(ClassProperty::Transferable | ClassProperty::Burnable),
+		vec![cate_id]
+	));
+	let deposit = Nftmart::mint_token_deposit(1);
+	assert_eq!(Balances::deposit_into_existing(&class_id0_account(), deposit).is_ok(), true);
+	assert_ok!(Nftmart::mint(
+		Origin::signed(class_id0_account()),
+		who,
+		CLASS_ID0,
+		vec![1],
+		quantity,
+		None,
+	));
+}
+
+pub fn ensure_account(who: &AccountId, reserved: TokenId, free: TokenId) {
+	let account: AccountToken<TokenId> =
+		orml_nft::Pallet::<Runtime>::tokens_by_owner(who, (CLASS_ID0, TOKEN_ID0))
+			.unwrap_or_default();
+	assert_eq!(account.reserved, reserved);
+	assert_eq!(account.quantity, free);
+}
+
+pub fn free_balance(who: &AccountId) -> Balance {
+	Balances::free_balance(who)
+}
@@ -0,0 +1,297 @@
+#![cfg(test)]
+
+use super::{Error, NATIVE_CURRENCY_ID};
+use crate::mock::{
+	add_class_and_token, ensure_account, free_balance, last_event, Event, ExtBuilder, Nftmart,
+	NftmartRental, Origin, System, ALICE, BOB, CHARLIE, CLASS_ID0, TOKEN_ID0,
+};
+use frame_support::{assert_noop, assert_ok, traits::Hooks, weights::Weight};
+
+#[test]
+fn rent_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class_and_token(ALICE, 10);
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0,
+			4,
+			NATIVE_CURRENCY_ID,
+			2,
+			20,
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartRental(crate::Event::ListedForRent(ALICE, CLASS_ID0, TOKEN_ID0, 4))
+		);
+		ensure_account(&ALICE, 4, 6);
+
+		// The reserved quantity cannot be transferred by the lender.
+		assert_noop!(
+			Nftmart::transfer(Origin::signed(ALICE), BOB, vec![(CLASS_ID0, TOKEN_ID0, 7)]),
+			orml_nft::Error::<crate::mock::Runtime>::NumOverflow
+		);
+
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 10, 1),
+			Error::<crate::mock::Runtime>::RentPriceTooHigh
+		);
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 21, 2),
+			Error::<crate::mock::Runtime>::InvalidDuration
+		);
+
+		let (alice_balance, bob_balance) = (free_balance(&ALICE), free_balance(&BOB));
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 10, 2));
+		assert_eq!(
+			last_event(),
+			Event::NftmartRental(crate::Event::UpdatedUser(BOB, ALICE, CLASS_ID0, TOKEN_ID0, 11))
+		);
+		assert_eq!(free_balance(&ALICE), alice_balance + 20);
+		assert_eq!(free_balance(&BOB), bob_balance - 20);
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), Some((BOB, 11)));
+		assert_eq!(NftmartRental::users_of(CLASS_ID0, TOKEN_ID0), vec![(ALICE, BOB, 11)]);
+		// The ownership stays with the lender.
+		ensure_account(&ALICE, 4, 6);
+
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(CHARLIE), CLASS_ID0, TOKEN_ID0, ALICE, 1, 2),
+			Error::<crate::mock::Runtime>::TokenRented
+		);
+		assert_noop!(
+			NftmartRental::cancel_rental_listing(Origin::signed(ALICE), CLASS_ID0, TOKEN_ID0),
+			Error::<crate::mock::Runtime>::TokenRented
+		);
+
+		// The user extends the rental.
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 5, 2));
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), Some((BOB, 16)));
+
+		System::set_block_number(16);
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), None);
+		assert_eq!(NftmartRental::users_of(CLASS_ID0, TOKEN_ID0), vec![]);
+
+		assert_ok!(NftmartRental::rent(Origin::signed(CHARLIE), CLASS_ID0, TOKEN_ID0, ALICE, 1, 2));
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), Some((CHARLIE, 17)));
+	});
+}
+
+#[test]
+fn cancel_rental_listing_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class_and_token(ALICE, 10);
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0,
+			10,
+			NATIVE_CURRENCY_ID,
+			2,
+			20,
+		));
+		assert_noop!(
+			NftmartRental::list_for_rent(
+				Origin::signed(ALICE),
+				CLASS_ID0,
+				TOKEN_ID0,
+				1,
+				NATIVE_CURRENCY_ID,
+				2,
+				20,
+			),
+			Error::<crate::mock::Runtime>::AlreadyListed
+		);
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 3, 2));
+
+		System::set_block_number(4);
+		assert_ok!(NftmartRental::cancel_rental_listing(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0
+		));
+		assert_eq!(
+			last_event(),
+			Event::NftmartRental(crate::Event::CancelledRentalListing(ALICE, CLASS_ID0, TOKEN_ID0))
+		);
+		ensure_account(&ALICE, 0, 10);
+		assert_eq!(NftmartRental::token_users((CLASS_ID0, TOKEN_ID0), ALICE), None);
+		assert_eq!(NftmartRental::rental_listings((CLASS_ID0, TOKEN_ID0), ALICE), None);
+
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 3, 2),
+			Error::<crate::mock::Runtime>::ListingNotFound
+		);
+	});
+}
+
+#[test]
+fn rent_should_extend_within_max_duration() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class_and_token(ALICE, 10);
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0,
+			10,
+			NATIVE_CURRENCY_ID,
+			2,
+			20,
+		));
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 0, 2),
+			Error::<crate::mock::Runtime>::InvalidDuration
+		);
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 20, 2));
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), Some((BOB, 21)));
+
+		// The extension counts from the current expiry, within `max_duration` from now.
+		System::set_block_number(6);
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 6, 2),
+			Error::<crate::mock::Runtime>::InvalidDuration
+		);
+		let (alice_balance, bob_balance) = (free_balance(&ALICE), free_balance(&BOB));
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 5, 2));
+		assert_eq!(
+			last_event(),
+			Event::NftmartRental(crate::Event::UpdatedUser(BOB, ALICE, CLASS_ID0, TOKEN_ID0, 26))
+		);
+		// Only the extension is paid.
+		assert_eq!(free_balance(&ALICE), alice_balance + 10);
+		assert_eq!(free_balance(&BOB), bob_balance - 10);
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), Some((BOB, 26)));
+
+		// Another renter can neither extend nor take over the rental.
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(CHARLIE), CLASS_ID0, TOKEN_ID0, ALICE, 1, 2),
+			Error::<crate::mock::Runtime>::TokenRented
+		);
+
+		// Once expired, a new rental starts from now.
+		System::set_block_number(30);
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 20, 2));
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &ALICE), Some((BOB, 50)));
+	});
+}
+
+#[test]
+fn rent_should_respect_the_price_limit() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class_and_token(ALICE, 10);
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0,
+			10,
+			NATIVE_CURRENCY_ID,
+			3,
+			20,
+		));
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 5, 2),
+			Error::<crate::mock::Runtime>::RentPriceTooHigh
+		);
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(ALICE), CLASS_ID0, TOKEN_ID0, ALICE, 5, 3),
+			Error::<crate::mock::Runtime>::RentOwnToken
+		);
+		assert_noop!(
+			NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, CHARLIE, 5, 3),
+			Error::<crate::mock::Runtime>::ListingNotFound
+		);
+
+		// A higher limit pays the listed price.
+		let bob_balance = free_balance(&BOB);
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 5, 10));
+		assert_eq!(free_balance(&BOB), bob_balance - 15);
+	});
+}
+
+#[test]
+fn rented_token_should_be_neither_cancelled_nor_transferred() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class_and_token(ALICE, 10);
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0,
+			10,
+			NATIVE_CURRENCY_ID,
+			2,
+			20,
+		));
+		assert_ok!(NftmartRental::rent(Origin::signed(BOB), CLASS_ID0, TOKEN_ID0, ALICE, 5, 2));
+
+		assert_noop!(
+			NftmartRental::cancel_rental_listing(Origin::signed(ALICE), CLASS_ID0, TOKEN_ID0),
+			Error::<crate::mock::Runtime>::TokenRented
+		);
+		assert_noop!(
+			Nftmart::transfer(Origin::signed(ALICE), CHARLIE, vec![(CLASS_ID0, TOKEN_ID0, 1)]),
+			orml_nft::Error::<crate::mock::Runtime>::NumOverflow
+		);
+		// Neither can the user, who has no ownership.
+		assert_noop!(
+			Nftmart::transfer(Origin::signed(BOB), CHARLIE, vec![(CLASS_ID0, TOKEN_ID0, 1)]),
+			orml_nft::Error::<crate::mock::Runtime>::NumOverflow
+		);
+		ensure_account(&ALICE, 10, 0);
+
+		// The lender gets the token back once the rental expires and the listing is cancelled.
+		System::set_block_number(6);
+		assert_ok!(NftmartRental::cancel_rental_listing(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0
+		));
+		assert_ok!(Nftmart::transfer(
+			Origin::signed(ALICE),
+			CHARLIE,
+			vec![(CLASS_ID0, TOKEN_ID0, 1)]
+		));
+		ensure_account(&ALICE, 0, 9);
+	});
+}
+
+#[test]
+fn on_idle_should_remove_expired_users() {
+	ExtBuilder::default().build().execute_with(|| {
+		add_class_and_token(ALICE, 10);
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(ALICE),
+			CLASS_ID0,
+			TOKEN_ID0,
+			5,
+			NATIVE_CURRENCY_ID,
+			2,
+			20,
+		));
+		assert_ok!(Nftmart::transfer(Origin::signed(ALICE), BOB, vec![(CLASS_ID0, TOKEN_ID0, 5)]));
+		assert_ok!(NftmartRental::list_for_rent(
+			Origin::signed(BOB),
+			CLASS_ID0,
+			TOKEN_ID0,
+			5,
+			NATIVE_CURRENCY_ID,
+			2,
+			20,
+		));
+		assert_ok!(NftmartRental::rent(Origin::signed(CHARLIE), CLASS_ID0, TOKEN_ID0, ALICE, 3, 2));
+		assert_ok!(NftmartRental::rent(Origin::signed(CHARLIE), CLASS_ID0, TOKEN_ID0, BOB, 10, 2));
+
+		// Nothing is done without weight.
+		System::set_block_number(4);
+		assert_eq!(NftmartRental::on_idle(4, 0), 0);
+		assert!(NftmartRental::token_users((CLASS_ID0, TOKEN_ID0), ALICE).is_some());
+
+		NftmartRental::on_idle(4, Weight::max_value());
+		assert_eq!(NftmartRental::token_users((CLASS_ID0, TOKEN_ID0), ALICE), None);
+		assert_eq!(NftmartRental::user_of(CLASS_ID0, TOKEN_ID0, &BOB), Some((CHARLIE, 11)));
+		// The listings stay.
+		assert!(NftmartRental::rental_listings((CLASS_ID0, TOKEN_ID0), ALICE).is_some());
+
+		System::set_block_number(11);
+		NftmartRental::on_idle(11, Weight::max_value());
+		assert_eq!(NftmartRental::token_users((CLASS_ID0, TOKEN_ID0), BOB), None);
+	});
+}
@@ -41,6 +41,9 @@ pub type NFTMetadata = sp_std::prelude::Vec<u8>;
 /// An index to a block.
 pub type BlockNumber = u32;
 
+/// The default step interval of the Dutch auction price.
+pub const DESC_INTERVAL: BlockNumber = time::MINUTES * 30;
+
 /// Alias to 512-bit hash when used in the context of a transaction signature on the chain.
 pub type Signature = MultiSignature;
 
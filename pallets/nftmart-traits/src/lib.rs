@@ -12,6 +12,9 @@ use sp_std::{vec, vec::Vec};
 
 pub mod constants_types;
 pub use crate::constants_types::*;
+pub mod notification;
+pub use crate::notification::Notification;
+pub mod webhook;
 pub use contract_types::*;
 pub use log;
 
@@ -34,6 +37,8 @@ pub trait NftmartConfig<AccountId, BlockNumber> {
 	fn get_platform_fee_rate() -> PerU16;
 	fn get_max_commission_reward_rate() -> PerU16;
 	fn get_min_commission_agent_deposit() -> Balance;
+	fn get_reaper_bounty_rate() -> PerU16;
+	fn get_fee_distribution() -> FeeDistribution;
 }
 
 pub trait NftmartOrder<AccountId, ClassId, TokenId> {
@@ -41,6 +46,33 @@ pub trait NftmartOrder<AccountId, ClassId, TokenId> {
 	fn burn_offers(owner: &AccountId, class_id: ClassId, token_id: TokenId) -> DispatchResult;
 }
 
+pub trait NftmartCommissionAgent<AccountId, CurrencyId> {
+	fn is_registered_agent(who: &AccountId) -> bool;
+	fn note_commission(
+		agent: &AccountId,
+		currency_id: CurrencyId,
+		volume: Balance,
+		commission: Balance,
+	);
+}
+
+pub trait NftmartFeeRouter<AccountId, CurrencyId> {
+	fn route_fee(payer: &AccountId, currency_id: CurrencyId, fee: Balance) -> DispatchResult;
+}
+
+impl<AccountId, CurrencyId> NftmartCommissionAgent<AccountId, CurrencyId> for () {
+	fn is_registered_agent(_who: &AccountId) -> bool {
+		false
+	}
+	fn note_commission(
+		_agent: &AccountId,
+		_currency_id: CurrencyId,
+		_volume: Balance,
+		_commission: Balance,
+	) {
+	}
+}
+
 pub trait NftmartNft<AccountId, ClassId, TokenId> {
 	fn peek_next_class_id() -> ClassId;
 	fn transfer(
@@ -98,6 +130,8 @@ pub trait NftmartNft<AccountId, ClassId, TokenId> {
 		quantity: TokenId,
 		charge_royalty: Option<PerU16>,
 	) -> ResultPost<(AccountId, AccountId, ClassId, TokenId, TokenId)>;
+	fn mint_token_deposit(metadata_len: u32) -> Balance;
+	fn is_class_admin(who: &AccountId, class_id: ClassId) -> bool;
 }
 
 #[repr(u8)]
@@ -172,6 +206,85 @@ pub struct TokenData<AccountId, BlockNumber> {
 	pub royalty_beneficiary: AccountId,
 }
 
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, Default, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ClassSupplyCap<TokenId> {
+	/// The maximum number of tokens that can ever be minted in this class.
+	pub max_tokens: Option<TokenId>,
+	/// The maximum total issuance of this class.
+	pub max_supply: Option<TokenId>,
+}
+
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct TokenApproval<TokenId, BlockNumber> {
+	/// The quantity the spender is still allowed to transfer.
+	#[codec(compact)]
+	pub quantity: TokenId,
+	/// This approval will be invalidated after `deadline` block number.
+	#[codec(compact)]
+	pub deadline: BlockNumber,
+}
+
+/// How the native currency trading fees are split, the rest goes to the treasury.
+#[derive(Encode, Decode, Clone, Copy, Default, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct FeeDistribution {
+	/// The part of fees to be burned.
+	pub burn: PerU16,
+	/// The part of fees to be paid to stakers through the era payout.
+	pub staking: PerU16,
+}
+
+impl FeeDistribution {
+	pub fn is_valid(&self) -> bool {
+		(self.burn.deconstruct() as u32).saturating_add(self.staking.deconstruct() as u32) <=
+			PerU16::one().deconstruct() as u32
+	}
+}
+
+/// Anti-sniping policy of a British auction.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct SoftClose<BlockNumber> {
+	/// Once a bid is placed within `window` blocks before the deadline, the auction goes into
+	/// soft-close.
+	#[codec(compact)]
+	pub window: BlockNumber,
+	/// In soft-close, the auction ends `extension` blocks after the last bid.
+	#[codec(compact)]
+	pub extension: BlockNumber,
+	/// The deadline can be extended by at most `max_extension` blocks, `None` for no limit.
+	pub max_extension: Option<BlockNumber>,
+}
+
+/// How the price of a Dutch auction declines from `max_price` to `min_price`.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum DutchAuctionCurve<BlockNumber> {
+	/// Declines linearly until the deadline, stepping once every `interval` blocks.
+	Linear {
+		#[codec(compact)]
+		interval: BlockNumber,
+	},
+	/// Every `interval` blocks, the part of the price above `min_price` drops by `decay_rate`.
+	Exponential {
+		#[codec(compact)]
+		interval: BlockNumber,
+		#[codec(compact)]
+		decay_rate: PerU16,
+	},
+	/// A list of `(blocks after creation, price)`, sorted by block number.
+	/// The price is `max_price` before the first breakpoint.
+	Breakpoints(Vec<(BlockNumber, Balance)>),
+}
+
+impl<BlockNumber: From<constants_types::BlockNumber>> Default for DutchAuctionCurve<BlockNumber> {
+	fn default() -> Self {
+		Self::Linear { interval: DESC_INTERVAL.into() }
+	}
+}
+
 #[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct CategoryData {
@@ -196,6 +309,66 @@ pub struct OrderItem<ClassId, TokenId> {
 	pub quantity: TokenId,
 }
 
+/// The cost breakdown of a marketplace action.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+pub struct Quote<AccountId, CurrencyId> {
+	/// The currency of the price.
+	pub currency_id: CurrencyId,
+	/// The price paid by the buyer.
+	pub price: Balance,
+	/// The platform fee taken from the price.
+	pub platform_fee: Balance,
+	/// The royalty taken from the price.
+	pub royalty: Balance,
+	/// The receiver of the royalty.
+	pub royalty_beneficiary: Option<AccountId>,
+	/// The commission at the rate of the listing, taken from the price if `commission_paid`.
+	pub commission: Balance,
+	/// The commission agent.
+	pub commission_agent: Option<AccountId>,
+	/// The commission is only paid to a registered agent at a nonzero rate.
+	pub commission_paid: bool,
+	/// The balance reserved by the action, in the native currency for orders and mints, or in
+	/// `currency_id` for bids.
+	pub deposit: Balance,
+	/// The transaction fee in the native currency.
+	pub tx_fee: Balance,
+}
+
+/// The balances of an account changed by a dry run.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+pub struct BalanceChange<AccountId, CurrencyId> {
+	pub who: AccountId,
+	pub currency_id: CurrencyId,
+	/// The free and reserved balances before the call.
+	pub before: (Balance, Balance),
+	/// The free and reserved balances after the call.
+	pub after: (Balance, Balance),
+}
+
+/// The tokens of an account changed by a dry run.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+pub struct TokenChange<AccountId, ClassId, TokenId> {
+	pub who: AccountId,
+	pub class_id: ClassId,
+	pub token_id: TokenId,
+	pub before: Option<AccountToken<TokenId>>,
+	pub after: Option<AccountToken<TokenId>>,
+}
+
+/// The outcome of a call dispatched in a throw-away overlay, nothing is committed.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, TypeInfo)]
+pub struct DryRunOutcome<AccountId, CurrencyId, ClassId, TokenId, Event> {
+	/// The result of the call, the error message is only known to the runtime.
+	pub result: DispatchResult,
+	/// The events emitted by the call.
+	pub events: Vec<Event>,
+	/// The balances changed by the call, for the origin and the accounts in the events.
+	pub balances: Vec<BalanceChange<AccountId, CurrencyId>>,
+	/// The tokens changed by the call, for the origin and the accounts in the events.
+	pub tokens: Vec<TokenChange<AccountId, ClassId, TokenId>>,
+}
+
 #[cfg(feature = "std")]
 #[derive(
 	Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq, Serialize, Deserialize, Default, TypeInfo,
@@ -210,6 +383,13 @@ pub struct ClassConfig<ClassId, AccountId, TokenId> {
 	pub properties: u8,
 	pub admins: Vec<AccountId>,
 	pub tokens: Vec<TokenConfig<AccountId, TokenId>>,
+	#[serde(default)]
+	pub max_tokens: Option<TokenId>,
+	#[serde(default)]
+	pub max_supply: Option<TokenId>,
+	/// The minimum royalty rate of the tokens of the class.
+	#[serde(default)]
+	pub min_royalty: PerU16,
 }
 
 #[cfg(feature = "std")]
@@ -224,6 +404,10 @@ pub struct TokenConfig<AccountId, TokenId> {
 	pub token_creator: AccountId,
 	pub royalty_beneficiary: AccountId,
 	pub quantity: TokenId,
+	/// Other holders of the token, given their quantity out of the `quantity` minted to
+	/// `token_owner`.
+	#[serde(default)]
+	pub holders: Vec<(AccountId, TokenId)>,
 }
 
 /// Check only one royalty constrains.
@@ -250,44 +434,91 @@ where
 	Ok((count_of_charged_royalty, who, royalty_rate))
 }
 
-/// Swap assets between nfts owner and nfts purchaser.
+/// The platform fee, the royalty and the commission taken from `price` by [`swap_assets`].
+pub fn sale_fees(
+	price: Balance,
+	platform_fee_rate: PerU16,
+	royalty_rate: PerU16,
+	commission_rate: Option<PerU16>,
+) -> (Balance, Balance, Balance) {
+	let trading_fee = platform_fee_rate.mul_ceil(price);
+	let royalty_fee = royalty_rate.mul_ceil(price);
+	let commission = commission_rate
+		.map(|rate| rate.mul_ceil(price.saturating_sub(trading_fee).saturating_sub(royalty_fee)))
+		.unwrap_or_default();
+	(trading_fee, royalty_fee, commission)
+}
+
+/// Quote a sale made by [`swap_assets`], the deposit and the transaction fee are left zero.
+pub fn quote_sale<AccountId, CurrencyId>(
+	currency_id: CurrencyId,
+	price: Balance,
+	platform_fee_rate: PerU16,
+	beneficiary: AccountId,
+	royalty_rate: PerU16,
+	commission_agent: Option<(bool, AccountId, PerU16)>,
+) -> Quote<AccountId, CurrencyId> {
+	let (platform_fee, royalty, commission) = sale_fees(
+		price,
+		platform_fee_rate,
+		royalty_rate,
+		commission_agent.as_ref().map(|(_, _, rate)| *rate),
+	);
+	Quote {
+		currency_id,
+		price,
+		platform_fee,
+		royalty,
+		royalty_beneficiary: if royalty_rate.is_zero() { None } else { Some(beneficiary) },
+		commission,
+		commission_paid: matches!(commission_agent, Some((true, ..))),
+		commission_agent: commission_agent.map(|(_, agent, _)| agent),
+		deposit: 0,
+		tx_fee: 0,
+	}
+}
+
+/// Swap assets between nfts owner and nfts purchaser, returns the royalty paid.
 #[allow(clippy::too_many_arguments)]
-pub fn swap_assets<MultiCurrency, NFT, AccountId, ClassId, TokenId, CurrencyId>(
+pub fn swap_assets<MultiCurrency, NFT, Agent, FeeRouter, AccountId, ClassId, TokenId, CurrencyId>(
 	pay_currency: &AccountId,
 	pay_nfts: &AccountId,
 	currency_id: CurrencyId,
 	price: Balance,
 	items: &[(ClassId, TokenId, TokenId)],
-	treasury: &AccountId,
 	platform_fee_rate: PerU16,
 	beneficiary: &AccountId,
 	royalty_rate: PerU16,
 	commission_agent: &Option<(bool, AccountId, PerU16)>,
-) -> ResultPost<()>
+) -> ResultPost<Balance>
 where
 	MultiCurrency:
 		orml_traits::MultiCurrency<AccountId, CurrencyId = CurrencyId, Balance = Balance>,
 	NFT: NftmartNft<AccountId, ClassId, TokenId>,
+	Agent: NftmartCommissionAgent<AccountId, CurrencyId>,
+	FeeRouter: NftmartFeeRouter<AccountId, CurrencyId>,
 	ClassId: Copy,
 	TokenId: Copy,
 	CurrencyId: Copy,
 {
-	let trading_fee = platform_fee_rate.mul_ceil(price);
-	let royalty_fee = royalty_rate.mul_ceil(price);
+	let commission_rate = match commission_agent {
+		Some((true, _, rate)) => Some(*rate),
+		_ => None,
+	};
+	let (trading_fee, royalty_fee, commission) =
+		sale_fees(price, platform_fee_rate, royalty_rate, commission_rate);
 	MultiCurrency::transfer(currency_id, pay_currency, pay_nfts, price)?;
-	MultiCurrency::transfer(currency_id, pay_nfts, treasury, trading_fee)?;
+	FeeRouter::route_fee(pay_nfts, currency_id, trading_fee)?;
 	MultiCurrency::transfer(currency_id, pay_nfts, beneficiary, royalty_fee)?;
-	if let Some((status, agent, rate)) = commission_agent {
-		if *status {
-			let r = price.saturating_sub(trading_fee).saturating_sub(royalty_fee);
-			MultiCurrency::transfer(currency_id, pay_nfts, agent, rate.mul_ceil(r))?;
-		}
+	if let Some((true, agent, _)) = commission_agent {
+		MultiCurrency::transfer(currency_id, pay_nfts, agent, commission)?;
+		Agent::note_commission(agent, currency_id, price, commission);
 	}
 
 	for (class_id, token_id, quantity) in items {
 		NFT::transfer(pay_nfts, pay_currency, *class_id, *token_id, *quantity)?;
 	}
-	Ok(())
+	Ok(royalty_fee)
 }
 
 #[macro_export]
@@ -301,10 +532,7 @@ macro_rules! to_item_vec {
 
 		let commission_agent: Option<(bool, T::AccountId, PerU16)> =
 			$commission_agent.and_then(|ca| {
-				let b: Balance = <T as Config>::Currency::total_balance(&ca).saturated_into();
-				if b < T::ExtraConfig::get_min_commission_agent_deposit() ||
-					$obj.commission_rate.is_zero()
-				{
+				if !T::CommissionAgent::is_registered_agent(&ca) || $obj.commission_rate.is_zero() {
 					Some((false, ca, $obj.commission_rate))
 				} else {
 					Some((true, ca, $obj.commission_rate))
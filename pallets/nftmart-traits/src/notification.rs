@@ -0,0 +1,232 @@
+//! Marketplace notifications, recorded by the dispatchables through offchain indexing and posted
+//! to webhooks once per block by the offchain worker of `nftmart-config`.
+//!
+//! The records only exist on nodes started with `--enable-offchain-indexing true`.
+
+use crate::{
+	constants_types::{Balance, GlobalId},
+	webhook::Webhooks,
+};
+use codec::{Decode, Encode};
+use sp_runtime::{offchain::storage::StorageValueRef, KeyTypeId, RuntimeDebug};
+use sp_std::{fmt, fmt::Write, vec::Vec};
+
+/// The prefix of the webhook URLs and the delivery queue in the offchain local storage.
+pub const WEBHOOK_PREFIX: &[u8] = b"nftmart/webhook";
+
+/// The key type of the ed25519 key signing the webhook payloads.
+pub const WEBHOOK_KEY_TYPE: KeyTypeId = KeyTypeId(*b"nfwh");
+
+/// A change of an auction or an order which the accounts involved want to hear about.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub enum Notification<AccountId, CurrencyId> {
+	/// `bidder` bid `price` on the auction of `owner`, outbidding `outbid`.
+	Bid {
+		auction_id: GlobalId,
+		owner: AccountId,
+		bidder: AccountId,
+		outbid: Option<AccountId>,
+		currency_id: CurrencyId,
+		price: Balance,
+	},
+	/// The auction of `owner` is sold to `winner`.
+	AuctionWon {
+		auction_id: GlobalId,
+		owner: AccountId,
+		winner: AccountId,
+		currency_id: CurrencyId,
+		price: Balance,
+	},
+	/// The order of `owner` is taken by `taker`.
+	OrderTaken {
+		order_id: GlobalId,
+		owner: AccountId,
+		taker: AccountId,
+		currency_id: CurrencyId,
+		price: Balance,
+	},
+	/// The offer of `owner` is taken by `taker`.
+	OfferTaken {
+		offer_id: GlobalId,
+		owner: AccountId,
+		taker: AccountId,
+		currency_id: CurrencyId,
+		price: Balance,
+	},
+	/// The swap of `owner` is taken by `taker`.
+	SwapTaken { swap_id: GlobalId, owner: AccountId, taker: AccountId },
+	/// The order of `owner` expired and is removed.
+	OrderExpired { order_id: GlobalId, owner: AccountId },
+	/// The offer of `owner` expired and is removed.
+	OfferExpired { offer_id: GlobalId, owner: AccountId },
+}
+
+/// Writes the JSON of a notification.
+struct Json(Vec<u8>);
+
+impl Write for Json {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.0.extend_from_slice(s.as_bytes());
+		Ok(())
+	}
+}
+
+impl Json {
+	fn account<A: Encode>(&mut self, name: &str, who: &A) -> fmt::Result {
+		write!(self, ",\"{}\":\"0x", name)?;
+		for b in who.encode() {
+			write!(self, "{:02x}", b)?;
+		}
+		self.write_str("\"")
+	}
+
+	fn price<C: fmt::Debug>(&mut self, currency_id: &C, price: Balance) -> fmt::Result {
+		write!(self, ",\"currencyId\":{:?},\"price\":\"{}\"", currency_id, price)
+	}
+}
+
+impl<AccountId: Encode, CurrencyId: fmt::Debug> Notification<AccountId, CurrencyId> {
+	/// The JSON payload of the notification in block `block_number`, such as
+	/// `{"event":"orderTaken","block":10,"orderId":3,"owner":"0x..","taker":"0x..",
+	/// "currencyId":0,"price":"100"}`. Accounts are hex encoded and prices are strings.
+	pub fn to_json(&self, block_number: u64) -> Vec<u8> {
+		let mut out = Json(Vec::new());
+		// Writing to a `Vec` never fails.
+		let _ = self.write_json(&mut out, block_number);
+		out.0
+	}
+
+	fn write_json(&self, out: &mut Json, block_number: u64) -> fmt::Result {
+		let event = match self {
+			Self::Bid { .. } => "bid",
+			Self::AuctionWon { .. } => "auctionWon",
+			Self::OrderTaken { .. } => "orderTaken",
+			Self::OfferTaken { .. } => "offerTaken",
+			Self::SwapTaken { .. } => "swapTaken",
+			Self::OrderExpired { .. } => "orderExpired",
+			Self::OfferExpired { .. } => "offerExpired",
+		};
+		write!(out, "{{\"event\":\"{}\",\"block\":{}", event, block_number)?;
+		match self {
+			Self::Bid { auction_id, owner, bidder, outbid, currency_id, price } => {
+				write!(out, ",\"auctionId\":{}", auction_id)?;
+				out.account("owner", owner)?;
+				out.account("bidder", bidder)?;
+				match outbid {
+					Some(outbid) => out.account("outbid", outbid)?,
+					None => out.write_str(",\"outbid\":null")?,
+				}
+				out.price(currency_id, *price)?;
+			},
+			Self::AuctionWon { auction_id, owner, winner, currency_id, price } => {
+				write!(out, ",\"auctionId\":{}", auction_id)?;
+				out.account("owner", owner)?;
+				out.account("winner", winner)?;
+				out.price(currency_id, *price)?;
+			},
+			Self::OrderTaken { order_id, owner, taker, currency_id, price } => {
+				write!(out, ",\"orderId\":{}", order_id)?;
+				out.account("owner", owner)?;
+				out.account("taker", taker)?;
+				out.price(currency_id, *price)?;
+			},
+			Self::OfferTaken { offer_id, owner, taker, currency_id, price } => {
+				write!(out, ",\"offerId\":{}", offer_id)?;
+				out.account("owner", owner)?;
+				out.account("taker", taker)?;
+				out.price(currency_id, *price)?;
+			},
+			Self::SwapTaken { swap_id, owner, taker } => {
+				write!(out, ",\"swapId\":{}", swap_id)?;
+				out.account("owner", owner)?;
+				out.account("taker", taker)?;
+			},
+			Self::OrderExpired { order_id, owner } => {
+				write!(out, ",\"orderId\":{}", order_id)?;
+				out.account("owner", owner)?;
+			},
+			Self::OfferExpired { offer_id, owner } => {
+				write!(out, ",\"offerId\":{}", offer_id)?;
+				out.account("owner", owner)?;
+			},
+		}
+		out.write_str("}")
+	}
+}
+
+/// The offchain storage key of the notification going with the event `event_index` of block
+/// `block_number`, whose parent is `parent_hash`.
+///
+/// Offchain indexing writes the records of every imported block, the parent hash keeps the
+/// records of the blocks of different forks apart.
+pub fn notification_key(block_number: u64, parent_hash: &[u8], event_index: u32) -> Vec<u8> {
+	(b"nftmart/notification", block_number, parent_hash, event_index).encode()
+}
+
+/// Record the JSON payload of `notification`, going with the next event deposited in block
+/// `block_number`, whose parent is `parent_hash`, and whose index is `event_index`.
+pub fn note_notification<AccountId: Encode, CurrencyId: fmt::Debug>(
+	block_number: u64,
+	parent_hash: &[u8],
+	event_index: u32,
+	notification: &Notification<AccountId, CurrencyId>,
+) {
+	sp_io::offchain_index::set(
+		&notification_key(block_number, parent_hash, event_index),
+		&notification.to_json(block_number).encode(),
+	);
+}
+
+/// Take the payloads of the notifications recorded in block `block_number`, whose parent is
+/// `parent_hash`, which has `event_count` events. Only callable from an offchain worker.
+pub fn take_notifications(block_number: u64, parent_hash: &[u8], event_count: u32) -> Vec<Vec<u8>> {
+	(0..event_count)
+		.filter_map(|event_index| {
+			let key = notification_key(block_number, parent_hash, event_index);
+			let mut record = StorageValueRef::persistent(&key);
+			let payload = record.get::<Vec<u8>>().ok().flatten()?;
+			record.clear();
+			Some(payload)
+		})
+		.collect()
+}
+
+/// Post the notifications recorded in block `block_number`, whose parent is `parent_hash`, which
+/// has `event_count` events, to the webhooks, along with the earlier deliveries due for a retry.
+/// Only callable from an offchain worker, once per block.
+pub fn post_notifications(block_number: u64, parent_hash: &[u8], event_count: u32) {
+	let payloads = take_notifications(block_number, parent_hash, event_count);
+	let webhooks = Webhooks::new(WEBHOOK_PREFIX, WEBHOOK_KEY_TYPE);
+	if webhooks.urls().is_empty() {
+		return
+	}
+	if let Err(e) = webhooks.enqueue(&payloads).and_then(|_| webhooks.deliver()) {
+		log::warn!("Unable to post the notifications to the webhooks: {:?}", e);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn to_json_should_work() {
+		let bid = Notification::<[u8; 2], u32>::Bid {
+			auction_id: 7,
+			owner: [1, 2],
+			bidder: [0xab, 0xcd],
+			outbid: None,
+			currency_id: 0,
+			price: 100,
+		};
+		assert_eq!(
+			sp_std::str::from_utf8(&bid.to_json(5)).unwrap(),
+			r#"{"event":"bid","block":5,"auctionId":7,"owner":"0x0102","bidder":"0xabcd","outbid":null,"currencyId":0,"price":"100"}"#
+		);
+		let expired = Notification::<[u8; 1], u32>::OfferExpired { offer_id: 3, owner: [0xff] };
+		assert_eq!(
+			sp_std::str::from_utf8(&expired.to_json(9)).unwrap(),
+			r#"{"event":"offerExpired","block":9,"offerId":3,"owner":"0xff"}"#
+		);
+	}
+}
@@ -0,0 +1,364 @@
+//! Post signed payloads to webhooks from an offchain worker.
+//!
+//! The webhook URLs are a SCALE encoded `Vec<Vec<u8>>` kept in the persistent offchain local
+//! storage under `{prefix}/urls`, which can be set with the `offchain_localStorageSet` RPC.
+//! Payloads are queued under `{prefix}/queue` and posted by [`Webhooks::deliver`], and the
+//! failed deliveries are retried with an exponential backoff.
+//!
+//! Every payload is signed with the first ed25519 key of the key type of the [`Webhooks`] in the
+//! keystore, the hex encoded public key and signature are sent in the [`SIGNER_HEADER`] and
+//! [`SIGNATURE_HEADER`] headers. The due deliveries are posted at once, and a run of
+//! [`Webhooks::deliver`] waits [`DELIVERY_TIMEOUT_MS`] at most for all the answers.
+
+use codec::{Decode, Encode};
+use sp_runtime::{
+	offchain::{http, storage::StorageValueRef, Duration, Timestamp},
+	KeyTypeId, RuntimeDebug,
+};
+use sp_std::{prelude::*, str, vec};
+
+/// The header carrying the public key which signed the payload.
+pub const SIGNER_HEADER: &str = "X-Webhook-Signer";
+/// The header carrying the signature of the payload.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// The most deliveries waiting in the queue, the oldest ones are dropped beyond it.
+pub const MAX_QUEUE_LEN: usize = 1_000;
+/// The most deliveries attempted by a single [`Webhooks::deliver`].
+pub const MAX_DELIVERIES_PER_RUN: usize = 16;
+/// The most attempts of a delivery before it is dropped.
+pub const MAX_ATTEMPTS: u32 = 8;
+/// The delay before the first retry in milliseconds, doubled by every failed attempt.
+pub const BASE_BACKOFF_MS: u64 = 10_000;
+/// The time the webhooks are given to answer all the deliveries of a run, in milliseconds.
+pub const DELIVERY_TIMEOUT_MS: u64 = 3_000;
+
+/// Error which may occur while delivering the payloads.
+#[derive(PartialEq, Eq, RuntimeDebug)]
+pub enum WebhookError {
+	/// Failed to manipulate the offchain storage.
+	OffchainStore,
+	/// No key of the key type of the webhooks in the keystore.
+	SigningKeyNotFound,
+}
+
+/// A payload waiting to be posted to a webhook.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Delivery {
+	/// The URL of the webhook.
+	pub url: Vec<u8>,
+	/// The body of the request.
+	pub payload: Vec<u8>,
+	/// The failed attempts so far.
+	pub attempts: u32,
+	/// The unix time in milliseconds before which the delivery is not attempted.
+	pub next_attempt: u64,
+}
+
+/// The webhooks and the delivery queue under a prefix of the offchain local storage.
+pub struct Webhooks<'a> {
+	prefix: &'a [u8],
+	key_type: KeyTypeId,
+}
+
+impl<'a> Webhooks<'a> {
+	/// Webhooks stored under `prefix`, signing with the keys of `key_type`.
+	pub fn new(prefix: &'a [u8], key_type: KeyTypeId) -> Self {
+		Self { prefix, key_type }
+	}
+
+	fn key(&self, name: &[u8]) -> Vec<u8> {
+		[self.prefix, b"/", name].concat()
+	}
+
+	/// The URLs of the webhooks.
+	pub fn urls(&self) -> Vec<Vec<u8>> {
+		StorageValueRef::persistent(&self.key(b"urls"))
+			.get::<Vec<Vec<u8>>>()
+			.ok()
+			.flatten()
+			.unwrap_or_default()
+	}
+
+	/// Replace the URLs of the webhooks.
+	pub fn set_urls(&self, urls: &[Vec<u8>]) {
+		StorageValueRef::persistent(&self.key(b"urls")).set(&urls.to_vec());
+	}
+
+	/// The deliveries waiting in the queue.
+	pub fn queue(&self) -> Vec<Delivery> {
+		StorageValueRef::persistent(&self.key(b"queue"))
+			.get::<Vec<Delivery>>()
+			.ok()
+			.flatten()
+			.unwrap_or_default()
+	}
+
+	fn mutate_queue<R>(&self, f: impl FnOnce(&mut Vec<Delivery>) -> R) -> Result<R, WebhookError> {
+		let mut result = None;
+		StorageValueRef::persistent(&self.key(b"queue"))
+			.mutate(|queue: Result<Option<Vec<Delivery>>, _>| -> Result<_, ()> {
+				let mut queue = queue.ok().flatten().unwrap_or_default();
+				result = Some(f(&mut queue));
+				if queue.len() > MAX_QUEUE_LEN {
+					queue.drain(..queue.len() - MAX_QUEUE_LEN);
+				}
+				Ok(queue)
+			})
+			.map_err(|_| WebhookError::OffchainStore)?;
+		result.ok_or(WebhookError::OffchainStore)
+	}
+
+	/// Queue `payloads` for every webhook, doing nothing if there is none.
+	pub fn enqueue(&self, payloads: &[Vec<u8>]) -> Result<(), WebhookError> {
+		let urls = self.urls();
+		if urls.is_empty() || payloads.is_empty() {
+			return Ok(())
+		}
+		self.mutate_queue(|queue| {
+			for payload in payloads {
+				queue.extend(urls.iter().map(|url| Delivery {
+					url: url.clone(),
+					payload: payload.clone(),
+					attempts: 0,
+					next_attempt: 0,
+				}));
+			}
+		})
+	}
+
+	/// Post the deliveries which are due and return how many succeeded.
+	///
+	/// The failed deliveries are queued again with a doubled delay, and dropped after
+	/// [`MAX_ATTEMPTS`] attempts.
+	pub fn deliver(&self) -> Result<u32, WebhookError> {
+		let public = sp_io::crypto::ed25519_public_keys(self.key_type)
+			.into_iter()
+			.next()
+			.ok_or(WebhookError::SigningKeyNotFound)?;
+		let now = sp_io::offchain::timestamp();
+		let due = self.mutate_queue(|queue| {
+			let (mut due, waiting): (Vec<_>, Vec<_>) =
+				queue.drain(..).partition(|d| d.next_attempt <= now.unix_millis());
+			*queue = waiting;
+			if due.len() > MAX_DELIVERIES_PER_RUN {
+				queue.extend(due.drain(MAX_DELIVERIES_PER_RUN..));
+			}
+			due
+		})?;
+
+		let signer = hex(public.as_ref());
+		let deadline = now.add(Duration::from_millis(DELIVERY_TIMEOUT_MS));
+		let mut failed = Vec::new();
+		let mut sent = Vec::new();
+		for delivery in due {
+			let pending = sp_io::crypto::ed25519_sign(self.key_type, &public, &delivery.payload)
+				.ok_or(http::Error::Unknown)
+				.and_then(|signature| post(&delivery, &signer, &hex(signature.as_ref()), deadline));
+			match pending {
+				Ok(pending) => sent.push((delivery, pending)),
+				Err(_) => failed.push(delivery),
+			}
+		}
+
+		let (sent, pending): (Vec<_>, Vec<_>) = sent.into_iter().unzip();
+		let mut delivered = 0;
+		for (delivery, response) in
+			sent.into_iter().zip(http::PendingRequest::try_wait_all(pending, deadline))
+		{
+			match response {
+				Ok(Ok(response)) if (200..=299).contains(&response.code) => delivered += 1,
+				_ => failed.push(delivery),
+			}
+		}
+
+		let retries: Vec<Delivery> = failed
+			.into_iter()
+			.filter_map(|mut delivery| {
+				delivery.attempts += 1;
+				(delivery.attempts < MAX_ATTEMPTS).then(|| Delivery {
+					next_attempt: now
+						.add(Duration::from_millis(BASE_BACKOFF_MS << (delivery.attempts - 1)))
+						.unix_millis(),
+					..delivery
+				})
+			})
+			.collect();
+		if !retries.is_empty() {
+			self.mutate_queue(|queue| queue.extend(retries))?;
+		}
+		Ok(delivered)
+	}
+}
+
+/// Start posting a delivery, which must be answered before `deadline`.
+fn post(
+	delivery: &Delivery,
+	signer: &[u8],
+	signature: &[u8],
+	deadline: Timestamp,
+) -> Result<http::PendingRequest, http::Error> {
+	let url = str::from_utf8(&delivery.url).map_err(|_| http::Error::Unknown)?;
+	http::Request::post(url, vec![delivery.payload.clone()])
+		.add_header("Content-Type", "application/json")
+		.add_header(SIGNER_HEADER, str::from_utf8(signer).unwrap_or_default())
+		.add_header(SIGNATURE_HEADER, str::from_utf8(signature).unwrap_or_default())
+		.deadline(deadline)
+		.send()
+		.map_err(|_| http::Error::IoError)
+}
+
+/// `0x` followed by the lower case hex digits of `bytes`.
+pub fn hex(bytes: &[u8]) -> Vec<u8> {
+	const DIGITS: &[u8; 16] = b"0123456789abcdef";
+	let mut out = Vec::with_capacity(2 + bytes.len() * 2);
+	out.extend_from_slice(b"0x");
+	for b in bytes {
+		out.push(DIGITS[(b >> 4) as usize]);
+		out.push(DIGITS[(b & 0xf) as usize]);
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::RwLock;
+	use sp_core::{
+		ed25519,
+		offchain::{
+			testing::{OffchainState, PendingRequest, TestOffchainExt},
+			OffchainDbExt, OffchainWorkerExt,
+		},
+		Pair,
+	};
+	use sp_io::TestExternalities;
+	use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
+	use std::sync::Arc;
+
+	const KEY_TYPE: KeyTypeId = KeyTypeId(*b"test");
+	const SEED: &str = "//Webhook";
+	const URL: &str = "http://localhost:8080/hook";
+
+	fn new_test_ext(with_key: bool) -> (TestExternalities, Arc<RwLock<OffchainState>>) {
+		let (offchain, state) = TestOffchainExt::new();
+		let keystore = KeyStore::new();
+		if with_key {
+			SyncCryptoStore::ed25519_generate_new(&keystore, KEY_TYPE, Some(SEED)).unwrap();
+		}
+		let mut t = TestExternalities::default();
+		t.register_extension(OffchainDbExt::new(offchain.clone()));
+		t.register_extension(OffchainWorkerExt::new(offchain));
+		t.register_extension(KeystoreExt(Arc::new(keystore)));
+		(t, state)
+	}
+
+	/// Expect `body` to be posted to the local stand-in of a webhook.
+	fn expect_post(state: &RwLock<OffchainState>, body: &[u8]) {
+		let pair = ed25519::Pair::from_string(SEED, None).unwrap();
+		let header = |bytes: &[u8]| String::from_utf8(hex(bytes)).unwrap();
+		state.write().expect_request(PendingRequest {
+			method: "POST".into(),
+			uri: URL.into(),
+			headers: vec![
+				("Content-Type".into(), "application/json".into()),
+				(SIGNER_HEADER.into(), header(pair.public().as_ref())),
+				(SIGNATURE_HEADER.into(), header(pair.sign(body).as_ref())),
+			],
+			body: body.to_vec(),
+			response: Some(vec![]),
+			sent: true,
+			..Default::default()
+		});
+	}
+
+	#[test]
+	fn hex_should_work() {
+		assert_eq!(hex(&[]), b"0x".to_vec());
+		assert_eq!(hex(&[0, 0x1f, 0xff]), b"0x001fff".to_vec());
+	}
+
+	#[test]
+	fn enqueue_without_urls_should_do_nothing() {
+		let (mut t, _) = new_test_ext(true);
+		t.execute_with(|| {
+			let webhooks = Webhooks::new(b"test/webhook", KEY_TYPE);
+			assert_eq!(webhooks.enqueue(&[b"{}".to_vec()]), Ok(()));
+			assert!(webhooks.queue().is_empty());
+		});
+	}
+
+	#[test]
+	fn deliver_should_work() {
+		let (mut t, state) = new_test_ext(true);
+		t.execute_with(|| {
+			let webhooks = Webhooks::new(b"test/webhook", KEY_TYPE);
+			webhooks.set_urls(&[URL.as_bytes().to_vec()]);
+			assert_eq!(webhooks.enqueue(&[br#"{"a":1}"#.to_vec(), br#"{"b":2}"#.to_vec()]), Ok(()));
+			assert_eq!(webhooks.queue().len(), 2);
+
+			expect_post(&state, br#"{"a":1}"#);
+			expect_post(&state, br#"{"b":2}"#);
+			assert_eq!(webhooks.deliver(), Ok(2));
+			assert!(webhooks.queue().is_empty());
+		});
+	}
+
+	#[test]
+	fn deliver_should_retry_with_backoff() {
+		let (mut t, state) = new_test_ext(true);
+		t.execute_with(|| {
+			let webhooks = Webhooks::new(b"test/webhook", KEY_TYPE);
+			// Not a valid URL, so that every attempt fails.
+			webhooks.set_urls(&[vec![0xff]]);
+			assert_eq!(webhooks.enqueue(&[b"{}".to_vec()]), Ok(()));
+
+			let mut now = 0;
+			for attempts in 1..MAX_ATTEMPTS {
+				state.write().timestamp = Timestamp::from_unix_millis(now);
+				assert_eq!(webhooks.deliver(), Ok(0));
+				let queue = webhooks.queue();
+				assert_eq!(queue.len(), 1);
+				assert_eq!(queue[0].attempts, attempts);
+				assert_eq!(queue[0].next_attempt, now + (BASE_BACKOFF_MS << (attempts - 1)));
+
+				// Not due yet.
+				state.write().timestamp = Timestamp::from_unix_millis(queue[0].next_attempt - 1);
+				assert_eq!(webhooks.deliver(), Ok(0));
+				assert_eq!(webhooks.queue()[0].attempts, attempts);
+				now = queue[0].next_attempt;
+			}
+
+			// Dropped after the last attempt.
+			state.write().timestamp = Timestamp::from_unix_millis(now);
+			assert_eq!(webhooks.deliver(), Ok(0));
+			assert!(webhooks.queue().is_empty());
+		});
+	}
+
+	#[test]
+	fn enqueue_should_drop_oldest() {
+		let (mut t, _) = new_test_ext(true);
+		t.execute_with(|| {
+			let webhooks = Webhooks::new(b"test/webhook", KEY_TYPE);
+			webhooks.set_urls(&[URL.as_bytes().to_vec()]);
+			let payloads: Vec<Vec<u8>> =
+				(0..MAX_QUEUE_LEN as u32 + 1).map(|i| i.encode()).collect();
+			assert_eq!(webhooks.enqueue(&payloads), Ok(()));
+			let queue = webhooks.queue();
+			assert_eq!(queue.len(), MAX_QUEUE_LEN);
+			assert_eq!(queue[0].payload, 1u32.encode());
+		});
+	}
+
+	#[test]
+	fn deliver_without_key_should_fail() {
+		let (mut t, _) = new_test_ext(false);
+		t.execute_with(|| {
+			let webhooks = Webhooks::new(b"test/webhook", KEY_TYPE);
+			webhooks.set_urls(&[URL.as_bytes().to_vec()]);
+			assert_eq!(webhooks.enqueue(&[b"{}".to_vec()]), Ok(()));
+			assert_eq!(webhooks.deliver(), Err(WebhookError::SigningKeyNotFound));
+			assert_eq!(webhooks.queue().len(), 1);
+		});
+	}
+}
@@ -14,13 +14,24 @@ interface INftmartNft {
     function tokenMetadata(uint _classId, uint _tokenId) external view returns (string memory);
     function tokenName(uint _classId, uint _tokenId) external view returns (string memory);
     function ownerOfToken(uint _classId, uint _tokenId) external view returns (bytes32);
+    function userOf(uint _classId, uint _tokenId, bytes32 _lender) external view returns (bytes32, uint256);
+    // The quantity `_spender` can still transfer, zero once the approval expires.
+    function allowance(bytes32 _owner, bytes32 _spender, uint _classId, uint _tokenId) external view returns (uint256);
+    function isApprovedForAll(bytes32 _owner, bytes32 _operator) external view returns (bool);
     // write
+    function approve(bytes32 _spender, uint _classId, uint _tokenId, uint _quantity, uint _deadline) external;
+    function approveForAll(bytes32 _operator, uint _deadline) external;
     function burn(uint _classId, uint tokenId, uint _quantity) external;
     function createClass(string memory _metadata, string memory _name, string memory _description, uint _royaltyRate, uint8 _properties, uint[] memory _categoryIds) external returns (uint256);
+    // `_maxTokens` and `_maxSupply` are zero for unbounded.
+    function createClassWithSupplyCap(string memory _metadata, string memory _name, string memory _description, uint _royaltyRate, uint8 _properties, uint[] memory _categoryIds, uint _maxTokens, uint _maxSupply) external returns (uint256);
     function destroyClass(uint _classId, bytes32 _dest) external;
     function mint(bytes32 _to, uint _classId, string memory _metadata, uint _quantity, uint _chargeRoyalty) external;
     function proxyMint(bytes32 _to, uint _classId, string memory _metadata, uint _quantity, uint _chargeRoyalty) external;
+    function revoke(bytes32 _spender, uint _classId, uint _tokenId) external;
+    function revokeForAll(bytes32 _operator) external;
     function transfer(bytes32 _to, NftItem[] memory _items) external;
+    function transferFrom(bytes32 _from, bytes32 _to, NftItem[] memory _items) external;
     function updateClass(uint _classId, string memory _metadata, string memory _name, string memory _description, uint _royaltyRate, uint8 _properties, uint[] memory _categoryIds) external;
     function updateToken(bytes32 _to, uint _classId, uint _tokenId, uint _quantity, string memory _metadata, uint _chargeRoyalty) external;
     function updateTokenMetadata(uint _classId, uint _tokenId, string memory _metadata) external;
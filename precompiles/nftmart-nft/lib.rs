@@ -5,7 +5,10 @@
 
 use frame_support::{
 	dispatch::{Dispatchable, GetDispatchInfo, PostDispatchInfo},
-	sp_runtime::traits::{IdentifyAccount, StaticLookup},
+	sp_runtime::{
+		traits::{IdentifyAccount, StaticLookup},
+		SaturatedConversion,
+	},
 };
 use nftmart_nft::Call as NftCall;
 use pallet_evm::{AddressMapping, ExitSucceed, Precompile};
@@ -33,24 +36,34 @@ pub type NftItem = (u32, u64, u64);
 #[precompile_utils::generate_function_selector]
 #[derive(Debug, PartialEq)]
 enum Action {
+	Allowance = "allowance(bytes32,bytes32,uint256,uint256)",
+	Approve = "approve(bytes32,uint256,uint256,uint256,uint256)",
+	ApproveForAll = "approveForAll(bytes32,uint256)",
 	Burn = "burn(uint256,uint256,uint256)",
 	CreateClass = "createClass(string,string,string,uint256,uint8,uint256[])",
+	CreateClassWithSupplyCap =
+		"createClassWithSupplyCap(string,string,string,uint256,uint8,uint256[],uint256,uint256)",
 	DestroyClass = "destroyClass(uint256,bytes32)",
+	IsApprovedForAll = "isApprovedForAll(bytes32,bytes32)",
 	Mint = "mint(bytes32,uint256,string,uint256,uint256)",
 	ProxyMint = "proxyMint(bytes32,uint256,string,uint256,uint256)",
+	Revoke = "revoke(bytes32,uint256,uint256)",
+	RevokeForAll = "revokeForAll(bytes32)",
 	Transfer = "transfer(bytes32,(uint256,uint256,uint256)[])",
+	TransferFrom = "transferFrom(bytes32,bytes32,(uint256,uint256,uint256)[])",
 	UpdateClass = "updateClass(uint256,string,string,string,uint256,uint8,uint256[])",
 	UpdateToken = "updateToken(bytes32,uint256,uint256,uint256,string,uint256)",
 	UpdateTokenMetadata = "updateTokenMetadata(uint256,uint256,string)",
 	UpdateTokenRoyalty = "updateTokenRoyalty(uint256,uint256,uint256)",
 	UpdateTokenRoyaltyBeneficiary = "updateTokenRoyaltyBeneficiary(uint256,uint256,bytes32)",
+	UserOf = "userOf(uint256,uint256,bytes32)",
 }
 
 pub struct NftmartNftPrecompile<T>(PhantomData<T>);
 
 impl<T> Precompile for NftmartNftPrecompile<T>
 where
-	T: pallet_evm::Config + nftmart_nft::Config + orml_nft::Config,
+	T: pallet_evm::Config + nftmart_nft::Config + nftmart_rental::Config + orml_nft::Config,
 	<T as frame_system::Config>::Call:
 		Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo + From<NftCall<T>>,
 	<<T as frame_system::Config>::Call as Dispatchable>::Origin:
@@ -80,12 +93,22 @@ where
 
 		match selector {
 			// Check for accessor methods first. These return results immediately
+			Action::Allowance => Self::allowance(&mut input, &mut gasometer, context),
+			Action::Approve => Self::approve(&mut input, &mut gasometer, context),
+			Action::ApproveForAll => Self::approve_for_all(&mut input, &mut gasometer, context),
 			Action::Burn => Self::burn(&mut input, &mut gasometer, context),
-			Action::CreateClass => Self::create_class(&mut input, &mut gasometer, context),
+			Action::CreateClass => Self::create_class(&mut input, &mut gasometer, context, false),
+			Action::CreateClassWithSupplyCap =>
+				Self::create_class(&mut input, &mut gasometer, context, true),
 			Action::DestroyClass => Self::destroy_class(&mut input, &mut gasometer, context),
+			Action::IsApprovedForAll =>
+				Self::is_approved_for_all(&mut input, &mut gasometer, context),
 			Action::Mint => Self::mint(&mut input, &mut gasometer, context),
 			Action::ProxyMint => Self::proxy_mint(&mut input, &mut gasometer, context),
+			Action::Revoke => Self::revoke(&mut input, &mut gasometer, context),
+			Action::RevokeForAll => Self::revoke_for_all(&mut input, &mut gasometer, context),
 			Action::Transfer => Self::transfer(&mut input, &mut gasometer, context),
+			Action::TransferFrom => Self::transfer_from(&mut input, &mut gasometer, context),
 			Action::UpdateClass => Self::update_class(&mut input, &mut gasometer, context),
 			Action::UpdateToken => Self::update_token(&mut input, &mut gasometer, context),
 			Action::UpdateTokenMetadata =>
@@ -94,6 +117,7 @@ where
 				Self::update_token_royalty(&mut input, &mut gasometer, context),
 			Action::UpdateTokenRoyaltyBeneficiary =>
 				Self::update_token_royalty_beneficiary(&mut input, &mut gasometer, context),
+			Action::UserOf => Self::user_of(&mut input, &mut gasometer, context),
 		}
 	}
 }
@@ -107,7 +131,7 @@ pub type BalanceOf<Runtime> =
 
 impl<T> NftmartNftPrecompile<T>
 where
-	T: pallet_evm::Config + nftmart_nft::Config + orml_nft::Config,
+	T: pallet_evm::Config + nftmart_nft::Config + nftmart_rental::Config + orml_nft::Config,
 	// `<<T as frame_system::Config>::Call as Dispatchable>::PostInfo = PostDispatchInfo`
 	// `<T as frame_system::Config>::Call: GetDispatchInfo`
 	// T: pallet_evm::Config + pallet_balances::Config,
@@ -295,13 +319,16 @@ where
 		})
 	}
 
+	/// `createClass`, or `createClassWithSupplyCap` whose last two arguments are the maximum
+	/// number of tokens and the maximum total issuance, zero for unbounded.
 	fn create_class(
 		input: &mut EvmDataReader,
 		gasometer: &mut Gasometer,
 		context: &Context,
+		with_supply_cap: bool,
 	) -> EvmResult<PrecompileOutput> {
 		// Bound check. We expect a single argument passed in.
-		input.expect_arguments(gasometer, 6)?;
+		input.expect_arguments(gasometer, if with_supply_cap { 8 } else { 6 })?;
 
 		let origin: <T as frame_system::pallet::Config>::AccountId =
 			T::AddressMapping::into_account_id(context.caller);
@@ -326,6 +353,11 @@ where
 		let properties: u8 = input.read::<u8>(gasometer)?.into();
 
 		let category_ids: Vec<u64> = input.read::<Vec<u64>>(gasometer)?.into();
+		let (max_tokens, max_supply): (u64, u64) = if with_supply_cap {
+			(input.read::<u64>(gasometer)?, input.read::<u64>(gasometer)?)
+		} else {
+			(0, 0)
+		};
 		// how do I convert sp_core::sr25519::Public to AccountId?
 		//
 		// AccountId is defined in ../../pallets/nftmart-traits/src/constants_types.rs
@@ -338,14 +370,34 @@ where
 		log::debug!(target: "nftmart-evm", "royalty_rate: {:?}", &royalty_rate);
 		log::debug!(target: "nftmart-evm", "properties: {:?}", &properties);
 		log::debug!(target: "nftmart-evm", "category_ids: {:?}", &category_ids);
-
-		let call = NftCall::<T>::create_class {
-			metadata: metadata.into(),
-			name: name.into(),
-			description: description.into(),
-			royalty_rate: PerU16::from_parts(royalty_rate.try_into().unwrap()),
-			properties: Properties(ClassProperty::Transferable.into()), // TODO: use real properties,
-			category_ids,
+		log::debug!(target: "nftmart-evm", "max_tokens: {:?}", &max_tokens);
+		log::debug!(target: "nftmart-evm", "max_supply: {:?}", &max_supply);
+
+		let metadata = metadata.into();
+		let name = name.into();
+		let description = description.into();
+		let royalty_rate = PerU16::from_parts(royalty_rate.try_into().unwrap());
+		let properties = Properties(ClassProperty::Transferable.into()); // TODO: use real properties,
+		let call = if with_supply_cap {
+			NftCall::<T>::create_class_with_supply_cap {
+				metadata,
+				name,
+				description,
+				royalty_rate,
+				properties,
+				category_ids,
+				max_tokens: (max_tokens != 0).then(|| max_tokens.saturated_into()),
+				max_supply: (max_supply != 0).then(|| max_supply.saturated_into()),
+			}
+		} else {
+			NftCall::<T>::create_class {
+				metadata,
+				name,
+				description,
+				royalty_rate,
+				properties,
+				category_ids,
+			}
 		};
 
 		let class_id = orml_nft::NextClassId::<T>::get();
@@ -462,6 +514,205 @@ where
 		})
 	}
 
+	fn transfer_from(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		// Bound check. We expect three arguments passed in.
+		input.expect_arguments(gasometer, 3)?;
+
+		let origin: <T as frame_system::pallet::Config>::AccountId =
+			T::AddressMapping::into_account_id(context.caller);
+
+		log::debug!(target: "nftmart-evm", "from(evm): {:?}", &origin);
+
+		let from: H256 = input.read::<H256>(gasometer)?;
+		let from: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(from.0);
+		let to: H256 = input.read::<H256>(gasometer)?;
+		let to: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(to.0);
+
+		let items: Vec<NftItem> = input.read::<Vec<NftItem>>(gasometer)?.into();
+		log::debug!(target: "nftmart-evm", "from: {:?}", &from);
+		log::debug!(target: "nftmart-evm", "to: {:?}", &to);
+		log::debug!(target: "nftmart-evm", "items: {:?}", &items);
+
+		let call = NftCall::<T>::transfer_from {
+			from: <T as frame_system::Config>::Lookup::unlookup(from),
+			to: <T as frame_system::Config>::Lookup::unlookup(to),
+			items: items
+				.into_iter()
+				.map(|(class_id, token_id, quantity)| {
+					(class_id.into(), token_id.saturated_into(), quantity.saturated_into())
+				})
+				.collect(),
+		};
+
+		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Stopped,
+			cost: gasometer.used_gas(),
+			output: Default::default(),
+			logs: Default::default(),
+		})
+	}
+
+	fn approve(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		// Bound check. We expect five arguments passed in.
+		input.expect_arguments(gasometer, 5)?;
+
+		let origin: <T as frame_system::pallet::Config>::AccountId =
+			T::AddressMapping::into_account_id(context.caller);
+
+		log::debug!(target: "nftmart-evm", "from(evm): {:?}", &origin);
+
+		let spender: H256 = input.read::<H256>(gasometer)?;
+		let spender: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(spender.0);
+		let class_id: u32 = input.read::<u32>(gasometer)?.into();
+		let token_id: u64 = input.read::<u64>(gasometer)?.into();
+		let quantity: u64 = input.read::<u64>(gasometer)?.into();
+		let deadline: u32 = input.read::<u32>(gasometer)?.into();
+
+		log::debug!(target: "nftmart-evm", "spender: {:?}", &spender);
+		log::debug!(target: "nftmart-evm", "classId: {:?}", &class_id);
+		log::debug!(target: "nftmart-evm", "tokenId: {:?}", &token_id);
+		log::debug!(target: "nftmart-evm", "quantity: {:?}", &quantity);
+		log::debug!(target: "nftmart-evm", "deadline: {:?}", &deadline);
+
+		let call = NftCall::<T>::approve {
+			spender: <T as frame_system::Config>::Lookup::unlookup(spender),
+			class_id: class_id.into(),
+			token_id: token_id.saturated_into(),
+			quantity: quantity.saturated_into(),
+			deadline: deadline.into(),
+		};
+
+		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Stopped,
+			cost: gasometer.used_gas(),
+			output: Default::default(),
+			logs: Default::default(),
+		})
+	}
+
+	fn revoke(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		// Bound check. We expect three arguments passed in.
+		input.expect_arguments(gasometer, 3)?;
+
+		let origin: <T as frame_system::pallet::Config>::AccountId =
+			T::AddressMapping::into_account_id(context.caller);
+
+		log::debug!(target: "nftmart-evm", "from(evm): {:?}", &origin);
+
+		let spender: H256 = input.read::<H256>(gasometer)?;
+		let spender: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(spender.0);
+		let class_id: u32 = input.read::<u32>(gasometer)?.into();
+		let token_id: u64 = input.read::<u64>(gasometer)?.into();
+
+		log::debug!(target: "nftmart-evm", "spender: {:?}", &spender);
+		log::debug!(target: "nftmart-evm", "classId: {:?}", &class_id);
+		log::debug!(target: "nftmart-evm", "tokenId: {:?}", &token_id);
+
+		let call = NftCall::<T>::revoke {
+			spender: <T as frame_system::Config>::Lookup::unlookup(spender),
+			class_id: class_id.into(),
+			token_id: token_id.saturated_into(),
+		};
+
+		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Stopped,
+			cost: gasometer.used_gas(),
+			output: Default::default(),
+			logs: Default::default(),
+		})
+	}
+
+	fn approve_for_all(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		// Bound check. We expect two arguments passed in.
+		input.expect_arguments(gasometer, 2)?;
+
+		let origin: <T as frame_system::pallet::Config>::AccountId =
+			T::AddressMapping::into_account_id(context.caller);
+
+		log::debug!(target: "nftmart-evm", "from(evm): {:?}", &origin);
+
+		let operator: H256 = input.read::<H256>(gasometer)?;
+		let operator: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(operator.0);
+		let deadline: u32 = input.read::<u32>(gasometer)?.into();
+
+		log::debug!(target: "nftmart-evm", "operator: {:?}", &operator);
+		log::debug!(target: "nftmart-evm", "deadline: {:?}", &deadline);
+
+		let call = NftCall::<T>::approve_for_all {
+			operator: <T as frame_system::Config>::Lookup::unlookup(operator),
+			deadline: deadline.into(),
+		};
+
+		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Stopped,
+			cost: gasometer.used_gas(),
+			output: Default::default(),
+			logs: Default::default(),
+		})
+	}
+
+	fn revoke_for_all(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		// Bound check. We expect a single argument passed in.
+		input.expect_arguments(gasometer, 1)?;
+
+		let origin: <T as frame_system::pallet::Config>::AccountId =
+			T::AddressMapping::into_account_id(context.caller);
+
+		log::debug!(target: "nftmart-evm", "from(evm): {:?}", &origin);
+
+		let operator: H256 = input.read::<H256>(gasometer)?;
+		let operator: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(operator.0);
+
+		log::debug!(target: "nftmart-evm", "operator: {:?}", &operator);
+
+		let call = NftCall::<T>::revoke_for_all {
+			operator: <T as frame_system::Config>::Lookup::unlookup(operator),
+		};
+
+		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Stopped,
+			cost: gasometer.used_gas(),
+			output: Default::default(),
+			logs: Default::default(),
+		})
+	}
+
 	fn update_class(
 		input: &mut EvmDataReader,
 		gasometer: &mut Gasometer,
@@ -711,4 +962,111 @@ where
 			logs: Default::default(),
 		})
 	}
+	fn user_of(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		_context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		gasometer.record_cost(RuntimeHelper::<T>::db_read_gas_cost())?;
+
+		// Bound check. We expect three arguments passed in.
+		input.expect_arguments(gasometer, 3)?;
+
+		let class_id: u32 = input.read::<u32>(gasometer)?.into();
+		let token_id: u64 = input.read::<u64>(gasometer)?.into();
+		let lender = input.read::<H256>(gasometer)?;
+		let lender: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(lender.0);
+
+		log::debug!(target: "nftmart-evm", "classId: {:?}", &class_id);
+		log::debug!(target: "nftmart-evm", "tokenId: {:?}", &token_id);
+		log::debug!(target: "nftmart-evm", "lender: {:?}", &lender);
+
+		// A zero user means the token is not rented out.
+		let (user, expires) = match nftmart_rental::Pallet::<T>::user_of(
+			class_id.into(),
+			token_id.saturated_into(),
+			&lender,
+		) {
+			Some((user, expires)) => (H256::from(user.into()), expires.saturated_into::<u64>()),
+			None => (H256::zero(), 0),
+		};
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			cost: gasometer.used_gas(),
+			output: EvmDataWriter::new().write(user).write(expires).build(),
+			logs: Default::default(),
+		})
+	}
+	fn allowance(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		_context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		gasometer.record_cost(RuntimeHelper::<T>::db_read_gas_cost())?;
+
+		// Bound check. We expect four arguments passed in.
+		input.expect_arguments(gasometer, 4)?;
+
+		let owner = input.read::<H256>(gasometer)?;
+		let owner: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(owner.0);
+		let spender = input.read::<H256>(gasometer)?;
+		let spender: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(spender.0);
+		let class_id: u32 = input.read::<u32>(gasometer)?.into();
+		let token_id: u64 = input.read::<u64>(gasometer)?.into();
+
+		log::debug!(target: "nftmart-evm", "owner: {:?}", &owner);
+		log::debug!(target: "nftmart-evm", "spender: {:?}", &spender);
+		log::debug!(target: "nftmart-evm", "classId: {:?}", &class_id);
+		log::debug!(target: "nftmart-evm", "tokenId: {:?}", &token_id);
+
+		// The allowance is zero once the approval expires.
+		let quantity: u64 = nftmart_nft::Pallet::<T>::allowance(
+			&owner,
+			&spender,
+			class_id.into(),
+			token_id.saturated_into(),
+		)
+		.saturated_into();
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			cost: gasometer.used_gas(),
+			output: EvmDataWriter::new().write(quantity).build(),
+			logs: Default::default(),
+		})
+	}
+
+	fn is_approved_for_all(
+		input: &mut EvmDataReader,
+		gasometer: &mut Gasometer,
+		_context: &Context,
+	) -> EvmResult<PrecompileOutput> {
+		gasometer.record_cost(RuntimeHelper::<T>::db_read_gas_cost())?;
+
+		// Bound check. We expect two arguments passed in.
+		input.expect_arguments(gasometer, 2)?;
+
+		let owner = input.read::<H256>(gasometer)?;
+		let owner: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(owner.0);
+		let operator = input.read::<H256>(gasometer)?;
+		let operator: <T as frame_system::Config>::AccountId =
+			<T as frame_system::Config>::AccountId::from(operator.0);
+
+		log::debug!(target: "nftmart-evm", "owner: {:?}", &owner);
+		log::debug!(target: "nftmart-evm", "operator: {:?}", &operator);
+
+		let approved = nftmart_nft::Pallet::<T>::is_approved_for_all(&owner, &operator);
+
+		Ok(PrecompileOutput {
+			exit_status: ExitSucceed::Returned,
+			cost: gasometer.used_gas(),
+			output: EvmDataWriter::new().write(approved).build(),
+			logs: Default::default(),
+		})
+	}
 }
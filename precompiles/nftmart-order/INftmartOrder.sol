@@ -9,4 +9,6 @@ interface INftmartOrder {
     function submitOrder(uint _currencyId, uint256 _deposit, uint256 _price, uint _deadline, uint[3][] memory _items, uint _commissionRate) external;
     function takeOffer(uint _offerId, bytes32 _offerOwner, bytes32 _commissionAgent, string memory _commissionData) external;
     function takeOrder(uint _orderId, bytes32 _orderOwner, bytes32 _commissionAgent, string memory _commissionData) external;
+    function takeOfferWithMinPrice(uint _offerId, bytes32 _offerOwner, uint _minPrice, bytes32 _commissionAgent, string memory _commissionData) external;
+    function takeOrderWithMaxPrice(uint _orderId, bytes32 _orderOwner, uint _maxPrice, bytes32 _commissionAgent, string memory _commissionData) external;
 }
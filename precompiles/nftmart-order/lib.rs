@@ -28,6 +28,8 @@ enum Action {
 	SubmitOrder = "submitOrder(uint256,uint256,uint256,uint256,uint256[3][],uint256)",
 	TakeOffer = "takeOffer(uint256,bytes32,bytes32,string)",
 	TakeOrder = "takeOrder(uint256,bytes32,bytes32,string)",
+	TakeOfferWithMinPrice = "takeOfferWithMinPrice(uint256,bytes32,uint256,bytes32,string)",
+	TakeOrderWithMaxPrice = "takeOrderWithMaxPrice(uint256,bytes32,uint256,bytes32,string)",
 }
 
 pub struct NftmartOrderPrecompile<T>(PhantomData<T>);
@@ -66,8 +68,12 @@ where
 			// Check for accessor methods first. These return results immediately
 			Action::RemoveOffer => Self::remove_offer(&mut input, &mut gasometer, context),
 			Action::RemoveOrder => Self::remove_order(&mut input, &mut gasometer, context),
-			Action::TakeOffer => Self::take_offer(&mut input, &mut gasometer, context),
-			Action::TakeOrder => Self::take_order(&mut input, &mut gasometer, context),
+			Action::TakeOffer => Self::take_offer(&mut input, &mut gasometer, context, false),
+			Action::TakeOrder => Self::take_order(&mut input, &mut gasometer, context, false),
+			Action::TakeOfferWithMinPrice =>
+				Self::take_offer(&mut input, &mut gasometer, context, true),
+			Action::TakeOrderWithMaxPrice =>
+				Self::take_order(&mut input, &mut gasometer, context, true),
 			/*
 			Action::SubmitOffer => Self::submit_offer(&mut input, &mut gasometer, context),
 			Action::SubmitOrder => Self::submit_order(&mut input, &mut gasometer, context),
@@ -169,13 +175,18 @@ where
 	 *
 	function takeOffer(uint _offerId, bytes32 _offerOwner, bytes32 _commissionAgent, string memory _commissionData) external;
 	function takeOrder(uint _orderId, bytes32 _orderOwner, bytes32 _commissionAgent, string memory _commissionData) external;
+	function takeOfferWithMinPrice(uint _offerId, bytes32 _offerOwner, uint _minPrice, bytes32 _commissionAgent, string memory _commissionData) external;
+	function takeOrderWithMaxPrice(uint _orderId, bytes32 _orderOwner, uint _maxPrice, bytes32 _commissionAgent, string memory _commissionData) external;
 	*/
+	/// `takeOrder` accepts the current price of the order, `takeOrderWithMaxPrice` fails if the
+	/// price is higher than `_maxPrice`.
 	fn take_order(
 		input: &mut EvmDataReader,
 		gasometer: &mut Gasometer,
 		context: &Context,
+		with_max_price: bool,
 	) -> EvmResult<PrecompileOutput> {
-		input.expect_arguments(gasometer, 4)?;
+		input.expect_arguments(gasometer, if with_max_price { 5 } else { 4 })?;
 
 		let origin: <T as frame_system::pallet::Config>::AccountId =
 			T::AddressMapping::into_account_id(context.caller);
@@ -190,6 +201,7 @@ where
 		let order_owner = input.read::<H256>(gasometer)?;
 		let order_owner: <T as frame_system::Config>::AccountId =
 			<T as frame_system::Config>::AccountId::from(order_owner.0);
+		let max_price = if with_max_price { Some(input.read::<u128>(gasometer)?) } else { None };
 		let commission_agent = input.read::<H256>(gasometer)?;
 		let commission_agent: <T as frame_system::Config>::AccountId =
 			<T as frame_system::Config>::AccountId::from(commission_agent.0);
@@ -200,11 +212,21 @@ where
 		log::debug!(target: "nftmart-evm", "commissionAgent: {:?}", &commission_agent);
 		log::debug!(target: "nftmart-evm", "commissionData: {:?}", &commission_data);
 
-		let call = OrderCall::<T>::take_order {
-			order_id: order_id.into(),
-			order_owner: <T as frame_system::Config>::Lookup::unlookup(order_owner),
-			commission_agent: Some(commission_agent),
-			commission_data: Some(commission_data.into()),
+		let order_owner = <T as frame_system::Config>::Lookup::unlookup(order_owner);
+		let call = match max_price {
+			Some(max_price) => OrderCall::<T>::take_order_with_max_price {
+				order_id: order_id.into(),
+				order_owner,
+				max_price,
+				commission_agent: Some(commission_agent),
+				commission_data: Some(commission_data.into()),
+			},
+			None => OrderCall::<T>::take_order {
+				order_id: order_id.into(),
+				order_owner,
+				commission_agent: Some(commission_agent),
+				commission_data: Some(commission_data.into()),
+			},
 		};
 
 		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;
@@ -217,12 +239,15 @@ where
 		})
 	}
 
+	/// `takeOffer` accepts the current price of the offer, `takeOfferWithMinPrice` fails if the
+	/// price is lower than `_minPrice`.
 	fn take_offer(
 		input: &mut EvmDataReader,
 		gasometer: &mut Gasometer,
 		context: &Context,
+		with_min_price: bool,
 	) -> EvmResult<PrecompileOutput> {
-		input.expect_arguments(gasometer, 4)?;
+		input.expect_arguments(gasometer, if with_min_price { 5 } else { 4 })?;
 
 		let origin: <T as frame_system::pallet::Config>::AccountId =
 			T::AddressMapping::into_account_id(context.caller);
@@ -237,6 +262,7 @@ where
 		let offer_owner = input.read::<H256>(gasometer)?;
 		let offer_owner: <T as frame_system::Config>::AccountId =
 			<T as frame_system::Config>::AccountId::from(offer_owner.0);
+		let min_price = if with_min_price { Some(input.read::<u128>(gasometer)?) } else { None };
 		let commission_agent = input.read::<H256>(gasometer)?;
 		let commission_agent: <T as frame_system::Config>::AccountId =
 			<T as frame_system::Config>::AccountId::from(commission_agent.0);
@@ -247,11 +273,21 @@ where
 		log::debug!(target: "nftmart-evm", "commissionAgent: {:?}", &commission_agent);
 		log::debug!(target: "nftmart-evm", "commissionData: {:?}", &commission_data);
 
-		let call = OrderCall::<T>::take_offer {
-			offer_id: offer_id.into(),
-			offer_owner: <T as frame_system::Config>::Lookup::unlookup(offer_owner),
-			commission_agent: Some(commission_agent),
-			commission_data: Some(commission_data.into()),
+		let offer_owner = <T as frame_system::Config>::Lookup::unlookup(offer_owner);
+		let call = match min_price {
+			Some(min_price) => OrderCall::<T>::take_offer_with_min_price {
+				offer_id: offer_id.into(),
+				offer_owner,
+				min_price,
+				commission_agent: Some(commission_agent),
+				commission_data: Some(commission_data.into()),
+			},
+			None => OrderCall::<T>::take_offer {
+				offer_id: offer_id.into(),
+				offer_owner,
+				commission_agent: Some(commission_agent),
+				commission_data: Some(commission_data.into()),
+			},
 		};
 
 		RuntimeHelper::<T>::try_dispatch(Some(origin).into(), call, gasometer)?;